@@ -1,5 +1,16 @@
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{
+    AnalysisSource, ChapterMode, ContainerFormat, DefaultTrackStrategy, FileMode, NoMatchPolicy,
+    RemovedManifest, SavingsFloor,
+};
+use crate::core::schema::SchemaDocument;
+use crate::utils::{
+    ConsoleMode, DigitGrouping, NumberFormat, SizeUnits, parse_age, parse_size, set_console_mode,
+    set_number_format,
+};
 
 /// Creates the clap Command structure for CLI argument parsing
 ///
@@ -14,13 +25,32 @@ use std::path::PathBuf;
 /// use mkv_slimmer::cli::create_app;
 ///
 /// let app = create_app();
-/// let matches = app.try_get_matches_from(vec!["mkv-slimmer", "input.mkv"]);
+/// let matches = app.try_get_matches_from(vec!["mkv-slimmer", "input.mkv", "/output"]);
+/// assert!(matches.is_ok());
+///
+/// let matches = create_app().try_get_matches_from(vec!["mkv-slimmer", "chapters", "export", "input.mkv"]);
 /// assert!(matches.is_ok());
 /// ```
 pub fn create_app() -> Command {
     Command::new("mkv-slimmer")
         .version("0.1.0")
         .about("Analyze and remove unnecessary streams from MKV files")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(chapters_command())
+        .subcommand(tags_command())
+        .subcommand(fonts_command())
+        .subcommand(inspect_command())
+        .subcommand(analyze_command())
+        .subcommand(fix_languages_command())
+        .subcommand(rollback_command())
+        .subcommand(retry_failed_command())
+        .subcommand(scan_command())
+        .subcommand(process_command())
+        .subcommand(history_command())
+        .subcommand(audit_command())
+        .subcommand(journal_command())
+        .subcommand(schema_command())
         .arg(
             Arg::new("input_path")
                 .help("Path to the MKV file or directory to process")
@@ -29,10 +59,17 @@ pub fn create_app() -> Command {
         )
         .arg(
             Arg::new("target_path")
-                .help("Path where the modified MKV will be created (can be a file or directory)")
-                .required(true)
+                .help("Path where the modified MKV will be created (can be a file or directory); left out with --in-place")
+                .required_unless_present("in_place")
                 .value_parser(clap::value_parser!(PathBuf))
         )
+        .arg(
+            Arg::new("in_place")
+                .long("in-place")
+                .help("Replace the input file with its output instead of writing it to a target (single files only)")
+                .conflicts_with("target_path")
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             Arg::new("audio_languages")
                 .short('a')
@@ -64,6 +101,12 @@ pub fn create_app() -> Command {
                 .default_value("settings.yaml")
                 .value_parser(clap::value_parser!(PathBuf))
         )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .help("Profile from the config's 'profiles' to apply (default: the first Sonarr/Radarr tag naming one)")
+                .value_name("NAME")
+        )
         .arg(
             Arg::new("recursive")
                 .short('r')
@@ -78,35 +121,1368 @@ pub fn create_app() -> Command {
                 .help("Glob pattern to filter files (applies to filename in non-recursive mode, relative path in recursive mode)")
                 .value_name("PATTERN")
         )
+        .arg(
+            Arg::new("newer_than")
+                .long("newer-than")
+                .help("Only process batch files modified less than AGE ago (e.g. 30m, 12h, 7d, 2w)")
+                .value_name("AGE")
+        )
+        .arg(
+            Arg::new("older_than")
+                .long("older-than")
+                .help("Only process batch files modified more than AGE ago (e.g. 30d)")
+                .value_name("AGE")
+        )
+        .arg(
+            Arg::new("show_streams")
+                .long("show-streams")
+                .help("In directory mode, show the stream table with decisions of each file before processing it; --show-streams=FILE writes the tables to FILE instead")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("-")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("FILE")
+        )
+        .arg(
+            Arg::new("target_template")
+                .long("target-template")
+                .help("Output path of batch files below the target directory, e.g. '{relative_dir}/slimmed/{filename}' (placeholders: {relative_dir}, {filename}, {lang_summary})")
+                .value_name("TEMPLATE")
+        )
+        .arg(
+            Arg::new("container")
+                .long("container")
+                .help("Output container format (webm requires VP8/VP9/AV1 video, Opus/Vorbis audio and WebVTT subtitles)")
+                .value_parser(["mkv", "webm"])
+                .value_name("FORMAT")
+        )
+        .arg(
+            Arg::new("chapters")
+                .long("chapters")
+                .help("Keep the source's chapters or strip them from the output")
+                .value_parser(["keep", "strip"])
+                .value_name("MODE")
+        )
+        .arg(
+            Arg::new("prefer_source")
+                .long("prefer-source")
+                .help("Analyzer that defines the streams: mkvmerge (default; ffprobe only runs when mkvmerge can't identify a file) or ffprobe (both run, ffprobe wins when they disagree)")
+                .value_parser(["ffprobe", "mkvmerge"])
+                .value_name("SOURCE")
+        )
+        .arg(
+            Arg::new("cross_check")
+                .long("cross-check")
+                .help("Also run ffprobe when mkvmerge defines the streams and warn where the two disagree")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("audio_no_match")
+                .long("audio-no-match")
+                .help("What to do when no audio track matches the preferences")
+                .value_parser(["remove_all", "keep_first", "keep_all", "fail"])
+                .value_name("POLICY")
+        )
+        .arg(
+            Arg::new("default_audio")
+                .long("default-audio")
+                .help("How the default audio track is chosen: the most preferred language, the original language of the movie/series, or none")
+                .value_parser(["preference", "original", "none"])
+                .value_name("STRATEGY")
+        )
+        .arg(
+            Arg::new("min_channels")
+                .long("min-channels")
+                .help("Remove audio tracks with fewer channels when a track of the same language has at least this many (e.g. 6 drops stereo downmixes of 5.1)")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .value_name("N")
+        )
+        .arg(
+            Arg::new("keep_stereo_companion")
+                .long("keep-stereo-companion")
+                .help("With --min-channels, still keep one stereo track per language for device compatibility")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("prefer_channels")
+                .long("prefer-channels")
+                .help("Keep only audio tracks with exactly this many channels in languages that have one (e.g. 2 keeps just the stereo track)")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .value_name("N")
+        )
+        .arg(
+            Arg::new("audio_codec_preference")
+                .long("audio-codec-preference")
+                .help("Audio format ranking, best first (e.g. truehd, dts-hd, eac3, ac3, aac); only the best-ranked tracks of each kept language stay (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("CODEC")
+        )
+        .arg(
+            Arg::new("audio_tracks_per_language")
+                .long("audio-tracks-per-language")
+                .help("With --audio-codec-preference, how many tracks of each language to keep (default: 1)")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .value_name("N")
+        )
+        .arg(
+            Arg::new("subtitle_no_match")
+                .long("subtitle-no-match")
+                .help("What to do when no subtitle track matches the preferences")
+                .value_parser(["remove_all", "keep_first", "keep_all", "fail"])
+                .value_name("POLICY")
+        )
+        .arg(
+            Arg::new("default_subtitle")
+                .long("default-subtitle")
+                .help("How the default subtitle track is chosen: the most preferred language, the original language of the movie/series, or none")
+                .value_parser(["preference", "original", "none"])
+                .value_name("STRATEGY")
+        )
+        .arg(
+            Arg::new("split_subtitles")
+                .long("split-subtitles")
+                .help("Write one output per subtitle language when processing .mks files")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("fix_sub_charset")
+                .long("fix-sub-charset")
+                .help("Detect kept text subtitles that aren't UTF-8 and convert them from their language's legacy charset")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("keep_forced_subtitles")
+                .long("keep-forced-subtitles")
+                .help("Keep subtitles flagged as forced whatever their language, with the forced flag intact")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("remove_subtitle_codec")
+                .long("remove-subtitle-codec")
+                .help("Remove subtitles of this codec regardless of language, e.g. hdmv_pgs_subtitle or dvd_subtitle (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("CODEC")
+        )
+        .arg(
+            Arg::new("keep_subtitle_codec")
+                .long("keep-subtitle-codec")
+                .help("Keep only subtitles of this codec, e.g. subrip or ass, removing others regardless of language (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("CODEC")
+        )
+        .arg(
+            Arg::new("remove_codecs_if_text_kept")
+                .long("remove-codecs-if-text-kept")
+                .help("Only remove subtitles by codec (--remove-subtitle-codec, --keep-subtitle-codec) when a text subtitle of the same language is kept")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("merge_external_audio")
+                .long("merge-external-audio")
+                .help("Merge companion audio files (e.g. Movie.eng.mka next to Movie.mkv) into the output")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("sync")
+                .long("sync")
+                .help("Delay for merged external tracks, by filename or language (e.g. 'Movie.eng.mka:+250ms', 'eng:-1s'; can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("KEY:OFFSET")
+        )
+        .arg(
+            Arg::new("strip_global_tags")
+                .long("strip-global-tags")
+                .help("Drop the source's global tags when remuxing")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("strip_track_tags")
+                .long("strip-track-tags")
+                .help("Drop the source's track tags when remuxing")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("clear_title")
+                .long("clear-title")
+                .help("Clear the segment title when remuxing")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("title_template")
+                .long("title-template")
+                .help("Segment title to write when remuxing, from Sonarr/Radarr metadata (e.g. '{series_title} – S{season}E{episode} – {episode_title}')")
+                .value_name("TEMPLATE")
+        )
+        .arg(
+            Arg::new("attach_file")
+                .long("attach-file")
+                .help("Attach a file (font, cover art, ...) to the output; may use Sonarr placeholders like '{series_path}/poster.jpg' (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("PATH")
+        )
+        .arg(
+            Arg::new("remove_cover_art")
+                .long("remove-cover-art")
+                .help("Remove embedded cover art (cover.jpg and similar attachments, shown as mjpeg/png video streams); fonts and other attachments are kept")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("result_json")
+                .long("result-json")
+                .help("Write the run's file counts (remuxed, transferred, up to date, failed, skipped), the failed files and, for batches, the settings to this JSON file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH")
+        )
+        .arg(
+            Arg::new("console")
+                .long("console")
+                .help("Console output: human (default), quiet (warnings and errors only) or json (one JSON object per line, with the file it concerns)")
+                .value_parser(["human", "quiet", "json"])
+                .value_name("MODE")
+                .global(true)
+        )
+        .arg(
+            Arg::new("size_units")
+                .long("size-units")
+                .help("Units for sizes in messages: binary (1024, default), si (1000) or bytes (exact counts for scripts)")
+                .value_parser(["binary", "si", "bytes"])
+                .value_name("UNITS")
+                .global(true)
+        )
+        .arg(
+            Arg::new("digit_grouping")
+                .long("digit-grouping")
+                .help("Thousands separator in numbers: none (default), comma (1,234.5), period (1.234,5), space (1 234,5) or apostrophe (1'234.5)")
+                .value_parser(["none", "comma", "period", "space", "apostrophe"])
+                .value_name("STYLE")
+                .global(true)
+        )
+        .arg(
+            Arg::new("log_dir")
+                .long("log-dir")
+                .help("Save the full mkvmerge output of every remux to <DIR>/<output filename>.mkvmerge.log")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR")
+        )
+        .arg(
+            Arg::new("options_dir")
+                .long("options-dir")
+                .help("With --dry-run, write the mkvmerge command of every remux to <DIR>/<output filename>.options.json, to run later with `mkvmerge @FILE`")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR")
+        )
+        .arg(
+            Arg::new("backup_dir")
+                .long("backup-dir")
+                .help("Save originals to DIR before they are moved or replaced, so `rollback` can restore them")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR")
+        )
+        .arg(
+            Arg::new("history_db")
+                .long("history-db")
+                .help("Record every processed file in this SQLite database (see `history`)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH")
+        )
+        .arg(
+            Arg::new("journal")
+                .long("journal")
+                .help("Append every state change of batch files to this journal (see `journal show`)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH")
+        )
+        .arg(
+            Arg::new("skip_processed")
+                .long("skip-processed")
+                .help("Skip batch files the history database records as processed, unchanged, with the same settings")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("marker_tags")
+                .long("marker-tags")
+                .help("Tag remuxed outputs with the mkv-slimmer version, date and a hash of the settings")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("removed_manifest")
+                .long("removed-manifest")
+                .help("Record the tracks each remux removed (type, language, codec, title, size) in <output>.removed.json (sidecar) or a global tag of the output (tag)")
+                .value_parser(["sidecar", "tag"])
+                .value_name("MODE")
+        )
+        .arg(
+            Arg::new("skip_marked")
+                .long("skip-marked")
+                .help("Don't remux files whose marker tags show they were slimmed with the same settings (they are only transferred)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("print_command")
+                .long("print-command")
+                .help("Print the full mkvmerge command line, quoted for a shell, before each remux (dry runs always print it)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("scrub")
+                .long("scrub")
+                .help("Leave out the date, random UIDs and muxing app versions so outputs are reproducible (always remuxes)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("allow_ordered_chapters")
+                .long("allow-ordered-chapters")
+                .help("Remux files with ordered chapters or linked segments instead of failing (segment UIDs are preserved)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("output_mode")
+                .long("output-mode")
+                .help("Permissions for written outputs and created directories, in octal (e.g. 0640; directories also get search permission)")
+                .value_name("MODE")
+        )
+        .arg(
+            Arg::new("output_group")
+                .long("output-group")
+                .help("Group (name or numeric ID) for written outputs and created directories")
+                .value_name("GROUP")
+        )
+        .arg(
+            Arg::new("output_owner")
+                .long("output-owner")
+                .help("Owner for written outputs and created directories: 'target' to match the target directory's owner and group, or USER[:GROUP] (needs root)")
+                .value_name("OWNER")
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Process despite safety checks: outputs without video/audio, foreign audio without subtitles, batches larger than the target's free space")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("min_savings")
+                .long("min-savings")
+                .help("Keep the original when a remux saves no more than this (e.g. 500MB or 5%); the output is discarded")
+                .value_name("SIZE")
+        )
+        .arg(
+            Arg::new("min_throughput")
+                .long("min-throughput")
+                .help("Warn when a remux or copy runs below this many MB/s for a minute (storage may be degraded)")
+                .value_parser(clap::value_parser!(f64))
+                .value_name("MBPS")
+        )
+        .arg(
+            Arg::new("duration_tolerance")
+                .long("duration-tolerance")
+                .help("Warn when a remuxed output runs longer or shorter than the source by more than SECS (default 2), e.g. cut short by an I/O error")
+                .value_parser(clap::value_parser!(f64))
+                .value_name("SECS")
+        )
+        .arg(
+            Arg::new("skip_samples")
+                .long("skip-samples")
+                .help("Leave out of batches files shorter than MINUTES (default 10) that are named like a sample or sit next to much longer files")
+                .value_parser(clap::value_parser!(u64))
+                .num_args(0..=1)
+                .default_missing_value("10")
+                .value_name("MINUTES")
+        )
+        .arg(
+            Arg::new("free_up")
+                .long("free-up")
+                .help("Process the files with the most removable data first and stop once remuxes have saved SIZE (e.g. 500G)")
+                .value_name("SIZE")
+        )
+        .arg(
+            Arg::new("fill_space")
+                .long("fill-space")
+                .help("When a batch doesn't fit the target's free space, process the files that fill it best and skip the rest")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("infer_languages")
+                .long("infer-languages")
+                .help("Take the language of untagged (und) audio/subtitle tracks from titles like \"English\" or \"Japanese Commentary\"")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("trust_import_languages")
+                .long("trust-import-languages")
+                .help("When Sonarr/Radarr report a language for every audio/subtitle track and the file's tags disagree, match on the reported languages")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("import_markers")
+                .long("import-markers")
+                .help("When run by Sonarr/Radarr, print [MkvSlimmer] lines with the kept and removed languages so they appear in the import's script output")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("set_language")
+                .long("set-language")
+                .help("Language of a stream by index, replacing its tag (e.g. '2:jpn'; can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("INDEX:LANG")
+        )
+        .arg(
+            Arg::new("tag_languages")
+                .long("tag-languages")
+                .help("Write inferred (--infer-languages) and set (--set-language) languages into the output's track tags")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("flags_only")
+                .long("flags-only")
+                .help("Remove nothing; only set default/forced flags per the language preferences (mkvpropedit, no remux)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Fail a file instead of guessing when its analysis is incomplete: unparsable ffprobe output, unknown stream types, audio without a language tag")
+                .action(ArgAction::SetTrue)
+        )
+}
+
+/// `chapters export|import` subcommands
+fn chapters_command() -> Command {
+    Command::new("chapters")
+        .about("Export or import Matroska chapters (uses mkvextract/mkvpropedit)")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Write the chapters of a file to a Matroska chapters XML file")
+                .arg(
+                    Arg::new("file")
+                        .help("MKV file to read chapters from")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help(
+                            "XML file to write (defaults to <name>.chapters.xml next to the file)",
+                        )
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .value_name("XML"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Replace the chapters of a file in place from a chapters XML file")
+                .arg(
+                    Arg::new("file")
+                        .help("MKV file to modify")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("chapters")
+                        .help("Matroska chapters XML file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+}
+
+/// `tags export|import|strip` subcommands
+fn tags_command() -> Command {
+    let file_arg = |help: &'static str| {
+        Arg::new("file")
+            .help(help)
+            .required(true)
+            .value_parser(clap::value_parser!(PathBuf))
+    };
+
+    Command::new("tags")
+        .about("Export, import or strip Matroska global tags (uses mkvextract/mkvpropedit)")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Write the tags of a file to a Matroska tags XML file")
+                .arg(file_arg("MKV file to read tags from"))
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("XML file to write (defaults to <name>.tags.xml next to the file)")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .value_name("XML"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Replace the global tags of a file in place from a tags XML file")
+                .arg(file_arg("MKV file to modify"))
+                .arg(
+                    Arg::new("tags")
+                        .help("Matroska tags XML file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("strip")
+                .about("Remove all global tags of a file in place")
+                .arg(file_arg("MKV file to modify")),
+        )
+}
+
+/// `fonts extract` subcommand
+fn fonts_command() -> Command {
+    Command::new("fonts")
+        .about("Collect font attachments from MKV files")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("extract")
+                .about("Write the font attachments of a file or directory to a folder, one copy per distinct font")
+                .arg(
+                    Arg::new("input")
+                        .help("MKV file or directory to read fonts from")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("Directory the fonts are written to")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("recursive")
+                        .short('r')
+                        .long("recursive")
+                        .help("Scan subdirectories as well")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+/// `inspect` subcommand
+fn inspect_command() -> Command {
+    Command::new("inspect")
+        .about("Print the merged stream information and the raw ffprobe / mkvmerge -J output for a file")
+        .arg(
+            Arg::new("file")
+                .help("MKV file to inspect")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Write <name>.streams.json, <name>.ffprobe.json and <name>.mkvmerge.json to this directory instead of printing")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file whose analysis settings are used (e.g. processing.preferred_source)")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `analyze` subcommand
+fn analyze_command() -> Command {
+    Command::new("analyze")
+        .about("Show which streams of a file would be kept or removed, without a target or running mkvmerge")
+        .arg(
+            Arg::new("file")
+                .help("MKV file to analyze")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("audio_languages")
+                .short('a')
+                .long("audio-languages")
+                .help("Languages to keep for audio tracks (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("LANG"),
+        )
+        .arg(
+            Arg::new("subtitle_languages")
+                .short('s')
+                .long("subtitle-languages")
+                .help("Languages to keep for subtitle tracks (can be specified multiple times)")
+                .action(ArgAction::Append)
+                .value_name("LANG"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file whose language and processing settings are used")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `fix-languages` subcommand
+fn fix_languages_command() -> Command {
+    Command::new("fix-languages")
+        .about("Correct track language tags in place (mkvpropedit, no remux) from a mapping file and/or track titles")
+        .arg(
+            Arg::new("input")
+                .help("MKV file or directory to fix")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("mapping")
+                .short('m')
+                .long("mapping")
+                .help("YAML file mapping glob patterns (relative to the input directory) to stream index → language")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("YAML"),
+        )
+        .arg(
+            Arg::new("infer")
+                .long("infer")
+                .help("Take the language of untagged (und) tracks from their titles")
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("corrections")
+                .args(["mapping", "infer"])
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .help("Scan subdirectories as well")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Only print the corrections")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("backup_dir")
+                .long("backup-dir")
+                .help("Copy each file to DIR before editing it, so `rollback` can restore it")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file whose analysis settings are used (e.g. processing.preferred_source)")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `rollback` subcommand
+fn rollback_command() -> Command {
+    Command::new("rollback")
+        .about(
+            "Restore originals saved with --backup-dir (lists the backups when no file is given)",
+        )
+        .arg(
+            Arg::new("files")
+                .help("Original paths to restore; each gets its most recent backup back")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .help("Restore the most recent backup of every backed-up file")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("files"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Only print what would be restored")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("backup_dir")
+                .long("backup-dir")
+                .help("Backup directory (default: processing.backup_dir of the config)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file whose processing.backup_dir is used")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `retry-failed` subcommand
+fn retry_failed_command() -> Command {
+    Command::new("retry-failed")
+        .about("Process again the files that failed in a batch, from its --result-json report, with the same targets and settings")
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .help("Result JSON written by the batch (--result-json)")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("JSON"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Only show what would be done, even if the batch wasn't a dry run")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("result_json")
+                .long("result-json")
+                .help("Write the result of the retry, itself retryable, to this JSON file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+}
+
+/// `scan` subcommand
+fn scan_command() -> Command {
+    Command::new("scan")
+        .about("Collect and analyze the files of a directory into a manifest for process --manifest, without processing them")
+        .arg(
+            Arg::new("input")
+                .help("Directory to scan")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Manifest JSON to write")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("JSON"),
+        )
+        .arg(
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .help("Scan subdirectories as well")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("filter")
+                .short('f')
+                .long("filter")
+                .help("Glob pattern to filter files (filename, or relative path with --recursive)")
+                .value_name("PATTERN"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file the files are analyzed with")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `process` subcommand
+fn process_command() -> Command {
+    Command::new("process")
+        .about("Process the files of a scan manifest, reusing the analysis of files unchanged since the scan")
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .help("Manifest written by scan")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("JSON"),
+        )
+        .arg(
+            Arg::new("target")
+                .help("Target directory")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("candidates_only")
+                .long("candidates-only")
+                .help("Only process files the scan found streams to remove in, instead of every file")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Only show what would be done")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("result_json")
+                .long("result-json")
+                .help("Write the result of the run, retryable with retry-failed, to this JSON file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file the files are processed with")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `history` subcommand
+fn history_command() -> Command {
+    Command::new("history")
+        .about("List files recorded in the history database (--history-db) and the total savings")
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .help("History database (default: processing.history_db of the config)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("file")
+                .long("file")
+                .help("Only entries whose source path contains PATTERN")
+                .value_name("PATTERN"),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .help("Number of most recent entries to list")
+                .default_value("20")
+                .value_parser(clap::value_parser!(usize))
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file whose processing.history_db is used")
+                .default_value("settings.yaml")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// `journal show` subcommand
+fn journal_command() -> Command {
+    Command::new("journal")
+        .about("Inspect the batch journal (--journal)")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("show")
+                .about("Reconstruct what a batch run did, including files a crash interrupted")
+                .arg(
+                    Arg::new("journal")
+                        .help("Journal file (default: processing.journal of the config)")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Show every run in the journal, not just the last one")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("config")
+                        .short('c')
+                        .long("config")
+                        .help("Config file whose processing.journal is used")
+                        .default_value("settings.yaml")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+}
+
+/// `audit untagged` subcommand
+fn audit_command() -> Command {
+    Command::new("audit")
+        .about("Report library problems that make automatic slimming risky")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("untagged")
+                .about("List files with audio/subtitle tracks that have no language tag")
+                .arg(
+                    Arg::new("input")
+                        .help("MKV file or directory to audit")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("recursive")
+                        .short('r')
+                        .long("recursive")
+                        .help("Scan subdirectories as well")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Also write the report as JSON to this file")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .value_name("JSON"),
+                )
+                .arg(
+                    Arg::new("config")
+                        .short('c')
+                        .long("config")
+                        .help("Config file whose analysis settings are used (e.g. processing.preferred_source)")
+                        .default_value("settings.yaml")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+}
+
+/// `schema` subcommand
+fn schema_command() -> Command {
+    Command::new("schema")
+        .about("Print the JSON Schema of a document mkv-slimmer writes, for integrations")
+        .arg(
+            Arg::new("document")
+                .help("Document: result (--result-json), events (--console json lines), audit-untagged, manifest (scan) or removed (--removed-manifest sidecar)")
+                .required(true)
+                .value_parser(SchemaDocument::ALL.map(|document| document.name())),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Write the schema to this file instead of printing it")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("JSON"),
+        )
+}
+
+/// What the command line asks for: the regular slimming run or a subcommand
+pub enum CliCommand {
+    Process(Box<CliArgs>),
+    Chapters(ChaptersCommand),
+    Tags(TagsCommand),
+    Fonts(FontsCommand),
+    Inspect(InspectCommand),
+    Analyze(AnalyzeCommand),
+    FixLanguages(FixLanguagesCommand),
+    Audit(AuditCommand),
+    Journal(JournalCommand),
+    Rollback(RollbackCommand),
+    RetryFailed(RetryFailedCommand),
+    Scan(ScanCommand),
+    ProcessManifest(ProcessManifestCommand),
+    History(HistoryCommand),
+    Schema(SchemaCommand),
+}
+
+pub enum ChaptersCommand {
+    Export {
+        file: PathBuf,
+        output: Option<PathBuf>,
+    },
+    Import {
+        file: PathBuf,
+        chapters: PathBuf,
+    },
+}
+
+pub enum TagsCommand {
+    Export {
+        file: PathBuf,
+        output: Option<PathBuf>,
+    },
+    Import {
+        file: PathBuf,
+        tags: PathBuf,
+    },
+    Strip {
+        file: PathBuf,
+    },
+}
+
+pub enum FontsCommand {
+    Extract {
+        input: PathBuf,
+        output: PathBuf,
+        recursive: bool,
+    },
+}
+
+pub struct InspectCommand {
+    pub file: PathBuf,
+    pub output: Option<PathBuf>,
+    pub config: PathBuf,
+}
+
+pub struct AnalyzeCommand {
+    pub file: PathBuf,
+    pub audio_languages: Option<Vec<String>>,
+    pub subtitle_languages: Option<Vec<String>>,
+    pub config: PathBuf,
+}
+
+pub struct FixLanguagesCommand {
+    pub input: PathBuf,
+    pub mapping: Option<PathBuf>,
+    pub infer: bool,
+    pub recursive: bool,
+    pub dry_run: bool,
+    pub backup_dir: Option<PathBuf>,
+    pub config: PathBuf,
+}
+
+pub struct RollbackCommand {
+    pub files: Vec<PathBuf>,
+    pub all: bool,
+    pub dry_run: bool,
+    pub backup_dir: Option<PathBuf>,
+    pub config: PathBuf,
+}
+
+pub struct HistoryCommand {
+    pub db: Option<PathBuf>,
+    pub file: Option<String>,
+    pub limit: usize,
+    pub config: PathBuf,
+}
+
+pub struct RetryFailedCommand {
+    pub report: PathBuf,
+    pub dry_run: bool,
+    pub result_json: Option<PathBuf>,
+}
+
+pub struct ScanCommand {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub recursive: bool,
+    pub filter_pattern: Option<String>,
+    pub config: PathBuf,
+}
+
+pub struct ProcessManifestCommand {
+    pub manifest: PathBuf,
+    pub target: PathBuf,
+    pub candidates_only: bool,
+    pub dry_run: bool,
+    pub result_json: Option<PathBuf>,
+    pub config: PathBuf,
+}
+
+pub struct SchemaCommand {
+    pub document: SchemaDocument,
+    pub output: Option<PathBuf>,
+}
+
+pub enum AuditCommand {
+    Untagged {
+        input: PathBuf,
+        recursive: bool,
+        output: Option<PathBuf>,
+        config: PathBuf,
+    },
+}
+
+pub enum JournalCommand {
+    Show {
+        journal: Option<PathBuf>,
+        all: bool,
+        config: PathBuf,
+    },
+}
+
+impl CliCommand {
+    pub fn parse() -> anyhow::Result<Self> {
+        let matches = create_app().get_matches();
+
+        // The console mode and number format apply to every command
+        let global = match matches.subcommand() {
+            Some((_, sub_matches)) => sub_matches,
+            None => &matches,
+        };
+        let console = global
+            .get_one::<String>("console")
+            .map(|s| s.parse::<ConsoleMode>())
+            .transpose()?;
+        set_console_mode(console.unwrap_or_default());
+        let units = global
+            .get_one::<String>("size_units")
+            .map(|s| s.parse::<SizeUnits>())
+            .transpose()?;
+        let grouping = global
+            .get_one::<String>("digit_grouping")
+            .map(|s| s.parse::<DigitGrouping>())
+            .transpose()?;
+        set_number_format(NumberFormat {
+            units: units.unwrap_or_default(),
+            grouping: grouping.unwrap_or_default(),
+        });
+
+        match matches.subcommand() {
+            Some(("chapters", chapters)) => Ok(CliCommand::Chapters(
+                ChaptersCommand::from_matches(chapters)?,
+            )),
+            Some(("tags", tags)) => Ok(CliCommand::Tags(TagsCommand::from_matches(tags)?)),
+            Some(("fonts", fonts)) => Ok(CliCommand::Fonts(FontsCommand::from_matches(fonts)?)),
+            Some(("fix-languages", fix)) => Ok(CliCommand::FixLanguages(
+                FixLanguagesCommand::from_matches(fix)?,
+            )),
+            Some(("audit", audit)) => Ok(CliCommand::Audit(AuditCommand::from_matches(audit)?)),
+            Some(("journal", journal)) => {
+                Ok(CliCommand::Journal(JournalCommand::from_matches(journal)?))
+            }
+            Some(("rollback", rollback)) => Ok(CliCommand::Rollback(
+                RollbackCommand::from_matches(rollback)?,
+            )),
+            Some(("retry-failed", retry)) => Ok(CliCommand::RetryFailed(RetryFailedCommand {
+                report: required_path(retry, "report")?,
+                dry_run: retry.get_flag("dry_run"),
+                result_json: retry.get_one::<PathBuf>("result_json").cloned(),
+            })),
+            Some(("scan", scan)) => Ok(CliCommand::Scan(ScanCommand {
+                input: required_path(scan, "input")?,
+                output: required_path(scan, "output")?,
+                recursive: scan.get_flag("recursive"),
+                filter_pattern: scan.get_one::<String>("filter").cloned(),
+                config: required_path(scan, "config")?,
+            })),
+            Some(("process", process)) => Ok(CliCommand::ProcessManifest(ProcessManifestCommand {
+                manifest: required_path(process, "manifest")?,
+                target: required_path(process, "target")?,
+                candidates_only: process.get_flag("candidates_only"),
+                dry_run: process.get_flag("dry_run"),
+                result_json: process.get_one::<PathBuf>("result_json").cloned(),
+                config: required_path(process, "config")?,
+            })),
+            Some(("schema", schema)) => Ok(CliCommand::Schema(SchemaCommand {
+                document: schema
+                    .get_one::<String>("document")
+                    .map(|s| s.parse::<SchemaDocument>())
+                    .transpose()?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Required document argument missing - clap configuration error"
+                        )
+                    })?,
+                output: schema.get_one::<PathBuf>("output").cloned(),
+            })),
+            Some(("history", history)) => {
+                Ok(CliCommand::History(HistoryCommand::from_matches(history)?))
+            }
+            Some(("inspect", inspect)) => {
+                Ok(CliCommand::Inspect(InspectCommand::from_matches(inspect)?))
+            }
+            Some(("analyze", analyze)) => {
+                Ok(CliCommand::Analyze(AnalyzeCommand::from_matches(analyze)?))
+            }
+            _ => Ok(CliCommand::Process(Box::new(CliArgs::from_matches(
+                &matches,
+            )?))),
+        }
+    }
+}
+
+impl InspectCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        Ok(InspectCommand {
+            file: required_path(matches, "file")?,
+            output: matches.get_one::<PathBuf>("output").cloned(),
+            config: required_path(matches, "config")?,
+        })
+    }
+}
+
+impl AnalyzeCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let languages = |name: &str| {
+            matches
+                .get_many::<String>(name)
+                .map(|values| values.cloned().collect())
+        };
+        Ok(AnalyzeCommand {
+            file: required_path(matches, "file")?,
+            audio_languages: languages("audio_languages"),
+            subtitle_languages: languages("subtitle_languages"),
+            config: required_path(matches, "config")?,
+        })
+    }
+}
+
+impl FixLanguagesCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        Ok(FixLanguagesCommand {
+            input: required_path(matches, "input")?,
+            mapping: matches.get_one::<PathBuf>("mapping").cloned(),
+            infer: matches.get_flag("infer"),
+            recursive: matches.get_flag("recursive"),
+            dry_run: matches.get_flag("dry_run"),
+            backup_dir: matches.get_one::<PathBuf>("backup_dir").cloned(),
+            config: required_path(matches, "config")?,
+        })
+    }
+}
+
+impl RollbackCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        Ok(RollbackCommand {
+            files: matches
+                .get_many::<PathBuf>("files")
+                .map(|files| files.cloned().collect())
+                .unwrap_or_default(),
+            all: matches.get_flag("all"),
+            dry_run: matches.get_flag("dry_run"),
+            backup_dir: matches.get_one::<PathBuf>("backup_dir").cloned(),
+            config: required_path(matches, "config")?,
+        })
+    }
+}
+
+impl HistoryCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        Ok(HistoryCommand {
+            db: matches.get_one::<PathBuf>("db").cloned(),
+            file: matches.get_one::<String>("file").cloned(),
+            limit: matches.get_one::<usize>("limit").copied().unwrap_or(20),
+            config: required_path(matches, "config")?,
+        })
+    }
+}
+
+fn required_path(matches: &ArgMatches, name: &str) -> anyhow::Result<PathBuf> {
+    matches.get_one::<PathBuf>(name).cloned().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Required {} argument missing - clap configuration error",
+            name
+        )
+    })
+}
+
+impl ChaptersCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        match matches.subcommand() {
+            Some(("export", export)) => Ok(ChaptersCommand::Export {
+                file: required_path(export, "file")?,
+                output: export.get_one::<PathBuf>("output").cloned(),
+            }),
+            Some(("import", import)) => Ok(ChaptersCommand::Import {
+                file: required_path(import, "file")?,
+                chapters: required_path(import, "chapters")?,
+            }),
+            _ => anyhow::bail!("Unknown chapters subcommand - clap configuration error"),
+        }
+    }
 }
 
 pub struct CliArgs {
     pub input_path: PathBuf,
-    pub target_path: PathBuf,
+    pub target_path: Option<PathBuf>,
+    pub in_place: bool,
     pub config_path: PathBuf,
+    pub profile: Option<String>,
     pub dry_run: bool,
     pub recursive: bool,
     pub filter_pattern: Option<String>,
+    pub newer_than: Option<Duration>,
+    pub older_than: Option<Duration>,
+    /// `--show-streams`: `-` for the console, otherwise a file
+    pub show_streams: Option<PathBuf>,
     pub audio_languages: Option<Vec<String>>,
     pub subtitle_languages: Option<Vec<String>>,
+    pub container: Option<ContainerFormat>,
+    pub chapters: Option<ChapterMode>,
+    pub audio_no_match: Option<NoMatchPolicy>,
+    pub subtitle_no_match: Option<NoMatchPolicy>,
+    pub default_audio: Option<DefaultTrackStrategy>,
+    pub default_subtitle: Option<DefaultTrackStrategy>,
+    pub min_channels: Option<u32>,
+    pub keep_stereo_companion: bool,
+    pub prefer_channels: Option<u32>,
+    pub audio_codec_preference: Vec<String>,
+    pub audio_tracks_per_language: Option<u32>,
+    pub split_subtitles: bool,
+    pub merge_external_audio: bool,
+    pub sync_offsets: Vec<String>,
+    pub strip_global_tags: bool,
+    pub strip_track_tags: bool,
+    pub clear_title: bool,
+    pub title_template: Option<String>,
+    pub scrub: bool,
+    pub print_command: bool,
+    pub allow_ordered_chapters: bool,
+    pub output_mode: Option<FileMode>,
+    pub output_group: Option<String>,
+    pub output_owner: Option<String>,
+    pub force: bool,
+    pub min_savings: Option<SavingsFloor>,
+    pub min_throughput: Option<f64>,
+    pub duration_tolerance: Option<f64>,
+    pub skip_samples: Option<u64>,
+    pub free_up: Option<u64>,
+    pub fill_space: bool,
+    pub strict: bool,
+    pub infer_languages: bool,
+    pub trust_import_languages: bool,
+    pub import_markers: bool,
+    pub language_overrides: Vec<String>,
+    pub tag_languages: bool,
+    pub flags_only: bool,
+    pub fix_sub_charset: bool,
+    pub keep_forced_subtitles: bool,
+    pub remove_subtitle_codecs: Vec<String>,
+    pub keep_subtitle_codecs: Vec<String>,
+    pub remove_codecs_if_text_kept: bool,
+    pub attach_files: Vec<String>,
+    pub remove_cover_art: bool,
+    pub log_dir: Option<PathBuf>,
+    pub options_dir: Option<PathBuf>,
+    pub backup_dir: Option<PathBuf>,
+    pub history_db: Option<PathBuf>,
+    pub journal: Option<PathBuf>,
+    pub skip_processed: bool,
+    pub marker_tags: bool,
+    pub removed_manifest: Option<RemovedManifest>,
+    pub skip_marked: bool,
+    pub result_json: Option<PathBuf>,
+    pub target_template: Option<String>,
+    pub preferred_source: Option<AnalysisSource>,
+    pub cross_check: bool,
 }
 
 impl CliArgs {
-    pub fn parse() -> anyhow::Result<Self> {
-        let matches = create_app().get_matches();
-
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
         let input_path = matches.get_one::<PathBuf>("input_path").ok_or_else(|| {
             anyhow::anyhow!("Required input_path argument missing - clap configuration error")
         })?;
-        let target_path = matches.get_one::<PathBuf>("target_path").ok_or_else(|| {
-            anyhow::anyhow!("Required target_path argument missing - clap configuration error")
-        })?;
+        let target_path = matches.get_one::<PathBuf>("target_path").cloned();
+        let in_place = matches.get_flag("in_place");
         let config_path = matches.get_one::<PathBuf>("config").ok_or_else(|| {
             anyhow::anyhow!("Config argument with default value missing - clap configuration error")
         })?;
+        let profile = matches.get_one::<String>("profile").cloned();
         let dry_run = matches.get_flag("dry_run");
         let recursive = matches.get_flag("recursive");
-        let filter_pattern = matches.get_one::<String>("filter").map(|s| s.clone());
+        let filter_pattern = matches.get_one::<String>("filter").cloned();
+        let newer_than = matches
+            .get_one::<String>("newer_than")
+            .map(|s| parse_age(s))
+            .transpose()?;
+        let older_than = matches
+            .get_one::<String>("older_than")
+            .map(|s| parse_age(s))
+            .transpose()?;
 
         let audio_languages: Option<Vec<String>> = matches
             .get_many::<String>("audio_languages")
@@ -116,15 +1492,249 @@ impl CliArgs {
             .get_many::<String>("subtitle_languages")
             .map(|values| values.cloned().collect());
 
+        let container = matches
+            .get_one::<String>("container")
+            .map(|s| s.parse::<ContainerFormat>())
+            .transpose()?;
+        let chapters = matches
+            .get_one::<String>("chapters")
+            .map(|s| s.parse::<ChapterMode>())
+            .transpose()?;
+        let preferred_source = matches
+            .get_one::<String>("prefer_source")
+            .map(|s| s.parse::<AnalysisSource>())
+            .transpose()?;
+        let cross_check = matches.get_flag("cross_check");
+        let min_channels = matches.get_one::<u32>("min_channels").copied();
+        let keep_stereo_companion = matches.get_flag("keep_stereo_companion");
+        let prefer_channels = matches.get_one::<u32>("prefer_channels").copied();
+        let audio_codec_preference: Vec<String> = matches
+            .get_many::<String>("audio_codec_preference")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let audio_tracks_per_language =
+            matches.get_one::<u32>("audio_tracks_per_language").copied();
+        let audio_no_match = matches
+            .get_one::<String>("audio_no_match")
+            .map(|s| s.parse::<NoMatchPolicy>())
+            .transpose()?;
+        let subtitle_no_match = matches
+            .get_one::<String>("subtitle_no_match")
+            .map(|s| s.parse::<NoMatchPolicy>())
+            .transpose()?;
+        let default_audio = matches
+            .get_one::<String>("default_audio")
+            .map(|s| s.parse::<DefaultTrackStrategy>())
+            .transpose()?;
+        let default_subtitle = matches
+            .get_one::<String>("default_subtitle")
+            .map(|s| s.parse::<DefaultTrackStrategy>())
+            .transpose()?;
+        let split_subtitles = matches.get_flag("split_subtitles");
+        let merge_external_audio = matches.get_flag("merge_external_audio");
+        let sync_offsets: Vec<String> = matches
+            .get_many::<String>("sync")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let strip_global_tags = matches.get_flag("strip_global_tags");
+        let strip_track_tags = matches.get_flag("strip_track_tags");
+        let clear_title = matches.get_flag("clear_title");
+        let title_template = matches.get_one::<String>("title_template").cloned();
+        let scrub = matches.get_flag("scrub");
+        let print_command = matches.get_flag("print_command");
+        let allow_ordered_chapters = matches.get_flag("allow_ordered_chapters");
+        let output_mode = matches
+            .get_one::<String>("output_mode")
+            .map(|s| s.parse::<FileMode>())
+            .transpose()?;
+        let output_group = matches.get_one::<String>("output_group").cloned();
+        let output_owner = matches.get_one::<String>("output_owner").cloned();
+        let force = matches.get_flag("force");
+        let min_savings = matches
+            .get_one::<String>("min_savings")
+            .map(|s| s.parse::<SavingsFloor>())
+            .transpose()?;
+        let min_throughput = matches.get_one::<f64>("min_throughput").copied();
+        let duration_tolerance = matches.get_one::<f64>("duration_tolerance").copied();
+        let skip_samples = matches.get_one::<u64>("skip_samples").copied();
+        let free_up = matches
+            .get_one::<String>("free_up")
+            .map(|s| parse_size(s))
+            .transpose()?;
+        let fill_space = matches.get_flag("fill_space");
+        let strict = matches.get_flag("strict");
+        let infer_languages = matches.get_flag("infer_languages");
+        let trust_import_languages = matches.get_flag("trust_import_languages");
+        let import_markers = matches.get_flag("import_markers");
+        let language_overrides: Vec<String> = matches
+            .get_many::<String>("set_language")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let tag_languages = matches.get_flag("tag_languages");
+        let flags_only = matches.get_flag("flags_only");
+        let fix_sub_charset = matches.get_flag("fix_sub_charset");
+        let keep_forced_subtitles = matches.get_flag("keep_forced_subtitles");
+        let remove_subtitle_codecs: Vec<String> = matches
+            .get_many::<String>("remove_subtitle_codec")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let keep_subtitle_codecs: Vec<String> = matches
+            .get_many::<String>("keep_subtitle_codec")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let remove_codecs_if_text_kept = matches.get_flag("remove_codecs_if_text_kept");
+        let log_dir = matches.get_one::<PathBuf>("log_dir").cloned();
+        let options_dir = matches.get_one::<PathBuf>("options_dir").cloned();
+        let backup_dir = matches.get_one::<PathBuf>("backup_dir").cloned();
+        let history_db = matches.get_one::<PathBuf>("history_db").cloned();
+        let journal = matches.get_one::<PathBuf>("journal").cloned();
+        let skip_processed = matches.get_flag("skip_processed");
+        let marker_tags = matches.get_flag("marker_tags");
+        let removed_manifest = matches
+            .get_one::<String>("removed_manifest")
+            .map(|s| s.parse::<RemovedManifest>())
+            .transpose()?;
+        let skip_marked = matches.get_flag("skip_marked");
+        let result_json = matches.get_one::<PathBuf>("result_json").cloned();
+        let show_streams = matches.get_one::<PathBuf>("show_streams").cloned();
+        let target_template = matches.get_one::<String>("target_template").cloned();
+        let attach_files: Vec<String> = matches
+            .get_many::<String>("attach_file")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let remove_cover_art = matches.get_flag("remove_cover_art");
+
         Ok(CliArgs {
             input_path: input_path.clone(),
-            target_path: target_path.clone(),
+            target_path,
+            in_place,
             config_path: config_path.clone(),
+            profile,
             dry_run,
             recursive,
             filter_pattern,
+            newer_than,
+            older_than,
+            show_streams,
             audio_languages,
             subtitle_languages,
+            container,
+            chapters,
+            audio_no_match,
+            min_channels,
+            keep_stereo_companion,
+            prefer_channels,
+            audio_codec_preference,
+            audio_tracks_per_language,
+            subtitle_no_match,
+            default_audio,
+            default_subtitle,
+            split_subtitles,
+            merge_external_audio,
+            sync_offsets,
+            strip_global_tags,
+            strip_track_tags,
+            clear_title,
+            title_template,
+            scrub,
+            print_command,
+            allow_ordered_chapters,
+            output_mode,
+            output_group,
+            output_owner,
+            force,
+            min_savings,
+            min_throughput,
+            duration_tolerance,
+            skip_samples,
+            free_up,
+            fill_space,
+            strict,
+            infer_languages,
+            trust_import_languages,
+            import_markers,
+            language_overrides,
+            tag_languages,
+            flags_only,
+            fix_sub_charset,
+            keep_forced_subtitles,
+            remove_subtitle_codecs,
+            keep_subtitle_codecs,
+            remove_codecs_if_text_kept,
+            attach_files,
+            remove_cover_art,
+            log_dir,
+            options_dir,
+            backup_dir,
+            history_db,
+            journal,
+            skip_processed,
+            marker_tags,
+            removed_manifest,
+            skip_marked,
+            result_json,
+            target_template,
+            preferred_source,
+            cross_check,
         })
     }
 }
+
+impl TagsCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        match matches.subcommand() {
+            Some(("export", export)) => Ok(TagsCommand::Export {
+                file: required_path(export, "file")?,
+                output: export.get_one::<PathBuf>("output").cloned(),
+            }),
+            Some(("import", import)) => Ok(TagsCommand::Import {
+                file: required_path(import, "file")?,
+                tags: required_path(import, "tags")?,
+            }),
+            Some(("strip", strip)) => Ok(TagsCommand::Strip {
+                file: required_path(strip, "file")?,
+            }),
+            _ => anyhow::bail!("Unknown tags subcommand - clap configuration error"),
+        }
+    }
+}
+
+impl AuditCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        match matches.subcommand() {
+            Some(("untagged", untagged)) => Ok(AuditCommand::Untagged {
+                input: required_path(untagged, "input")?,
+                recursive: untagged.get_flag("recursive"),
+                output: untagged.get_one::<PathBuf>("output").cloned(),
+                config: required_path(untagged, "config")?,
+            }),
+            _ => anyhow::bail!("Unknown audit subcommand - clap configuration error"),
+        }
+    }
+}
+
+impl JournalCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        match matches.subcommand() {
+            Some(("show", show)) => Ok(JournalCommand::Show {
+                journal: show.get_one::<PathBuf>("journal").cloned(),
+                all: show.get_flag("all"),
+                config: required_path(show, "config")?,
+            }),
+            _ => anyhow::bail!("Unknown journal subcommand - clap configuration error"),
+        }
+    }
+}
+
+impl FontsCommand {
+    fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        match matches.subcommand() {
+            Some(("extract", extract)) => Ok(FontsCommand::Extract {
+                input: required_path(extract, "input")?,
+                output: required_path(extract, "output")?,
+                recursive: extract.get_flag("recursive"),
+            }),
+            _ => anyhow::bail!("Unknown fonts subcommand - clap configuration error"),
+        }
+    }
+}
@@ -1,13 +1,46 @@
 use anyhow::{Context, Result};
 use colored::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::config::Config;
+use crate::config::{
+    AnalysisSource, ChapterMode, CliOverrides, Config, ContainerFormat, DefaultTrackStrategy,
+    NoMatchPolicy, apply_saved_settings, offer_to_save_choices,
+};
+use crate::core::analyze::analyze_file;
+use crate::core::audit::audit_untagged;
+use crate::core::backup::{Backup, list_backups, restore_backup};
+use crate::core::batch::{AgeFilter, StreamTables};
+use crate::core::chapters::{default_chapters_path, export_chapters, import_chapters};
+use crate::core::fix_languages::{LanguageMapping, fix_languages};
+use crate::core::fonts::extract_fonts;
+use crate::core::history::HistoryDb;
+use crate::core::inspect::inspect_file;
+use crate::core::journal::{
+    JournalRun, JournalState, format_journal_time, journal_runs, read_journal,
+};
+use crate::core::notify::{NotificationEvent, Notifications};
+use crate::core::partial::clean_orphans;
+use crate::core::tags::{default_tags_path, export_tags, import_tags, strip_tags};
+use crate::core::titles::title_rewrites;
+use crate::core::{BatchProcessor, LanguagePolicy, ProcessControl};
+use crate::display::StreamDisplayer;
 use crate::error::{config_error, file_validation_error};
-use crate::models::SonarrContext;
-use crate::utils::{check_dependencies, collect_sonarr_environment};
+use crate::models::{
+    ImportContext, MediaKind, RunSummary, SCHEMA_VERSION, ScanManifest, TransferMethod, Versioned,
+};
+use crate::utils::{
+    OWNER_OF_TARGET, check_dependencies, collect_radarr_environment, collect_sonarr_environment,
+    detect_tools, format_elapsed, format_size, resolve_target_path, validate_source_target_paths,
+};
+use crate::{report, report_warn};
 
-use super::args::CliArgs;
+use super::args::{
+    AnalyzeCommand, AuditCommand, ChaptersCommand, CliArgs, FixLanguagesCommand, FontsCommand,
+    HistoryCommand, InspectCommand, JournalCommand, ProcessManifestCommand, RetryFailedCommand,
+    RollbackCommand, ScanCommand, SchemaCommand, TagsCommand,
+};
+use super::progress::{cancel_on_ctrl_c, remux_progress_bar};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TargetType {
@@ -20,17 +53,23 @@ pub enum TargetType {
 pub struct ProcessingSettings {
     pub input_path: PathBuf,
     pub target_path: PathBuf,
+    /// The output replaces the input file (`target_path` is the input)
+    pub in_place: bool,
     pub target_type: TargetType,
     pub recursive: bool,
     pub filter_pattern: Option<String>,
+    pub age_filter: AgeFilter,
+    pub stream_tables: StreamTables,
+    /// Where to write the run's `RunSummary`
+    pub result_json: Option<PathBuf>,
     pub config: Config,
-    pub sonarr_context: Option<SonarrContext>,
+    pub import_context: Option<ImportContext>,
     pub input_is_file: bool,
     pub input_is_dir: bool,
 }
 
 /// Determine if target_path represents a file or directory
-pub fn determine_target_type(target_path: &PathBuf) -> TargetType {
+pub fn determine_target_type(target_path: &Path) -> TargetType {
     if target_path.exists() {
         // If it exists, check what it actually is
         if target_path.is_file() {
@@ -56,40 +95,58 @@ pub fn determine_target_type(target_path: &PathBuf) -> TargetType {
     }
 }
 
-/// Parse CLI arguments, validate settings, and prepare configuration
-/// Returns ProcessingSettings ready for main processing orchestration
-pub async fn prepare_processing_settings() -> Result<ProcessingSettings> {
-    let args = CliArgs::parse()?;
+/// The Sonarr or Radarr context when running as a custom import script
+fn collect_import_environment() -> Option<ImportContext> {
+    let sonarr_context = collect_sonarr_environment();
+    if sonarr_context.is_present() {
+        return Some(sonarr_context.into());
+    }
+    let radarr_context = collect_radarr_environment();
+    radarr_context.is_present().then(|| radarr_context.into())
+}
 
+/// Validate parsed CLI arguments and prepare configuration
+/// Returns ProcessingSettings ready for main processing orchestration
+pub async fn prepare_processing_settings(args: CliArgs) -> Result<ProcessingSettings> {
     // Check dependencies
-    let missing_deps = check_dependencies()?;
-    if !missing_deps.is_empty() {
-        eprintln!(
-            "Warning: Missing optional dependencies: {}",
-            missing_deps.join(", ")
-        );
-        eprintln!("Some features may be limited. Install ffmpeg for full functionality.\n");
+    for tool in check_dependencies()? {
+        report_warn!("Warning: {} not found - {}", tool.name, tool.needed_for);
+        report_warn!("   💡 {}", tool.install);
     }
 
     // Determine target type and validate combinations
-    let target_type = determine_target_type(&args.target_path);
     let input_is_file = args.input_path.is_file();
     let input_is_dir = args.input_path.is_dir();
+    // In place, the input is its own target file
+    let target_path = match &args.target_path {
+        Some(target_path) => target_path.clone(),
+        None if input_is_dir => {
+            anyhow::bail!(
+                "--in-place only processes single files.\nInput: {} (directory)\n\nGive a target directory to process a directory.",
+                args.input_path.display()
+            );
+        }
+        None => args.input_path.clone(),
+    };
+    let target_type = if args.in_place {
+        TargetType::File
+    } else {
+        determine_target_type(&target_path)
+    };
 
     // Validate input/output combinations
     match (input_is_file, input_is_dir, &target_type) {
         (true, false, TargetType::File) => {
             // File → File: Valid
             // Ensure target directory exists if target doesn't exist
-            if !args.target_path.exists() {
-                if let Some(parent) = args.target_path.parent() {
-                    if !parent.exists() {
-                        anyhow::bail!(
-                            "Target directory does not exist: {}\nPlease create the directory first or specify a different target path.",
-                            parent.display()
-                        );
-                    }
-                }
+            if !target_path.exists()
+                && let Some(parent) = target_path.parent()
+                && !parent.exists()
+            {
+                anyhow::bail!(
+                    "Target directory does not exist: {}\nPlease create the directory first or specify a different target path.",
+                    parent.display()
+                );
             }
         }
         (true, false, TargetType::Directory) => {
@@ -103,7 +160,7 @@ pub async fn prepare_processing_settings() -> Result<ProcessingSettings> {
             anyhow::bail!(
                 "Cannot process directory to single file.\nInput: {} (directory)\nTarget: {} (file)\n\nUse a target directory instead.",
                 args.input_path.display(),
-                args.target_path.display()
+                target_path.display()
             );
         }
         (false, false, _) => {
@@ -127,52 +184,1089 @@ pub async fn prepare_processing_settings() -> Result<ProcessingSettings> {
         )
     })?;
 
+    // Collect the Sonarr or Radarr environment if available
+    let import_context = collect_import_environment();
+
+    // Apply the profile before the CLI arguments, which take precedence over it
+    let profile = match args.profile {
+        Some(profile) => Some(profile),
+        None => import_context.as_ref().and_then(|context| {
+            let profile = config.profile_for_tags(&context.tags())?.to_string();
+            report!(
+                "🎛️  Profile '{}' selected by {} tag",
+                profile,
+                context.app_name()
+            );
+            Some(profile)
+        }),
+    };
+    if let Some(profile) = profile {
+        config = config.with_profile(&profile)?;
+    }
+
+    // Settings files saved for the file, e.g. by an earlier interactive run;
+    // batches apply them per directory and file
+    if input_is_file {
+        config = apply_saved_settings(config, &args.input_path)?;
+    }
+
     // Merge CLI arguments with config
     config
-        .merge_cli_args(args.audio_languages, args.subtitle_languages, args.dry_run)
+        .merge_cli_args(CliOverrides {
+            audio_languages: args.audio_languages,
+            subtitle_languages: args.subtitle_languages,
+            dry_run: args.dry_run,
+            container: args.container,
+            chapters: args.chapters,
+            audio_no_match: args.audio_no_match,
+            subtitle_no_match: args.subtitle_no_match,
+            default_audio: args.default_audio,
+            default_subtitle: args.default_subtitle,
+            min_channels: args.min_channels,
+            keep_stereo_companion: args.keep_stereo_companion,
+            prefer_channels: args.prefer_channels,
+            audio_codec_preference: args.audio_codec_preference,
+            audio_tracks_per_language: args.audio_tracks_per_language,
+            split_subtitles: args.split_subtitles,
+            merge_external_audio: args.merge_external_audio,
+            sync_offsets: args.sync_offsets,
+            strip_global_tags: args.strip_global_tags,
+            strip_track_tags: args.strip_track_tags,
+            clear_title: args.clear_title,
+            title_template: args.title_template,
+            scrub: args.scrub,
+            print_command: args.print_command,
+            allow_ordered_chapters: args.allow_ordered_chapters,
+            output_mode: args.output_mode,
+            output_group: args.output_group,
+            output_owner: args.output_owner,
+            force: args.force,
+            min_savings: args.min_savings,
+            min_throughput: args.min_throughput,
+            duration_tolerance: args.duration_tolerance,
+            skip_samples: args.skip_samples,
+            free_up: args.free_up,
+            fill_space: args.fill_space,
+            strict: args.strict,
+            infer_languages: args.infer_languages,
+            trust_import_languages: args.trust_import_languages,
+            import_markers: args.import_markers,
+            language_overrides: args.language_overrides,
+            tag_languages: args.tag_languages,
+            flags_only: args.flags_only,
+            fix_sub_charset: args.fix_sub_charset,
+            keep_forced_subtitles: args.keep_forced_subtitles,
+            remove_subtitle_codecs: args.remove_subtitle_codecs,
+            keep_subtitle_codecs: args.keep_subtitle_codecs,
+            remove_codecs_if_text_kept: args.remove_codecs_if_text_kept,
+            attach_files: args.attach_files,
+            remove_cover_art: args.remove_cover_art,
+            log_dir: args.log_dir,
+            options_dir: args.options_dir,
+            backup_dir: args.backup_dir,
+            history_db: args.history_db,
+            journal: args.journal,
+            skip_processed: args.skip_processed,
+            marker_tags: args.marker_tags,
+            removed_manifest: args.removed_manifest,
+            skip_marked: args.skip_marked,
+            target_template: args.target_template,
+            preferred_source: args.preferred_source,
+            cross_check: args.cross_check,
+        })
         .context("Failed to merge CLI arguments with configuration")?;
 
     // Prompt for missing values if running interactively
-    config
-        .prompt_missing_values()
+    let choices = config
+        .prompt_missing_values(input_is_file.then(|| MediaKind::from_path(&args.input_path)))
         .context("Failed to prompt for missing configuration values")?;
-
-    // Collect Sonarr environment if available
-    let sonarr_context = collect_sonarr_environment();
-    let sonarr_context_opt = if sonarr_context.is_present() {
-        Some(sonarr_context)
-    } else {
-        None
-    };
+    if let Some(choices) = choices {
+        offer_to_save_choices(choices, &args.input_path, input_is_file)
+            .context("Failed to save the chosen languages")?;
+    }
 
     Ok(ProcessingSettings {
         input_path: args.input_path,
-        target_path: args.target_path,
+        target_path,
+        in_place: args.in_place,
         target_type,
         recursive: args.recursive,
         filter_pattern: args.filter_pattern,
+        age_filter: AgeFilter {
+            newer_than: args.newer_than,
+            older_than: args.older_than,
+        },
+        stream_tables: match args.show_streams {
+            None => StreamTables::Hidden,
+            Some(path) if path.as_os_str() == "-" => StreamTables::Console,
+            Some(path) => StreamTables::File(path),
+        },
+        result_json: args.result_json,
         config,
-        sonarr_context: sonarr_context_opt,
+        import_context,
         input_is_file,
         input_is_dir,
     })
 }
 
-pub fn print_configuration_info(config: &Config) {
-    println!("\n⚙️  Configuration:");
-    println!("🎵 Audio languages: {:?}", config.audio.keep_languages);
-    println!(
-        "📄 Subtitle languages: {:?}",
-        config.subtitles.keep_languages
+/// Write the summary of a processing run for `--result-json`
+pub fn write_result_json(path: &Path, summary: &RunSummary) -> Result<()> {
+    let json = serde_json::to_string_pretty(&Versioned::new(summary))?;
+    std::fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write result JSON: {}", path.display()))
+}
+
+/// Run a `chapters export|import` subcommand
+pub async fn run_chapters_command(command: ChaptersCommand) -> Result<()> {
+    match command {
+        ChaptersCommand::Export { file, output } => {
+            let output = match output {
+                Some(output) => output,
+                None => default_chapters_path(&file)?,
+            };
+            export_chapters(&file, &output)?;
+            report!("📤 Exported chapters to: {}", output.display());
+        }
+        ChaptersCommand::Import { file, chapters } => {
+            import_chapters(&file, &chapters)?;
+            report!("📥 Imported chapters into: {}", file.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a `tags export|import|strip` subcommand
+pub async fn run_tags_command(command: TagsCommand) -> Result<()> {
+    match command {
+        TagsCommand::Export { file, output } => {
+            let output = match output {
+                Some(output) => output,
+                None => default_tags_path(&file)?,
+            };
+            export_tags(&file, &output)?;
+            report!("📤 Exported tags to: {}", output.display());
+        }
+        TagsCommand::Import { file, tags } => {
+            import_tags(&file, &tags)?;
+            report!("📥 Imported tags into: {}", file.display());
+        }
+        TagsCommand::Strip { file } => {
+            strip_tags(&file)?;
+            report!("🧹 Removed global tags from: {}", file.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a `fonts extract` subcommand
+pub async fn run_fonts_command(command: FontsCommand) -> Result<()> {
+    match command {
+        FontsCommand::Extract {
+            input,
+            output,
+            recursive,
+        } => {
+            let result = extract_fonts(&input, &output, recursive)?;
+            report!(
+                "🔤 Extracted {} font(s) from {} file(s) to: {} ({} duplicate(s) skipped)",
+                result.fonts_written,
+                result.files_scanned,
+                output.display(),
+                result.duplicates
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the `inspect` subcommand: dump merged streams and raw probe output
+pub async fn run_inspect_command(command: InspectCommand) -> Result<()> {
+    let config = Config::from_yaml(&command.config)?;
+    let inspection = inspect_file(&command.file, &config).await?;
+
+    match &command.output {
+        Some(output) => {
+            for path in inspection.write_to(&command.file, output)? {
+                report!("🔍 Wrote: {}", path.display());
+            }
+        }
+        None => {
+            for (name, json) in inspection.sections()? {
+                report!("=== {} ===", name);
+                report!("{}", json);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the `analyze` subcommand
+pub async fn run_analyze_command(command: AnalyzeCommand) -> Result<()> {
+    let mut config = Config::from_yaml(&command.config)?;
+    config.merge_cli_args(CliOverrides {
+        audio_languages: command.audio_languages,
+        subtitle_languages: command.subtitle_languages,
+        ..Default::default()
+    })?;
+
+    report!("📁 Analyzing: {}", command.file.display());
+    let analysis = analyze_file(&command.file, &config, &LanguagePolicy).await?;
+    StreamDisplayer::new(&analysis.streams, &analysis.decisions, analysis.media_kind)
+        .with_chapters(analysis.chapters, config.processing.chapters)
+        .with_dropped_metadata(config.tags.dropped_metadata())
+        .with_title_rewrites(title_rewrites(
+            &analysis.streams,
+            &analysis.decisions,
+            &config.titles,
+        ))
+        .display()
+        .context("Failed to display stream information")?;
+
+    Ok(())
+}
+
+/// Run the `fix-languages` subcommand
+pub async fn run_fix_languages_command(command: FixLanguagesCommand) -> Result<()> {
+    let mut config = Config::from_yaml(&command.config)?;
+    if let Some(backup_dir) = command.backup_dir {
+        config.processing.backup_dir = Some(backup_dir);
+    }
+    let mapping = command
+        .mapping
+        .as_deref()
+        .map(LanguageMapping::from_yaml)
+        .transpose()?;
+
+    let summary = fix_languages(
+        &command.input,
+        mapping.as_ref(),
+        command.infer,
+        command.recursive,
+        command.dry_run,
+        &config,
+    )
+    .await?;
+
+    let verb = if command.dry_run {
+        "Would correct"
+    } else {
+        "Corrected"
+    };
+    report!(
+        "🏷️  {} {} track(s) in {} of {} file(s)",
+        verb,
+        summary.tracks_changed,
+        summary.files_changed,
+        summary.files_scanned
     );
+    if !summary.failed.is_empty() {
+        anyhow::bail!("{} file(s) could not be fixed", summary.failed.len());
+    }
+
+    Ok(())
+}
+
+/// Run the `schema` subcommand
+pub async fn run_schema_command(command: SchemaCommand) -> Result<()> {
+    let json = serde_json::to_string_pretty(&command.document.schema())?;
+    match command.output {
+        Some(path) => {
+            std::fs::write(&path, json + "\n")
+                .with_context(|| format!("Failed to write schema: {}", path.display()))?;
+            report!(
+                "📐 Schema of {} (version {}) written to: {}",
+                command.document.name(),
+                SCHEMA_VERSION,
+                path.display()
+            );
+        }
+        // Printed directly so the schema stays plain JSON in every console mode
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// Run the `retry-failed` subcommand
+pub async fn run_retry_failed_command(command: RetryFailedCommand) -> Result<()> {
+    let json = std::fs::read_to_string(&command.report)
+        .with_context(|| format!("Failed to read report: {}", command.report.display()))?;
+    let previous: Versioned<RunSummary> = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse report: {}", command.report.display()))?;
+    previous.check_version("report")?;
+    let previous = previous.document;
+    if previous.failures.is_empty() {
+        report!("✅ No failed files in {}", command.report.display());
+        return Ok(());
+    }
+    let Some(batch) = previous.batch else {
+        return Err(config_error(
+            "Retry",
+            "The report has no batch settings - it was written by a single-file run or an older version",
+        ));
+    };
+
+    let mut config = batch.config;
+    if command.dry_run {
+        config.processing.dry_run = true;
+    }
+    let dry_run = config.processing.dry_run;
+    report!("📁 Source directory: {}", batch.source.display());
+    report!("📂 Target directory: {}", batch.target.display());
+    print_configuration_info(&config, None);
+    clean_orphans(&batch.target, batch.recursive, config.processing.dry_run);
+
+    let notifications = Arc::new(Notifications::from_config(&config.notifications));
+    let processor = BatchProcessor::new(
+        batch.source,
+        batch.target,
+        batch.recursive,
+        None,
+        config,
+        None,
+    )
+    .with_files(previous.failures.into_keys().collect())
+    .with_control(
+        ProcessControl::default()
+            .with_progress(remux_progress_bar())
+            .with_cancellation(cancel_on_ctrl_c()),
+    )
+    .with_notifications(notifications.clone());
+    let result = processor.process().await?;
+    result.print_summary();
+
+    let summary = RunSummary {
+        batch: Some(processor.batch_run()),
+        ..result.run_summary(dry_run)
+    };
+    notifications.notify(&NotificationEvent::RunFinished(Box::new(summary.clone())));
+    if let Some(path) = command.result_json {
+        write_result_json(&path, &summary)?;
+    }
+    if result.failed > 0 {
+        anyhow::bail!("{} file(s) failed again", result.failed);
+    }
+
+    Ok(())
+}
+
+/// Run the `scan` subcommand
+pub async fn run_scan_command(command: ScanCommand) -> Result<()> {
+    if !command.input.is_dir() {
+        return Err(file_validation_error(
+            &command.input,
+            "Scan input must be a directory",
+        ));
+    }
+    let config = Config::from_yaml(&command.config)?;
+
+    // No target: nothing is processed, and nothing is left out as an output
+    let processor = BatchProcessor::new(
+        command.input,
+        PathBuf::new(),
+        command.recursive,
+        command.filter_pattern,
+        config,
+        None,
+    )
+    .with_control(ProcessControl::default().with_cancellation(cancel_on_ctrl_c()));
+    let manifest = processor.scan().await?;
+
+    let json = serde_json::to_string_pretty(&Versioned::new(&manifest))?;
+    std::fs::write(&command.output, json + "\n")
+        .with_context(|| format!("Failed to write manifest: {}", command.output.display()))?;
+
+    let failed = manifest
+        .files
+        .iter()
+        .filter(|entry| entry.error.is_some())
+        .count();
+    report!(
+        "\n📋 Scanned {} file(s): {} with streams to remove ({}), {} failed to analyze",
+        manifest.files.len(),
+        manifest.candidates().count(),
+        format_size(manifest.removable_bytes()),
+        failed
+    );
+    report!("💾 Manifest written to: {}", command.output.display());
+    Ok(())
+}
+
+/// Run the `process --manifest` subcommand
+pub async fn run_process_manifest_command(command: ProcessManifestCommand) -> Result<()> {
+    let json = std::fs::read_to_string(&command.manifest)
+        .with_context(|| format!("Failed to read manifest: {}", command.manifest.display()))?;
+    let manifest: Versioned<ScanManifest> = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse manifest: {}", command.manifest.display()))?;
+    manifest.check_version("manifest")?;
+    let manifest = manifest.document;
+
+    let mut config = Config::from_yaml(&command.config)?;
+    if command.dry_run {
+        config.processing.dry_run = true;
+    }
+    let dry_run = config.processing.dry_run;
+    validate_source_target_paths(&manifest.source, &command.target)
+        .context("Source and target path validation failed")?;
+
+    report!(
+        "📋 Manifest: {} (scanned {} ago)",
+        command.manifest.display(),
+        format_elapsed(manifest.scanned_at)
+    );
+    let (entries, gone): (Vec<_>, Vec<_>) = manifest
+        .files
+        .into_iter()
+        .filter(|entry| !command.candidates_only || entry.is_candidate())
+        .partition(|entry| entry.path.is_file());
+    for entry in &gone {
+        report!("⏭️  No longer exists: {}", entry.path.display());
+    }
+    if entries.is_empty() {
+        report!("✅ No files of the manifest to process");
+        return Ok(());
+    }
+    report!("📁 Source directory: {}", manifest.source.display());
+    report!("📂 Target directory: {}", command.target.display());
+    print_configuration_info(&config, None);
+    clean_orphans(&command.target, manifest.recursive, dry_run);
+
+    let notifications = Arc::new(Notifications::from_config(&config.notifications));
+    let processor = BatchProcessor::new(
+        manifest.source,
+        command.target,
+        manifest.recursive,
+        None,
+        config,
+        None,
+    )
+    .with_manifest_entries(entries)
+    .with_control(
+        ProcessControl::default()
+            .with_progress(remux_progress_bar())
+            .with_cancellation(cancel_on_ctrl_c()),
+    )
+    .with_notifications(notifications.clone());
+    let result = processor.process().await?;
+    result.print_summary();
+    result.print_warnings();
+
+    let summary = RunSummary {
+        batch: Some(processor.batch_run()),
+        ..result.run_summary(dry_run)
+    };
+    notifications.notify(&NotificationEvent::RunFinished(Box::new(summary.clone())));
+    if let Some(path) = command.result_json {
+        write_result_json(&path, &summary)?;
+    }
+    if summary.exit_code() != 0 {
+        std::process::exit(summary.exit_code());
+    }
+
+    Ok(())
+}
+
+/// Run the `rollback` subcommand
+pub async fn run_history_command(command: HistoryCommand) -> Result<()> {
+    let config = Config::from_yaml(&command.config)?;
+    let db_path = command.db.or(config.processing.history_db).ok_or_else(|| {
+        config_error(
+            "History",
+            "No history database: use --db or set processing.history_db",
+        )
+    })?;
+    if !db_path.is_file() {
+        return Err(file_validation_error(
+            &db_path,
+            "History database not found",
+        ));
+    }
+    let history = HistoryDb::open(&db_path)?;
+    let pattern = command.file.as_deref();
+
+    let entries = history.entries(pattern, command.limit)?;
+    for entry in entries.iter().rev() {
+        let removed = if entry.removed_tracks.is_empty() {
+            "nothing removed".to_string()
+        } else {
+            format!("removed {}", entry.removed_tracks)
+        };
+        report!(
+            "📚 {} ({} ago, {}): {}, saved {}",
+            entry.source.path.display(),
+            format_elapsed(entry.processed_at),
+            entry.status,
+            removed,
+            format_size(entry.bytes_saved)
+        );
+    }
+
+    let totals = history.totals(pattern)?;
+    report!(
+        "📊 {} file(s) processed, {} remuxed, {} track(s) removed, {} saved",
+        totals.files,
+        totals.remuxed,
+        totals.tracks_removed,
+        format_size(totals.bytes_saved)
+    );
+    Ok(())
+}
+
+/// Run the `journal` subcommand
+pub async fn run_journal_command(command: JournalCommand) -> Result<()> {
+    let JournalCommand::Show {
+        journal,
+        all,
+        config,
+    } = command;
+    let config = Config::from_yaml(&config)?;
+    let path = journal.or(config.processing.journal).ok_or_else(|| {
+        config_error(
+            "Journal",
+            "No journal: pass its path or set processing.journal",
+        )
+    })?;
+    if !path.is_file() {
+        return Err(file_validation_error(&path, "Journal not found"));
+    }
+
+    let (entries, unreadable) = read_journal(&path)?;
+    if unreadable > 0 {
+        report_warn!(
+            "⚠️  Skipped {} unreadable journal line(s) - a crash can cut off the last one",
+            unreadable
+        );
+    }
+    let runs = journal_runs(&entries);
+    let Some(last) = runs.last() else {
+        report!("🧾 The journal has no runs");
+        return Ok(());
+    };
+    if all {
+        for run in &runs {
+            print_journal_run(run);
+        }
+    } else {
+        print_journal_run(last);
+    }
+    Ok(())
+}
+
+/// The start, the last state of every file and the end of a journal run
+fn print_journal_run(run: &JournalRun) {
+    match run.started.as_ref().map(|entry| (entry.time, &entry.state)) {
+        Some((
+            time,
+            JournalState::RunStarted {
+                source,
+                target,
+                files,
+                dry_run,
+            },
+        )) => report!(
+            "🧾 Run started {} UTC: {} → {}, {} file(s) to process{}",
+            format_journal_time(time),
+            source.display(),
+            target.display(),
+            files,
+            if *dry_run { " (dry run)" } else { "" }
+        ),
+        _ => report!("🧾 Run {} (start not recorded)", run.run),
+    }
+
+    for entry in &run.files {
+        let file = entry
+            .file
+            .as_deref()
+            .map(|file| file.display().to_string())
+            .unwrap_or_default();
+        match &entry.state {
+            JournalState::Started => report!(
+                "   💥 {}: interrupted - started {} UTC but never finished",
+                file,
+                format_journal_time(entry.time)
+            ),
+            JournalState::Skipped { reason, .. } => {
+                report!("   ⏭️  {}: skipped - {}", file, reason)
+            }
+            JournalState::Finished { outcome, .. } => report!("   ✅ {}: {}", file, outcome),
+            JournalState::Failed { error } => report!("   ❌ {}: failed - {}", file, error),
+            JournalState::Cancelled => report!("   🛑 {}: cancelled", file),
+            JournalState::RunStarted { .. } | JournalState::RunFinished { .. } => {}
+        }
+    }
+
+    match run
+        .finished
+        .as_ref()
+        .map(|entry| (entry.time, &entry.state))
+    {
+        Some((
+            time,
+            JournalState::RunFinished {
+                successful,
+                failed,
+                skipped,
+            },
+        )) => report!(
+            "🏁 Run finished {} UTC: {} successful, {} failed, {} skipped\n",
+            format_journal_time(time),
+            successful,
+            failed,
+            skipped
+        ),
+        Some((time, _)) => report!("🛑 Run cancelled {} UTC\n", format_journal_time(time)),
+        None => {
+            let last = run
+                .files
+                .iter()
+                .chain(&run.started)
+                .map(|entry| entry.time)
+                .max()
+                .unwrap_or(0);
+            report_warn!(
+                "⚠️  Run never finished - it crashed, lost power or was killed after {} UTC\n",
+                format_journal_time(last)
+            );
+        }
+    }
+}
+
+pub async fn run_rollback_command(command: RollbackCommand) -> Result<()> {
+    let config = Config::from_yaml(&command.config)?;
+    let backup_dir = command
+        .backup_dir
+        .or(config.processing.backup_dir)
+        .ok_or_else(|| {
+            config_error(
+                "Rollback",
+                "No backup directory: use --backup-dir or set processing.backup_dir",
+            )
+        })?;
+    let backups = list_backups(&backup_dir)?;
+
+    if command.files.is_empty() && !command.all {
+        for backup in &backups {
+            let method = match backup.method {
+                TransferMethod::HardLinked => "hard link",
+                _ => "copy",
+            };
+            report!(
+                "🗄️  {} ({} ago, {})",
+                backup.original.display(),
+                backup.age(),
+                method
+            );
+        }
+        report!(
+            "🗄️  {} backup(s) in {}",
+            backups.len(),
+            backup_dir.display()
+        );
+        return Ok(());
+    }
+
+    // Older backups of a file stay for the next rollback
+    let mut latest: Vec<&Backup> = Vec::new();
+    for backup in backups.iter().rev() {
+        if !latest.iter().any(|b| b.original == backup.original) {
+            latest.push(backup);
+        }
+    }
+    let mut missing = 0;
+    let selected: Vec<&Backup> = if command.all {
+        latest
+    } else {
+        let mut selected = Vec::new();
+        for file in &command.files {
+            let original = resolve_target_path(file)?;
+            match latest.iter().find(|b| b.original == original) {
+                Some(backup) => selected.push(*backup),
+                None => {
+                    report_warn!("⚠️  No backup of {}", file.display());
+                    missing += 1;
+                }
+            }
+        }
+        selected
+    };
+
+    for backup in selected {
+        if command.dry_run {
+            report!(
+                "♻️  Would restore {} (backed up {} ago)",
+                backup.original.display(),
+                backup.age()
+            );
+        } else {
+            restore_backup(backup)?;
+            report!(
+                "♻️  Restored {} (backed up {} ago)",
+                backup.original.display(),
+                backup.age()
+            );
+        }
+    }
+    if missing > 0 {
+        anyhow::bail!(
+            "{} file(s) have no backup in {}",
+            missing,
+            backup_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Run an `audit` subcommand
+pub async fn run_audit_command(command: AuditCommand) -> Result<()> {
+    match command {
+        AuditCommand::Untagged {
+            input,
+            recursive,
+            output,
+            config,
+        } => {
+            let config = Config::from_yaml(&config)?;
+            let report = audit_untagged(&input, recursive, &config).await?;
+
+            for file in &report.files {
+                report!("🏷️  {}", file.file.display());
+                for track in &file.tracks {
+                    report!("    {}", track);
+                }
+            }
+            report!(
+                "🔎 {} untagged track(s) in {} of {} file(s)",
+                report.track_count(),
+                report.files.len(),
+                report.files_scanned
+            );
+            if report.files.iter().any(|file| {
+                file.tracks
+                    .iter()
+                    .any(|track| track.suggested_language.is_some())
+            }) {
+                report!(
+                    "💡 Languages marked with ? come from track titles - `fix-languages --infer` can write them"
+                );
+            }
+
+            if let Some(path) = output {
+                let json = serde_json::to_string_pretty(&Versioned::new(&report))?;
+                std::fs::write(&path, json + "\n")
+                    .with_context(|| format!("Failed to write audit report: {}", path.display()))?;
+                report!("📝 Report written to: {}", path.display());
+            }
+            if !report.failed.is_empty() {
+                anyhow::bail!("{} file(s) could not be analyzed", report.failed.len());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the active configuration. `media_kind` is the kind of the input file
+/// (None in directory mode) and limits the output to the rules that apply to it.
+pub fn print_configuration_info(config: &Config, media_kind: Option<MediaKind>) {
+    report!("\n⚙️  Configuration:");
+    if let Some(profile) = &config.active_profile {
+        report!("🎛️  Profile: {}", profile);
+    }
+    if media_kind.is_none_or(|kind| kind.filters_audio()) {
+        report!("🎵 Audio languages: {:?}", config.audio.keep_languages);
+    }
+    if config.audio.merge_external && media_kind.is_none_or(|kind| kind == MediaKind::Video) {
+        report!("🔗 Merging companion audio files (e.g. Movie.eng.mka)");
+        for (key, offset) in &config.processing.sync_offsets {
+            report!("⏱️  Sync offset: {} {}", key, offset);
+        }
+    }
+    if media_kind.is_none_or(|kind| kind.filters_subtitles()) {
+        report!(
+            "📄 Subtitle languages: {:?}",
+            config.subtitles.keep_languages
+        );
+    }
+    if config.audio.no_match_policy != NoMatchPolicy::RemoveAll
+        && media_kind.is_none_or(|kind| kind.filters_audio())
+    {
+        report!(
+            "🎵 No matching audio: {}",
+            config.audio.no_match_policy.name()
+        );
+    }
+    if let Some(min_channels) = config.audio.min_channels
+        && media_kind.is_none_or(|kind| kind.filters_audio())
+    {
+        let companion = if config.audio.keep_stereo_companion {
+            ", keeping a stereo companion"
+        } else {
+            ""
+        };
+        report!(
+            "🔊 Dropping audio with fewer than {} channels when the language has a track with that many{}",
+            min_channels,
+            companion
+        );
+    }
+    if let Some(channels) = config.audio.prefer_channels
+        && media_kind.is_none_or(|kind| kind.filters_audio())
+    {
+        report!(
+            "🔈 Keeping only {}-channel audio in languages that have it",
+            channels
+        );
+    }
+    if !config.audio.codec_preference.is_empty()
+        && media_kind.is_none_or(|kind| kind.filters_audio())
+    {
+        report!(
+            "🏅 Audio codec preference: {} (keeping {} per language)",
+            config.audio.codec_preference.join(" > "),
+            config.audio.tracks_per_language
+        );
+    }
+    if config.subtitles.no_match_policy != NoMatchPolicy::RemoveAll
+        && media_kind.is_none_or(|kind| kind.filters_subtitles())
+    {
+        report!(
+            "📄 No matching subtitles: {}",
+            config.subtitles.no_match_policy.name()
+        );
+    }
+    if config.audio.default_strategy != DefaultTrackStrategy::Preference
+        && media_kind.is_none_or(|kind| kind.filters_audio())
+    {
+        report!("🎯 Default audio: {}", config.audio.default_strategy.name());
+    }
+    if config.subtitles.default_strategy != DefaultTrackStrategy::Preference
+        && media_kind.is_none_or(|kind| kind.filters_subtitles())
+    {
+        report!(
+            "🎯 Default subtitles: {}",
+            config.subtitles.default_strategy.name()
+        );
+    }
+    if config.subtitles.keep_forced && media_kind.is_none_or(|kind| kind.filters_subtitles()) {
+        report!("🎌 Keeping forced subtitles in any language");
+    }
+    if !config.subtitles.remove_codecs.is_empty()
+        && media_kind.is_none_or(|kind| kind.filters_subtitles())
+    {
+        let condition = if config.subtitles.remove_codecs_if_text_kept {
+            " (where a text subtitle of the language is kept)"
+        } else {
+            ""
+        };
+        report!(
+            "🗑️  Removing subtitle codecs: {}{}",
+            config.subtitles.remove_codecs.join(", "),
+            condition
+        );
+    }
+    if !config.subtitles.keep_codecs.is_empty()
+        && media_kind.is_none_or(|kind| kind.filters_subtitles())
+    {
+        let condition = if config.subtitles.remove_codecs_if_text_kept {
+            " (others removed where a text subtitle of the language is kept)"
+        } else {
+            ""
+        };
+        report!(
+            "📝 Keeping only subtitle codecs: {}{}",
+            config.subtitles.keep_codecs.join(", "),
+            condition
+        );
+    }
+    if config.subtitles.fix_charset && media_kind.is_none_or(|kind| kind.filters_subtitles()) {
+        report!("🔤 Converting non-UTF-8 text subtitles");
+    }
+    if config.processing.container == ContainerFormat::Webm {
+        report!("📦 Container: WebM");
+    }
+    if config.processing.chapters == ChapterMode::Strip {
+        report!("📑 Stripping chapters");
+    }
+    if config.processing.preferred_source != AnalysisSource::Mkvmerge {
+        report!(
+            "🔬 Preferred analysis source: {}",
+            config.processing.preferred_source.name()
+        );
+    }
+    if config.processing.cross_check
+        && config.processing.preferred_source == AnalysisSource::Mkvmerge
+    {
+        report!("🔬 Cross-checking mkvmerge with ffprobe");
+    }
+    if config.processing.infer_languages {
+        report!("🔤 Inferring languages of untagged tracks from their titles");
+    }
+    if config.processing.trust_import_languages && media_kind.is_some() {
+        report!("🔤 Trusting Sonarr/Radarr media info languages over the file's tags");
+    }
+    if config.processing.import_markers && media_kind.is_some() {
+        report!("🏷️  Printing kept/removed languages for Sonarr/Radarr's script output");
+    }
+    for (index, language) in &config.processing.language_overrides {
+        report!("🏷️  Language override: stream #{} → {}", index, language);
+    }
+    if config.processing.tag_languages {
+        report!("🏷️  Writing inferred/overridden languages into the output");
+    }
+    if config.processing.flags_only {
+        report!("🚩 Flags only: keeping all streams, normalizing default/forced flags");
+    }
+    if config.processing.strict {
+        report!("🔒 Strict analysis: incomplete stream information fails the file");
+    }
+    let dropped_metadata = config.tags.dropped_metadata();
+    if !dropped_metadata.is_empty() {
+        report!("🧹 Stripped when remuxing: {}", dropped_metadata.join(", "));
+    }
+    if let Some(template) = &config.tags.title {
+        report!("📝 Segment title: {}", template);
+    }
+    let titles: Vec<String> = [
+        ("video", &config.titles.video),
+        ("audio", &config.titles.audio),
+        ("subtitles", &config.titles.subtitles),
+    ]
+    .into_iter()
+    .filter_map(|(kind, template)| {
+        template.as_ref().map(|template| match template.as_str() {
+            "" => format!("{} cleared", kind),
+            template => format!("{} '{}'", kind, template),
+        })
+    })
+    .collect();
+    if !titles.is_empty() {
+        report!("🏷️  Track titles: {}", titles.join(", "));
+    }
+    if config.processing.allow_ordered_chapters {
+        report!("🔗 Ordered chapters/linked segments: remuxed, segment UIDs preserved");
+    }
+    if config.processing.scrub {
+        report!("🕶️  Scrub: no dates, deterministic UIDs, neutral muxing app");
+    }
+    if config.processing.print_command {
+        report!("💻 mkvmerge commands: printed before each remux");
+    }
+    if let Some(mode) = config.processing.removed_manifest {
+        report!("📋 Removed-tracks manifest: {}", mode.name());
+    }
+    if config.processing.marker_tags {
+        report!("🏷️  Marker tags: written to remuxed outputs");
+    }
+    if config.processing.skip_marked {
+        report!("⏭️  Skipping remuxes of files marked with these settings");
+    }
+    for attachment in &config.attachments.add {
+        report!("📎 Attaching: {}", attachment);
+    }
+    if config.attachments.remove_cover_art {
+        report!("🖼️  Removing embedded cover art");
+    }
+    if !config.notifications.is_empty() {
+        let backends: Vec<String> = config
+            .notifications
+            .iter()
+            .map(|n| format!("{} (on {})", n.kind.name(), n.rules.on.name()))
+            .collect();
+        report!("🔔 Notifications: {}", backends.join(", "));
+    }
+    if !config.tags.template.is_empty() {
+        let names: Vec<&str> = config.tags.template.keys().map(String::as_str).collect();
+        report!("🏷️  Tag template: {}", names.join(", "));
+    }
+    if let Some(mode) = config.processing.output_mode {
+        report!("🔐 Output mode: {}", mode);
+    }
+    if let Some(owner) = &config.processing.output_owner {
+        if owner == OWNER_OF_TARGET {
+            report!("👤 Output owner: same as the target directory");
+        } else {
+            report!("👤 Output owner: {}", owner);
+        }
+    }
+    if let Some(group) = &config.processing.output_group {
+        report!("👥 Output group: {}", group);
+    }
+    // Only batch runs use the template; single files go to the given target
+    if let Some(template) = &config.processing.target_template
+        && media_kind.is_none()
+    {
+        report!("🗂️  Target template: {}", template);
+    }
+    if let Some(minutes) = config.processing.skip_samples
+        && media_kind.is_none()
+    {
+        report!("🎞️  Skipping samples under {} minutes", minutes);
+    }
+    if let Some(bytes) = config.processing.free_up
+        && media_kind.is_none()
+    {
+        report!(
+            "🧹 Free-up target: {} (largest savings first)",
+            format_size(bytes)
+        );
+    }
+    if let Some(warning) = &config.processing.throughput_warning {
+        report!(
+            "🐢 Throughput warning: below {} MB/s for {}s",
+            warning.min_mb_per_sec,
+            warning.sustained_secs
+        );
+    }
+    if let Some(floor) = config.processing.min_savings {
+        report!("📉 Minimum savings: {}", floor);
+    }
+    if let Some(seconds) = config.processing.duration_tolerance_secs {
+        report!("⏱️  Duration tolerance: {}s", seconds);
+    }
+    if let Some(log_dir) = &config.processing.log_dir {
+        report!("📝 mkvmerge logs: {}", log_dir.display());
+    }
+    if let Some(backup_dir) = &config.processing.backup_dir {
+        report!("🗄️  Backups: {}", backup_dir.display());
+    }
+    if let Some(history_db) = &config.processing.history_db {
+        report!("📚 History: {}", history_db.display());
+        if config.processing.skip_processed {
+            report!("⏭️  Skipping files already processed with these settings");
+        }
+    }
+    if let Some(journal) = &config.processing.journal {
+        report!("🧾 Journal: {}", journal.display());
+    }
+    if let Some(options_dir) = &config.processing.options_dir
+        && config.processing.dry_run
+    {
+        report!("📄 mkvmerge options files: {}", options_dir.display());
+    }
     if config.processing.dry_run {
-        println!("🔍 Mode: Dry run (no files will be modified)");
+        report!("🔍 Mode: Dry run (no files will be modified)");
     } else {
-        println!("💾 Mode: Live processing");
+        report!("💾 Mode: Live processing");
     }
-    println!(
-        "ℹ️  Note: Video streams and attachments are always kept\n{}",
-        "     Forced subtitles are not automatically preserved".dimmed()
-    );
-    println!();
+    let tools: Vec<String> = detect_tools().iter().map(ToString::to_string).collect();
+    report!("🧰 Tools: {}", tools.join(", "));
+    if media_kind == Some(MediaKind::AudioOnly) {
+        report!("ℹ️  Note: Audio-only container - only audio tracks are filtered");
+    } else if media_kind == Some(MediaKind::SubtitlesOnly) {
+        report!("ℹ️  Note: Subtitle-only container - only subtitle tracks are filtered");
+        if config.subtitles.split_by_language {
+            report!("✂️  Splitting: one output per subtitle language");
+        }
+    } else {
+        report!(
+            "ℹ️  Note: Video streams and attachments are always kept\n{}",
+            "     Forced subtitles are not automatically preserved".dimmed()
+        );
+    }
+    report!();
 }
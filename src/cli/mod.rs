@@ -1,6 +1,17 @@
 pub mod args;
 pub mod commands;
+pub mod progress;
 
+pub use args::{
+    AnalyzeCommand, AuditCommand, ChaptersCommand, CliCommand, FixLanguagesCommand, FontsCommand,
+    HistoryCommand, InspectCommand, JournalCommand, ProcessManifestCommand, RetryFailedCommand,
+    RollbackCommand, ScanCommand, SchemaCommand, TagsCommand, create_app,
+};
 pub use commands::{
     ProcessingSettings, TargetType, prepare_processing_settings, print_configuration_info,
+    run_analyze_command, run_audit_command, run_chapters_command, run_fix_languages_command,
+    run_fonts_command, run_history_command, run_inspect_command, run_journal_command,
+    run_process_manifest_command, run_retry_failed_command, run_rollback_command, run_scan_command,
+    run_schema_command, run_tags_command, write_result_json,
 };
+pub use progress::{cancel_on_ctrl_c, remux_progress_bar};
@@ -0,0 +1,98 @@
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::core::{CancellationToken, ProgressEvent};
+use crate::report;
+use crate::utils::{ConsoleMode, console_mode, format_size};
+
+/// Progress callback drawing a bar while mkvmerge remuxes or the source is
+/// copied to its target (with the bytes copied, speed and time left)
+///
+/// A bar is created when a remux or copy starts and cleared once it is
+/// complete, so the regular status lines printed around it stay readable.
+/// Nothing is drawn when stdout is not a terminal or the console isn't in
+/// human mode.
+pub fn remux_progress_bar() -> impl Fn(ProgressEvent) + Send + Sync + 'static {
+    let interactive = atty::is(atty::Stream::Stdout) && console_mode() == ConsoleMode::Human;
+    let current: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+    move |event| {
+        if !interactive {
+            return;
+        }
+        let Ok(mut current) = current.lock() else {
+            return;
+        };
+
+        match event {
+            ProgressEvent::Remuxing { percent } => {
+                let bar = current.get_or_insert_with(|| {
+                    let bar = ProgressBar::new(100);
+                    if let Ok(style) =
+                        ProgressStyle::with_template("🔧 Remuxing [{bar:40.cyan/blue}] {pos:>3}%")
+                    {
+                        bar.set_style(style.progress_chars("=> "));
+                    }
+                    bar
+                });
+                bar.set_position(u64::from(percent));
+                if percent >= 100
+                    && let Some(bar) = current.take()
+                {
+                    bar.finish_and_clear();
+                }
+            }
+            ProgressEvent::Copying { copied, total } => {
+                let bar = current.get_or_insert_with(|| copy_bar(total));
+                bar.set_position(copied);
+                if copied >= total
+                    && let Some(bar) = current.take()
+                {
+                    bar.finish_and_clear();
+                }
+            }
+            ProgressEvent::Finished => {
+                if let Some(bar) = current.take() {
+                    bar.finish_and_clear();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Bar of a `total`-byte copy; sizes follow the configured size units
+fn copy_bar(total: u64) -> ProgressBar {
+    let bar = ProgressBar::new(total);
+    if let Ok(style) = ProgressStyle::with_template(
+        "📋 Copying [{bar:25.cyan/blue}] {copied}/{total} {speed} ETA {eta}",
+    ) {
+        let style = style
+            .with_key("copied", |state: &ProgressState, w: &mut dyn Write| {
+                let _ = write!(w, "{}", format_size(state.pos()));
+            })
+            .with_key("total", |state: &ProgressState, w: &mut dyn Write| {
+                let _ = write!(w, "{}", format_size(state.len().unwrap_or_default()));
+            })
+            .with_key("speed", |state: &ProgressState, w: &mut dyn Write| {
+                let _ = write!(w, "{}/s", format_size(state.per_sec() as u64));
+            });
+        bar.set_style(style.progress_chars("=> "));
+    }
+    bar
+}
+
+/// Cancellation token that is cancelled on Ctrl-C, letting a running remux
+/// stop cleanly instead of leaving a partial output behind
+pub fn cancel_on_ctrl_c() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            report!("\n⏹️  Cancelling...");
+            cancel.cancel();
+        }
+    });
+    token
+}
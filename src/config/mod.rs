@@ -1,4 +1,15 @@
 pub mod preferences;
+pub mod saved;
 pub mod settings;
 
-pub use settings::Config;
+pub use preferences::{
+    AnalysisSource, AttachmentConfig, AudioConfig, ChapterMode, ContainerFormat,
+    DefaultTrackStrategy, FileMode, NoMatchPolicy, NotificationRules, NotifierConfig, NotifierKind,
+    NotifyOn, RemovedManifest, SavingsFloor, SubtitleConfig, SubtitlePreference, SyncOffset,
+    TagConfig, ThroughputConfig, TitleConfig,
+};
+pub use saved::{
+    apply_saved_settings, file_settings_path, offer_to_save_choices, series_directory,
+    settings_files_for,
+};
+pub use settings::{CliOverrides, Config, DIRECTORY_CONFIG_FILE};
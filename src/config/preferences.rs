@@ -1,6 +1,10 @@
 use crate::error::config_error;
+use crate::models::{StreamType, TrackRef};
+use crate::utils::{format_size, normalize_language, parse_size};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SubtitlePreference {
@@ -13,7 +17,7 @@ impl SubtitlePreference {
     /// Format: "language" or "language, title prefix"
     pub fn parse(s: &str) -> Result<Self> {
         if let Some((lang, title)) = s.split_once(',') {
-            let language = lang.trim().to_string();
+            let language = normalize_language(lang);
             let title_prefix = title.trim().to_string();
 
             if language.is_empty() {
@@ -38,7 +42,7 @@ impl SubtitlePreference {
                 title_prefix,
             })
         } else {
-            let language = s.trim().to_string();
+            let language = normalize_language(s);
             if language.is_empty() {
                 return Err(config_error(
                     "Subtitle language preference",
@@ -93,13 +97,54 @@ impl SubtitlePreference {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioConfig {
+    #[serde(deserialize_with = "deserialize_languages")]
     pub keep_languages: Vec<String>,
+    /// Merge companion audio files (`Movie.eng.mka` next to `Movie.mkv`) into the output
+    #[serde(default)]
+    pub merge_external: bool,
+    /// What to do when no audio track matches `keep_languages`
+    #[serde(default)]
+    pub no_match_policy: NoMatchPolicy,
+    /// Remove kept tracks with fewer channels when a track of the same
+    /// language has at least this many (stereo downmixes of a 5.1 track)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_channels: Option<u32>,
+    /// With `min_channels`, still keep one stereo track per language next to
+    /// the multichannel one, for devices that can't downmix
+    #[serde(default)]
+    pub keep_stereo_companion: bool,
+    /// Keep only the tracks with exactly this many channels in languages
+    /// that have one (e.g. `2` for stereo on a small TV)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefer_channels: Option<u32>,
+    /// How the kept audio track flagged as default is chosen
+    #[serde(default)]
+    pub default_strategy: DefaultTrackStrategy,
+    /// Audio formats from best to worst (`truehd`, `dts-hd`, `eac3`, ...);
+    /// when a kept language has more tracks, only the best-ranked stay
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codec_preference: Vec<String>,
+    /// With `codec_preference`, how many tracks of each language are kept
+    #[serde(default = "default_tracks_per_language")]
+    pub tracks_per_language: u32,
+}
+
+fn default_tracks_per_language() -> u32 {
+    1
 }
 
 impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             keep_languages: vec!["eng".to_string(), "jpn".to_string(), "und".to_string()],
+            merge_external: false,
+            no_match_policy: NoMatchPolicy::RemoveAll,
+            min_channels: None,
+            keep_stereo_companion: false,
+            prefer_channels: None,
+            default_strategy: DefaultTrackStrategy::Preference,
+            codec_preference: Vec::new(),
+            tracks_per_language: default_tracks_per_language(),
         }
     }
 }
@@ -111,6 +156,43 @@ pub struct SubtitleConfig {
         deserialize_with = "deserialize_preferences"
     )]
     pub keep_languages: Vec<SubtitlePreference>,
+    /// Write one output per subtitle language when processing subtitle-only (.mks) files
+    #[serde(default)]
+    pub split_by_language: bool,
+    /// Check kept text subtitles for legacy (non-UTF-8) encodings and have
+    /// mkvmerge convert them
+    #[serde(default)]
+    pub fix_charset: bool,
+    /// Charset to convert non-UTF-8 subtitles from, by language code
+    /// (defaults to the usual legacy codepage of the language)
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_language_keys"
+    )]
+    pub charsets: BTreeMap<String, String>,
+    /// What to do when no subtitle track matches `keep_languages`
+    #[serde(default)]
+    pub no_match_policy: NoMatchPolicy,
+    /// Keep subtitles flagged as forced whatever their language, with the
+    /// forced flag intact, so foreign-dialogue scenes stay readable
+    #[serde(default)]
+    pub keep_forced: bool,
+    /// Subtitle codecs (ffprobe names, e.g. `hdmv_pgs_subtitle`) removed
+    /// regardless of language
+    #[serde(default, alias = "drop_formats", skip_serializing_if = "Vec::is_empty")]
+    pub remove_codecs: Vec<String>,
+    /// The only subtitle codecs kept (e.g. `subrip`, `ass`); others are
+    /// removed regardless of language. Empty keeps every codec
+    #[serde(default, alias = "keep_formats", skip_serializing_if = "Vec::is_empty")]
+    pub keep_codecs: Vec<String>,
+    /// Only remove `remove_codecs` (and unlisted `keep_codecs`) tracks when a
+    /// text subtitle of the same language is kept
+    #[serde(default)]
+    pub remove_codecs_if_text_kept: bool,
+    /// How the kept subtitle track flagged as default is chosen
+    #[serde(default)]
+    pub default_strategy: DefaultTrackStrategy,
 }
 
 // Custom serialization to maintain backward compatibility
@@ -145,6 +227,52 @@ where
         .collect()
 }
 
+// Language codes are stored in ISO 639-2/B form whichever form the config uses
+fn deserialize_languages<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let codes: Vec<String> = Vec::deserialize(deserializer)?;
+    Ok(codes.iter().map(|code| normalize_language(code)).collect())
+}
+
+fn deserialize_language_keys<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let map: BTreeMap<String, String> = BTreeMap::deserialize(deserializer)?;
+    Ok(map
+        .into_iter()
+        .map(|(code, value)| (normalize_language(&code), value))
+        .collect())
+}
+
+fn deserialize_language_values<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<TrackRef, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let map: BTreeMap<TrackRef, String> = BTreeMap::deserialize(deserializer)?;
+    Ok(map
+        .into_iter()
+        .map(|(track, code)| (track, normalize_language(&code)))
+        .collect())
+}
+
+fn deserialize_language_aliases<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let map: BTreeMap<String, String> = BTreeMap::deserialize(deserializer)?;
+    Ok(map
+        .into_iter()
+        .map(|(alias, code)| (alias.trim().to_lowercase(), normalize_language(&code)))
+        .collect())
+}
+
 impl Default for SubtitleConfig {
     fn default() -> Self {
         Self {
@@ -158,17 +286,899 @@ impl Default for SubtitleConfig {
                     title_prefix: None,
                 },
             ],
+            split_by_language: false,
+            fix_charset: false,
+            charsets: BTreeMap::new(),
+            keep_forced: false,
+            remove_codecs: Vec::new(),
+            keep_codecs: Vec::new(),
+            remove_codecs_if_text_kept: false,
+            no_match_policy: NoMatchPolicy::RemoveAll,
+            default_strategy: DefaultTrackStrategy::Preference,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Container format written by mkvmerge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerFormat {
+    /// Regular Matroska output
+    #[default]
+    Mkv,
+    /// WebM output (`mkvmerge --webm`), only possible for VP8/VP9/AV1, Opus/Vorbis and WebVTT tracks
+    Webm,
+}
+
+impl ContainerFormat {
+    /// File extension used for outputs in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContainerFormat::Mkv => "mkv",
+            ContainerFormat::Webm => "webm",
+        }
+    }
+}
+
+impl std::str::FromStr for ContainerFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "mkv" | "matroska" => Ok(ContainerFormat::Mkv),
+            "webm" => Ok(ContainerFormat::Webm),
+            _ => Err(config_error(
+                "Container format",
+                &format!("Unknown container '{}'. Use 'mkv' or 'webm'", s),
+            )),
+        }
+    }
+}
+
+/// What happens to the chapters of the source during a remux
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChapterMode {
+    /// Carry the chapters over to the output
+    #[default]
+    Keep,
+    /// Leave them out (`mkvmerge --no-chapters`); files with chapters are
+    /// remuxed even when all streams are kept
+    Strip,
+}
+
+impl ChapterMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChapterMode::Keep => "keep",
+            ChapterMode::Strip => "strip",
+        }
+    }
+}
+
+impl std::str::FromStr for ChapterMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(ChapterMode::Keep),
+            "strip" => Ok(ChapterMode::Strip),
+            _ => Err(config_error(
+                "Chapters",
+                &format!("Unknown chapter mode '{}'. Use 'keep' or 'strip'", s),
+            )),
+        }
+    }
+}
+
+/// Where `processing.removed_manifest` records the tracks a remux removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovedManifest {
+    /// `<output>.removed.json` next to the output
+    Sidecar,
+    /// A global tag of the output, which survives renames
+    Tag,
+}
+
+impl RemovedManifest {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RemovedManifest::Sidecar => "sidecar",
+            RemovedManifest::Tag => "tag",
+        }
+    }
+}
+
+impl std::str::FromStr for RemovedManifest {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sidecar" => Ok(RemovedManifest::Sidecar),
+            "tag" => Ok(RemovedManifest::Tag),
+            _ => Err(config_error(
+                "Removed manifest",
+                &format!("Unknown manifest mode '{}'. Use 'sidecar' or 'tag'", s),
+            )),
+        }
+    }
+}
+
+/// How the default track of a type is picked among the kept tracks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultTrackStrategy {
+    /// The first track in the most preferred language of `keep_languages`
+    /// (audio falls back to the first kept track)
+    #[default]
+    Preference,
+    /// A track in the original language of the movie or series (reported by
+    /// Sonarr/Radarr, else that of the source's first audio track), falling
+    /// back to `Preference`
+    Original,
+    /// No track of the type is flagged as default
+    None,
+}
+
+impl DefaultTrackStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DefaultTrackStrategy::Preference => "preference",
+            DefaultTrackStrategy::Original => "original",
+            DefaultTrackStrategy::None => "none",
+        }
+    }
+}
+
+impl std::str::FromStr for DefaultTrackStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "preference" => Ok(DefaultTrackStrategy::Preference),
+            "original" => Ok(DefaultTrackStrategy::Original),
+            "none" => Ok(DefaultTrackStrategy::None),
+            _ => Err(config_error(
+                "Default track strategy",
+                &format!(
+                    "Unknown strategy '{}'. Use 'preference', 'original' or 'none'",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// What to do when none of a file's tracks of a type match the language preferences
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoMatchPolicy {
+    /// Remove them all
+    #[default]
+    RemoveAll,
+    /// Keep the first track of the type
+    KeepFirst,
+    /// Keep every track of the type
+    KeepAll,
+    /// Fail the file (unless `--force`)
+    Fail,
+}
+
+impl NoMatchPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoMatchPolicy::RemoveAll => "remove_all",
+            NoMatchPolicy::KeepFirst => "keep_first",
+            NoMatchPolicy::KeepAll => "keep_all",
+            NoMatchPolicy::Fail => "fail",
+        }
+    }
+}
+
+impl std::str::FromStr for NoMatchPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "remove_all" => Ok(NoMatchPolicy::RemoveAll),
+            "keep_first" => Ok(NoMatchPolicy::KeepFirst),
+            "keep_all" => Ok(NoMatchPolicy::KeepAll),
+            "fail" => Ok(NoMatchPolicy::Fail),
+            _ => Err(config_error(
+                "No-match policy",
+                &format!(
+                    "Unknown policy '{}'. Use 'remove_all', 'keep_first', 'keep_all' or 'fail'",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// Analyzer that defines the streams. `mkvmerge -J` numbers tracks the way
+/// mkvmerge selects them and lists attachments separately, so it is the
+/// default and ffprobe only runs when it can't identify a file; with ffprobe
+/// preferred both run and ffprobe's view wins where they disagree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisSource {
+    Ffprobe,
+    #[default]
+    Mkvmerge,
+}
+
+impl AnalysisSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnalysisSource::Ffprobe => "ffprobe",
+            AnalysisSource::Mkvmerge => "mkvmerge",
+        }
+    }
+}
+
+impl std::str::FromStr for AnalysisSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ffprobe" => Ok(AnalysisSource::Ffprobe),
+            "mkvmerge" => Ok(AnalysisSource::Mkvmerge),
+            _ => Err(config_error(
+                "Analysis source",
+                &format!("Unknown source '{}'. Use 'ffprobe' or 'mkvmerge'", s),
+            )),
+        }
+    }
+}
+
+/// Delay applied to an external track via mkvmerge `--sync`
+///
+/// Parsed from `+250ms`, `-1.5s` or a plain number of milliseconds.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::SyncOffset;
+///
+/// assert_eq!("+250ms".parse::<SyncOffset>().unwrap().milliseconds, 250);
+/// assert_eq!("-1.5s".parse::<SyncOffset>().unwrap().milliseconds, -1500);
+/// assert_eq!("40".parse::<SyncOffset>().unwrap().milliseconds, 40);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawSyncOffset", into = "String")]
+pub struct SyncOffset {
+    pub milliseconds: i64,
+}
+
+/// Offsets may be written as strings (`+250ms`) or bare numbers in YAML
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSyncOffset {
+    Milliseconds(i64),
+    Text(String),
+}
+
+impl TryFrom<RawSyncOffset> for SyncOffset {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawSyncOffset) -> Result<Self> {
+        match raw {
+            RawSyncOffset::Milliseconds(milliseconds) => Ok(SyncOffset { milliseconds }),
+            RawSyncOffset::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<SyncOffset> for String {
+    fn from(offset: SyncOffset) -> Self {
+        offset.to_string()
+    }
+}
+
+impl std::fmt::Display for SyncOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+}ms", self.milliseconds)
+    }
+}
+
+impl std::str::FromStr for SyncOffset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let (number, scale) = if let Some(number) = trimmed.strip_suffix("ms") {
+            (number, 1.0)
+        } else if let Some(number) = trimmed.strip_suffix('s') {
+            (number, 1000.0)
+        } else {
+            (trimmed, 1.0)
+        };
+
+        let value: f64 = number.trim().parse().map_err(|_| {
+            config_error(
+                "Sync offset",
+                &format!("Invalid offset '{}'. Use e.g. '+250ms', '-1.5s' or '40'", s),
+            )
+        })?;
+
+        Ok(SyncOffset {
+            milliseconds: (value * scale).round() as i64,
+        })
+    }
+}
+
+/// Permission bits applied to outputs, written in octal (`0640`)
+///
+/// Directories get the execute bit wherever the mode grants read access.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::FileMode;
+///
+/// let mode: FileMode = "0640".parse().unwrap();
+/// assert_eq!(mode.bits, 0o640);
+/// assert_eq!(mode.directory_bits(), 0o750);
+/// assert_eq!(mode.to_string(), "0640");
+/// assert!("0999".parse::<FileMode>().is_err());
+///
+/// let bare: FileMode = serde_yaml::from_str("0640").unwrap();
+/// assert_eq!(bare, mode);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawFileMode", into = "String")]
+pub struct FileMode {
+    pub bits: u32,
+}
+
+impl FileMode {
+    /// Mode for created directories: `bits` plus search permission for
+    /// every class that may read
+    pub fn directory_bits(&self) -> u32 {
+        let readable = self.bits & 0o444;
+        self.bits | (readable >> 2)
+    }
+}
+
+impl std::str::FromStr for FileMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let digits = s.trim();
+        let digits = digits.strip_prefix("0o").unwrap_or(digits);
+        match u32::from_str_radix(digits, 8) {
+            Ok(bits) if bits <= 0o7777 => Ok(FileMode { bits }),
+            _ => Err(config_error(
+                "Output mode",
+                &format!("Invalid mode '{}'. Use octal permissions such as '0640'", s),
+            )),
+        }
+    }
+}
+
+/// Modes may be quoted (`"0640"`) or bare (`0640`), which YAML reads as the
+/// decimal number 640; its digits are taken as octal either way
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFileMode {
+    Number(u32),
+    Text(String),
+}
+
+impl TryFrom<RawFileMode> for FileMode {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawFileMode) -> Result<Self> {
+        match raw {
+            RawFileMode::Number(number) => number.to_string().parse(),
+            RawFileMode::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<FileMode> for String {
+    fn from(mode: FileMode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl std::fmt::Display for FileMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04o}", self.bits)
+    }
+}
+
+/// Smallest saving a remux must achieve for its output to replace the
+/// original: a size (`500MB`, `1.5GB`, bare bytes), a share of the source
+/// (`5%`) or both (`500MB, 5%`: the saving must exceed each)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::SavingsFloor;
+///
+/// let floor: SavingsFloor = "100MB".parse().unwrap();
+/// assert_eq!(floor, SavingsFloor::Bytes(100 * 1024 * 1024));
+/// assert!(floor.is_met(1 << 30, 500 * 1024 * 1024));
+/// assert!(!floor.is_met(1 << 30, (1 << 30) - 1024));
+///
+/// let share: SavingsFloor = "10%".parse().unwrap();
+/// assert!(share.is_met(1000, 850));
+/// assert!(!share.is_met(1000, 950));
+///
+/// let both: SavingsFloor = "100MB, 10%".parse().unwrap();
+/// assert_eq!(both, SavingsFloor::Both(100 * 1024 * 1024, 10.0));
+/// assert!(both.is_met(2 << 30, 1 << 30));
+/// // 150 MB saved: more than 100 MB, but not 10% of 2 GB
+/// assert!(!both.is_met(2 << 30, (2 << 30) - 150 * 1024 * 1024));
+/// assert!("lots".parse::<SavingsFloor>().is_err());
+/// assert!("5%, 10%".parse::<SavingsFloor>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SavingsFloor {
+    Bytes(u64),
+    Percent(f64),
+    /// A size and a share, both of which must be exceeded
+    Both(u64, f64),
+}
+
+impl SavingsFloor {
+    /// Whether shrinking `source_bytes` to `output_bytes` saves more than the floor
+    pub fn is_met(&self, source_bytes: u64, output_bytes: u64) -> bool {
+        let saved = source_bytes.saturating_sub(output_bytes);
+        match *self {
+            SavingsFloor::Bytes(bytes) => saved > bytes,
+            SavingsFloor::Percent(percent) => saved as f64 * 100.0 > percent * source_bytes as f64,
+            SavingsFloor::Both(bytes, percent) => {
+                SavingsFloor::Bytes(bytes).is_met(source_bytes, output_bytes)
+                    && SavingsFloor::Percent(percent).is_met(source_bytes, output_bytes)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for SavingsFloor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            config_error(
+                "Savings floor",
+                &format!(
+                    "Invalid floor '{}'. Use a size like '500MB', a share like '5%' or both ('500MB, 5%')",
+                    s
+                ),
+            )
+        };
+
+        if let Some((first, second)) = s.split_once(',') {
+            let part = |text: &str| text.parse::<SavingsFloor>().map_err(|_| invalid());
+            return match (part(first)?, part(second)?) {
+                (SavingsFloor::Bytes(bytes), SavingsFloor::Percent(percent))
+                | (SavingsFloor::Percent(percent), SavingsFloor::Bytes(bytes)) => {
+                    Ok(SavingsFloor::Both(bytes, percent))
+                }
+                _ => Err(invalid()),
+            };
+        }
+
+        let trimmed = s.trim();
+        if let Some(number) = trimmed.strip_suffix('%') {
+            let percent: f64 = number.trim().parse().map_err(|_| invalid())?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(invalid());
+            }
+            return Ok(SavingsFloor::Percent(percent));
+        }
+
+        parse_size(trimmed)
+            .map(SavingsFloor::Bytes)
+            .map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for SavingsFloor {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        text.parse()
+    }
+}
+
+impl From<SavingsFloor> for String {
+    fn from(floor: SavingsFloor) -> Self {
+        floor.to_string()
+    }
+}
+
+impl std::fmt::Display for SavingsFloor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SavingsFloor::Bytes(bytes) => write!(f, "{}", format_size(*bytes)),
+            SavingsFloor::Percent(percent) => write!(f, "{}%", percent),
+            SavingsFloor::Both(bytes, percent) => {
+                write!(f, "{}, {}%", format_size(*bytes), percent)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessingConfig {
     pub dry_run: bool,
+    /// Output container format
+    #[serde(default)]
+    pub container: ContainerFormat,
+    /// Keep or strip the chapters of the source
+    #[serde(default)]
+    pub chapters: ChapterMode,
+    /// Delays for merged external tracks, keyed by external filename
+    /// (`Movie.eng.mka`) or language code (`eng`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sync_offsets: BTreeMap<String, SyncOffset>,
+    /// Keep processing metadata (date, random UIDs, muxing/writing app) out of
+    /// remuxed outputs so reruns are byte-identical
+    #[serde(default)]
+    pub scrub: bool,
+    /// Print the shell-quoted mkvmerge command line before each remux (dry
+    /// runs always print it)
+    #[serde(default)]
+    pub print_command: bool,
+    /// Remux files with ordered chapters or linked segments instead of
+    /// failing; their segment UIDs are carried over
+    #[serde(default)]
+    pub allow_ordered_chapters: bool,
+    /// Directory where the full mkvmerge output of every remux is saved
+    /// (`<output filename>.mkvmerge.log`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_dir: Option<PathBuf>,
+    /// In dry-run mode, directory where the would-be mkvmerge command of every
+    /// remux is saved as an options file (`<output filename>.options.json`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options_dir: Option<PathBuf>,
+    /// Directory where originals are saved before a move, before an existing
+    /// output is replaced and before in-place edits; `rollback` restores them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<PathBuf>,
+    /// SQLite database recording every processed file (source, output,
+    /// removed tracks, bytes saved, settings hash); see `history`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_db: Option<PathBuf>,
+    /// Append-only journal of batch runs: one line per state transition of
+    /// every file, synced to disk, to see with `journal show` what a crashed
+    /// run was doing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal: Option<PathBuf>,
+    /// Leave out of batches the files `history_db` records as processed,
+    /// unchanged since and with the same output-shaping settings
+    #[serde(default)]
+    pub skip_processed: bool,
+    /// Write mkv-slimmer marker tags (version, date, settings hash) into the
+    /// global tags of remuxed outputs
+    #[serde(default)]
+    pub marker_tags: bool,
+    /// Record the tracks each remux removed (type, language, codec, title,
+    /// size) in a sidecar file or a global tag of the output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_manifest: Option<RemovedManifest>,
+    /// Transfer files whose marker tags say they were slimmed with the same
+    /// output-shaping settings instead of remuxing them again
+    #[serde(default)]
+    pub skip_marked: bool,
+    /// Process despite safety checks: degenerate outputs (no video or audio
+    /// left, foreign audio without subtitles) and batches whose projected
+    /// output exceeds the target's free space
+    #[serde(default)]
+    pub force: bool,
+    /// When a batch doesn't fit the target's free space, process the subset
+    /// of files that fills it best and skip the rest
+    #[serde(default)]
+    pub fill_space: bool,
+    /// Fail a file whose analysis had to guess (unparsable ffprobe output,
+    /// Unknown stream types, audio without a language tag) instead of
+    /// processing it best-effort
+    #[serde(default)]
+    pub strict: bool,
+    /// Take the language of untagged ("und") audio and subtitle tracks from
+    /// language names in their titles before matching
+    #[serde(default)]
+    pub infer_languages: bool,
+    /// When Sonarr/Radarr report one language per audio or subtitle track
+    /// and the analysis disagrees, use the reported languages for matching
+    #[serde(default)]
+    pub trust_import_languages: bool,
+    /// When run by Sonarr/Radarr, print `[MkvSlimmer]` lines naming the kept
+    /// and removed audio/subtitle languages, which end up in the script
+    /// output the import logs
+    #[serde(default)]
+    pub import_markers: bool,
+    /// Languages set by hand, keyed by stream index or `uid:<track UID>`;
+    /// they replace whatever the file's tags (or title inference) said
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_language_values"
+    )]
+    pub language_overrides: BTreeMap<TrackRef, String>,
+    /// Non-standard language tags (in files or this config) with the ISO
+    /// 639-2 code they stand for, e.g. `latino: spa`
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_language_aliases"
+    )]
+    pub language_aliases: BTreeMap<String, String>,
+    /// Write inferred and overridden languages into the output as track
+    /// language tags (`--language TID:lang`), remuxing if needed
+    #[serde(default)]
+    pub tag_languages: bool,
+    /// Keep every stream and only normalize default/forced flags per the
+    /// language preferences, editing a copy of the source with mkvpropedit
+    /// instead of remuxing
+    #[serde(default)]
+    pub flags_only: bool,
+    /// Permissions set on written outputs and created directories; unset
+    /// leaves them to the umask (copies keep the source's permissions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<FileMode>,
+    /// Group (name or numeric ID) given to written outputs and created directories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_group: Option<String>,
+    /// Owner of written outputs and created directories: `target` for the
+    /// owner and group of the directory they're written to, or `USER[:GROUP]`
+    /// (names or numeric IDs). Changing the owner needs root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_owner: Option<String>,
+    /// Warn when remuxes or copies run slower than a minimum rate for a while
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throughput_warning: Option<ThroughputConfig>,
+    /// Minimum saving of a remux. Files whose removed streams are estimated
+    /// to save less aren't remuxed; when the estimate passes, the real saving
+    /// is checked again and a smaller one discards the output. Either way the
+    /// original is transferred to the target instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_savings: Option<SavingsFloor>,
+    /// Seconds the container or a track of a remuxed output may run shorter
+    /// or longer than in the source before a warning (default 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_tolerance_secs: Option<f64>,
+    /// Analyzer that defines the streams (mkvmerge, with ffprobe as fallback,
+    /// unless ffprobe is preferred)
+    #[serde(default)]
+    pub preferred_source: AnalysisSource,
+    /// Run ffprobe next to mkvmerge even when mkvmerge is preferred, so
+    /// disagreements between them are reported
+    #[serde(default)]
+    pub cross_check: bool,
+    /// Output path of batch files below the target directory, built from
+    /// `{relative_dir}`, `{filename}` and `{lang_summary}`; unset mirrors the source tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_template: Option<String>,
+    /// Leave samples out of batches: files shorter than this many minutes
+    /// that are named like a sample or sit next to a much longer file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_samples: Option<u64>,
+    /// Stop a batch once its remuxes have saved this many bytes, processing
+    /// the files with the most removable data first
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_size"
+    )]
+    pub free_up: Option<u64>,
 }
 
-impl Default for ProcessingConfig {
-    fn default() -> Self {
-        Self { dry_run: false }
+/// A size given as a byte count or a string like `500G`
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawSize {
+        Bytes(u64),
+        Text(String),
     }
+
+    match Option::<RawSize>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(RawSize::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(RawSize::Text(text)) => parse_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+/// Sustained slow remux/copy detection (`processing.throughput_warning`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputConfig {
+    /// Rate in MB/s below which storage counts as degraded
+    pub min_mb_per_sec: f64,
+    /// How long the rate has to stay below the minimum before warning
+    #[serde(default = "default_sustained_secs")]
+    pub sustained_secs: u64,
+    /// Shell command run on a warning, with the message in `$MKV_SLIMMER_WARNING`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_command: Option<String>,
+}
+
+fn default_sustained_secs() -> u64 {
+    60
+}
+
+impl ThroughputConfig {
+    /// Warn below `min_mb_per_sec` sustained for the default 60 seconds, without notification
+    pub fn new(min_mb_per_sec: f64) -> Self {
+        Self {
+            min_mb_per_sec,
+            sustained_secs: default_sustained_secs(),
+            notify_command: None,
+        }
+    }
+}
+
+/// Matroska tag and title handling during the remux
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagConfig {
+    /// Drop the global tags of the source file
+    #[serde(default)]
+    pub strip_global: bool,
+    /// Drop the tags of the source's tracks (including mkvmerge's track
+    /// statistics tags, which are written anew)
+    #[serde(default)]
+    pub strip_track: bool,
+    /// Clear the segment title of the output
+    #[serde(default)]
+    pub clear_title: bool,
+    /// Segment title to write, from Sonarr/Radarr metadata such as
+    /// `{series_title} – S{season}E{episode} – {episode_title}`. When a
+    /// placeholder can't be filled the title is left as it is (or cleared
+    /// with `clear_title`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Global tags to write, by tag name. Values may reference Sonarr metadata
+    /// such as `{series_title}`, `{season}`, `{episode}` or `{episode_title}`;
+    /// tags whose placeholders can't be filled are skipped.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub template: BTreeMap<String, String>,
+}
+
+impl TagConfig {
+    /// The source metadata a remux leaves out, for display
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::config::TagConfig;
+    ///
+    /// let tags = TagConfig {
+    ///     strip_global: true,
+    ///     clear_title: true,
+    ///     ..TagConfig::default()
+    /// };
+    /// assert_eq!(tags.dropped_metadata(), vec!["global tags", "segment title"]);
+    /// assert!(TagConfig::default().dropped_metadata().is_empty());
+    /// ```
+    pub fn dropped_metadata(&self) -> Vec<&'static str> {
+        [
+            (self.strip_global, "global tags"),
+            (self.strip_track, "track tags"),
+            (self.clear_title, "segment title"),
+        ]
+        .into_iter()
+        .filter_map(|(dropped, name)| dropped.then_some(name))
+        .collect()
+    }
+}
+
+/// Track titles written to remuxed outputs, per stream type. A template
+/// rewrites the titles of kept tracks of its type from `{placeholders}`
+/// (see `core::titles`), an empty one clears them; unset keeps them as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TitleConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitles: Option<String>,
+}
+
+impl TitleConfig {
+    /// The template for streams of `stream_type`, if titles of that type are rewritten
+    pub fn template(&self, stream_type: StreamType) -> Option<&str> {
+        match stream_type {
+            StreamType::Video => self.video.as_deref(),
+            StreamType::Audio => self.audio.as_deref(),
+            StreamType::Subtitle => self.subtitles.as_deref(),
+            StreamType::Attachment | StreamType::Unknown => None,
+        }
+    }
+
+    /// Whether any track titles are rewritten
+    pub fn is_active(&self) -> bool {
+        self.video.is_some() || self.audio.is_some() || self.subtitles.is_some()
+    }
+}
+
+/// Files attached to remuxed outputs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttachmentConfig {
+    /// Paths of files to attach (fonts, cover art, ...). Entries may reference
+    /// Sonarr metadata such as `{series_path}/poster.jpg`; the MIME type is
+    /// detected from the file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add: Vec<String>,
+    /// Remove embedded cover art (attachments named `cover.jpg` and the like,
+    /// which ffprobe shows as mjpeg/png video streams); other attachments
+    /// such as fonts are kept
+    #[serde(default)]
+    pub remove_cover_art: bool,
+}
+
+/// Which events of a run a notification backend is sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyOn {
+    /// A message for every processed or failed file
+    File,
+    /// One summary message when the run finishes
+    #[default]
+    Batch,
+    /// A message for every failed file
+    Failures,
+}
+
+impl NotifyOn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotifyOn::File => "file",
+            NotifyOn::Batch => "batch",
+            NotifyOn::Failures => "failures",
+        }
+    }
+}
+
+/// Service a notification backend delivers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierKind {
+    /// Shell command run through `sh -c`, with the message in
+    /// `$MKV_SLIMMER_MESSAGE` (and `$MKV_SLIMMER_TITLE`, `$MKV_SLIMMER_EVENT`)
+    Command { command: String },
+    /// JSON `{"event", "title", "message"}` POSTed to the URL with curl
+    Webhook { url: String },
+}
+
+impl NotifierKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotifierKind::Command { .. } => "command",
+            NotifierKind::Webhook { .. } => "webhook",
+        }
+    }
+}
+
+/// When and how a notification backend is sent messages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRules {
+    /// Events the backend is sent
+    #[serde(default)]
+    pub on: NotifyOn,
+    /// Message template with `{event}`, `{file}`, `{outcome}`, `{saved}`,
+    /// `{error}`, `{total}`, `{successful}`, `{failed}` and `{skipped}`
+    /// placeholders; unset uses a default message per event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Minimum seconds between two messages; messages in between are left
+    /// out and counted in the next one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_interval_secs: Option<u64>,
+}
+
+/// A notification backend (`notifications` list)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+    #[serde(flatten)]
+    pub rules: NotificationRules,
 }
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use dialoguer::Select;
+use std::path::{Path, PathBuf};
+
+use super::settings::{Config, DIRECTORY_CONFIG_FILE, merge_yaml};
+use crate::core::batch::season_label;
+use crate::report;
+
+/// Settings file of a single source: `Movie.mkv` → `Movie.mkv-slimmer.yaml`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::file_settings_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(
+///     file_settings_path(Path::new("/tv/Show/Season 01/Show - S01E01.mkv")),
+///     Some(PathBuf::from("/tv/Show/Season 01/Show - S01E01.mkv-slimmer.yaml"))
+/// );
+/// ```
+pub fn file_settings_path(source: &Path) -> Option<PathBuf> {
+    let stem = source.file_stem()?.to_string_lossy();
+    Some(source.with_file_name(format!("{}.mkv-slimmer.yaml", stem)))
+}
+
+/// Folder whose `.mkv-slimmer.yaml` covers a whole series: `directory`, or
+/// its parent when `directory` is a season folder (`Season 01`, `Specials`)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::series_directory;
+/// use std::path::Path;
+///
+/// assert_eq!(series_directory(Path::new("/tv/Show/Season 1")), Path::new("/tv/Show"));
+/// assert_eq!(series_directory(Path::new("/tv/Show")), Path::new("/tv/Show"));
+/// ```
+pub fn series_directory(directory: &Path) -> &Path {
+    let is_season = directory
+        .file_name()
+        .is_some_and(|name| season_label(&name.to_string_lossy()).is_some());
+    match directory.parent() {
+        Some(parent) if is_season => parent,
+        _ => directory,
+    }
+}
+
+/// Settings files that apply to `file` when it's processed on its own, least
+/// specific first: the series folder's and the file's folder's
+/// `.mkv-slimmer.yaml`, then the file's own settings file
+pub fn settings_files_for(file: &Path) -> Vec<PathBuf> {
+    let directory = file.parent().unwrap_or(Path::new(""));
+    let series = series_directory(directory);
+    let mut files = vec![series.join(DIRECTORY_CONFIG_FILE)];
+    if series != directory {
+        files.push(directory.join(DIRECTORY_CONFIG_FILE));
+    }
+    files.extend(file_settings_path(file));
+    files.retain(|path| path.is_file());
+    files
+}
+
+/// `config` with the settings files of `settings_files_for(file)` merged over it
+pub fn apply_saved_settings(config: Config, file: &Path) -> Result<Config> {
+    settings_files_for(file)
+        .into_iter()
+        .try_fold(config, |config, path| {
+            report!("⚙️  Saved settings: {}", path.display());
+            config.with_overrides_file(&path)
+        })
+}
+
+/// Ask whether the languages picked at the prompts should be saved, for the
+/// file alone or for its series, so the next run doesn't ask again.
+/// `choices` holds the picked settings as overrides YAML.
+pub fn offer_to_save_choices(
+    choices: serde_yaml::Value,
+    input_path: &Path,
+    input_is_file: bool,
+) -> Result<()> {
+    let directory = if input_is_file {
+        input_path.parent().unwrap_or(Path::new(""))
+    } else {
+        input_path
+    };
+    let mut targets = Vec::new();
+    if input_is_file && let Some(path) = file_settings_path(input_path) {
+        targets.push(("this file", path));
+    }
+    targets.push((
+        "this series",
+        series_directory(directory).join(DIRECTORY_CONFIG_FILE),
+    ));
+
+    let mut items = vec!["Don't save".to_string()];
+    items.extend(
+        targets
+            .iter()
+            .map(|(scope, path)| format!("Save for {} ({})", scope, path.display())),
+    );
+    let choice = Select::new()
+        .with_prompt("Remember these choices?")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    let Some((_, path)) = choice.checked_sub(1).and_then(|index| targets.get(index)) else {
+        return Ok(());
+    };
+
+    save_choices(path, choices)?;
+    report!("💾 Saved choices to {}", path.display());
+    Ok(())
+}
+
+/// Merge `choices` into the settings file at `path`, creating it if needed.
+/// Other settings in the file are kept, its comments aren't.
+fn save_choices(path: &Path, choices: serde_yaml::Value) -> Result<()> {
+    let mut settings = if path.is_file() {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings file: {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse settings file: {}", path.display()))?
+    } else {
+        serde_yaml::Value::Null
+    };
+    if settings.is_null() {
+        settings = serde_yaml::Value::Mapping(Default::default());
+    }
+    merge_yaml(&mut settings, choices);
+
+    let yaml = serde_yaml::to_string(&settings).context("Failed to serialize choices")?;
+    std::fs::write(path, yaml)
+        .with_context(|| format!("Failed to write settings file: {}", path.display()))
+}
@@ -1,9 +1,28 @@
 use anyhow::{Context, Result};
-use dialoguer::MultiSelect;
+use dialoguer::FuzzySelect;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use super::preferences::{AudioConfig, ProcessingConfig, SubtitleConfig, SubtitlePreference};
+use super::preferences::{
+    AnalysisSource, AttachmentConfig, AudioConfig, ChapterMode, ContainerFormat,
+    DefaultTrackStrategy, FileMode, NoMatchPolicy, NotifierConfig, ProcessingConfig,
+    RemovedManifest, SavingsFloor, SubtitleConfig, SubtitlePreference, TagConfig, ThroughputConfig,
+    TitleConfig,
+};
+use crate::core::notify::validate_notifications;
+use crate::core::template::validate_target_template;
+use crate::core::titles::validate_title_template;
+use crate::error::config_error;
+use crate::models::{MediaKind, TrackRef};
+use crate::utils::{
+    LANGUAGES, OWNER_OF_TARGET, language_name, normalize_language, normalize_languages,
+    suggest_language,
+};
+use crate::{report, report_warn};
+
+/// Name of the per-directory settings file batches merge over the config
+pub const DIRECTORY_CONFIG_FILE: &str = ".mkv-slimmer.yaml";
 
 /// Main configuration for mkv-slimmer processing
 ///
@@ -18,7 +37,7 @@ use super::preferences::{AudioConfig, ProcessingConfig, SubtitleConfig, Subtitle
 /// let config = Config::default();
 /// assert!(!config.audio.keep_languages.is_empty());
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     /// Configuration for audio stream processing
     pub audio: AudioConfig,
@@ -26,16 +45,92 @@ pub struct Config {
     pub subtitles: SubtitleConfig,
     /// General processing behavior settings
     pub processing: ProcessingConfig,
+    /// Global tag handling during the remux
+    #[serde(default)]
+    pub tags: TagConfig,
+    /// Track titles rewritten during the remux
+    #[serde(default)]
+    pub titles: TitleConfig,
+    /// Files attached during the remux
+    #[serde(default)]
+    pub attachments: AttachmentConfig,
+    /// Backends told about processed files and finished runs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<NotifierConfig>,
+    /// Named sets of settings (`anime`, `movies`, ...) that `with_profile()`
+    /// merges over the rest of the config
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_yaml::Value>,
+    /// Profile applied by `with_profile()`, for display
+    #[serde(skip)]
+    pub active_profile: Option<String>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            audio: AudioConfig::default(),
-            subtitles: SubtitleConfig::default(),
-            processing: ProcessingConfig::default(),
-        }
-    }
+/// Values supplied on the command line that take precedence over the config file
+#[derive(Debug, Clone, Default)]
+pub struct CliOverrides {
+    pub audio_languages: Option<Vec<String>>,
+    pub subtitle_languages: Option<Vec<String>>,
+    pub dry_run: bool,
+    pub container: Option<ContainerFormat>,
+    pub chapters: Option<ChapterMode>,
+    pub audio_no_match: Option<NoMatchPolicy>,
+    pub subtitle_no_match: Option<NoMatchPolicy>,
+    pub default_audio: Option<DefaultTrackStrategy>,
+    pub default_subtitle: Option<DefaultTrackStrategy>,
+    pub min_channels: Option<u32>,
+    pub keep_stereo_companion: bool,
+    pub prefer_channels: Option<u32>,
+    pub audio_codec_preference: Vec<String>,
+    pub audio_tracks_per_language: Option<u32>,
+    pub split_subtitles: bool,
+    pub merge_external_audio: bool,
+    /// `KEY:OFFSET` entries, e.g. `Movie.eng.mka:+250ms` or `eng:-1s`
+    pub sync_offsets: Vec<String>,
+    pub strip_global_tags: bool,
+    pub strip_track_tags: bool,
+    pub clear_title: bool,
+    pub title_template: Option<String>,
+    pub scrub: bool,
+    pub print_command: bool,
+    pub allow_ordered_chapters: bool,
+    pub output_mode: Option<FileMode>,
+    pub output_group: Option<String>,
+    pub output_owner: Option<String>,
+    pub force: bool,
+    pub min_savings: Option<SavingsFloor>,
+    pub min_throughput: Option<f64>,
+    pub duration_tolerance: Option<f64>,
+    pub skip_samples: Option<u64>,
+    pub free_up: Option<u64>,
+    pub fill_space: bool,
+    pub strict: bool,
+    pub infer_languages: bool,
+    pub trust_import_languages: bool,
+    pub import_markers: bool,
+    pub language_overrides: Vec<String>,
+    pub tag_languages: bool,
+    pub flags_only: bool,
+    pub fix_sub_charset: bool,
+    pub keep_forced_subtitles: bool,
+    pub remove_subtitle_codecs: Vec<String>,
+    pub keep_subtitle_codecs: Vec<String>,
+    pub remove_codecs_if_text_kept: bool,
+    /// Files to attach in addition to `attachments.add`
+    pub attach_files: Vec<String>,
+    pub remove_cover_art: bool,
+    pub log_dir: Option<PathBuf>,
+    pub options_dir: Option<PathBuf>,
+    pub backup_dir: Option<PathBuf>,
+    pub history_db: Option<PathBuf>,
+    pub journal: Option<PathBuf>,
+    pub skip_processed: bool,
+    pub marker_tags: bool,
+    pub removed_manifest: Option<RemovedManifest>,
+    pub skip_marked: bool,
+    pub target_template: Option<String>,
+    pub preferred_source: Option<AnalysisSource>,
+    pub cross_check: bool,
 }
 
 impl Config {
@@ -47,35 +142,145 @@ impl Config {
 
         // Gracefully handle missing config file by using defaults
         if !path.exists() {
-            eprintln!("Missing config file: {}", path.display());
+            report_warn!("Missing config file: {}", path.display());
             return Ok(Self::default());
         }
 
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: Config = serde_yaml::from_str(&content)
+        let mut config: Config = serde_yaml::from_str(&content)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        config.apply_language_aliases();
 
-        // Validate configuration
+        // Validate configuration, including what each profile turns it into
         config.validate()?;
+        for name in config.profiles.keys() {
+            config.with_profile(name)?;
+        }
 
         Ok(config)
     }
 
-    pub fn merge_cli_args(
-        &mut self,
-        audio_languages: Option<Vec<String>>,
-        subtitle_languages: Option<Vec<String>>,
-        dry_run: bool,
-    ) -> Result<()> {
+    /// This configuration with the settings of a partial YAML config merged
+    /// over it: sections merge key by key, while values (lists included)
+    /// replace the ones they name
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let anime = config
+    ///     .with_yaml_overrides("audio:\n  keep_languages: [jpn]\n")
+    ///     .unwrap();
+    /// assert_eq!(anime.audio.keep_languages, vec!["jpn"]);
+    /// assert_eq!(anime.subtitles.keep_languages.len(), config.subtitles.keep_languages.len());
+    ///
+    /// assert!(config.with_yaml_overrides("audio: [jpn]").is_err());
+    /// ```
+    pub fn with_yaml_overrides(&self, yaml: &str) -> Result<Self> {
+        let overrides: serde_yaml::Value =
+            serde_yaml::from_str(yaml).context("Failed to parse settings overrides")?;
+        self.with_overrides_value(overrides)
+    }
+
+    fn with_overrides_value(&self, overrides: serde_yaml::Value) -> Result<Self> {
+        // An empty file overrides nothing
+        if overrides.is_null() {
+            return Ok(self.clone());
+        }
+        let mut merged = serde_yaml::to_value(self).context("Failed to serialize configuration")?;
+        merge_yaml(&mut merged, overrides);
+        let mut config: Config =
+            serde_yaml::from_value(merged).context("Invalid settings overrides")?;
+        config.apply_language_aliases();
+        config.validate()?;
+        config.active_profile = self.active_profile.clone();
+        Ok(config)
+    }
+
+    /// This configuration with the settings of profile `name` merged over it,
+    /// like `with_yaml_overrides()` does
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::config::Config;
+    ///
+    /// let config = Config::default()
+    ///     .with_yaml_overrides("profiles:\n  anime:\n    audio:\n      keep_languages: [jpn]\n")
+    ///     .unwrap();
+    /// let anime = config.with_profile("anime").unwrap();
+    /// assert_eq!(anime.audio.keep_languages, vec!["jpn"]);
+    /// assert_eq!(anime.active_profile.as_deref(), Some("anime"));
+    ///
+    /// assert!(config.with_profile("kids").is_err());
+    /// ```
+    pub fn with_profile(&self, name: &str) -> Result<Self> {
+        let Some(overrides) = self.profiles.get(name) else {
+            let available = if self.profiles.is_empty() {
+                "none are defined".to_string()
+            } else {
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            };
+            return Err(config_error(
+                "Profile",
+                &format!(
+                    "Unknown profile '{}'. Available profiles: {}",
+                    name, available
+                ),
+            ));
+        };
+        let mut config = self
+            .with_overrides_value(overrides.clone())
+            .with_context(|| format!("Invalid profile '{}'", name))?;
+        config.active_profile = Some(name.to_string());
+        Ok(config)
+    }
+
+    /// The first of `tags` (e.g. Sonarr's series tags) naming a profile,
+    /// ignoring case
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::config::Config;
+    ///
+    /// let config = Config::default()
+    ///     .with_yaml_overrides("profiles:\n  anime: {}\n  kids: {}\n")
+    ///     .unwrap();
+    /// let tags = ["4k".to_string(), "Kids".to_string(), "anime".to_string()];
+    /// assert_eq!(config.profile_for_tags(&tags), Some("kids"));
+    /// assert_eq!(config.profile_for_tags(&["4k".to_string()]), None);
+    /// ```
+    pub fn profile_for_tags(&self, tags: &[String]) -> Option<&str> {
+        tags.iter().find_map(|tag| {
+            self.profiles
+                .keys()
+                .find(|name| name.eq_ignore_ascii_case(tag))
+                .map(String::as_str)
+        })
+    }
+
+    /// `with_yaml_overrides()` with the contents of the file at `path`
+    pub fn with_overrides_file(&self, path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings file: {}", path.display()))?;
+        self.with_yaml_overrides(&content)
+            .with_context(|| format!("Failed to apply settings file: {}", path.display()))
+    }
+
+    pub fn merge_cli_args(&mut self, overrides: CliOverrides) -> Result<()> {
         // Audio languages
-        if let Some(langs) = audio_languages {
-            self.audio.keep_languages = langs;
+        if let Some(langs) = overrides.audio_languages {
+            self.audio.keep_languages = normalize_languages(&langs);
+        }
+
+        if overrides.merge_external_audio {
+            self.audio.merge_external = true;
         }
 
         // Subtitle languages
-        if let Some(langs) = subtitle_languages {
+        if let Some(langs) = overrides.subtitle_languages {
             self.subtitles.keep_languages = langs
                 .into_iter()
                 .map(|s| SubtitlePreference::parse(&s))
@@ -86,63 +291,526 @@ impl Config {
         }
 
         // Processing options
-        if dry_run {
+        if overrides.dry_run {
             self.processing.dry_run = true;
         }
+        if let Some(container) = overrides.container {
+            self.processing.container = container;
+        }
+        if let Some(chapters) = overrides.chapters {
+            self.processing.chapters = chapters;
+        }
+        if let Some(policy) = overrides.audio_no_match {
+            self.audio.no_match_policy = policy;
+        }
+        if let Some(min_channels) = overrides.min_channels {
+            self.audio.min_channels = Some(min_channels);
+        }
+        if overrides.keep_stereo_companion {
+            self.audio.keep_stereo_companion = true;
+        }
+        if let Some(channels) = overrides.prefer_channels {
+            self.audio.prefer_channels = Some(channels);
+        }
+        if !overrides.audio_codec_preference.is_empty() {
+            self.audio.codec_preference = overrides.audio_codec_preference;
+        }
+        if let Some(tracks) = overrides.audio_tracks_per_language {
+            self.audio.tracks_per_language = tracks;
+        }
+        if let Some(policy) = overrides.subtitle_no_match {
+            self.subtitles.no_match_policy = policy;
+        }
+        if let Some(strategy) = overrides.default_audio {
+            self.audio.default_strategy = strategy;
+        }
+        if let Some(strategy) = overrides.default_subtitle {
+            self.subtitles.default_strategy = strategy;
+        }
+        if overrides.split_subtitles {
+            self.subtitles.split_by_language = true;
+        }
+        if overrides.fix_sub_charset {
+            self.subtitles.fix_charset = true;
+        }
+        if overrides.keep_forced_subtitles {
+            self.subtitles.keep_forced = true;
+        }
+        if !overrides.remove_subtitle_codecs.is_empty() {
+            self.subtitles.remove_codecs = overrides.remove_subtitle_codecs;
+        }
+        if !overrides.keep_subtitle_codecs.is_empty() {
+            self.subtitles.keep_codecs = overrides.keep_subtitle_codecs;
+        }
+        if overrides.remove_codecs_if_text_kept {
+            self.subtitles.remove_codecs_if_text_kept = true;
+        }
+        if overrides.strip_global_tags {
+            self.tags.strip_global = true;
+        }
+        if overrides.strip_track_tags {
+            self.tags.strip_track = true;
+        }
+        if overrides.clear_title {
+            self.tags.clear_title = true;
+        }
+        if let Some(template) = overrides.title_template {
+            self.tags.title = Some(template);
+        }
+        self.attachments.add.extend(overrides.attach_files);
+        if overrides.remove_cover_art {
+            self.attachments.remove_cover_art = true;
+        }
+        if let Some(log_dir) = overrides.log_dir {
+            self.processing.log_dir = Some(log_dir);
+        }
+        if let Some(options_dir) = overrides.options_dir {
+            self.processing.options_dir = Some(options_dir);
+        }
+        if let Some(backup_dir) = overrides.backup_dir {
+            self.processing.backup_dir = Some(backup_dir);
+        }
+        if let Some(history_db) = overrides.history_db {
+            self.processing.history_db = Some(history_db);
+        }
+        if let Some(journal) = overrides.journal {
+            self.processing.journal = Some(journal);
+        }
+        if overrides.skip_processed {
+            self.processing.skip_processed = true;
+        }
+        if overrides.marker_tags {
+            self.processing.marker_tags = true;
+        }
+        if let Some(mode) = overrides.removed_manifest {
+            self.processing.removed_manifest = Some(mode);
+        }
+        if overrides.skip_marked {
+            self.processing.skip_marked = true;
+        }
+        if let Some(template) = overrides.target_template {
+            self.processing.target_template = Some(template);
+        }
+        if let Some(source) = overrides.preferred_source {
+            self.processing.preferred_source = source;
+        }
+        if overrides.cross_check {
+            self.processing.cross_check = true;
+        }
+        if overrides.scrub {
+            self.processing.scrub = true;
+        }
+        if overrides.print_command {
+            self.processing.print_command = true;
+        }
+        if overrides.allow_ordered_chapters {
+            self.processing.allow_ordered_chapters = true;
+        }
+        if let Some(mode) = overrides.output_mode {
+            self.processing.output_mode = Some(mode);
+        }
+        if let Some(group) = overrides.output_group {
+            self.processing.output_group = Some(group);
+        }
+        if let Some(owner) = overrides.output_owner {
+            self.processing.output_owner = Some(owner);
+        }
+        if overrides.force {
+            self.processing.force = true;
+        }
+        if let Some(floor) = overrides.min_savings {
+            self.processing.min_savings = Some(floor);
+        }
+        if let Some(min_mb_per_sec) = overrides.min_throughput {
+            // Keeps the configured period and notification command
+            match &mut self.processing.throughput_warning {
+                Some(warning) => warning.min_mb_per_sec = min_mb_per_sec,
+                None => {
+                    self.processing.throughput_warning = Some(ThroughputConfig::new(min_mb_per_sec))
+                }
+            }
+        }
+        if let Some(seconds) = overrides.duration_tolerance {
+            self.processing.duration_tolerance_secs = Some(seconds);
+        }
+        if let Some(minutes) = overrides.skip_samples {
+            self.processing.skip_samples = Some(minutes);
+        }
+        if let Some(bytes) = overrides.free_up {
+            self.processing.free_up = Some(bytes);
+        }
+        if overrides.fill_space {
+            self.processing.fill_space = true;
+        }
+        if overrides.strict {
+            self.processing.strict = true;
+        }
+        if overrides.infer_languages {
+            self.processing.infer_languages = true;
+        }
+        if overrides.trust_import_languages {
+            self.processing.trust_import_languages = true;
+        }
+        if overrides.import_markers {
+            self.processing.import_markers = true;
+        }
+        if overrides.tag_languages {
+            self.processing.tag_languages = true;
+        }
+        if overrides.flags_only {
+            self.processing.flags_only = true;
+        }
+        for entry in overrides.language_overrides {
+            let parsed = entry
+                .rsplit_once(':')
+                .and_then(|(track, language)| {
+                    Some((track.parse::<TrackRef>().ok()?, language.trim()))
+                })
+                .filter(|(_, language)| !language.is_empty());
+            let Some((track, language)) = parsed else {
+                return Err(config_error(
+                    "Language override",
+                    &format!(
+                        "Invalid entry '{}'. Expected INDEX:LANG or uid:UID:LANG",
+                        entry
+                    ),
+                ));
+            };
+            self.processing
+                .language_overrides
+                .insert(track, normalize_language(language));
+        }
+        for entry in overrides.sync_offsets {
+            let (key, offset) = entry.rsplit_once(':').ok_or_else(|| {
+                config_error(
+                    "Sync offset",
+                    &format!("Invalid entry '{}'. Expected KEY:OFFSET", entry),
+                )
+            })?;
+            self.processing
+                .sync_offsets
+                .insert(key.trim().to_string(), offset.parse()?);
+        }
 
         // Validate configuration after CLI merge
+        self.apply_language_aliases();
         self.validate()
             .with_context(|| "Configuration validation failed after merging CLI arguments")?;
 
         Ok(())
     }
 
-    pub fn prompt_missing_values(&mut self) -> Result<()> {
+    /// Prompt for empty language lists when running interactively.
+    /// When the input's `MediaKind` is known, only lists that apply to it are prompted.
+    /// Returns the picked lists as overrides YAML (see `offer_to_save_choices()`),
+    /// None when nothing was prompted.
+    pub fn prompt_missing_values(
+        &mut self,
+        media_kind: Option<MediaKind>,
+    ) -> Result<Option<serde_yaml::Value>> {
         // Check if we're running in a TTY
         if !atty::is(atty::Stream::Stdin) {
-            return Ok(());
+            return Ok(None);
         }
+        let mut choices = serde_yaml::Mapping::new();
+
+        let prompt_audio = media_kind.is_none_or(|kind| kind.filters_audio());
+        let prompt_subtitles = media_kind.is_none_or(|kind| kind.filters_subtitles());
 
         // Prompt for audio languages if empty
-        if self.audio.keep_languages.is_empty() {
-            println!("No audio languages specified. Select languages to keep:");
-            let languages = vec!["eng", "jpn", "spa", "fre", "ger", "ita", "und"];
-            let selections = MultiSelect::new()
-                .with_prompt("Audio languages to keep")
-                .items(&languages)
-                .interact()?;
-
-            self.audio.keep_languages = selections
-                .into_iter()
-                .map(|i| languages[i].to_string())
-                .collect();
+        if prompt_audio && self.audio.keep_languages.is_empty() {
+            report!("No audio languages specified. Select languages to keep:");
+            self.audio.keep_languages = prompt_language_list("Audio languages to keep")?;
+            choices.insert(
+                "audio".into(),
+                serde_yaml::to_value(BTreeMap::from([(
+                    "keep_languages",
+                    &self.audio.keep_languages,
+                )]))?,
+            );
         }
 
         // Prompt for subtitle languages if empty
-        if self.subtitles.keep_languages.is_empty() {
-            println!("No subtitle languages specified. Select languages to keep:");
-            let languages = vec!["eng", "jpn", "spa", "fre", "ger", "ita", "und"];
-            let selections = MultiSelect::new()
-                .with_prompt("Subtitle languages to keep")
-                .items(&languages)
-                .interact()?;
-
-            self.subtitles.keep_languages = selections
+        if prompt_subtitles && self.subtitles.keep_languages.is_empty() {
+            report!("No subtitle languages specified. Select languages to keep:");
+            self.subtitles.keep_languages = prompt_language_list("Subtitle languages to keep")?
                 .into_iter()
-                .map(|i| SubtitlePreference {
-                    language: languages[i].to_string(),
+                .map(|language| SubtitlePreference {
+                    language,
                     title_prefix: None,
                 })
                 .collect();
+            let languages: Vec<&str> = self
+                .subtitles
+                .keep_languages
+                .iter()
+                .map(|preference| preference.language.as_str())
+                .collect();
+            choices.insert(
+                "subtitles".into(),
+                serde_yaml::to_value(BTreeMap::from([("keep_languages", languages)]))?,
+            );
         }
 
-        Ok(())
+        Ok((!choices.is_empty()).then_some(serde_yaml::Value::Mapping(choices)))
+    }
+
+    /// The ISO 639-2/B code for a language tag from a file or the command
+    /// line: `normalize_language()` with `processing.language_aliases` applied
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::config::Config;
+    ///
+    /// let config = Config::default()
+    ///     .with_yaml_overrides("processing:\n  language_aliases:\n    Latino: es\n")
+    ///     .unwrap();
+    /// assert_eq!(config.resolve_language("latino"), "spa");
+    /// assert_eq!(config.resolve_language("de"), "ger");
+    /// ```
+    pub fn resolve_language(&self, code: &str) -> String {
+        let code = normalize_language(code);
+        self.processing
+            .language_aliases
+            .get(&code.to_lowercase())
+            .cloned()
+            .unwrap_or(code)
     }
 
-    /// Validate configuration - currently no specific validations needed
+    /// Replace aliased codes in the language settings with their ISO code
+    fn apply_language_aliases(&mut self) {
+        if self.processing.language_aliases.is_empty() {
+            return;
+        }
+        let resolved = self.clone();
+        for code in &mut self.audio.keep_languages {
+            *code = resolved.resolve_language(code);
+        }
+        for preference in &mut self.subtitles.keep_languages {
+            preference.language = resolved.resolve_language(&preference.language);
+        }
+        self.subtitles.charsets = std::mem::take(&mut self.subtitles.charsets)
+            .into_iter()
+            .map(|(code, charset)| (resolved.resolve_language(&code), charset))
+            .collect();
+        for code in self.processing.language_overrides.values_mut() {
+            *code = resolved.resolve_language(code);
+        }
+    }
+
+    /// Validate configuration. Empty language lists are allowed (video and
+    /// attachment streams are always kept).
     pub fn validate(&self) -> Result<()> {
-        // No specific validation needed since default languages are removed
-        // and video/attachment streams are always kept
+        self.validate_languages()?;
+        if let Some(template) = &self.processing.target_template {
+            validate_target_template(template)?;
+        }
+        validate_notifications(&self.notifications)?;
+        for template in [
+            &self.titles.video,
+            &self.titles.audio,
+            &self.titles.subtitles,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            validate_title_template(template)?;
+        }
+        if let Some(warning) = &self.processing.throughput_warning
+            && !(warning.min_mb_per_sec.is_finite() && warning.min_mb_per_sec > 0.0)
+        {
+            return Err(config_error(
+                "Throughput warning",
+                &format!(
+                    "Invalid minimum '{}'. Use a positive rate in MB/s",
+                    warning.min_mb_per_sec
+                ),
+            ));
+        }
+        if let Some(seconds) = self.processing.duration_tolerance_secs
+            && !(seconds.is_finite() && seconds >= 0.0)
+        {
+            return Err(config_error(
+                "Duration tolerance",
+                &format!("Invalid tolerance '{}'. Use a number of seconds", seconds),
+            ));
+        }
+        if self.audio.min_channels == Some(0) {
+            return Err(config_error(
+                "Audio channels",
+                "Invalid min_channels '0'. Use a channel count of at least 1",
+            ));
+        }
+        if let Some(owner) = &self.processing.output_owner
+            && owner != OWNER_OF_TARGET
+            && owner.split(':').any(str::is_empty)
+        {
+            return Err(config_error(
+                "Output owner",
+                &format!(
+                    "Invalid owner '{}'. Use '{}' or USER[:GROUP] (names or numeric IDs)",
+                    owner, OWNER_OF_TARGET
+                ),
+            ));
+        }
+        if let Some(codec) = self.subtitles.remove_codecs.iter().find(|codec| {
+            self.subtitles
+                .keep_codecs
+                .iter()
+                .any(|kept| kept.eq_ignore_ascii_case(codec))
+        }) {
+            return Err(config_error(
+                "Subtitle codecs",
+                &format!(
+                    "Codec '{}' is in both subtitles.remove_codecs and subtitles.keep_codecs. List it in one of them",
+                    codec
+                ),
+            ));
+        }
+        if self.audio.prefer_channels == Some(0) {
+            return Err(config_error(
+                "Audio channels",
+                "Invalid prefer_channels '0'. Use a channel count of at least 1",
+            ));
+        }
+        if self.audio.tracks_per_language == 0 {
+            return Err(config_error(
+                "Audio codec preference",
+                "Invalid tracks_per_language '0'. Keep at least 1 track per language",
+            ));
+        }
+        if self.processing.skip_samples == Some(0) {
+            return Err(config_error(
+                "Sample detection",
+                "Invalid limit '0'. Use a positive number of minutes",
+            ));
+        }
+        if self.processing.free_up == Some(0) {
+            return Err(config_error(
+                "Free-up target",
+                "Invalid size '0'. Use the amount of space to reclaim, e.g. '500G'",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject language codes outside ISO 639-2 (and the user's aliases): a
+    /// typo in a keep list would otherwise silently remove every such track
+    fn validate_languages(&self) -> Result<()> {
+        let subtitle_languages: Vec<String> = self
+            .subtitles
+            .keep_languages
+            .iter()
+            .map(|preference| preference.language.clone())
+            .collect();
+        let settings: [(&str, Vec<String>); 5] = [
+            ("audio.keep_languages", self.audio.keep_languages.clone()),
+            ("subtitles.keep_languages", subtitle_languages),
+            (
+                "subtitles.charsets",
+                self.subtitles.charsets.keys().cloned().collect(),
+            ),
+            (
+                "processing.language_overrides",
+                self.processing
+                    .language_overrides
+                    .values()
+                    .cloned()
+                    .collect(),
+            ),
+            (
+                "processing.language_aliases",
+                self.processing.language_aliases.values().cloned().collect(),
+            ),
+        ];
+
+        for (setting, codes) in settings {
+            for code in codes {
+                if is_known_language(&code) {
+                    continue;
+                }
+                let hint = match suggest_language(&code) {
+                    Some(suggestion) => format!(
+                        "Did you mean '{}' ({})? ",
+                        suggestion,
+                        language_name(suggestion).unwrap_or(suggestion)
+                    ),
+                    None => String::new(),
+                };
+                return Err(config_error(
+                    "Language code",
+                    &format!(
+                        "Unknown language '{}' in {}. {}Use an ISO 639 code, or map the tag to one in processing.language_aliases",
+                        code, setting, hint
+                    ),
+                ));
+            }
+        }
         Ok(())
     }
 }
+
+/// Whether `code` is an ISO 639-2/B code, including the `qaa`-`qtz` range
+/// reserved for local use
+fn is_known_language(code: &str) -> bool {
+    language_name(code).is_some() || (code.len() == 3 && ("qaa"..="qtz").contains(&code))
+}
+
+/// Let the user pick languages one at a time from the full ISO 639-2 table,
+/// searching by code or English name. The pick order is the preference order.
+fn prompt_language_list(prompt: &str) -> Result<Vec<String>> {
+    let mut selected: Vec<&str> = Vec::new();
+
+    loop {
+        let remaining: Vec<&(&str, &str)> = LANGUAGES
+            .iter()
+            .filter(|(code, _)| !selected.contains(code))
+            .collect();
+
+        let mut items = vec![if selected.is_empty() {
+            "✔ Done (keep none)".to_string()
+        } else {
+            "✔ Done".to_string()
+        }];
+        items.extend(
+            remaining
+                .iter()
+                .map(|(code, name)| format!("{} - {}", code, name)),
+        );
+
+        let full_prompt = if selected.is_empty() {
+            format!("{} (type to search, pick in order of preference)", prompt)
+        } else {
+            format!("{} [{}]", prompt, selected.join(", "))
+        };
+
+        let choice = FuzzySelect::new()
+            .with_prompt(full_prompt)
+            .items(&items)
+            .default(0)
+            .interact()?;
+        if choice == 0 {
+            break;
+        }
+        selected.push(remaining[choice - 1].0);
+    }
+
+    Ok(selected.into_iter().map(String::from).collect())
+}
+
+/// Merge `overlay` into `base`: mappings key by key, anything else replaces
+pub(super) fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::analyzer::{analyze_mkv_streams, decide_streams};
+use super::chapters::chapter_count;
+use super::external::add_companion_audio;
+use super::policy::{FileContext, StreamPolicy};
+use crate::config::Config;
+use crate::error::file_validation_error;
+use crate::models::{MediaKind, StreamDecisions, StreamInfo};
+use crate::utils::is_valid_mkv_file;
+
+/// Streams of a file and what processing would do with each of them
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub streams: Vec<StreamInfo>,
+    pub decisions: StreamDecisions,
+    pub media_kind: MediaKind,
+    /// Number of chapters; None when it couldn't be read
+    pub chapters: Option<usize>,
+}
+
+/// Analyze `file` and decide its streams exactly as processing would,
+/// without needing a target or running mkvmerge
+pub async fn analyze_file(
+    file: &Path,
+    config: &Config,
+    policy: &dyn StreamPolicy,
+) -> Result<Analysis> {
+    if !is_valid_mkv_file(file) {
+        return Err(file_validation_error(file, "Not an MKV file"));
+    }
+
+    let mut streams = analyze_mkv_streams(file, config)
+        .await
+        .with_context(|| format!("Failed to analyze MKV streams: {}", file.display()))?;
+    if config.audio.merge_external {
+        add_companion_audio(file, &mut streams, config).await?;
+    }
+
+    let media_kind = MediaKind::from_path(file);
+    let context = FileContext {
+        source_file: file,
+        streams: &streams,
+        media_kind,
+        config,
+        import_context: None,
+    };
+    let decisions = decide_streams(&context, policy);
+
+    Ok(Analysis {
+        streams,
+        decisions,
+        media_kind,
+        chapters: chapter_count(file),
+    })
+}
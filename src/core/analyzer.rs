@@ -1,11 +1,53 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::os::unix::process::ExitStatusExt;
-use std::path::PathBuf;
-use std::process::Command;
-
-use crate::config::Config;
-use crate::models::{FFProbeOutput, SonarrContext, StreamInfo, StreamType};
-use crate::utils::{SonarrMoveStatus, output_sonarr_move_status};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+use super::attachments::{NewAttachment, resolve_attachments};
+use super::backup::{backup_file, backup_replaced_file};
+use super::charset::{detect_subtitle_charsets, is_text_subtitle};
+use super::external::{resolve_sync_offsets, sync_offset_for};
+use super::history::config_hash;
+use super::identify::{
+    detect_source_conflicts, fill_missing_fields, fill_missing_uids, get_matroska_data,
+    get_mkvmerge_data, known_tracks, parse_duration_tag, streams_from_matroska,
+    streams_from_mkvmerge,
+};
+use super::mkvtoolnix::{MkvmergeCommand, command_line, run_tool};
+use super::partial::{copy_via_partial, finish_partial, partial_path};
+use super::policy::{FileContext, StreamPolicy, keeps_forced_flag};
+use super::progress::{ProcessControl, ProgressEvent};
+use super::removed::{
+    REMOVED_TRACKS_TAG, RemovedTrack, removed_tracks, removed_tracks_tag, write_removed_manifest,
+};
+use super::sanity::{degenerate_output_problems, incomplete_analysis_problems, no_match_failures};
+use super::scrub::{add_scrub_args, scrub_application_info};
+use super::space::{check_copy_space, check_remux_space, existing_ancestor, same_filesystem};
+use super::tags::{
+    SlimmerMarker, global_tags_path, global_tags_xml, render_placeholders, render_tag_template,
+};
+use super::throughput::{ThroughputMonitor, slow_transfer_rate, warn_slow_throughput};
+use super::titles::title_rewrites;
+use super::up_to_date::{flags_differ, output_is_up_to_date};
+use super::verify::verify_output_durations;
+use crate::config::{
+    AnalysisSource, AudioConfig, ChapterMode, Config, ContainerFormat, DefaultTrackStrategy,
+    NoMatchPolicy, RemovedManifest, SavingsFloor, SubtitleConfig,
+};
+use crate::error::{Cancelled, file_validation_error, processing_error};
+use crate::models::{
+    DecisionReason, ExternalTrack, FFProbeOutput, ImportContext, LanguageSource, MediaKind,
+    MkvmergeIdentify, ProcessingOutcome, ProcessingTask, SegmentLinking, StreamAction,
+    StreamDecision, StreamDecisions, StreamInfo, StreamType, TrackIdMap, TransferMethod,
+};
+use crate::utils::{
+    SonarrMoveStatus, WarningKind, apply_output_permissions, format_size, language_from_title,
+    output_sonarr_move_status, record_warning, require_mkvtoolnix_tool, tool_available,
+};
+use crate::{report, report_warn};
 
 // MkvAnalyzer struct removed - migrated to ProcessingTask pattern
 // See standalone functions below for the new implementation
@@ -18,6 +60,19 @@ struct StreamsByType {
     attachment: Vec<u32>,
 }
 
+/// What an mkvmerge run adds on top of selecting the source's streams
+#[derive(Default)]
+struct MuxExtras {
+    /// Generated tags file passed as `--global-tags`
+    global_tags: Option<PathBuf>,
+    /// Segment title rendered from `tags.title`, passed as `--title`
+    title: Option<String>,
+    /// `(track ID, charset)` pairs passed as `--sub-charset`
+    sub_charsets: Vec<(u32, String)>,
+    /// Files passed as `--attach-file`
+    attachments: Vec<NewAttachment>,
+}
+
 /// Separates a list of stream indices into groups by stream type
 fn separate_streams_by_type(all_streams: &[StreamInfo], indices_to_keep: &[u32]) -> StreamsByType {
     let mut result = StreamsByType {
@@ -29,7 +84,7 @@ fn separate_streams_by_type(all_streams: &[StreamInfo], indices_to_keep: &[u32])
 
     for &index in indices_to_keep {
         if let Some(stream) = all_streams.iter().find(|s| s.index == index) {
-            match stream.stream_type {
+            match stream.container_type() {
                 StreamType::Video => result.video.push(index),
                 StreamType::Audio => result.audio.push(index),
                 StreamType::Subtitle => result.subtitle.push(index),
@@ -45,15 +100,14 @@ fn separate_streams_by_type(all_streams: &[StreamInfo], indices_to_keep: &[u32])
 fn parse_framerate(framerate_str: &str) -> Option<f64> {
     if framerate_str.contains('/') {
         let fraction_parts: Vec<&str> = framerate_str.split('/').collect();
-        if fraction_parts.len() == 2 {
-            if let (Ok(numerator), Ok(denominator)) = (
+        if fraction_parts.len() == 2
+            && let (Ok(numerator), Ok(denominator)) = (
                 fraction_parts[0].parse::<f64>(),
                 fraction_parts[1].parse::<f64>(),
-            ) {
-                if denominator != 0.0 {
-                    return Some(numerator / denominator);
-                }
-            }
+            )
+            && denominator != 0.0
+        {
+            return Some(numerator / denominator);
         }
     } else if let Ok(framerate_value) = framerate_str.parse::<f64>() {
         return Some(framerate_value);
@@ -61,196 +115,1736 @@ fn parse_framerate(framerate_str: &str) -> Option<f64> {
     None
 }
 
-fn parse_duration_tag(duration_str: &str) -> Option<f64> {
-    // Parse duration in format "00:01:31.010000000"
-    let parts: Vec<&str> = duration_str.split(':').collect();
-    if parts.len() == 3 {
-        if let (Ok(hours), Ok(minutes)) = (parts[0].parse::<f64>(), parts[1].parse::<f64>()) {
-            if let Ok(seconds) = parts[2].parse::<f64>() {
-                return Some(hours * 3600.0 + minutes * 60.0 + seconds);
-            }
-        }
-    }
-    None
-}
-
 // ===== New ProcessingTask-based functions =====
 
 /// Analyze MKV file streams and return StreamInfo vector
 /// This replaces MkvAnalyzer::analyze()
-pub async fn analyze_mkv_streams(file_path: &std::path::Path) -> Result<Vec<StreamInfo>> {
-    // Try to get ffprobe data first
-    let ffprobe_data = get_ffprobe_data(file_path).await;
+pub async fn analyze_mkv_streams(
+    file_path: &std::path::Path,
+    config: &Config,
+) -> Result<Vec<StreamInfo>> {
+    // mkvmerge's track IDs are what the remux selects by, so its
+    // identification defines the streams; the matroska crate's parser fills in
+    // what it didn't report
+    let mkvmerge_data = get_mkvmerge_data(file_path).await;
+
+    // ffprobe runs when it's preferred, to cross-check mkvmerge or as the
+    // fallback for files mkvmerge couldn't identify
+    let needs_ffprobe = config.processing.preferred_source == AnalysisSource::Ffprobe
+        || config.processing.cross_check
+        || mkvmerge_data
+            .as_ref()
+            .is_none_or(|identify| identify.tracks.is_empty());
+    let ffprobe_data = if needs_ffprobe {
+        match get_ffprobe_data(file_path).await {
+            Ok(data) => data,
+            Err(e) => {
+                analysis_problem(file_path, config, &format!("{:#}", e))?;
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    merge_probe_data(file_path, ffprobe_data, mkvmerge_data, config).await
+}
 
-    // Try to get matroska data
+/// Build the stream list from already collected ffprobe and mkvmerge output,
+/// adding the matroska parser's view and estimated sizes
+pub async fn merge_probe_data(
+    file_path: &std::path::Path,
+    ffprobe_data: Option<serde_json::Value>,
+    mkvmerge_data: Option<MkvmergeIdentify>,
+    config: &Config,
+) -> Result<Vec<StreamInfo>> {
+    let ffprobe_streams = match ffprobe_data.map(parse_ffprobe_streams).transpose() {
+        Ok(streams) => streams,
+        Err(e) => {
+            analysis_problem(file_path, config, &format!("{:#}", e))?;
+            None
+        }
+    };
     let matroska_data = get_matroska_data(file_path).await;
 
     // Combine the data sources
-    extract_streams_from_data(ffprobe_data, matroska_data)
+    let mut streams = extract_streams_from_data(
+        file_path,
+        ffprobe_streams,
+        mkvmerge_data,
+        matroska_data,
+        config.processing.preferred_source,
+    )?;
+
+    // Files mix ISO 639-1, 639-2/B and 639-2/T tags (and aliases); match and show them in one form
+    for language in streams.iter_mut().filter_map(|s| s.language.as_mut()) {
+        *language = config.resolve_language(language);
+    }
+
+    if config.processing.strict {
+        let problems = incomplete_analysis_problems(&streams);
+        if !problems.is_empty() {
+            return Err(processing_error(
+                file_path,
+                "Strict analysis",
+                &problems.join("; "),
+            ));
+        }
+    }
+
+    if config.processing.infer_languages {
+        infer_languages_from_titles(&mut streams);
+    }
+    apply_language_overrides(file_path, &mut streams, config);
+    let untagged = streams
+        .iter()
+        .filter(|s| {
+            matches!(s.stream_type, StreamType::Audio | StreamType::Subtitle)
+                && s.effective_language() == "und"
+        })
+        .count();
+    if untagged > 0 {
+        record_warning(
+            WarningKind::MissingLanguage,
+            format!(
+                "{} audio/subtitle track(s) without a language tag",
+                untagged
+            ),
+        );
+    }
+
+    // Fill in sizes the probes couldn't report so summaries don't count them as zero
+    if let Ok(metadata) = std::fs::metadata(file_path) {
+        let estimated = estimate_missing_stream_sizes(&mut streams, metadata.len());
+        if estimated > 0 {
+            record_warning(
+                WarningKind::EstimatedSizes,
+                format!(
+                    "sizes of {} stream(s) estimated from the container size",
+                    estimated
+                ),
+            );
+        }
+    }
+
+    Ok(streams)
+}
+
+/// Give untagged audio and subtitle streams the language their title names,
+/// recording `LanguageSource::Title` so the display can mark them
+fn infer_languages_from_titles(streams: &mut [StreamInfo]) {
+    for stream in streams.iter_mut().filter(|s| {
+        matches!(s.stream_type, StreamType::Audio | StreamType::Subtitle)
+            && s.effective_language() == "und"
+    }) {
+        if let Some(code) = stream.title.as_deref().and_then(language_from_title) {
+            stream.language = Some(code.to_string());
+            stream.language_source = LanguageSource::Title;
+        }
+    }
+}
+
+/// Replace the language of streams listed in `processing.language_overrides`
+fn apply_language_overrides(file_path: &Path, streams: &mut [StreamInfo], config: &Config) {
+    for (track, language) in &config.processing.language_overrides {
+        match streams
+            .iter_mut()
+            .find(|s| track.matches(s) && s.external.is_none())
+        {
+            Some(stream) => {
+                stream.language = Some(language.clone());
+                stream.language_source = LanguageSource::Override;
+            }
+            None => report_warn!(
+                "⚠️  Ignoring language override for stream #{}: {} has no such stream",
+                track,
+                file_path.display()
+            ),
+        }
+    }
+}
+
+/// Compare the audio and subtitle languages Sonarr/Radarr report for the
+/// imported file with the analyzed streams and warn when they disagree, which
+/// usually means mis-tagged tracks. With one reported language per track the
+/// lists are compared in track order, otherwise (the application may list each
+/// language once) as sets. Under `processing.trust_import_languages` a
+/// per-track list replaces the analyzed languages.
+pub fn check_import_languages(
+    streams: &mut [StreamInfo],
+    import_context: &ImportContext,
+    config: &Config,
+) {
+    for stream_type in [StreamType::Audio, StreamType::Subtitle] {
+        let Some(reported) = import_context.media_info_languages(stream_type) else {
+            continue;
+        };
+        let mut tracks: Vec<&mut StreamInfo> = streams
+            .iter_mut()
+            .filter(|s| s.stream_type == stream_type && s.external.is_none())
+            .collect();
+        let found: Vec<String> = tracks
+            .iter()
+            .map(|s| s.effective_language().to_lowercase())
+            .collect();
+
+        let per_track = reported.len() == found.len();
+        let agrees = if per_track {
+            reported == found
+        } else {
+            let distinct = |languages: &[String]| {
+                languages
+                    .iter()
+                    .collect::<std::collections::BTreeSet<_>>()
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+            distinct(&reported) == distinct(&found)
+        };
+        if agrees {
+            continue;
+        }
+
+        let kind = match stream_type {
+            StreamType::Audio => "audio",
+            _ => "subtitle",
+        };
+        report_warn!(
+            "⚠️  {} reports {} languages [{}], the file has [{}] - tracks may be mis-tagged",
+            import_context.app_name(),
+            kind,
+            reported.join(" / "),
+            found.join(" / ")
+        );
+
+        if !config.processing.trust_import_languages {
+            continue;
+        }
+        if !per_track {
+            report_warn!(
+                "⚠️  {} lists {} {} language(s) for {} track(s) - keeping the file's languages",
+                import_context.app_name(),
+                reported.len(),
+                kind,
+                found.len()
+            );
+            continue;
+        }
+        for (stream, language) in tracks.iter_mut().zip(reported) {
+            if stream.effective_language().to_lowercase() != language {
+                stream.language = Some(language);
+                stream.language_source = LanguageSource::Import;
+            }
+        }
+        report!(
+            "🔤 Using the {} languages reported by {}",
+            kind,
+            import_context.app_name()
+        );
+    }
+}
+
+/// Kept streams of the source file whose language should be written into the
+/// output (inferred or overridden ones), when `processing.tag_languages` is set.
+/// External tracks always get their language passed along.
+pub fn language_retags<'a>(
+    streams: &'a [StreamInfo],
+    decisions: &StreamDecisions,
+    config: &Config,
+) -> Vec<(u32, &'a str)> {
+    if !config.processing.tag_languages {
+        return Vec::new();
+    }
+    streams
+        .iter()
+        .filter(|s| s.external.is_none() && s.language_source != LanguageSource::Tag)
+        .filter(|s| decisions.get(s.index).is_some_and(|d| d.is_kept()))
+        .map(|s| (s.index, s.effective_language()))
+        .collect()
+}
+
+/// Under `processing.strict` a problem found while analyzing fails the file;
+/// otherwise it's printed as a warning and analysis continues best-effort
+fn analysis_problem(file_path: &Path, config: &Config, problem: &str) -> Result<()> {
+    if config.processing.strict {
+        return Err(processing_error(file_path, "Strict analysis", problem));
+    }
+    report_warn!("Warning: {}", problem);
+    record_warning(WarningKind::Analysis, problem);
+    Ok(())
+}
+
+/// Detect ordered chapters and segment linking with the matroska parser.
+/// Returns None when the file uses neither (or can't be parsed).
+pub fn analyze_segment_linking(file_path: &Path) -> Option<SegmentLinking> {
+    let file = std::fs::File::open(file_path).ok()?;
+    let mkv = matroska::Matroska::open(file).ok()?;
+
+    let own_uid = mkv.info.uid.as_deref();
+    let linking = SegmentLinking {
+        segment_uid: own_uid.map(hex_uid),
+        previous_uid: mkv.info.prev_uid.as_deref().map(hex_uid),
+        next_uid: mkv.info.next_uid.as_deref().map(hex_uid),
+        ordered_chapters: mkv.chapters.iter().any(|edition| edition.ordered),
+        external_chapters: mkv
+            .chapters
+            .iter()
+            .flat_map(|edition| &edition.chapters)
+            .any(|chapter| {
+                chapter
+                    .segment_uid
+                    .as_deref()
+                    .is_some_and(|uid| Some(uid) != own_uid)
+            }),
+    };
+
+    linking.is_linked().then_some(linking)
+}
+
+/// mkv-slimmer markers in the global tags of `file_path`. A file remuxed
+/// again keeps the markers of earlier runs next to the new one, so there can
+/// be several.
+pub fn read_slimmer_markers(file_path: &Path) -> Vec<SlimmerMarker> {
+    let Some(mkv) = std::fs::File::open(file_path)
+        .ok()
+        .and_then(|file| matroska::Matroska::open(file).ok())
+    else {
+        return Vec::new();
+    };
+
+    mkv.tags
+        .iter()
+        .filter(|tag| {
+            tag.targets.as_ref().is_none_or(|targets| {
+                targets.track_uids.is_empty()
+                    && targets.edition_uids.is_empty()
+                    && targets.chapter_uids.is_empty()
+                    && targets.attachment_uids.is_empty()
+            })
+        })
+        .filter_map(|tag| {
+            let values = tag
+                .simple
+                .iter()
+                .filter_map(|simple| match &simple.value {
+                    Some(matroska::TagValue::String(value)) => {
+                        Some((simple.name.clone(), value.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            SlimmerMarker::from_tags(&values)
+        })
+        .collect()
+}
+
+/// Map the analyzed streams of `file_path` onto the IDs mkvmerge selects
+/// tracks and attachments by. Without `mkvmerge -J` or the matroska parser the
+/// stream indices are taken as track IDs.
+pub async fn resolve_track_ids(file_path: &Path, streams: &[StreamInfo]) -> TrackIdMap {
+    let Some(known) = known_tracks(file_path).await else {
+        return TrackIdMap::from_streams(streams);
+    };
+    let track_ids = TrackIdMap::resolve(streams, &known);
+    if track_ids.differs() {
+        report!("🔢 Stream numbering differs from mkvmerge's track IDs - selecting tracks by ID");
+    }
+    track_ids
+}
+
+/// Format a segment UID the way mkvmerge's `--segment-uid` expects it
+fn hex_uid(uid: &[u8]) -> String {
+    uid.iter().map(|byte| format!("{:02x}", byte)).collect()
 }
 
 /// Process MKV streams using a ProcessingTask and global config/sonarr context
 /// This replaces MkvAnalyzer::process_streams()
 pub async fn process_mkv_streams(
     task: &crate::models::ProcessingTask,
+    decisions: StreamDecisions,
     config: &Config,
-    sonarr_context: Option<&SonarrContext>,
-) -> Result<()> {
-    // Determine streams to keep based on config
-    let streams_to_keep = determine_streams_to_keep(&task.streams, config);
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
+    // A file an earlier run slimmed with the same settings only needs its transfer
+    if config.processing.skip_marked {
+        let hash = config_hash(config);
+        if let Some(marker) = read_slimmer_markers(&task.source_file)
+            .into_iter()
+            .find(|marker| marker.config_hash == hash)
+        {
+            let date = marker
+                .date
+                .map(|date| format!(" on {}", date))
+                .unwrap_or_default();
+            report!(
+                "🏷️  Already slimmed with these settings (mkv-slimmer {}{}) - not remuxing",
+                marker.version,
+                date
+            );
+            return handle_no_processing_needed_task(task, config, import_context, control).await;
+        }
+    }
 
-    // Check if we need to do any processing
-    let all_stream_indices: Vec<u32> = task.streams.iter().map(|s| s.index).collect();
-    let needs_processing =
-        streams_to_keep.len() != all_stream_indices.len() || streams_to_keep != all_stream_indices;
+    let streams_to_keep = decisions.kept_indices();
+
+    let context = FileContext {
+        source_file: &task.source_file,
+        streams: &task.streams,
+        media_kind: task.media_kind(),
+        config,
+        import_context,
+    };
+    let mut problems = degenerate_output_problems(&context, &decisions);
+    problems.extend(no_match_failures(&context, &decisions));
+    if !problems.is_empty() {
+        if !config.processing.force && !config.processing.dry_run {
+            return Err(processing_error(
+                &task.source_file,
+                "Sanity check",
+                &format!(
+                    "{}.\n   💡 Check the language settings, or use --force to process it anyway",
+                    problems.join("; ")
+                ),
+            ));
+        }
+        for problem in &problems {
+            report!("⚠️  Output check: {}", problem);
+        }
+    }
+
+    if config.processing.flags_only {
+        return normalize_flags_task(task, &decisions, config, import_context, control).await;
+    }
+
+    let webm_output = config.processing.container == ContainerFormat::Webm;
+    if webm_output {
+        let dropped_attachments = decisions
+            .streams
+            .iter()
+            .filter(|d| d.reason == DecisionReason::UnsupportedByContainer)
+            .count();
+        if dropped_attachments > 0 {
+            report!(
+                "ℹ️  Dropping {} attachment(s): WebM cannot contain attachments",
+                dropped_attachments
+            );
+        }
+        check_webm_compatibility(task, &streams_to_keep)?;
+    }
+
+    let split_subtitles =
+        task.media_kind() == MediaKind::SubtitlesOnly && config.subtitles.split_by_language;
 
-    if !needs_processing {
+    let merges_external = task
+        .streams
+        .iter()
+        .any(|s| s.external.is_some() && streams_to_keep.contains(&s.index));
+    let retags_languages = !language_retags(&task.streams, &decisions, config).is_empty();
+    let rewrites_titles = !title_rewrites(&task.streams, &decisions, &config.titles).is_empty();
+
+    let mut extras = MuxExtras::default();
+    if config.subtitles.fix_charset {
+        extras.sub_charsets = detect_subtitle_charsets(task, &streams_to_keep, config)?;
+    }
+    extras.attachments = resolve_attachments(config, import_context);
+    if webm_output && !extras.attachments.is_empty() {
+        report!(
+            "ℹ️  Not attaching {} file(s): WebM cannot contain attachments",
+            extras.attachments.len()
+        );
+        extras.attachments.clear();
+    }
+
+    // Check if we need to do any processing (WebM output, subtitle splitting,
+    // merging external tracks, scrubbing, charset fixes, added attachments and
+    // stripping chapters always need a remux, as do language tag corrections
+    // and rewritten track titles)
+    let all_stream_indices: Vec<u32> = task.streams.iter().map(|s| s.index).collect();
+    let strips_chapters = config.processing.chapters == ChapterMode::Strip && task.has_chapters();
+    let other_changes = webm_output
+        || config.processing.scrub
+        || strips_chapters
+        || !extras.sub_charsets.is_empty()
+        || !extras.attachments.is_empty()
+        || split_subtitles
+        || merges_external
+        || retags_languages
+        || rewrites_titles;
+    let needs_processing = other_changes || streams_to_keep != all_stream_indices;
+
+    // Only flags change: edit them on a copy instead of rewriting the file,
+    // or remux after all when mkvpropedit isn't installed
+    let flags_change = !needs_processing && flags_differ(&task.streams, &decisions, config);
+    if flags_change && tool_available("mkvpropedit") {
+        report!("🚩 Only default/forced flags change - setting them with mkvpropedit");
+        return normalize_flags_task(task, &decisions, config, import_context, control).await;
+    }
+    if flags_change {
+        report!("ℹ️  Only default/forced flags change, but mkvpropedit isn't installed - remuxing");
+    } else if !needs_processing {
         // No processing needed, just copy/hardlink
-        let _output_path = task.generate_output_path()?;
-        return handle_no_processing_needed_task(task, config, sonarr_context).await;
+        return handle_no_processing_needed_task(task, config, import_context, control).await;
     }
 
-    let output_path = task.generate_output_path()?;
+    // Marginal remuxes are skipped when the sizes of the removed streams show
+    // the floor can't be met; the real saving is checked after the remux
+    if let Some(floor) = config.processing.min_savings
+        && let Some(removed_bytes) = decisions.known_removed_bytes()
+        && let Ok(metadata) = std::fs::metadata(&task.source_file)
+        && !floor.is_met(metadata.len(), metadata.len().saturating_sub(removed_bytes))
+    {
+        report!(
+            "ℹ️  Removing streams would save about {} (floor: {}) - not remuxing",
+            format_size(removed_bytes),
+            floor
+        );
+        return transfer_below_min_savings(task, removed_bytes, config, import_context, control)
+            .await;
+    }
+
+    // An output left by an earlier run that already has the planned tracks is kept
+    let output_path = resolve_output_path(task, config)?;
+    if !split_subtitles
+        && !task.is_in_place()
+        && output_is_up_to_date(&output_path, &task.streams, &decisions, config).await
+    {
+        report!("✅ Up to date: {}", output_path.display());
+        return Ok(ProcessingOutcome::UpToDate {
+            output_path,
+            decisions,
+        });
+    }
+
+    // Dropping attachments (fonts, cover art) alone doesn't need a remux
+    if !other_changes
+        && let Some(attachment_ids) =
+            attachment_removal_fast_path(task, &decisions, config, import_context)
+    {
+        report!("📎 Only attachments are removed - deleting them with mkvpropedit");
+        return remove_attachments_task(
+            task,
+            decisions,
+            &attachment_ids,
+            output_path,
+            config,
+            import_context,
+            control,
+        )
+        .await;
+    }
+
+    // Remuxing can break playback of files that rely on their segment structure
+    if let Some(linking) = &task.segment_linking {
+        if !config.processing.allow_ordered_chapters {
+            return Err(processing_error(
+                &task.source_file,
+                "Segment linking check",
+                &format!(
+                    "File uses {}; remuxing it may break playback.\n   💡 Use --allow-ordered-chapters to process it anyway (segment UIDs are preserved)",
+                    linking.describe()
+                ),
+            ));
+        }
+        report!(
+            "⚠️  File uses {} - preserving segment UIDs, check playback of the output",
+            linking.describe()
+        );
+    }
+
+    // Fail now rather than with a half-written output and a cryptic mkvmerge error
+    check_remux_space(task, &decisions, &output_path, config)?;
+
+    let removed = removed_tracks(&task.streams, &decisions);
+    extras.global_tags = write_global_tags_file(&output_path, &removed, config, import_context)?;
+    extras.title = config
+        .tags
+        .title
+        .as_deref()
+        .and_then(|template| render_placeholders(template, import_context));
+    if config.tags.title.is_some() && extras.title.is_none() {
+        report!(
+            "ℹ️  Not setting the segment title: its template needs Sonarr/Radarr metadata this run doesn't have"
+        );
+    }
+
+    if split_subtitles {
+        let result = split_subtitles_by_language(
+            task,
+            &decisions,
+            &output_path,
+            &extras,
+            config,
+            import_context,
+            control,
+        )
+        .await;
+        remove_global_tags_file(extras.global_tags.as_deref(), config);
+        let (output_paths, log_paths) = result?;
+
+        if config.processing.dry_run {
+            report!("✅ Dry-run completed successfully!");
+            return Ok(ProcessingOutcome::DryRun {
+                output_path,
+                needs_remux: true,
+            });
+        }
+
+        // Handle Sonarr/Radarr communication
+        if import_context.is_some() {
+            output_sonarr_move_status(SonarrMoveStatus::RenameRequested);
+        }
+
+        return Ok(ProcessingOutcome::Split {
+            output_paths,
+            decisions,
+            log_paths,
+        });
+    }
 
     // Build and execute mkvmerge command
-    let mut cmd = build_mkvmerge_command_for_task(task, &streams_to_keep, &output_path, config)?;
+    let cmd = build_mkvmerge_command_for_task(task, &decisions, &output_path, &extras, config)?;
 
     // Check for dry-run mode before executing
     if config.processing.dry_run {
-        println!(
+        report!(
             "🚧 Dry-run mode: Would execute mkvmerge to create: {}",
             output_path.display()
         );
-        println!("🚧 Dry-run mode: Command: '{:?}'", cmd);
-        println!("✅ Dry-run completed successfully!");
-        return Ok(());
+        report!("🚧 Dry-run mode: Command: {}", cmd.command_line());
+        write_mkvmerge_options(&cmd, &output_path, config)?;
+        report!("✅ Dry-run completed successfully!");
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: true,
+        });
+    }
+
+    print_mkvmerge_command(&cmd, config);
+    // In place, mkvmerge still reads the source: back it up without removing it
+    if task.is_in_place() {
+        backup_file(&output_path, true, config)?;
+    } else {
+        backup_replaced_file(&output_path, config)?;
+    }
+    let log_path = mkvmerge_log_path(&output_path, config)?;
+    let monitor = remux_monitor(task, config);
+    let min_savings = config
+        .processing
+        .min_savings
+        .map(|floor| (task.source_file.as_path(), floor));
+    let result = run_mkvmerge(
+        cmd,
+        &output_path,
+        log_path.as_deref(),
+        monitor,
+        min_savings,
+        control,
+    )
+    .await;
+    remove_global_tags_file(extras.global_tags.as_deref(), config);
+    if let Some(saved_bytes) = result? {
+        return transfer_below_min_savings(task, saved_bytes, config, import_context, control)
+            .await;
+    }
+    if config.processing.scrub {
+        scrub_application_info(&output_path)?;
+    }
+    verify_output_durations(task, &decisions, &output_path, config).await;
+    apply_output_permissions(&output_path, config)?;
+    if config.processing.removed_manifest == Some(RemovedManifest::Sidecar) && !removed.is_empty() {
+        let manifest = write_removed_manifest(&task.source_file, &output_path, &removed)?;
+        apply_output_permissions(&manifest, config)?;
+        report!("📋 Removed tracks: {}", manifest.display());
+    }
+
+    report!("✅ Successfully processed: {}", output_path.display());
+    if let Some(log_path) = &log_path {
+        report!("📝 mkvmerge log: {}", log_path.display());
+    }
+
+    // Handle Sonarr/Radarr communication
+    if import_context.is_some() {
+        output_sonarr_move_status(SonarrMoveStatus::RenameRequested);
+    }
+
+    Ok(ProcessingOutcome::Remuxed {
+        output_path,
+        decisions,
+        log_path,
+    })
+}
+
+/// Bytes a finished partial output saves over the source when that is
+/// below the floor; None when it saves enough, there is no floor or either
+/// size can't be read
+fn below_min_savings(min_savings: Option<(&Path, SavingsFloor)>, partial: &Path) -> Option<u64> {
+    let (source, floor) = min_savings?;
+    let source_bytes = std::fs::metadata(source).ok()?.len();
+    let output_bytes = std::fs::metadata(partial).ok()?.len();
+    if floor.is_met(source_bytes, output_bytes) {
+        return None;
+    }
+    let saved_bytes = source_bytes.saturating_sub(output_bytes);
+    report!(
+        "ℹ️  Remux saved {} (floor: {}) - keeping the original",
+        format_size(saved_bytes),
+        floor
+    );
+    Some(saved_bytes)
+}
+
+/// Render the configured tag template, plus the marker tags with
+/// `processing.marker_tags`, and write it next to the output for
+/// mkvmerge's `--global-tags`. Returns the file's path, or None when there are
+/// no tags to write. In dry-run mode the path is returned without writing,
+/// unless options files are written: then the tags go next to them.
+fn write_global_tags_file(
+    output_path: &Path,
+    removed: &[RemovedTrack],
+    config: &Config,
+    import_context: Option<&ImportContext>,
+) -> Result<Option<PathBuf>> {
+    let mut tags = render_tag_template(&config.tags, import_context);
+    if config.processing.marker_tags {
+        tags.extend(SlimmerMarker::current(config).tags());
+    }
+    if config.processing.removed_manifest == Some(RemovedManifest::Tag) && !removed.is_empty() {
+        tags.insert(REMOVED_TRACKS_TAG.to_string(), removed_tracks_tag(removed));
+    }
+    if tags.is_empty() {
+        return Ok(None);
+    }
+
+    if config.processing.dry_run
+        && let Some(options_dir) = &config.processing.options_dir
+    {
+        let filename = output_path
+            .file_name()
+            .context("Could not extract filename from output path")?;
+        let tags_file = global_tags_path(&options_dir.join(filename));
+        std::fs::create_dir_all(options_dir).with_context(|| {
+            format!(
+                "Failed to create options directory: {}",
+                options_dir.display()
+            )
+        })?;
+        std::fs::write(&tags_file, global_tags_xml(&tags))
+            .with_context(|| format!("Failed to write tags file: {}", tags_file.display()))?;
+        return Ok(Some(tags_file));
+    }
+
+    let tags_file = global_tags_path(output_path);
+    if !config.processing.dry_run {
+        std::fs::write(&tags_file, global_tags_xml(&tags))
+            .with_context(|| format!("Failed to write tags file: {}", tags_file.display()))?;
+    }
+    Ok(Some(tags_file))
+}
+
+fn remove_global_tags_file(tags_file: Option<&Path>, config: &Config) {
+    if let Some(tags_file) = tags_file
+        && !config.processing.dry_run
+    {
+        let _ = std::fs::remove_file(tags_file);
+    }
+}
+
+/// Execute a prepared mkvmerge command, reporting its progress and turning a
+/// non-zero exit into an error carrying mkvmerge's output. mkvmerge writes to
+/// a partial file (`partial_path()`) that replaces the output once it is
+/// complete; on failure or cancellation it is removed. Long commands are
+/// passed through a temporary option file (`mkvmerge @FILE`) that is removed
+/// afterwards.
+///
+/// With `min_savings` (the source and `processing.min_savings`), a complete
+/// partial file that doesn't save enough is discarded before it replaces
+/// anything, so the original stays untouched even in place; its savings are
+/// returned.
+async fn run_mkvmerge(
+    cmd: MkvmergeCommand,
+    output_path: &Path,
+    log_path: Option<&Path>,
+    monitor: Option<ThroughputMonitor>,
+    min_savings: Option<(&Path, SavingsFloor)>,
+    control: &ProcessControl,
+) -> Result<Option<u64>> {
+    let partial = partial_path(output_path);
+    let cmd = cmd.with_output(&partial);
+    let command_line = cmd.command_line();
+    let option_file = write_option_file_if_long(&cmd, output_path)?;
+    let cmd = match &option_file {
+        Some(option_file) => {
+            let mut short = MkvmergeCommand::new();
+            short.arg(format!("@{}", option_file.display()));
+            short.to_command()
+        }
+        None => cmd.to_command(),
+    };
+
+    let result = execute_mkvmerge(cmd, &command_line, &partial, log_path, monitor, control)
+        .await
+        .and_then(|()| match below_min_savings(min_savings, &partial) {
+            Some(saved_bytes) => {
+                std::fs::remove_file(&partial).with_context(|| {
+                    format!("Failed to discard remuxed output: {}", partial.display())
+                })?;
+                Ok(Some(saved_bytes))
+            }
+            None => finish_partial(&partial, output_path).map(|()| None),
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
     }
+    if let Some(option_file) = option_file {
+        let _ = std::fs::remove_file(option_file);
+    }
+    result
+}
 
-    let output = cmd
-        .output()
+/// Total argument length from which mkvmerge is run through an option file;
+/// well below the 8191 characters cmd.exe and Windows tooling accept
+const OPTION_FILE_THRESHOLD: usize = 4096;
+
+/// Write the arguments of `cmd` to a temporary JSON option file when they are
+/// too long for a safe command line. Returns None (run as is) for short commands
+/// and for arguments that aren't UTF-8, which JSON can't carry.
+fn write_option_file_if_long(cmd: &MkvmergeCommand, output_path: &Path) -> Result<Option<PathBuf>> {
+    let length: usize = cmd.args().iter().map(|arg| arg.len() + 1).sum();
+    if length < OPTION_FILE_THRESHOLD {
+        return Ok(None);
+    }
+    let Some(args) = cmd.option_args() else {
+        return Ok(None);
+    };
+
+    let filename = output_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let option_file = std::env::temp_dir().join(format!(
+        "mkv-slimmer-{}-{}.options.json",
+        std::process::id(),
+        filename
+    ));
+    std::fs::write(&option_file, serde_json::to_string(&args)?).with_context(|| {
+        format!(
+            "Failed to write mkvmerge option file: {}",
+            option_file.display()
+        )
+    })?;
+    Ok(Some(option_file))
+}
+
+async fn execute_mkvmerge(
+    cmd: Command,
+    command_line: &str,
+    output_path: &Path,
+    log_path: Option<&Path>,
+    mut monitor: Option<ThroughputMonitor>,
+    control: &ProcessControl,
+) -> Result<()> {
+    let mut child = tokio::process::Command::from(cmd)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
         .with_context(|| "Failed to execute mkvmerge command")?;
 
-    if !output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = child
+        .stdout
+        .take()
+        .context("mkvmerge stdout was not captured")?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("mkvmerge stderr was not captured")?;
+    let stderr_reader = tokio::spawn(async move {
+        let mut buffer = String::new();
+        let _ = stderr.read_to_string(&mut buffer).await;
+        buffer
+    });
+
+    let mut lines = BufReader::new(stdout).lines();
+    let mut stdout_lines = Vec::new();
+    let status = loop {
+        tokio::select! {
+            _ = control.cancellation().cancelled() => {
+                let _ = child.kill().await;
+                let _ = tokio::fs::remove_file(output_path).await;
+                return Err(Cancelled.into());
+            }
+            line = lines.next_line() => match line.context("Failed to read mkvmerge output")? {
+                Some(line) => match parse_mkvmerge_progress(&line) {
+                    Some(percent) => {
+                        control.report(ProgressEvent::Remuxing { percent });
+                        if let Some(monitor) = &mut monitor
+                            && let Some(rate) = monitor.record_percent(percent, Instant::now())
+                        {
+                            warn_slow_throughput(monitor.settings(), "Remux", output_path, rate);
+                        }
+                    }
+                    None => stdout_lines.push(line),
+                },
+                None => break child.wait().await.context("Failed to wait for mkvmerge")?,
+            }
+        }
+    };
+
+    let stderr = stderr_reader.await.unwrap_or_default();
+    if let Some(log_path) = log_path {
+        write_mkvmerge_log(log_path, command_line, &status, &stdout_lines, &stderr)?;
+    }
+
+    // Exit code 1: the output is complete, but mkvmerge warned about something
+    if status.code() == Some(1) {
+        let warnings: Vec<&str> = stdout_lines
+            .iter()
+            .filter_map(|line| mkvmerge_warning(line))
+            .collect();
+        if warnings.is_empty() {
+            report_warn!("⚠️  mkvmerge finished with warnings");
+            record_warning(WarningKind::Mkvmerge, "finished with warnings");
+        }
+        for warning in warnings {
+            report_warn!("⚠️  mkvmerge: {}", warning);
+            record_warning(WarningKind::Mkvmerge, warning);
+        }
+        return Ok(());
+    }
+
+    if !status.success() {
         return Err(anyhow::anyhow!(
             "mkvmerge failed with exit code {:?}, termination signal {:?}, stop signal {:?}:\n{}\n{}",
-            output.status.code(),
-            output.status.signal(),
-            output.status.stopped_signal(),
-            stdout,
+            status.code(),
+            status.signal(),
+            status.stopped_signal(),
+            stdout_lines.join("\n"),
             stderr
         ));
     }
 
-    println!("✅ Successfully processed: {}", output_path.display());
+    Ok(())
+}
 
-    // Handle Sonarr communication
-    if sonarr_context.is_some() {
-        output_sonarr_move_status(SonarrMoveStatus::RenameRequested);
+/// Throughput monitor for remuxing the task's source, when warnings are configured
+fn remux_monitor(
+    task: &crate::models::ProcessingTask,
+    config: &Config,
+) -> Option<ThroughputMonitor> {
+    let settings = config.processing.throughput_warning.as_ref()?;
+    let source_bytes = std::fs::metadata(&task.source_file).ok()?.len();
+    Some(ThroughputMonitor::new(
+        settings,
+        source_bytes,
+        Instant::now(),
+    ))
+}
+
+/// Where the mkvmerge output for `output_path` is saved: `<log_dir>/<output
+/// filename>.mkvmerge.log`, or None when no log directory is configured
+fn mkvmerge_log_path(output_path: &Path, config: &Config) -> Result<Option<PathBuf>> {
+    let Some(log_dir) = &config.processing.log_dir else {
+        return Ok(None);
+    };
+    let filename = output_path
+        .file_name()
+        .context("Could not extract filename from output path")?
+        .to_string_lossy();
+    Ok(Some(log_dir.join(format!("{}.mkvmerge.log", filename))))
+}
+
+/// Save the command line, exit status and full output of an mkvmerge run
+fn write_mkvmerge_log(
+    log_path: &Path,
+    command_line: &str,
+    status: &std::process::ExitStatus,
+    stdout_lines: &[String],
+    stderr: &str,
+) -> Result<()> {
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create log directory: {}", parent.display()))?;
     }
+    let log = format!(
+        "Command: {}\nExit status: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+        command_line,
+        status,
+        stdout_lines.join("\n"),
+        stderr
+    );
+    std::fs::write(log_path, log)
+        .with_context(|| format!("Failed to write mkvmerge log: {}", log_path.display()))
+}
 
+/// In dry-run mode with `processing.options_dir`, save the mkvmerge command
+/// that would create `output_path` as `<options_dir>/<output
+/// filename>.options.json`, a JSON array of its arguments that `mkvmerge
+/// @FILE` runs as it is. `--gui-mode` is left out: it only serves our progress
+/// parsing.
+fn write_mkvmerge_options(
+    cmd: &MkvmergeCommand,
+    output_path: &Path,
+    config: &Config,
+) -> Result<()> {
+    let Some(options_dir) = &config.processing.options_dir else {
+        return Ok(());
+    };
+    let filename = output_path
+        .file_name()
+        .context("Could not extract filename from output path")?
+        .to_string_lossy();
+    let options_path = options_dir.join(format!("{}.options.json", filename));
+
+    let mut args = cmd.option_args().with_context(|| {
+        format!(
+            "mkvmerge options files need UTF-8 arguments: {}",
+            output_path.display()
+        )
+    })?;
+    args.retain(|arg| arg != "--gui-mode");
+
+    std::fs::create_dir_all(options_dir).with_context(|| {
+        format!(
+            "Failed to create options directory: {}",
+            options_dir.display()
+        )
+    })?;
+    let json = serde_json::to_string_pretty(&args)?;
+    std::fs::write(&options_path, json + "\n").with_context(|| {
+        format!(
+            "Failed to write mkvmerge options: {}",
+            options_path.display()
+        )
+    })?;
+    report!("📄 mkvmerge options: {}", options_path.display());
     Ok(())
 }
 
+/// With `processing.print_command`, show the mkvmerge command line about to
+/// run (dry runs always show it). mkv-slimmer itself points `-o` at a partial
+/// file that replaces the output once complete.
+fn print_mkvmerge_command(cmd: &MkvmergeCommand, config: &Config) {
+    if config.processing.print_command {
+        report!("💻 mkvmerge command: {}", cmd.command_line());
+    }
+}
+
+/// The text of an mkvmerge warning line (`#GUI#warning ...` in `--gui-mode`)
+fn mkvmerge_warning(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix("#GUI#warning ")
+        .or_else(|| line.strip_prefix("Warning: "))
+        .map(str::trim)
+}
+
+/// Parse a `--gui-mode` progress line (`#GUI#progress 42%`)
+fn parse_mkvmerge_progress(line: &str) -> Option<u8> {
+    line.trim()
+        .strip_prefix("#GUI#progress ")?
+        .strip_suffix('%')?
+        .parse()
+        .ok()
+}
+
+/// Write one output per subtitle language (`name.<lang>.mks`), each holding the
+/// kept subtitle tracks of that language plus any other kept streams.
+/// Returns the paths of the outputs (not written in dry-run mode) and of their
+/// mkvmerge logs.
+async fn split_subtitles_by_language(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    output_path: &Path,
+    extras: &MuxExtras,
+    config: &Config,
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let streams_to_keep = decisions.kept_indices();
+    let kept_streams: Vec<&StreamInfo> = task
+        .streams
+        .iter()
+        .filter(|s| streams_to_keep.contains(&s.index))
+        .collect();
+
+    // Languages in stream order, so outputs are created in a predictable order
+    let mut languages: Vec<&str> = Vec::new();
+    for stream in &kept_streams {
+        let lang = stream.effective_language();
+        if stream.stream_type == StreamType::Subtitle && !languages.contains(&lang) {
+            languages.push(lang);
+        }
+    }
+
+    if languages.is_empty() {
+        return Err(processing_error(
+            &task.source_file,
+            "Subtitle split",
+            "No subtitle tracks are left to split after applying subtitle preferences",
+        ));
+    }
+
+    let mut output_paths = Vec::new();
+    let mut log_paths = Vec::new();
+    for lang in languages {
+        let tracks: Vec<u32> = kept_streams
+            .iter()
+            .filter(|s| s.stream_type != StreamType::Subtitle || s.effective_language() == lang)
+            .map(|s| s.index)
+            .collect();
+        let language_path = language_output_path(output_path, lang);
+        let language_decisions =
+            decisions_for_tracks(task, decisions, &tracks, config, import_context);
+        let cmd = build_mkvmerge_command_for_task(
+            task,
+            &language_decisions,
+            &language_path,
+            extras,
+            config,
+        )?;
+
+        if config.processing.dry_run {
+            report!(
+                "🚧 Dry-run mode: Would execute mkvmerge to create: {}",
+                language_path.display()
+            );
+            report!("🚧 Dry-run mode: Command: {}", cmd.command_line());
+            write_mkvmerge_options(&cmd, &language_path, config)?;
+        } else {
+            print_mkvmerge_command(&cmd, config);
+            backup_replaced_file(&language_path, config)?;
+            let log_path = mkvmerge_log_path(&language_path, config)?;
+            let monitor = remux_monitor(task, config);
+            run_mkvmerge(
+                cmd,
+                &language_path,
+                log_path.as_deref(),
+                monitor,
+                None,
+                control,
+            )
+            .await?;
+            log_paths.extend(log_path);
+            if config.processing.scrub {
+                scrub_application_info(&language_path)?;
+            }
+            apply_output_permissions(&language_path, config)?;
+            report!("✅ Successfully processed: {}", language_path.display());
+        }
+        output_paths.push(language_path);
+    }
+
+    Ok((output_paths, log_paths))
+}
+
+/// Insert the language before the extension: `subs.mks` → `subs.eng.mks`
+fn language_output_path(output_path: &Path, language: &str) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let filename = match output_path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, language, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, language),
+    };
+    output_path.with_file_name(filename)
+}
+
+/// Output path for the task, with the extension matching the configured container.
+/// An explicit target file must already carry the right extension.
+fn resolve_output_path(task: &ProcessingTask, config: &Config) -> Result<PathBuf> {
+    let output_path = task.generate_output_path()?;
+    let container = config.processing.container;
+    if container == ContainerFormat::Mkv {
+        return Ok(output_path);
+    }
+
+    if task.output_filename.is_some() {
+        let has_extension = output_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(container.extension()));
+        if !has_extension {
+            return Err(file_validation_error(
+                &output_path,
+                &format!(
+                    "Target file must use the .{} extension for {:?} output",
+                    container.extension(),
+                    container
+                ),
+            ));
+        }
+        Ok(output_path)
+    } else {
+        Ok(output_path.with_extension(container.extension()))
+    }
+}
+
+/// Codecs WebM allows, per stream type (ffprobe codec names)
+const WEBM_VIDEO_CODECS: &[&str] = &["vp8", "vp9", "av1"];
+const WEBM_AUDIO_CODECS: &[&str] = &["opus", "vorbis"];
+const WEBM_SUBTITLE_CODECS: &[&str] = &["webvtt"];
+
+/// Fail with a clear message if any kept track can't be stored in WebM
+fn check_webm_compatibility(task: &ProcessingTask, streams_to_keep: &[u32]) -> Result<()> {
+    let incompatible: Vec<String> = task
+        .streams
+        .iter()
+        .filter(|s| streams_to_keep.contains(&s.index))
+        .filter(|s| {
+            let allowed = match s.stream_type {
+                StreamType::Video => WEBM_VIDEO_CODECS,
+                StreamType::Audio => WEBM_AUDIO_CODECS,
+                StreamType::Subtitle => WEBM_SUBTITLE_CODECS,
+                StreamType::Attachment | StreamType::Unknown => &[],
+            };
+            !allowed.contains(&s.codec.to_lowercase().as_str())
+        })
+        .map(|s| format!("#{} {} ({})", s.index, s.stream_type, s.codec))
+        .collect();
+
+    if incompatible.is_empty() {
+        return Ok(());
+    }
+
+    Err(processing_error(
+        &task.source_file,
+        "WebM codec compatibility",
+        &format!(
+            "Kept tracks use codecs WebM doesn't support: {}\n   WebM allows VP8/VP9/AV1 video, Opus/Vorbis audio and WebVTT subtitles",
+            incompatible.join(", ")
+        ),
+    ))
+}
+
 /// Handle no processing needed scenario for ProcessingTask
 /// This replaces MkvAnalyzer::handle_no_processing_needed()
 pub async fn handle_no_processing_needed_task(
     task: &crate::models::ProcessingTask,
     config: &Config,
-    sonarr_context: Option<&SonarrContext>,
-) -> Result<()> {
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
     let output_path = task.generate_output_path()?;
 
+    // An in-place run leaves an unchanged source where it is
+    if task.is_in_place() {
+        if config.processing.dry_run {
+            report!("🔍 Dry run: Nothing to change in {}", output_path.display());
+            return Ok(ProcessingOutcome::DryRun {
+                output_path,
+                needs_remux: false,
+            });
+        }
+        report!("✅ Nothing to change: {}", output_path.display());
+        return Ok(ProcessingOutcome::Transferred {
+            output_path,
+            method: TransferMethod::InPlace,
+        });
+    }
+
     if config.processing.dry_run {
-        println!(
-            "🔍 Dry run: Would copy {} to {}",
+        let (method, detail) =
+            predict_transfer(&task.source_file, &output_path, import_context, true);
+        report!(
+            "🔍 Dry run: Would {} {} to {} ({})",
+            transfer_verb(method),
             task.source_file.display(),
-            output_path.display()
+            output_path.display(),
+            detail
         );
-        return Ok(());
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: false,
+        });
     }
 
-    // Determine transfer mode from Sonarr context
-    let transfer_mode = sonarr_context
-        .and_then(|ctx| ctx.transfer_mode.as_deref())
-        .unwrap_or("HardLinkOrCopy");
+    let method = timed_transfer(task, &output_path, import_context, true, config, control).await?;
+
+    // A hard link shares the source's inode, so changing it would change the source
+    if method != TransferMethod::HardLinked {
+        apply_output_permissions(&output_path, config)?;
+    }
+
+    // Handle Sonarr communication
+    if import_context.is_some() {
+        output_sonarr_move_status(SonarrMoveStatus::MoveComplete);
+    }
+
+    Ok(ProcessingOutcome::Transferred {
+        output_path,
+        method,
+    })
+}
+
+/// Flags-only processing (`--flags-only`, or a file that keeps every track but
+/// needs other flags): copy (or move) the source to the output and set the
+/// default/forced flags of its audio and subtitle tracks with mkvpropedit
+async fn normalize_flags_task(
+    task: &crate::models::ProcessingTask,
+    decisions: &StreamDecisions,
+    config: &Config,
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
+    let output_path = task.generate_output_path()?;
+    if task.streams.iter().any(|s| s.external.is_some()) {
+        report!("ℹ️  Not merging external tracks: flags-only runs don't remux");
+    }
+
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(&output_path);
+    for stream in task.streams.iter().filter(|s| {
+        s.external.is_none() && matches!(s.stream_type, StreamType::Audio | StreamType::Subtitle)
+    }) {
+        let is_default = decisions.get(stream.index).is_some_and(|d| d.default);
+        cmd.arg("--edit")
+            .arg(task.track_ids.propedit_selector(stream.index))
+            .arg("--set")
+            .arg(format!("flag-default={}", if is_default { 1 } else { 0 }))
+            .arg("--set")
+            .arg(format!(
+                "flag-forced={}",
+                if keeps_forced_flag(stream, config) {
+                    1
+                } else {
+                    0
+                }
+            ));
+    }
+
+    if config.processing.dry_run && task.is_in_place() {
+        report!("🚧 Dry-run mode: Would run: {}", command_line(&cmd));
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: false,
+        });
+    }
+    if config.processing.dry_run {
+        let (method, detail) =
+            predict_transfer(&task.source_file, &output_path, import_context, false);
+        report!(
+            "🚧 Dry-run mode: Would {} {} to {} ({}) and run: {}",
+            transfer_verb(method),
+            task.source_file.display(),
+            output_path.display(),
+            detail,
+            command_line(&cmd)
+        );
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: false,
+        });
+    }
+    require_mkvtoolnix_tool("mkvpropedit")?;
+
+    // The output is edited in place, so it must not share the source's inode
+    // (in-place runs edit the source itself, after a copy for the backup)
+    let method = if task.is_in_place() {
+        backup_file(&output_path, false, config)?;
+        TransferMethod::InPlace
+    } else {
+        timed_transfer(task, &output_path, import_context, false, config, control).await?
+    };
+    run_tool(&mut cmd, &output_path, "Flag normalization")?;
+    report!(
+        "🚩 Normalized default/forced flags: {}",
+        output_path.display()
+    );
+    apply_output_permissions(&output_path, config)?;
+
+    if import_context.is_some() {
+        output_sonarr_move_status(SonarrMoveStatus::MoveComplete);
+    }
+
+    Ok(ProcessingOutcome::FlagsNormalized {
+        output_path,
+        method,
+        decisions: decisions.clone(),
+    })
+}
+
+/// mkvmerge attachment IDs to delete with mkvpropedit instead of remuxing,
+/// when only attachments of the source are removed and nothing else a remux
+/// would do changes. mkvpropedit may leave their space as padding, so a
+/// savings floor rules the fast path out, as do settings only a remux
+/// applies (tags, title).
+pub(crate) fn attachment_removal_fast_path(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    config: &Config,
+    import_context: Option<&ImportContext>,
+) -> Option<Vec<u32>> {
+    if config.processing.min_savings.is_some()
+        || remux_only_settings(config, import_context)
+        || flags_differ(&task.streams, decisions, config)
+        || !tool_available("mkvpropedit")
+    {
+        return None;
+    }
+    attachment_only_removals(task, decisions)
+}
+
+/// mkvmerge attachment IDs of the removed streams, in ascending order, when
+/// they are all attachments of the source; None if any other stream is
+/// removed, an attachment ID isn't known or nothing is removed
+fn attachment_only_removals(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+) -> Option<Vec<u32>> {
+    let mut ids = task
+        .streams
+        .iter()
+        .filter(|s| decisions.get(s.index).is_some_and(|d| !d.is_kept()))
+        .map(|s| {
+            if s.external.is_some() || s.container_type() != StreamType::Attachment {
+                return None;
+            }
+            task.track_ids.attachment_id(s.index)
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    ids.sort_unstable();
+    (!ids.is_empty()).then_some(ids)
+}
 
-    match transfer_mode {
+/// Whether settings are in effect that only a remux writes: global tags
+/// (template, marker tags, the removed-tracks tag), the segment title and
+/// stripped tags
+fn remux_only_settings(config: &Config, import_context: Option<&ImportContext>) -> bool {
+    !render_tag_template(&config.tags, import_context).is_empty()
+        || config.processing.marker_tags
+        || config.processing.removed_manifest == Some(RemovedManifest::Tag)
+        || config.tags.title.is_some()
+        || config.tags.clear_title
+        || config.tags.strip_global
+        || config.tags.strip_track
+}
+
+/// Delete attachments with mkvpropedit from a copy (or the moved source)
+/// instead of remuxing, when they are all that changes
+async fn remove_attachments_task(
+    task: &ProcessingTask,
+    decisions: StreamDecisions,
+    attachment_ids: &[u32],
+    output_path: PathBuf,
+    config: &Config,
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(&output_path);
+    // Highest ID first, so no deletion renumbers one still to come
+    for id in attachment_ids.iter().rev() {
+        cmd.arg("--delete-attachment").arg(id.to_string());
+    }
+
+    if config.processing.dry_run && task.is_in_place() {
+        report!("🚧 Dry-run mode: Would run: {}", command_line(&cmd));
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: false,
+        });
+    }
+    // Only a move within the filesystem takes no space on the target
+    let directory = output_path.parent().unwrap_or(Path::new("."));
+    if !task.is_in_place()
+        && (effective_transfer_mode(import_context, false) != "Move"
+            || !same_filesystem(&task.source_file, directory))
+    {
+        check_copy_space(task, &output_path, config)?;
+    }
+    if config.processing.dry_run {
+        let (method, detail) =
+            predict_transfer(&task.source_file, &output_path, import_context, false);
+        report!(
+            "🚧 Dry-run mode: Would {} {} to {} ({}) and run: {}",
+            transfer_verb(method),
+            task.source_file.display(),
+            output_path.display(),
+            detail,
+            command_line(&cmd)
+        );
+        return Ok(ProcessingOutcome::DryRun {
+            output_path,
+            needs_remux: false,
+        });
+    }
+    require_mkvtoolnix_tool("mkvpropedit")?;
+
+    // The output is edited in place, so it must not share the source's inode
+    let method = if task.is_in_place() {
+        backup_file(&output_path, false, config)?;
+        TransferMethod::InPlace
+    } else {
+        timed_transfer(task, &output_path, import_context, false, config, control).await?
+    };
+    run_tool(&mut cmd, &output_path, "Attachment removal")?;
+    report!(
+        "📎 Removed {} attachment(s): {}",
+        attachment_ids.len(),
+        output_path.display()
+    );
+    apply_output_permissions(&output_path, config)?;
+    if config.processing.removed_manifest == Some(RemovedManifest::Sidecar) {
+        let removed = removed_tracks(&task.streams, &decisions);
+        let manifest = write_removed_manifest(&task.source_file, &output_path, &removed)?;
+        apply_output_permissions(&manifest, config)?;
+        report!("📋 Removed tracks: {}", manifest.display());
+    }
+
+    if import_context.is_some() {
+        output_sonarr_move_status(SonarrMoveStatus::MoveComplete);
+    }
+
+    Ok(ProcessingOutcome::AttachmentsRemoved {
+        output_path,
+        method,
+        decisions,
+    })
+}
+
+/// Transfer the unchanged source in place of a remux that saves (or would
+/// save) only `saved_bytes`, below `processing.min_savings`
+async fn transfer_below_min_savings(
+    task: &ProcessingTask,
+    saved_bytes: u64,
+    config: &Config,
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
+    let outcome = handle_no_processing_needed_task(task, config, import_context, control).await?;
+    Ok(match outcome {
+        ProcessingOutcome::Transferred {
+            output_path,
+            method,
+        } => ProcessingOutcome::BelowMinSavings {
+            output_path,
+            method,
+            saved_bytes,
+        },
+        other => other,
+    })
+}
+
+/// Put the unchanged source at `output_path` using the Sonarr/Radarr transfer mode
+/// (hard link or copy by default). Without `allow_hard_link`, linking modes
+/// copy instead, for outputs that get edited afterwards.
+/// `transfer_source`, warning when a copy ran below the configured throughput
+async fn timed_transfer(
+    task: &crate::models::ProcessingTask,
+    output_path: &Path,
+    import_context: Option<&ImportContext>,
+    allow_hard_link: bool,
+    config: &Config,
+    control: &ProcessControl,
+) -> Result<TransferMethod> {
+    backup_replaced_file(output_path, config)?;
+    let started = Instant::now();
+    let method = transfer_source(
+        task,
+        output_path,
+        import_context,
+        allow_hard_link,
+        config,
+        control,
+    )
+    .await?;
+
+    if let Some(settings) = &config.processing.throughput_warning
+        && method != TransferMethod::HardLinked
+        && let Ok(metadata) = std::fs::metadata(output_path)
+        && let Some(rate) = slow_transfer_rate(settings, metadata.len(), started.elapsed())
+    {
+        warn_slow_throughput(settings, "Copy", output_path, rate);
+    }
+    Ok(method)
+}
+
+async fn transfer_source(
+    task: &crate::models::ProcessingTask,
+    output_path: &Path,
+    import_context: Option<&ImportContext>,
+    allow_hard_link: bool,
+    config: &Config,
+    control: &ProcessControl,
+) -> Result<TransferMethod> {
+    let method = match effective_transfer_mode(import_context, allow_hard_link) {
         "Move" => {
-            match std::fs::rename(&task.source_file, &output_path) {
-                Ok(()) => println!(
+            backup_file(&task.source_file, true, config)?;
+            match std::fs::rename(&task.source_file, output_path) {
+                Ok(()) => report!(
                     "📁 Moved: {} → {}",
                     task.source_file.display(),
                     output_path.display()
                 ),
                 Err(_) => {
                     // Cross-filesystem move: copy then delete
-                    std::fs::copy(&task.source_file, &output_path).with_context(|| {
-                        format!("Failed to copy file for cross-filesystem move")
-                    })?;
+                    copy_via_partial(&task.source_file, output_path, control)
+                        .await
+                        .with_context(|| "Failed to copy file for cross-filesystem move")?;
                     std::fs::remove_file(&task.source_file)
-                        .with_context(|| format!("Failed to remove source file after copy"))?;
-                    println!(
+                        .with_context(|| "Failed to remove source file after copy")?;
+                    report!(
                         "📁 Moved (cross-filesystem): {} → {}",
                         task.source_file.display(),
                         output_path.display()
                     );
                 }
             }
+            TransferMethod::Moved
         }
         "Copy" => {
-            std::fs::copy(&task.source_file, &output_path)
-                .with_context(|| format!("Failed to copy file"))?;
-            println!(
+            copy_via_partial(&task.source_file, output_path, control)
+                .await
+                .with_context(|| "Failed to copy file")?;
+            report!(
                 "📋 Copied: {} → {}",
                 task.source_file.display(),
                 output_path.display()
             );
+            TransferMethod::Copied
         }
         "HardLink" => {
-            std::fs::hard_link(&task.source_file, &output_path)
-                .with_context(|| format!("Failed to create hard link"))?;
-            println!(
+            std::fs::hard_link(&task.source_file, output_path)
+                .with_context(|| "Failed to create hard link")?;
+            report!(
                 "🔗 Hard linked: {} → {}",
                 task.source_file.display(),
                 output_path.display()
             );
+            TransferMethod::HardLinked
         }
-        "HardLinkOrCopy" | _ => {
+        _ => {
             // Default behavior: try hard link, fall back to copy
-            match std::fs::hard_link(&task.source_file, &output_path) {
+            match std::fs::hard_link(&task.source_file, output_path) {
                 Ok(()) => {
-                    println!(
+                    report!(
                         "🔗 Hard linked: {} → {}",
                         task.source_file.display(),
                         output_path.display()
                     );
+                    TransferMethod::HardLinked
                 }
                 Err(_) => {
-                    std::fs::copy(&task.source_file, &output_path)
-                        .with_context(|| format!("Failed to copy file after hard link failed"))?;
-                    println!(
+                    copy_via_partial(&task.source_file, output_path, control)
+                        .await
+                        .with_context(|| "Failed to copy file after hard link failed")?;
+                    report!(
                         "📋 Copied (hard link failed): {} → {}",
                         task.source_file.display(),
                         output_path.display()
                     );
+                    TransferMethod::Copied
                 }
             }
         }
+    };
+
+    Ok(method)
+}
+
+/// Transfer mode from the Sonarr/Radarr context (hard link or copy by
+/// default); linking modes copy when `allow_hard_link` is off
+fn effective_transfer_mode(import_context: Option<&ImportContext>, allow_hard_link: bool) -> &str {
+    let transfer_mode = import_context
+        .and_then(|ctx| ctx.transfer_mode())
+        .unwrap_or("HardLinkOrCopy");
+    if allow_hard_link || transfer_mode == "Move" {
+        transfer_mode
+    } else {
+        "Copy"
     }
+}
+
+fn transfer_verb(method: TransferMethod) -> &'static str {
+    match method {
+        TransferMethod::Moved => "move",
+        TransferMethod::Copied => "copy",
+        TransferMethod::HardLinked => "hard link",
+        TransferMethod::InPlace => "keep",
+    }
+}
 
-    // Handle Sonarr communication
-    if sonarr_context.is_some() {
-        output_sonarr_move_status(SonarrMoveStatus::MoveComplete);
+/// The method `transfer_source()` would use for `source`, with why, for dry
+/// runs. Probes the filesystems: whether the output's directory (or the
+/// nearest one that exists) is on the source's filesystem and, for linking
+/// modes, whether a hard link can actually be created there. Copies are full
+/// byte copies: mkv-slimmer doesn't use reflinks.
+fn predict_transfer(
+    source: &Path,
+    output_path: &Path,
+    import_context: Option<&ImportContext>,
+    allow_hard_link: bool,
+) -> (TransferMethod, String) {
+    let size = std::fs::metadata(source)
+        .map(|metadata| format_size(metadata.len()))
+        .unwrap_or_else(|_| "unknown size".to_string());
+    let directory = output_path.parent().unwrap_or(Path::new("."));
+    let same_filesystem = same_filesystem(source, directory);
+
+    match effective_transfer_mode(import_context, allow_hard_link) {
+        "Move" if same_filesystem => (
+            TransferMethod::Moved,
+            "same filesystem: instant rename".to_string(),
+        ),
+        "Move" => (
+            TransferMethod::Moved,
+            format!(
+                "different filesystems: {} copied, then the source deleted",
+                size
+            ),
+        ),
+        "Copy" if allow_hard_link => (TransferMethod::Copied, format!("full copy of {}", size)),
+        "Copy" => (
+            TransferMethod::Copied,
+            format!(
+                "full copy of {}: the output is edited, so it can't share the source's inode",
+                size
+            ),
+        ),
+        mode => match probe_hard_link(source, directory, same_filesystem) {
+            Ok(()) => (
+                TransferMethod::HardLinked,
+                "same filesystem, hard link probe succeeded: no data copied".to_string(),
+            ),
+            Err(reason) if mode == "HardLink" => (
+                TransferMethod::HardLinked,
+                format!("hard link impossible, the transfer would fail: {}", reason),
+            ),
+            Err(reason) => (
+                TransferMethod::Copied,
+                format!("hard link impossible ({}): full copy of {}", reason, size),
+            ),
+        },
     }
+}
 
-    Ok(())
+/// Try hard linking `source` into the nearest existing ancestor of
+/// `directory` under a temporary name, removing the link right away
+fn probe_hard_link(
+    source: &Path,
+    directory: &Path,
+    same_filesystem: bool,
+) -> std::result::Result<(), String> {
+    if !same_filesystem {
+        return Err("different filesystems".to_string());
+    }
+    let probe =
+        existing_ancestor(directory).join(format!(".mkv-slimmer-link-test-{}", std::process::id()));
+    match std::fs::hard_link(source, &probe) {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 // ===== Helper functions extracted from MkvAnalyzer =====
 
-async fn get_ffprobe_data(file_path: &std::path::Path) -> Option<serde_json::Value> {
+/// Run ffprobe; None (with a warning) when it isn't available or fails, an
+/// error when it ran but its output isn't JSON
+pub async fn get_ffprobe_data(file_path: &std::path::Path) -> Result<Option<serde_json::Value>> {
     let output = Command::new("ffprobe")
         .args([
             "-v",
@@ -264,77 +1858,110 @@ async fn get_ffprobe_data(file_path: &std::path::Path) -> Option<serde_json::Val
         .output();
 
     match output {
-        Ok(output) if output.status.success() => match serde_json::from_slice(&output.stdout) {
-            Ok(data) => Some(data),
-            Err(e) => {
-                eprintln!("Warning: Could not parse ffprobe output: {}", e);
-                None
-            }
-        },
+        Ok(output) if output.status.success() => serde_json::from_slice(&output.stdout)
+            .map(Some)
+            .context("Could not parse ffprobe output"),
         Ok(_) => {
-            eprintln!("Warning: ffprobe failed, using limited stream information");
-            None
+            report_warn!("Warning: ffprobe failed, using mkvmerge/matroska stream information");
+            record_warning(WarningKind::Analysis, "ffprobe failed");
+            Ok(None)
         }
         Err(_) => {
-            eprintln!("Warning: ffprobe not available, using limited stream information");
-            None
+            report_warn!(
+                "Warning: ffprobe not available, using mkvmerge/matroska stream information"
+            );
+            Ok(None)
         }
     }
 }
 
-async fn get_matroska_data(file_path: &std::path::Path) -> Option<matroska::Matroska> {
-    match std::fs::File::open(file_path) {
-        Ok(file) => match matroska::Matroska::open(file) {
-            Ok(mkv) => Some(mkv),
-            Err(e) => {
-                eprintln!("Warning: Could not parse with matroska crate: {}", e);
-                None
+/// Merge the stream lists of the available sources. The preferred analyzer
+/// (`processing.preferred_source`, `mkvmerge -J` by default) defines the
+/// stream list and flags; the other analyzer and then the matroska crate only
+/// fill in fields it left empty. When the preferred analyzer produced no
+/// streams, the next source that did takes its place.
+fn extract_streams_from_data(
+    file_path: &Path,
+    ffprobe_streams: Option<Vec<StreamInfo>>,
+    mkvmerge_data: Option<MkvmergeIdentify>,
+    matroska_data: Option<matroska::Matroska>,
+    preferred_source: AnalysisSource,
+) -> Result<Vec<StreamInfo>> {
+    let mkvmerge_streams = mkvmerge_data.as_ref().map(streams_from_mkvmerge);
+
+    if let (Some(ffprobe), Some(mkvmerge)) = (&ffprobe_streams, &mkvmerge_streams)
+        && !ffprobe.is_empty()
+        && !mkvmerge.is_empty()
+    {
+        let conflicts = detect_source_conflicts(ffprobe, mkvmerge);
+        if !conflicts.is_empty() {
+            report_warn!(
+                "⚠️  ffprobe and mkvmerge disagree about {} (using {}):",
+                file_path.display(),
+                preferred_source.name()
+            );
+            for conflict in &conflicts {
+                report_warn!("   - {}", conflict);
+                record_warning(WarningKind::SourceConflict, conflict.to_string());
             }
-        },
-        Err(e) => {
-            eprintln!("Warning: Could not open file for matroska parsing: {}", e);
-            None
         }
     }
-}
 
-fn extract_streams_from_data(
-    ffprobe_data: Option<serde_json::Value>,
-    _matroska_data: Option<matroska::Matroska>,
-) -> Result<Vec<StreamInfo>> {
-    let mut streams = Vec::new();
-
-    // For now, focus on ffprobe data
-    if let Some(data) = ffprobe_data {
-        // Parse JSON into structured FFProbe output
-        match serde_json::from_value::<FFProbeOutput>(data) {
-            Ok(ffprobe_output) => {
-                if let Some(stream_array) = ffprobe_output.streams {
-                    for (index, stream) in stream_array.iter().enumerate() {
-                        let stream_info =
-                            create_stream_info_from_ffprobe_struct(index as u32, stream)?;
-                        streams.push(stream_info);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Warning: Could not parse ffprobe output with serde: {}", e);
-                // Fallback to minimal stream info
-                let stream_info = StreamInfo::new(0, StreamType::Unknown);
-                streams.push(stream_info);
-                return Ok(streams);
+    // The preferred analyzer defines the streams; the other one and the
+    // matroska crate only fill in what it left empty
+    let (first, second) = match preferred_source {
+        AnalysisSource::Ffprobe => (ffprobe_streams, mkvmerge_streams),
+        AnalysisSource::Mkvmerge => (mkvmerge_streams, ffprobe_streams),
+    };
+    let sources = [
+        first,
+        second,
+        matroska_data.as_ref().map(streams_from_matroska),
+    ];
+
+    let mut sources = sources
+        .into_iter()
+        .flatten()
+        .filter(|streams| !streams.is_empty());
+    let Some(mut streams) = sources.next() else {
+        // Fallback: create minimal stream info
+        report_warn!("Warning: No stream information available - using fallback");
+        return Ok(vec![StreamInfo::new(0, StreamType::Unknown)]);
+    };
+
+    // Sources that read track UIDs are matched by them, so a stream gets the
+    // fields of the same track however the sources numbered them
+    for fallback in sources {
+        for stream in &mut streams {
+            if let Some(other) = fallback.iter().find(|s| match (s.uid, stream.uid) {
+                (Some(uid), Some(stream_uid)) => uid == stream_uid,
+                _ => s.index == stream.index && s.stream_type == stream.stream_type,
+            }) {
+                fill_missing_fields(stream, other);
             }
         }
-    } else {
-        // Fallback: create minimal stream info
-        eprintln!("Warning: No stream information available - using fallback");
-        let stream_info = StreamInfo::new(0, StreamType::Unknown);
-        streams.push(stream_info);
+        if streams.iter().any(|s| s.uid.is_none()) {
+            fill_missing_uids(&mut streams, &fallback);
+        }
     }
 
     Ok(streams)
 }
 
+/// Parse ffprobe's JSON into structured FFProbe output and convert its streams
+fn parse_ffprobe_streams(data: serde_json::Value) -> Result<Vec<StreamInfo>> {
+    let ffprobe_output = serde_json::from_value::<FFProbeOutput>(data)
+        .context("Could not parse ffprobe output with serde")?;
+
+    ffprobe_output
+        .streams
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, stream)| create_stream_info_from_ffprobe_struct(index as u32, stream))
+        .collect()
+}
+
 fn create_stream_info_from_ffprobe_struct(
     index: u32,
     stream: &crate::models::FFProbeStream,
@@ -357,6 +1984,7 @@ fn create_stream_info_from_ffprobe_struct(
         .or(stream.codec_long_name.as_ref())
         .map(|s| s.to_string())
         .unwrap_or_else(|| "unknown".to_string());
+    info.profile = stream.profile.clone();
 
     // Language and metadata from tags
     if let Some(tags) = &stream.tags {
@@ -364,46 +1992,46 @@ fn create_stream_info_from_ffprobe_struct(
         info.title = tags.title.clone();
 
         // Check for DURATION tag (format: "00:01:31.010000000")
-        if let Some(duration_str) = &tags.duration {
-            if let Some(duration_seconds) = parse_duration_tag(duration_str) {
-                info.duration_seconds = Some(duration_seconds);
-            }
+        if let Some(duration_str) = &tags.duration
+            && let Some(duration_seconds) = parse_duration_tag(duration_str)
+        {
+            info.duration_seconds = Some(duration_seconds);
         }
 
         // Check for NUMBER_OF_BYTES tag
-        if let Some(bytes_str) = &tags.number_of_bytes {
-            if let Ok(bytes) = bytes_str.parse::<u64>() {
-                info.size_bytes = Some(bytes);
-            }
+        if let Some(bytes_str) = &tags.number_of_bytes
+            && let Ok(bytes) = bytes_str.parse::<u64>()
+        {
+            info.size_bytes = Some(bytes);
         }
     }
 
-    // Disposition (default/forced flags)
+    // Disposition (default/forced flags, cover art)
     if let Some(disposition) = &stream.disposition {
         info.default = disposition.default.unwrap_or(0) == 1;
         info.forced = disposition.forced.unwrap_or(0) == 1;
+        info.cover_art = disposition.attached_pic.unwrap_or(0) == 1;
     }
 
     // Size and duration (from standard fields if tags didn't provide them)
-    if let Some(bit_rate_str) = &stream.bit_rate {
-        if let Ok(bit_rate) = bit_rate_str.parse::<u64>() {
-            info.bitrate = Some(bit_rate);
-
-            // Use standard duration field if we didn't get it from tags
-            if info.duration_seconds.is_none() {
-                if let Some(duration_str) = &stream.duration {
-                    if let Ok(duration) = duration_str.parse::<f64>() {
-                        info.duration_seconds = Some(duration);
-                    }
-                }
-            }
+    if let Some(bit_rate_str) = &stream.bit_rate
+        && let Ok(bit_rate) = bit_rate_str.parse::<u64>()
+    {
+        info.bitrate = Some(bit_rate);
 
-            // Calculate size from bitrate and duration if we didn't get it from tags
-            if info.size_bytes.is_none() {
-                if let Some(duration) = info.duration_seconds {
-                    info.size_bytes = Some((bit_rate * duration as u64) / 8);
-                }
-            }
+        // Use standard duration field if we didn't get it from tags
+        if info.duration_seconds.is_none()
+            && let Some(duration_str) = &stream.duration
+            && let Ok(duration) = duration_str.parse::<f64>()
+        {
+            info.duration_seconds = Some(duration);
+        }
+
+        // Calculate size from bitrate and duration if we didn't get it from tags
+        if info.size_bytes.is_none()
+            && let Some(duration) = info.duration_seconds
+        {
+            info.size_bytes = Some((bit_rate * duration as u64) / 8);
         }
     }
 
@@ -443,187 +2071,697 @@ fn create_stream_info_from_ffprobe_struct(
     Ok(info)
 }
 
-fn create_stream_info_from_ffprobe(index: u32, stream: &serde_json::Value) -> Result<StreamInfo> {
-    let codec_type = stream["codec_type"].as_str().unwrap_or("unknown");
-    let stream_type = match codec_type {
-        "video" => StreamType::Video,
-        "audio" => StreamType::Audio,
-        "subtitle" => StreamType::Subtitle,
-        "attachment" => StreamType::Attachment,
-        _ => StreamType::Unknown,
-    };
-
-    let mut info = StreamInfo::new(index, stream_type);
-
-    // Basic information
-    info.codec = stream["codec_name"]
-        .as_str()
-        .or_else(|| stream["codec_long_name"].as_str())
-        .unwrap_or("unknown")
-        .to_string();
-
-    // Language and metadata
-    if let Some(tags) = stream["tags"].as_object() {
-        info.language = tags
-            .get("language")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        info.title = tags
-            .get("title")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+/// Apportion the container bytes not accounted for by measured streams across
+/// streams with unknown sizes, weighted by `size_estimation_weight`. Estimated
+/// streams are flagged via `size_estimated` so the display can mark them.
+/// Returns the number of streams estimated.
+fn estimate_missing_stream_sizes(streams: &mut [StreamInfo], container_size: u64) -> usize {
+    let known_size: u64 = streams.iter().filter_map(|s| s.size_bytes).sum();
+    let unaccounted = container_size.saturating_sub(known_size);
+    if unaccounted == 0 {
+        return 0;
+    }
 
-        // Check for DURATION tag (format: "00:01:31.010000000")
-        if let Some(duration_str) = tags.get("DURATION").and_then(|v| v.as_str()) {
-            if let Some(duration_seconds) = parse_duration_tag(duration_str) {
-                info.duration_seconds = Some(duration_seconds);
-            }
-        }
+    let weights: Vec<(usize, f64)> = streams
+        .iter()
+        .enumerate()
+        .filter(|(_, stream)| stream.size_bytes.is_none())
+        .map(|(position, stream)| (position, size_estimation_weight(stream)))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    let total_weight: f64 = weights.iter().map(|(_, weight)| weight).sum();
+    if total_weight <= 0.0 {
+        return 0;
+    }
 
-        // Check for NUMBER_OF_BYTES tag
-        if let Some(bytes_str) = tags.get("NUMBER_OF_BYTES").and_then(|v| v.as_str()) {
-            if let Ok(bytes) = bytes_str.parse::<u64>() {
-                info.size_bytes = Some(bytes);
-            }
-        }
+    for (position, weight) in &weights {
+        let stream = &mut streams[*position];
+        stream.size_bytes = Some((unaccounted as f64 * weight / total_weight) as u64);
+        stream.size_estimated = true;
     }
+    weights.len()
+}
 
-    // Disposition (default/forced flags)
-    if let Some(disposition) = stream["disposition"].as_object() {
-        info.default = disposition
-            .get("default")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0)
-            == 1;
-        info.forced = disposition
-            .get("forced")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0)
-            == 1;
+/// Relative weight (roughly kbit/s) of a stream with unknown size. The reported
+/// bitrate is used when present; otherwise a typical figure for the codec keeps
+/// video dominant, lossless audio ahead of lossy, and text subtitles negligible.
+/// Attachments (cover art included) and unknown streams get no share since
+/// their size isn't bitrate-bound.
+fn size_estimation_weight(stream: &StreamInfo) -> f64 {
+    if let Some(bitrate) = stream.bitrate {
+        return bitrate as f64 / 1000.0;
     }
 
-    // Size and duration (from standard fields if tags didn't provide them)
-    if let Some(bit_rate) = stream["bit_rate"]
-        .as_str()
-        .and_then(|s| s.parse::<u64>().ok())
-    {
-        info.bitrate = Some(bit_rate);
+    let codec = stream.codec.to_lowercase();
+    match stream.container_type() {
+        StreamType::Video => match codec.as_str() {
+            "mjpeg" | "png" => 1.0, // Cover art, a single frame
+            "hevc" | "av1" | "vp9" => 4000.0,
+            _ => 8000.0,
+        },
+        StreamType::Audio => match codec.as_str() {
+            "truehd" | "mlp" | "flac" | "pcm_s16le" | "pcm_s24le" => 3000.0,
+            "dts" => 1500.0,
+            "ac3" | "eac3" => 640.0,
+            _ => 192.0,
+        },
+        StreamType::Subtitle => match codec.as_str() {
+            "hdmv_pgs_subtitle" | "dvd_subtitle" | "dvb_subtitle" => 30.0,
+            _ => 1.0,
+        },
+        StreamType::Attachment | StreamType::Unknown => 0.0,
+    }
+}
 
-        // Use standard duration field if we didn't get it from tags
-        if info.duration_seconds.is_none() {
-            if let Some(duration) = stream["duration"]
-                .as_str()
-                .and_then(|s| s.parse::<f64>().ok())
+/// Decide for every stream whether it is kept (according to `policy`), why,
+/// and whether it becomes the default track of its type in the output.
+/// Streams the output container can't hold are removed regardless of the policy.
+pub fn decide_streams(context: &FileContext, policy: &dyn StreamPolicy) -> StreamDecisions {
+    let webm_output = context.config.processing.container == ContainerFormat::Webm;
+    let mut decisions: Vec<StreamDecision> = context
+        .streams
+        .iter()
+        .map(|stream| {
+            let decision = policy.decide(stream, context);
+            let mut stream_decision = StreamDecision {
+                index: stream.index,
+                stream_type: stream.stream_type,
+                action: decision.action,
+                default: false,
+                reason: decision.reason,
+                size_bytes: stream.size_bytes,
+            };
+            // WebM has no attachment support, so fonts/images can't be carried over
+            if webm_output
+                && stream.container_type() == StreamType::Attachment
+                && stream_decision.is_kept()
             {
-                info.duration_seconds = Some(duration);
+                stream_decision.action = StreamAction::Remove;
+                stream_decision.reason = DecisionReason::UnsupportedByContainer;
             }
-        }
-
-        // Calculate size from bitrate and duration if we didn't get it from tags
-        if info.size_bytes.is_none() {
-            if let Some(duration) = info.duration_seconds {
-                info.size_bytes = Some((bit_rate * duration as u64) / 8);
+            // Flags-only runs never remux, so nothing is removed (and the
+            // container setting doesn't apply)
+            if context.config.processing.flags_only && !stream_decision.is_kept() {
+                stream_decision.action = StreamAction::Keep;
+                stream_decision.reason = DecisionReason::FlagsOnly;
             }
+            stream_decision
+        })
+        .collect();
+
+    if context.media_kind.filters_audio() && !context.config.processing.flags_only {
+        keep_preferred_channels(context.streams, &mut decisions, &context.config.audio);
+        remove_downmix_duplicates(context.streams, &mut decisions, &context.config.audio);
+        keep_preferred_codecs(context.streams, &mut decisions, &context.config.audio);
+    }
+    if context.media_kind.filters_subtitles() && !context.config.processing.flags_only {
+        remove_subtitle_codecs(context.streams, &mut decisions, &context.config.subtitles);
+    }
+    if context.media_kind.filters_audio() {
+        apply_no_match_policy(
+            &mut decisions,
+            StreamType::Audio,
+            context.config.audio.no_match_policy,
+        );
+    }
+    if context.media_kind.filters_subtitles() {
+        apply_no_match_policy(
+            &mut decisions,
+            StreamType::Subtitle,
+            context.config.subtitles.no_match_policy,
+        );
+    }
+
+    let original_language = original_language(context.streams, context.import_context);
+    assign_default_tracks(
+        context.streams,
+        decisions,
+        context.config,
+        original_language.as_deref(),
+    )
+}
+
+/// With `audio.prefer_channels`, remove kept audio tracks with another
+/// channel count when a kept track of the same language has exactly the
+/// preferred one. Commentary tracks and tracks of unknown channel count are
+/// left alone. Runs before `remove_downmix_duplicates()`, which then only
+/// sees the tracks that stayed.
+fn keep_preferred_channels(
+    streams: &[StreamInfo],
+    decisions: &mut [StreamDecision],
+    audio: &AudioConfig,
+) {
+    let Some(preferred) = audio.prefer_channels else {
+        return;
+    };
+    let candidates: Vec<&StreamInfo> = streams
+        .iter()
+        .filter(|s| s.stream_type == StreamType::Audio && !is_commentary(s))
+        .filter(|s| {
+            decisions
+                .iter()
+                .any(|d| d.index == s.index && d.reason == DecisionReason::LanguageMatch)
+        })
+        .collect();
+    let preferred_languages: Vec<&str> = candidates
+        .iter()
+        .filter(|s| s.channels == Some(preferred))
+        .map(|s| s.effective_language())
+        .collect();
+
+    for stream in candidates {
+        if stream.channels.is_none_or(|c| c == preferred)
+            || !preferred_languages.contains(&stream.effective_language())
+        {
+            continue;
+        }
+        if let Some(decision) = decisions.iter_mut().find(|d| d.index == stream.index) {
+            decision.action = StreamAction::Remove;
+            decision.reason = DecisionReason::ChannelsNotPreferred;
         }
     }
+}
 
-    // Type-specific information
-    match info.stream_type {
-        StreamType::Video => {
-            info.resolution = Some(format!(
-                "{}x{}",
-                stream["width"].as_i64().unwrap_or(0),
-                stream["height"].as_i64().unwrap_or(0)
-            ));
+/// Whether the stream's title marks it as a commentary track, which the
+/// audio clean-ups leave alone
+fn is_commentary(stream: &StreamInfo) -> bool {
+    stream
+        .title
+        .as_deref()
+        .is_some_and(|title| title.to_lowercase().contains("commentary"))
+}
 
-            if let Some(fps_str) = stream["r_frame_rate"].as_str() {
-                info.framerate = parse_framerate(fps_str);
-            }
+/// With `audio.min_channels`, remove kept audio tracks with fewer channels
+/// when another kept track of the same language has at least that many.
+/// Commentary tracks and tracks of unknown channel count are left alone;
+/// `keep_stereo_companion` spares the first stereo track of each language.
+fn remove_downmix_duplicates(
+    streams: &[StreamInfo],
+    decisions: &mut [StreamDecision],
+    audio: &AudioConfig,
+) {
+    let Some(min_channels) = audio.min_channels else {
+        return;
+    };
+    let candidates: Vec<&StreamInfo> = streams
+        .iter()
+        .filter(|s| s.stream_type == StreamType::Audio && !is_commentary(s))
+        .filter(|s| {
+            decisions
+                .iter()
+                .any(|d| d.index == s.index && d.reason == DecisionReason::LanguageMatch)
+        })
+        .collect();
+
+    let mut spared_languages: Vec<&str> = Vec::new();
+    for stream in &candidates {
+        let Some(channels) = stream.channels.filter(|&c| c < min_channels) else {
+            continue;
+        };
+        let language = stream.effective_language();
+        let has_full_track = candidates.iter().any(|other| {
+            other.effective_language() == language
+                && other.channels.is_some_and(|c| c >= min_channels)
+        });
+        if !has_full_track {
+            continue;
         }
-        StreamType::Audio => {
-            info.channels = stream["channels"].as_i64().map(|c| c as u32);
-            info.sample_rate = stream["sample_rate"]
-                .as_str()
-                .and_then(|s| s.parse::<u32>().ok());
+        if audio.keep_stereo_companion && channels == 2 && !spared_languages.contains(&language) {
+            spared_languages.push(language);
+            continue;
         }
-        StreamType::Subtitle => {
-            info.subtitle_format = stream["codec_name"].as_str().map(|s| s.to_string());
+        if let Some(decision) = decisions.iter_mut().find(|d| d.index == stream.index) {
+            decision.action = StreamAction::Remove;
+            decision.reason = DecisionReason::DownmixDuplicate;
         }
-        _ => {}
     }
+}
 
-    Ok(info)
+/// With `audio.codec_preference`, keep only the `tracks_per_language`
+/// best-ranked of the kept audio tracks of each language, ranking formats
+/// missing from the list last and ties in track order. Like downmix
+/// duplicates, commentary tracks are left alone.
+fn keep_preferred_codecs(
+    streams: &[StreamInfo],
+    decisions: &mut [StreamDecision],
+    audio: &AudioConfig,
+) {
+    if audio.codec_preference.is_empty() {
+        return;
+    }
+    let rank = |stream: &StreamInfo| {
+        let format = stream.audio_format();
+        audio
+            .codec_preference
+            .iter()
+            .position(|codec| codec.eq_ignore_ascii_case(&format))
+            // A plain entry (`dts`) also ranks its variants (`dts-hd`)
+            .or_else(|| {
+                audio
+                    .codec_preference
+                    .iter()
+                    .position(|codec| codec.eq_ignore_ascii_case(&stream.codec))
+            })
+            .unwrap_or(audio.codec_preference.len())
+    };
+    let mut candidates: Vec<&StreamInfo> = streams
+        .iter()
+        .filter(|s| s.stream_type == StreamType::Audio && !is_commentary(s))
+        .filter(|s| {
+            decisions
+                .iter()
+                .any(|d| d.index == s.index && d.reason == DecisionReason::LanguageMatch)
+        })
+        .collect();
+    // Stable, so equally ranked tracks stay in track order
+    candidates.sort_by_key(|stream| rank(stream));
+
+    let mut kept_per_language: HashMap<&str, u32> = HashMap::new();
+    for stream in candidates {
+        let kept = kept_per_language
+            .entry(stream.effective_language())
+            .or_default();
+        if *kept < audio.tracks_per_language {
+            *kept += 1;
+            continue;
+        }
+        if let Some(decision) = decisions.iter_mut().find(|d| d.index == stream.index) {
+            decision.action = StreamAction::Remove;
+            decision.reason = DecisionReason::CodecNotPreferred;
+        }
+    }
 }
 
-fn determine_streams_to_keep(streams: &[StreamInfo], config: &Config) -> Vec<u32> {
-    let mut streams_to_keep = Vec::new();
+/// Remove kept subtitles whose codec is in `subtitles.remove_codecs` or, with
+/// `subtitles.keep_codecs`, missing from it; with `remove_codecs_if_text_kept`,
+/// only where a text subtitle of the same language stays. Forced subtitles
+/// retained by `keep_forced` are spared.
+fn remove_subtitle_codecs(
+    streams: &[StreamInfo],
+    decisions: &mut [StreamDecision],
+    subtitles: &SubtitleConfig,
+) {
+    if subtitles.remove_codecs.is_empty() && subtitles.keep_codecs.is_empty() {
+        return;
+    }
+    let is_kept = |decisions: &[StreamDecision], index: u32| {
+        decisions.iter().any(|d| d.index == index && d.is_kept())
+    };
+    let lists = |codecs: &[String], stream: &StreamInfo| {
+        codecs
+            .iter()
+            .any(|codec| codec.eq_ignore_ascii_case(&stream.codec))
+    };
+    let is_listed = |stream: &StreamInfo| {
+        lists(&subtitles.remove_codecs, stream)
+            || (!subtitles.keep_codecs.is_empty() && !lists(&subtitles.keep_codecs, stream))
+    };
+    let kept_text_languages: Vec<&str> = streams
+        .iter()
+        .filter(|s| s.stream_type == StreamType::Subtitle && is_text_subtitle(&s.codec))
+        .filter(|s| !is_listed(s) && is_kept(decisions, s.index))
+        .map(|s| s.effective_language())
+        .collect();
+
+    for stream in streams.iter().filter(|s| {
+        s.stream_type == StreamType::Subtitle
+            && is_listed(s)
+            && !(subtitles.keep_forced && s.forced)
+    }) {
+        if subtitles.remove_codecs_if_text_kept
+            && !kept_text_languages.contains(&stream.effective_language())
+        {
+            continue;
+        }
+        if let Some(decision) = decisions
+            .iter_mut()
+            .find(|d| d.index == stream.index && d.is_kept())
+            .filter(|d| {
+                matches!(
+                    d.reason,
+                    DecisionReason::LanguageMatch | DecisionReason::TitleMatch
+                )
+            })
+        {
+            decision.action = StreamAction::Remove;
+            decision.reason = DecisionReason::CodecRemoved;
+        }
+    }
+}
 
-    for stream in streams {
-        let should_keep = match stream.stream_type {
-            StreamType::Video => {
-                // Always keep video streams
-                true
-            }
-            StreamType::Audio => {
-                let lang = stream.effective_language();
-                config.audio.keep_languages.iter().any(|l| l == lang)
-            }
-            StreamType::Subtitle => {
-                let lang = stream.effective_language();
-                // Check if any preference matches this subtitle
-                config.subtitles.keep_languages.iter().any(|pref| {
-                    pref.language == lang && pref.matches_title(stream.title.as_deref())
-                })
-            }
-            StreamType::Attachment => {
-                // Usually keep attachments (fonts, etc.)
-                true
-            }
-            StreamType::Unknown => {
-                // Keep unknown streams to be safe
-                true
-            }
-        };
+/// When no stream of `stream_type` is kept although the file has some, keep
+/// the first or all of them as the policy says. `Fail` is enforced with the
+/// output checks in `process_mkv_streams`.
+fn apply_no_match_policy(
+    decisions: &mut [StreamDecision],
+    stream_type: StreamType,
+    policy: NoMatchPolicy,
+) {
+    let of_type: Vec<&mut StreamDecision> = decisions
+        .iter_mut()
+        .filter(|d| d.stream_type == stream_type)
+        .collect();
+    if of_type.is_empty() || of_type.iter().any(|d| d.satisfies_no_match_policy()) {
+        return;
+    }
 
-        if should_keep {
-            streams_to_keep.push(stream.index);
-        }
+    let fallback_count = match policy {
+        NoMatchPolicy::KeepFirst => 1,
+        NoMatchPolicy::KeepAll => of_type.len(),
+        NoMatchPolicy::RemoveAll | NoMatchPolicy::Fail => 0,
+    };
+    for decision in of_type
+        .into_iter()
+        .filter(|d| d.reason == DecisionReason::NotPreferred)
+        .take(fallback_count)
+    {
+        decision.action = StreamAction::Keep;
+        decision.reason = DecisionReason::NoMatchFallback;
     }
+}
+
+/// Language of the original version of a file, for `DefaultTrackStrategy::Original`:
+/// the one Sonarr/Radarr report, else that of the source's first audio track
+fn original_language(
+    streams: &[StreamInfo],
+    import_context: Option<&ImportContext>,
+) -> Option<String> {
+    import_context
+        .and_then(ImportContext::original_language)
+        .or_else(|| {
+            streams
+                .iter()
+                .find(|s| s.stream_type == StreamType::Audio && s.external.is_none())
+                .map(|s| s.effective_language().to_string())
+                .filter(|language| language != "und")
+        })
+}
+
+/// Pick the default audio/subtitle track among the kept streams and flag it
+fn assign_default_tracks(
+    streams: &[StreamInfo],
+    mut decisions: Vec<StreamDecision>,
+    config: &Config,
+    original_language: Option<&str>,
+) -> StreamDecisions {
+    let kept: Vec<u32> = decisions
+        .iter()
+        .filter(|d| d.is_kept())
+        .map(|d| d.index)
+        .collect();
+    let kept_by_type = separate_streams_by_type(streams, &kept);
+    let default_audio =
+        get_default_audio_track(streams, &kept_by_type.audio, config, original_language);
+    let default_subtitle =
+        get_default_subtitle_track(streams, &kept_by_type.subtitle, config, original_language);
 
-    streams_to_keep
+    for decision in &mut decisions {
+        decision.default = match decision.stream_type {
+            StreamType::Audio => Some(decision.index) == default_audio,
+            StreamType::Subtitle => Some(decision.index) == default_subtitle,
+            _ => false,
+        };
+    }
+
+    let mut decisions = StreamDecisions {
+        streams: decisions,
+        default_audio,
+        default_subtitle,
+        stats: Vec::new(),
+    };
+    decisions.stats = decisions.type_stats();
+    decisions
+}
+
+/// Narrow `decisions` to `tracks` (one output of a split), re-picking the
+/// default tracks among what that output keeps
+fn decisions_for_tracks(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    tracks: &[u32],
+    config: &Config,
+    import_context: Option<&ImportContext>,
+) -> StreamDecisions {
+    let narrowed = decisions
+        .streams
+        .iter()
+        .cloned()
+        .map(|mut decision| {
+            if !tracks.contains(&decision.index) {
+                decision.action = StreamAction::Remove;
+            }
+            decision
+        })
+        .collect();
+    let original_language = original_language(&task.streams, import_context);
+    assign_default_tracks(
+        &task.streams,
+        narrowed,
+        config,
+        original_language.as_deref(),
+    )
 }
 
 fn build_mkvmerge_command_for_task(
     task: &crate::models::ProcessingTask,
-    streams_to_keep: &[u32],
-    output_path: &PathBuf,
+    decisions: &StreamDecisions,
+    output_path: &Path,
+    extras: &MuxExtras,
     config: &Config,
-) -> Result<Command> {
-    let mut cmd = Command::new("mkvmerge");
+) -> Result<MkvmergeCommand> {
+    let mut cmd = MkvmergeCommand::new();
 
-    // Output file
-    cmd.arg("-v").arg("-o").arg(output_path);
+    // Output file; GUI mode makes progress machine-readable
+    cmd.arg("-v").arg("--gui-mode").arg("-o").arg(output_path);
 
-    // Separate streams by type
-    let streams_by_type = separate_streams_by_type(&task.streams, streams_to_keep);
-    let all_streams_by_type = separate_streams_by_type(
-        &task.streams,
-        &task.streams.iter().map(|s| s.index).collect::<Vec<_>>(),
-    );
+    if config.processing.container == ContainerFormat::Webm {
+        cmd.arg("--webm");
+    }
+    if let Some(global_tags) = &extras.global_tags {
+        cmd.arg("--global-tags").arg(global_tags);
+    }
+    if config.processing.scrub {
+        add_scrub_args(&mut cmd, output_path);
+    }
+    if let Some(title) = &extras.title {
+        cmd.arg("--title").arg(title);
+    } else if config.tags.clear_title {
+        cmd.arg("--title").arg("");
+    }
+    if let Some(linking) = &task.segment_linking {
+        add_segment_linking_args(&mut cmd, linking);
+    }
 
-    add_track_selection_args(&mut cmd, &streams_by_type, &all_streams_by_type);
-    add_default_track_flags(&mut cmd, task, &streams_by_type, config);
+    // Default tracks were chosen across the combined set, external tracks included
+    let streams_to_keep = decisions.kept_indices();
+    let default_audio = decisions.default_audio;
+    let default_subtitle = decisions.default_subtitle;
+
+    // Separate the source file's own streams by type
+    let source_indices: Vec<u32> = task
+        .streams
+        .iter()
+        .filter(|s| s.external.is_none())
+        .map(|s| s.index)
+        .collect();
+    let source_kept: Vec<u32> = streams_to_keep
+        .iter()
+        .copied()
+        .filter(|index| source_indices.contains(index))
+        .collect();
+    let streams_by_type = separate_streams_by_type(&task.streams, &source_kept);
+    let all_streams_by_type = separate_streams_by_type(&task.streams, &source_indices);
+
+    let track_ids = &task.track_ids;
+    add_track_selection_args(&mut cmd, &streams_by_type, &all_streams_by_type, track_ids);
+    let forced: Vec<u32> = task
+        .streams
+        .iter()
+        .filter(|s| keeps_forced_flag(s, config))
+        .map(|s| s.index)
+        .collect();
+    set_track_flags(
+        &mut cmd,
+        &streams_by_type.audio,
+        default_audio,
+        &forced,
+        track_ids,
+    );
+    set_track_flags(
+        &mut cmd,
+        &streams_by_type.subtitle,
+        default_subtitle,
+        &forced,
+        track_ids,
+    );
+    for (track, charset) in &extras.sub_charsets {
+        if streams_by_type.subtitle.contains(track) {
+            cmd.arg("--sub-charset")
+                .arg(format!("{}:{}", track_ids.track_id(*track), charset));
+        }
+    }
+    for (track, language) in language_retags(&task.streams, decisions, config) {
+        cmd.arg("--language")
+            .arg(format!("{}:{}", track_ids.track_id(track), language));
+    }
+    let titles = title_rewrites(&task.streams, decisions, &config.titles);
+    for (track, title) in &titles {
+        if source_kept.contains(track) {
+            cmd.arg("--track-name")
+                .arg(format!("{}:{}", track_ids.track_id(*track), title));
+        }
+    }
+    if config.tags.strip_global {
+        cmd.arg("--no-global-tags");
+    }
+    if config.tags.strip_track {
+        cmd.arg("--no-track-tags");
+    }
+    if config.processing.chapters == ChapterMode::Strip {
+        cmd.arg("--no-chapters");
+    }
 
     // Input file
     cmd.arg(&task.source_file);
 
+    for attachment in &extras.attachments {
+        cmd.arg("--attachment-mime-type")
+            .arg(&attachment.mime_type)
+            .arg("--attach-file")
+            .arg(&attachment.path);
+    }
+
+    add_external_inputs(
+        &mut cmd,
+        task,
+        &streams_to_keep,
+        [default_audio, default_subtitle],
+        &titles,
+        config,
+    )?;
+
     Ok(cmd)
 }
 
+/// Carry the source's segment UID and previous/next links over to the output so
+/// ordered chapters and linked segments keep resolving
+fn add_segment_linking_args(cmd: &mut MkvmergeCommand, linking: &SegmentLinking) {
+    if let Some(uid) = &linking.segment_uid {
+        cmd.arg("--segment-uid").arg(uid);
+    }
+    if let Some(uid) = &linking.previous_uid {
+        cmd.arg("--link-to-previous").arg(uid);
+    }
+    if let Some(uid) = &linking.next_uid {
+        cmd.arg("--link-to-next").arg(uid);
+    }
+}
+
+/// Append every external file holding kept streams as an additional mkvmerge
+/// input, selecting only the kept tracks and carrying over their flags,
+/// rewritten titles and sync offsets
+fn add_external_inputs(
+    cmd: &mut MkvmergeCommand,
+    task: &crate::models::ProcessingTask,
+    streams_to_keep: &[u32],
+    default_tracks: [Option<u32>; 2],
+    titles: &[(u32, String)],
+    config: &Config,
+) -> Result<()> {
+    let kept_external: Vec<(&StreamInfo, &ExternalTrack)> = task
+        .streams
+        .iter()
+        .filter(|s| streams_to_keep.contains(&s.index))
+        .filter_map(|s| s.external.as_ref().map(|external| (s, external)))
+        .collect();
+
+    if kept_external.is_empty() {
+        return Ok(());
+    }
+    let sync_offsets = resolve_sync_offsets(&task.source_file, config)?;
+
+    let mut files: Vec<&Path> = Vec::new();
+    for (_, external) in &kept_external {
+        if !files.contains(&external.path.as_path()) {
+            files.push(&external.path);
+        }
+    }
+
+    for file in files {
+        let tracks: Vec<(&StreamInfo, &ExternalTrack)> = kept_external
+            .iter()
+            .copied()
+            .filter(|(_, external)| external.path == file)
+            .collect();
+        let track_ids = |stream_type: StreamType| -> Vec<String> {
+            tracks
+                .iter()
+                .filter(|(s, _)| s.stream_type == stream_type)
+                .map(|(_, external)| external.track_id.to_string())
+                .collect()
+        };
+
+        // Only the selected tracks are taken from external files
+        cmd.arg("--no-video")
+            .arg("--no-attachments")
+            .arg("--no-chapters");
+        if config.tags.strip_global {
+            cmd.arg("--no-global-tags");
+        }
+        if config.tags.strip_track {
+            cmd.arg("--no-track-tags");
+        }
+        for (stream_type, tracks_flag, no_flag) in [
+            (StreamType::Audio, "--audio-tracks", "--no-audio"),
+            (StreamType::Subtitle, "--subtitle-tracks", "--no-subtitles"),
+        ] {
+            let ids = track_ids(stream_type);
+            if ids.is_empty() {
+                cmd.arg(no_flag);
+            } else {
+                cmd.arg(tracks_flag).arg(ids.join(","));
+            }
+        }
+
+        for (stream, external) in &tracks {
+            let is_default = if default_tracks.contains(&Some(stream.index)) {
+                1
+            } else {
+                0
+            };
+            cmd.arg("--default-track-flag")
+                .arg(format!("{}:{}", external.track_id, is_default));
+            cmd.arg("--forced-display-flag")
+                .arg(format!("{}:0", external.track_id));
+            if let Some(language) = &stream.language {
+                cmd.arg("--language")
+                    .arg(format!("{}:{}", external.track_id, language));
+            }
+            if let Some((_, title)) = titles.iter().find(|(index, _)| *index == stream.index) {
+                cmd.arg("--track-name")
+                    .arg(format!("{}:{}", external.track_id, title));
+            }
+            if let Some(offset) = sync_offset_for(stream, external, &sync_offsets) {
+                cmd.arg("--sync")
+                    .arg(format!("{}:{}", external.track_id, offset.milliseconds));
+            }
+        }
+
+        cmd.arg(file);
+    }
+
+    Ok(())
+}
+
 /// Add `--*-tracks` / `--no-*` selection args, but only for stream types where some
 /// tracks are being dropped. When every track of a type is kept, mkvmerge's default
-/// (include all) is left untouched.
-fn add_track_selection_args(cmd: &mut Command, kept: &StreamsByType, all: &StreamsByType) {
+/// (include all) is left untouched. Streams are selected by their mkvmerge IDs.
+fn add_track_selection_args(
+    cmd: &mut MkvmergeCommand,
+    kept: &StreamsByType,
+    all: &StreamsByType,
+    track_ids: &TrackIdMap,
+) {
     let selections = [
         (&kept.video, &all.video, "--video-tracks", "--no-video"),
         (&kept.audio, &all.audio, "--audio-tracks", "--no-audio"),
@@ -648,42 +2786,47 @@ fn add_track_selection_args(cmd: &mut Command, kept: &StreamsByType, all: &Strea
         if kept_tracks.is_empty() {
             cmd.arg(no_flag);
         } else {
-            cmd.arg(tracks_flag);
-            cmd.arg(
+            let ids: Vec<String> = if tracks_flag == "--attachments" {
+                // Without every kept ID the selection would drop attachments
+                // that should stay, so all of them are kept instead
+                let Some(ids) = kept_tracks
+                    .iter()
+                    .map(|&i| track_ids.attachment_id(i).map(|id| id.to_string()))
+                    .collect::<Option<Vec<String>>>()
+                else {
+                    continue;
+                };
+                ids
+            } else {
                 kept_tracks
                     .iter()
-                    .map(|i| i.to_string())
-                    .collect::<Vec<_>>()
-                    .join(","),
-            );
+                    .map(|&i| track_ids.track_id(i).to_string())
+                    .collect()
+            };
+            cmd.arg(tracks_flag);
+            cmd.arg(ids.join(","));
         }
     }
 }
 
-/// Set default-track and forced-display flags so that at most one audio and one
-/// subtitle track are marked as default, based on language preferences.
-fn add_default_track_flags(
-    cmd: &mut Command,
-    task: &crate::models::ProcessingTask,
-    streams_by_type: &StreamsByType,
-    config: &Config,
-) {
-    let default_audio = get_default_audio_track(&task.streams, &streams_by_type.audio, config);
-    set_track_flags(cmd, &streams_by_type.audio, default_audio);
-
-    let default_subtitle =
-        get_default_subtitle_track(&task.streams, &streams_by_type.subtitle, config);
-    set_track_flags(cmd, &streams_by_type.subtitle, default_subtitle);
-}
-
 /// Emit `--default-track-flag` (1 only for `default_track`) and clear the forced
-/// display flag for every track in `tracks`.
-fn set_track_flags(cmd: &mut Command, tracks: &[u32], default_track: Option<u32>) {
+/// display flag for every track in `tracks` except those in `forced_tracks`.
+/// Tracks are given as stream indices and written as mkvmerge track IDs.
+fn set_track_flags(
+    cmd: &mut MkvmergeCommand,
+    tracks: &[u32],
+    default_track: Option<u32>,
+    forced_tracks: &[u32],
+    track_ids: &TrackIdMap,
+) {
     for &track in tracks {
         let is_default = if Some(track) == default_track { 1 } else { 0 };
+        let is_forced = if forced_tracks.contains(&track) { 1 } else { 0 };
+        let id = track_ids.track_id(track);
         cmd.arg("--default-track-flag")
-            .arg(format!("{}:{}", track, is_default));
-        cmd.arg("--forced-display-flag").arg(format!("{}:0", track));
+            .arg(format!("{}:{}", id, is_default));
+        cmd.arg("--forced-display-flag")
+            .arg(format!("{}:{}", id, is_forced));
     }
 }
 
@@ -691,7 +2834,20 @@ fn get_default_audio_track(
     streams: &[StreamInfo],
     audio_streams: &[u32],
     config: &Config,
+    original_language: Option<&str>,
 ) -> Option<u32> {
+    match config.audio.default_strategy {
+        DefaultTrackStrategy::None => return None,
+        DefaultTrackStrategy::Original => {
+            if let Some(track) = original_language
+                .and_then(|language| first_track_in_language(streams, audio_streams, language))
+            {
+                return Some(track);
+            }
+        }
+        DefaultTrackStrategy::Preference => {}
+    }
+
     // Find the first audio track that matches the highest priority language
     for preferred_lang in &config.audio.keep_languages {
         for &stream_index in audio_streams {
@@ -712,13 +2868,26 @@ fn get_default_subtitle_track(
     streams: &[StreamInfo],
     subtitle_streams: &[u32],
     config: &Config,
+    original_language: Option<&str>,
 ) -> Option<u32> {
+    match config.subtitles.default_strategy {
+        DefaultTrackStrategy::None => return None,
+        DefaultTrackStrategy::Original => {
+            if let Some(track) = original_language
+                .and_then(|language| first_track_in_language(streams, subtitle_streams, language))
+            {
+                return Some(track);
+            }
+        }
+        DefaultTrackStrategy::Preference => {}
+    }
+
     // Find the first subtitle track that matches the highest priority preference
     for pref in &config.subtitles.keep_languages {
         for &stream_index in subtitle_streams {
             if let Some(stream) = streams.iter().find(|s| s.index == stream_index) {
                 let lang = stream.effective_language();
-                if lang == &pref.language && pref.matches_title(stream.title.as_deref()) {
+                if lang == pref.language && pref.matches_title(stream.title.as_deref()) {
                     return Some(stream_index);
                 }
             }
@@ -728,3 +2897,12 @@ fn get_default_subtitle_track(
     // No default subtitle - let all subtitle tracks be non-default
     None
 }
+
+/// First of `tracks` whose language is `language`
+fn first_track_in_language(streams: &[StreamInfo], tracks: &[u32], language: &str) -> Option<u32> {
+    tracks.iter().copied().find(|&index| {
+        streams
+            .iter()
+            .any(|s| s.index == index && s.effective_language() == language)
+    })
+}
@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use super::tags::render_placeholders;
+use crate::config::Config;
+use crate::models::ImportContext;
+use crate::report;
+
+/// MIME types by file extension for files commonly attached to MKVs
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("ttc", "font/collection"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+    ("bmp", "image/bmp"),
+    ("txt", "text/plain"),
+    ("nfo", "text/plain"),
+    ("xml", "application/xml"),
+    ("pdf", "application/pdf"),
+];
+
+/// File attached to the output during the remux
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAttachment {
+    pub path: PathBuf,
+    pub mime_type: String,
+}
+
+/// Detect the MIME type of a file to attach, from its extension or, failing
+/// that, its leading bytes
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::attachments::detect_mime_type;
+/// use std::path::Path;
+///
+/// assert_eq!(detect_mime_type(Path::new("fonts/Arial.TTF")), "font/ttf");
+/// assert_eq!(detect_mime_type(Path::new("poster.jpg")), "image/jpeg");
+/// assert_eq!(detect_mime_type(Path::new("missing.bin")), "application/octet-stream");
+/// ```
+pub fn detect_mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    if let Some(extension) = extension
+        && let Some((_, mime_type)) = MIME_TYPES.iter().find(|(ext, _)| *ext == extension)
+    {
+        return mime_type;
+    }
+
+    let mut header = [0u8; 4];
+    let read = std::fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read(&mut file, &mut header))
+        .unwrap_or(0);
+    match &header[..read] {
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [0x89, b'P', b'N', b'G'] => "image/png",
+        [0x00, 0x01, 0x00, 0x00] | [b't', b'r', b'u', b'e'] => "font/ttf",
+        [b'O', b'T', b'T', b'O'] => "font/otf",
+        [b't', b't', b'c', b'f'] => "font/collection",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Files from `attachments.add` to attach to the output. `{placeholders}` are
+/// filled from the Sonarr/Radarr context (e.g. `{series_path}/poster.jpg`); entries
+/// that can't be filled or don't exist are skipped with a note.
+pub fn resolve_attachments(
+    config: &Config,
+    import_context: Option<&ImportContext>,
+) -> Vec<NewAttachment> {
+    let mut attachments = Vec::new();
+
+    for entry in &config.attachments.add {
+        let Some(path) = render_placeholders(entry, import_context) else {
+            report!("ℹ️  Not attaching '{}': placeholder has no value", entry);
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            report!("⚠️  Not attaching {}: file does not exist", path.display());
+            continue;
+        }
+
+        let mime_type = detect_mime_type(&path).to_string();
+        attachments.push(NewAttachment { path, mime_type });
+    }
+
+    attachments
+}
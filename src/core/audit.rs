@@ -0,0 +1,151 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::analyzer::analyze_mkv_streams;
+use crate::config::Config;
+use crate::error::file_validation_error;
+use crate::models::{StreamInfo, StreamType};
+use crate::report_warn;
+use crate::utils::{collect_mkv_files, is_valid_mkv_file, language_from_title};
+
+/// An audio or subtitle track without a language tag (missing or `und`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct UntaggedTrack {
+    pub index: u32,
+    pub stream_type: StreamType,
+    pub codec: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Language named in the title, which `fix-languages --infer` would write
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_language: Option<String>,
+}
+
+impl std::fmt::Display for UntaggedTrack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} {:?} ({})", self.index, self.stream_type, self.codec)?;
+        if let Some(title) = &self.title {
+            write!(f, " \"{}\"", title)?;
+        }
+        if let Some(language) = &self.suggested_language {
+            write!(f, " → {}?", language)?;
+        }
+        Ok(())
+    }
+}
+
+/// A file with untagged tracks, path relative to the audited directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct UntaggedFile {
+    pub file: PathBuf,
+    pub tracks: Vec<UntaggedTrack>,
+}
+
+/// Result of `audit_untagged`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct UntaggedReport {
+    /// MKV files analyzed
+    pub files_scanned: usize,
+    /// Files with at least one untagged audio/subtitle track
+    pub files: Vec<UntaggedFile>,
+    /// Files that couldn't be analyzed
+    pub failed: Vec<PathBuf>,
+}
+
+impl UntaggedReport {
+    /// Untagged tracks across all files
+    pub fn track_count(&self) -> usize {
+        self.files.iter().map(|file| file.tracks.len()).sum()
+    }
+}
+
+/// Audio and subtitle tracks of a file whose language isn't tagged, with the
+/// language their title suggests
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::audit::untagged_tracks;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let mut tagged = StreamInfo::new(1, StreamType::Audio);
+/// tagged.language = Some("eng".to_string());
+/// let mut titled = StreamInfo::new(2, StreamType::Subtitle);
+/// titled.title = Some("Japanese".to_string());
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), tagged, titled];
+///
+/// let untagged = untagged_tracks(&streams);
+/// assert_eq!(untagged.len(), 1);
+/// assert_eq!(untagged[0].index, 2);
+/// assert_eq!(untagged[0].suggested_language.as_deref(), Some("jpn"));
+/// ```
+pub fn untagged_tracks(streams: &[StreamInfo]) -> Vec<UntaggedTrack> {
+    streams
+        .iter()
+        .filter(|s| matches!(s.stream_type, StreamType::Audio | StreamType::Subtitle))
+        .filter(|s| s.effective_language() == "und")
+        .map(|stream| UntaggedTrack {
+            index: stream.index,
+            stream_type: stream.stream_type,
+            codec: stream.codec.clone(),
+            title: stream.title.clone(),
+            suggested_language: stream
+                .title
+                .as_deref()
+                .and_then(language_from_title)
+                .map(str::to_string),
+        })
+        .collect()
+}
+
+/// Find the files under `input` (an MKV file or a directory) with audio or
+/// subtitle tracks lacking a language tag. Files that fail to analyze are
+/// reported and listed in the report.
+pub async fn audit_untagged(
+    input: &Path,
+    recursive: bool,
+    config: &Config,
+) -> Result<UntaggedReport> {
+    let (root, files) = if input.is_dir() {
+        (input, collect_mkv_files(input, recursive)?)
+    } else if is_valid_mkv_file(input) {
+        (
+            input.parent().unwrap_or(Path::new("")),
+            vec![input.to_path_buf()],
+        )
+    } else {
+        return Err(file_validation_error(
+            input,
+            "Input must be an MKV file or a directory",
+        ));
+    };
+
+    // Report the tags as they are, not what inference or overrides make of them
+    let mut config = config.clone();
+    config.processing.infer_languages = false;
+    config.processing.language_overrides.clear();
+    config.processing.strict = false;
+
+    let mut report = UntaggedReport::default();
+    for file in files {
+        match analyze_mkv_streams(&file, &config).await {
+            Ok(streams) => {
+                report.files_scanned += 1;
+                let tracks = untagged_tracks(&streams);
+                if !tracks.is_empty() {
+                    report.files.push(UntaggedFile {
+                        file: file.strip_prefix(root).unwrap_or(&file).to_path_buf(),
+                        tracks,
+                    });
+                }
+            }
+            Err(e) => {
+                report_warn!("⚠️  {}: {:#}", file.display(), e);
+                report.failed.push(file);
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::models::TransferMethod;
+use crate::report;
+use crate::utils::format_elapsed;
+
+/// Name of the description file inside each backup's directory
+const BACKUP_INFO: &str = "backup.json";
+
+/// Backups taken by this process, so concurrent batch jobs get distinct directories
+static BACKUP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// An original file saved below `processing.backup_dir` before it was moved,
+/// replaced or edited in place. Each backup lives in its own directory
+/// (`<unix time>-<pid>-<sequence>/`) next to a `backup.json` describing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    /// Where the file was, and where `rollback` puts it back
+    pub original: PathBuf,
+    /// When the backup was taken (seconds since the Unix epoch)
+    pub created: u64,
+    /// Whether the backup shares the original's data or is a copy of it
+    pub method: TransferMethod,
+    /// Directory holding this backup
+    #[serde(skip)]
+    pub location: PathBuf,
+}
+
+impl Backup {
+    /// The saved file
+    pub fn file(&self) -> PathBuf {
+        self.location
+            .join(self.original.file_name().unwrap_or_default())
+    }
+
+    /// Time since the backup was taken, in whole minutes, hours or days
+    pub fn age(&self) -> String {
+        format_elapsed(self.created)
+    }
+}
+
+/// Save `path` below `processing.backup_dir` before an operation changes it.
+/// A hard link is used when `allow_hard_link` is set and the backup directory
+/// is on the same filesystem; in-place edits need a copy, since they would
+/// change a hard link's data too. Does nothing (None) without a backup
+/// directory, in dry runs and when `path` doesn't exist.
+pub fn backup_file(path: &Path, allow_hard_link: bool, config: &Config) -> Result<Option<Backup>> {
+    let Some(backup_dir) = &config.processing.backup_dir else {
+        return Ok(None);
+    };
+    if config.processing.dry_run || !path.is_file() {
+        return Ok(None);
+    }
+
+    let original = path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve file to back up: {}", path.display()))?;
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let location = backup_dir.join(format!(
+        "{}-{}-{:06}",
+        created,
+        std::process::id(),
+        BACKUP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&location)
+        .with_context(|| format!("Failed to create backup directory: {}", location.display()))?;
+
+    let mut backup = Backup {
+        original,
+        created,
+        method: TransferMethod::Copied,
+        location,
+    };
+    let file = backup.file();
+    if allow_hard_link && std::fs::hard_link(&backup.original, &file).is_ok() {
+        backup.method = TransferMethod::HardLinked;
+    } else {
+        std::fs::copy(&backup.original, &file)
+            .with_context(|| format!("Failed to back up {}", backup.original.display()))?;
+    }
+    let info = serde_json::to_string_pretty(&backup)?;
+    std::fs::write(backup.location.join(BACKUP_INFO), info + "\n").with_context(|| {
+        format!(
+            "Failed to write backup description: {}",
+            backup.location.display()
+        )
+    })?;
+
+    report!(
+        "🗄️  Backed up {} → {}",
+        backup.original.display(),
+        file.display()
+    );
+    Ok(Some(backup))
+}
+
+/// Back up an existing file that is about to be replaced by a new output and
+/// remove it, so the new file gets its own inode and a hard-linked backup
+/// keeps the old data. Without a backup directory the file is left for the
+/// writer to overwrite.
+pub fn backup_replaced_file(path: &Path, config: &Config) -> Result<()> {
+    if backup_file(path, true, config)?.is_some() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove backed-up file: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// All backups below `backup_dir`, oldest first. Directories without a
+/// readable `backup.json` or without their file are left out.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<Backup>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(backup_dir)
+        .with_context(|| format!("Failed to read backup directory: {}", backup_dir.display()))?;
+
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let location = entry.path();
+            let info = std::fs::read_to_string(location.join(BACKUP_INFO)).ok()?;
+            let mut backup: Backup = serde_json::from_str(&info).ok()?;
+            backup.location = location;
+            backup.file().is_file().then_some(backup)
+        })
+        .collect();
+    backups.sort_by(|a, b| (a.created, &a.location).cmp(&(b.created, &b.location)));
+    Ok(backups)
+}
+
+/// Put a backup back at its original location, replacing whatever is there
+/// now, and remove it from the backup directory
+pub fn restore_backup(backup: &Backup) -> Result<()> {
+    if let Some(parent) = backup.original.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let file = backup.file();
+    if std::fs::rename(&file, &backup.original).is_err() {
+        // Cross-filesystem: copy, then drop the backup's file
+        std::fs::copy(&file, &backup.original)
+            .with_context(|| format!("Failed to restore {}", backup.original.display()))?;
+    }
+    std::fs::remove_dir_all(&backup.location).with_context(|| {
+        format!(
+            "Failed to remove restored backup: {}",
+            backup.location.display()
+        )
+    })
+}
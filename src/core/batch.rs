@@ -1,13 +1,41 @@
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use tokio::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use super::analyzer::analyze_mkv_streams;
+use super::analyzer::{
+    analyze_mkv_streams, analyze_segment_linking, decide_streams, resolve_track_ids,
+};
+use super::chapters::chapter_count;
+use super::external::{add_companion_audio, is_companion_audio};
+use super::history::{HistoryDb, SourceStamp, config_hash};
+use super::journal::{Journal, JournalState};
+use super::notify::{NotificationEvent, Notifications};
+use super::parts::{PartGroup, find_part_groups, same_track_layout};
+use super::policy::{FileContext, LanguagePolicy};
 use super::processor::{handle_non_mkv_file, process_task};
-use crate::config::Config;
-use crate::models::{ProcessingTask, SonarrContext};
-use crate::utils::is_valid_mkv_file;
+use super::progress::ProcessControl;
+use super::samples::{find_samples, media_duration};
+use super::space::{SpacePlan, available_space, projected_output_size};
+use super::template::{
+    TargetPathValues, known_template_directory, lang_summary, render_target_path,
+};
+use super::titles::title_rewrites;
+use crate::config::{Config, DIRECTORY_CONFIG_FILE, file_settings_path, series_directory};
+use crate::display::StreamDisplayer;
+use crate::error::{Cancelled, directory_error};
+use crate::models::{
+    BatchRun, ImportContext, ManifestEntry, OutcomeCounts, ProcessingOutcome, ProcessingTask,
+    RunSummary, ScanManifest, SkipReason, StreamDecisions,
+};
+use crate::utils::{
+    CollectedWarning, WarningKind, check_directory_writable, collect_warnings,
+    create_output_directory, format_age, format_size, is_valid_mkv_file, print_warning_summary,
+    record_warning, resolve_target_path, strip_ansi, warning_counts, with_file,
+};
+use crate::{report, report_warn};
 
 /// Processes multiple MKV files in batch operations
 ///
@@ -17,7 +45,8 @@ use crate::utils::is_valid_mkv_file;
 ///
 /// # Examples
 /// ```rust
-/// use mkv_slimmer::core::{BatchProcessor, Config};
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::BatchProcessor;
 /// use std::path::PathBuf;
 ///
 /// let processor = BatchProcessor::new(
@@ -26,7 +55,7 @@ use crate::utils::is_valid_mkv_file;
 ///     false,  // not recursive
 ///     None,   // no filter pattern
 ///     Config::default(),
-///     None    // no Sonarr context
+///     None    // no Sonarr/Radarr context
 /// );
 /// ```
 pub struct BatchProcessor {
@@ -35,7 +64,64 @@ pub struct BatchProcessor {
     recursive: bool,
     filter_pattern: Option<String>,
     config: Config,
-    sonarr_context: Option<SonarrContext>,
+    import_context: Option<ImportContext>,
+    control: ProcessControl,
+    age_filter: AgeFilter,
+    stream_tables: StreamTables,
+    /// Told about each processed or failed file
+    notifications: Arc<Notifications>,
+    /// Files to process instead of collecting them from `input_path`
+    files: Option<Vec<PathBuf>>,
+    /// Entries of a `scan` manifest whose analysis may be reused
+    scanned: Vec<ManifestEntry>,
+    /// Settings per source directory and file, with their settings files applied
+    directory_configs: Mutex<HashMap<PathBuf, Arc<Config>>>,
+}
+
+/// Where a batch shows the stream table of each file (`--show-streams`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StreamTables {
+    /// Only the per-file status lines, as usual for batches
+    #[default]
+    Hidden,
+    /// Printed before each file is processed, like for a single file
+    Console,
+    /// Appended to this file, without colors, for review
+    File(PathBuf),
+}
+
+/// Modification-time window for batch files (`--newer-than` / `--older-than`)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::batch::AgeFilter;
+/// use std::time::Duration;
+///
+/// let day = Duration::from_secs(86_400);
+/// let recent = AgeFilter { newer_than: Some(day * 7), older_than: None };
+/// assert!(recent.matches(day * 2));
+/// assert!(!recent.matches(day * 30));
+/// assert!(AgeFilter::default().matches(day * 365));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgeFilter {
+    /// Only files modified less than this long ago
+    pub newer_than: Option<Duration>,
+    /// Only files modified more than this long ago
+    pub older_than: Option<Duration>,
+}
+
+impl AgeFilter {
+    /// Whether any bound is set
+    pub fn is_active(&self) -> bool {
+        self.newer_than.is_some() || self.older_than.is_some()
+    }
+
+    /// Whether a file last modified `age` ago passes the filter
+    pub fn matches(&self, age: Duration) -> bool {
+        self.newer_than.is_none_or(|limit| age < limit)
+            && self.older_than.is_none_or(|limit| age > limit)
+    }
 }
 
 /// Contains the results of a batch processing operation
@@ -51,6 +137,70 @@ pub struct BatchResult {
     pub failed: usize,
     /// Map of file paths to their specific error messages
     pub errors: HashMap<PathBuf, String>,
+    /// Files left out of the batch, or left as they were, with the reason
+    pub skipped: BTreeMap<PathBuf, SkipReason>,
+    /// Successfully processed files with the bytes they saved (None in dry runs)
+    pub successes: HashMap<PathBuf, Option<u64>>,
+    /// Successfully processed files by outcome
+    pub counts: OutcomeCounts,
+    /// Non-fatal problems that came up, for the warning summary
+    pub warnings: Vec<CollectedWarning>,
+}
+
+/// Batch results of one season folder in a Sonarr-style library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeasonSummary {
+    pub series: String,
+    pub season: String,
+    /// Files processed or attempted in this season
+    pub files: usize,
+    /// Files that failed to process
+    pub failed: usize,
+    /// Bytes saved by the successfully processed files
+    pub saved_bytes: u64,
+}
+
+/// Series and season of a file in a Sonarr-style layout
+/// (`<Series>/Season 01/<episode>.mkv`); None when its folder isn't a season
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::batch::series_season;
+/// use std::path::Path;
+///
+/// assert_eq!(
+///     series_season(Path::new("/tv/Show/Season 1/Show - S01E01.mkv")),
+///     Some(("Show".to_string(), "Season 01".to_string()))
+/// );
+/// assert_eq!(
+///     series_season(Path::new("/tv/Show/Specials/Show - S00E01.mkv")),
+///     Some(("Show".to_string(), "Specials".to_string()))
+/// );
+/// assert_eq!(series_season(Path::new("/movies/Movie (2020)/Movie.mkv")), None);
+/// ```
+pub fn series_season(path: &Path) -> Option<(String, String)> {
+    let season_dir = path.parent()?;
+    let season = season_label(&season_dir.file_name()?.to_string_lossy())?;
+    let series = season_dir
+        .parent()?
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    Some((series, season))
+}
+
+/// Normalized season name for folders like `Season 1`, `Season.01`, `S01` or `Specials`
+pub(crate) fn season_label(folder: &str) -> Option<String> {
+    let folder = folder.trim().to_lowercase();
+    if folder == "specials" {
+        return Some("Specials".to_string());
+    }
+    let number = folder
+        .strip_prefix("season")
+        .or_else(|| folder.strip_prefix('s'))?
+        .trim_start_matches([' ', '.', '_', '-']);
+    let season: u32 = number.parse().ok()?;
+    Some(format!("Season {:02}", season))
 }
 
 impl BatchProcessor {
@@ -60,7 +210,7 @@ impl BatchProcessor {
         recursive: bool,
         filter_pattern: Option<String>,
         config: Config,
-        sonarr_context: Option<SonarrContext>,
+        import_context: Option<ImportContext>,
     ) -> Self {
         Self {
             input_path,
@@ -68,94 +218,933 @@ impl BatchProcessor {
             recursive,
             filter_pattern,
             config,
-            sonarr_context,
+            import_context,
+            control: ProcessControl::default(),
+            age_filter: AgeFilter::default(),
+            stream_tables: StreamTables::default(),
+            notifications: Arc::new(Notifications::default()),
+            files: None,
+            scanned: Vec::new(),
+            directory_configs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Report progress of each file and stop the batch once cancelled
+    pub fn with_control(mut self, control: ProcessControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Only process files whose modification time falls in `age_filter`
+    pub fn with_age_filter(mut self, age_filter: AgeFilter) -> Self {
+        self.age_filter = age_filter;
+        self
+    }
+
+    /// Show the stream table of each file, see `StreamTables`
+    pub fn with_stream_tables(mut self, stream_tables: StreamTables) -> Self {
+        self.stream_tables = stream_tables;
+        self
+    }
+
+    /// Report each processed or failed file to `notifications`
+    pub fn with_notifications(mut self, notifications: Arc<Notifications>) -> Self {
+        self.notifications = notifications;
+        self
+    }
+
+    /// Process exactly `files` (below the source directory) instead of
+    /// collecting them; filters don't apply. Used to retry failed files.
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = Some(files);
+        self
+    }
+
+    /// Process the files of a `scan` manifest instead of collecting them,
+    /// reusing the analysis of those that are unchanged since the scan
+    pub fn with_manifest_entries(mut self, entries: Vec<ManifestEntry>) -> Self {
+        self.files = Some(entries.iter().map(|entry| entry.path.clone()).collect());
+        self.scanned = entries;
+        self
+    }
+
+    /// Source, target and settings of this batch, for its result. Paths are
+    /// made absolute so the result can be used from any directory.
+    pub fn batch_run(&self) -> BatchRun {
+        BatchRun {
+            source: absolute_path(&self.input_path),
+            target: absolute_path(&self.target_directory),
+            recursive: self.recursive,
+            config: self.config.clone(),
         }
     }
 
     pub async fn process(&self) -> Result<BatchResult> {
-        println!("🎬 Starting batch processing...");
-        println!("📁 Source: {}", self.input_path.display());
-        println!("📂 Target: {}", self.target_directory.display());
+        let (result, warnings) = collect_warnings(self.process_files()).await;
+        let mut result = result?;
+        result.warnings = warnings;
+        Ok(result)
+    }
+
+    /// Collect the files like `process()` does and analyze each for a
+    /// `scan` manifest, without a target and without processing anything
+    pub async fn scan(&self) -> Result<ScanManifest> {
+        report!("🔎 Scanning: {}", self.input_path.display());
+        if let Some(filter) = &self.filter_pattern {
+            report!("🔍 Filter: {}", filter);
+        }
+        let (mkv_files, filtered) = self.collect_mkv_files()?;
+        if filtered.is_empty() {
+            report!("📊 Found {} MKV file(s)\n", mkv_files.len());
+        } else {
+            report!(
+                "📊 Found {} MKV file(s) ({} filtered out)\n",
+                mkv_files.len(),
+                filtered.len()
+            );
+        }
+
+        let mut files = Vec::new();
+        for (index, file_path) in mkv_files.iter().enumerate() {
+            self.control.check_cancelled()?;
+            report!(
+                "🔬 Analyzing file {} of {}: {}",
+                index + 1,
+                mkv_files.len(),
+                file_path.display()
+            );
+            let Some(stamp) = SourceStamp::of(file_path) else {
+                report_warn!("⚠️  {} disappeared during the scan", file_path.display());
+                continue;
+            };
+
+            let mut entry = ManifestEntry {
+                path: absolute_path(file_path),
+                size: stamp.size,
+                modified: stamp.modified,
+                config_hash: String::new(),
+                removed_streams: 0,
+                removed_bytes: 0,
+                error: None,
+                task: None,
+            };
+            let analysis = match self.file_config(file_path) {
+                Ok(config) => {
+                    entry.config_hash = config_hash(&config);
+                    with_file(file_path, self.analyze_source(file_path, &config))
+                        .await
+                        .map(|task| (task, config))
+                }
+                Err(e) => Err(e),
+            };
+            match analysis {
+                Ok((task, config)) => {
+                    let decisions = self.decide(&task, &config);
+                    entry.removed_streams = decisions.removed_count();
+                    entry.removed_bytes = decisions.removed_bytes();
+                    entry.task = Some(task);
+                }
+                Err(e) if e.is::<Cancelled>() => return Err(e),
+                Err(e) => {
+                    report_warn!("❌ Failed to analyze: {} - {:#}", file_path.display(), e);
+                    entry.error = Some(format!("{:#}", e));
+                }
+            }
+            files.push(entry);
+        }
+
+        let scanned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        Ok(ScanManifest {
+            source: absolute_path(&self.input_path),
+            recursive: self.recursive,
+            scanned_at,
+            files,
+        })
+    }
+
+    async fn process_files(&self) -> Result<BatchResult> {
+        report!("🎬 Starting batch processing...");
+        report!("📁 Source: {}", self.input_path.display());
+        report!("📂 Target: {}", self.target_directory.display());
         if self.recursive {
-            println!("🔄 Mode: Recursive");
+            report!("🔄 Mode: Recursive");
         } else {
-            println!("📑 Mode: Non-recursive");
+            report!("📑 Mode: Non-recursive");
         }
         if let Some(filter) = &self.filter_pattern {
-            println!("🔍 Filter: {}", filter);
+            report!("🔍 Filter: {}", filter);
+        }
+        if let StreamTables::File(path) = &self.stream_tables {
+            // Each batch starts the file over
+            std::fs::write(path, "").with_context(|| {
+                format!("Failed to create stream table file: {}", path.display())
+            })?;
+            report!("📋 Stream tables: {}", path.display());
         }
-        println!();
+        if let Some(age) = self.age_filter.newer_than {
+            report!("🕒 Modified less than {} ago", format_age(age));
+        }
+        if let Some(age) = self.age_filter.older_than {
+            report!("🕰️  Modified more than {} ago", format_age(age));
+        }
+        report!();
 
-        let mkv_files = self.collect_mkv_files()?;
+        let (mkv_files, filtered) = self.collect_mkv_files()?;
+        let total_files = mkv_files.len() + filtered.len();
 
         if mkv_files.is_empty() {
-            println!("⚠️  No MKV files found matching criteria");
+            report!("⚠️  No MKV files found matching criteria");
             return Ok(BatchResult {
-                total_files: 0,
+                total_files,
                 successful: 0,
                 failed: 0,
                 errors: HashMap::new(),
+                skipped: filtered,
+                successes: HashMap::new(),
+                counts: OutcomeCounts::default(),
+                warnings: Vec::new(),
             });
         }
 
-        println!("📊 Found {} MKV file(s) to process\n", mkv_files.len());
+        if filtered.is_empty() {
+            report!("📊 Found {} MKV file(s) to process\n", mkv_files.len());
+        } else {
+            report!(
+                "📊 Found {} MKV file(s) to process ({} filtered out)\n",
+                mkv_files.len(),
+                filtered.len()
+            );
+        }
+
+        // Files that change from here on are still being written
+        let snapshots: HashMap<PathBuf, FileSnapshot> = mkv_files
+            .iter()
+            .filter_map(|file| Some((file.clone(), FileSnapshot::of(file)?)))
+            .collect();
+        let (mkv_files, processed) = self.separate_processed(mkv_files)?;
+        let (mkv_files, samples) = self.separate_samples(mkv_files);
+        let (mkv_files, mut skipped) = self.check_target_directories(mkv_files)?;
+        skipped.extend(filtered);
+        skipped.extend(processed);
+        skipped.extend(samples);
+        let mut tasks = self.scanned_tasks();
+        let (mkv_files, groups) = self
+            .separate_multi_part(mkv_files, &mut tasks, &mut skipped)
+            .await?;
+        let (mut to_process, no_space) = self.plan_free_space(mkv_files, &mut tasks).await?;
+        // A group is only processed when all of its parts are
+        for group in groups
+            .iter()
+            .filter(|group| group.files.iter().any(|file| no_space.contains(file)))
+        {
+            to_process.retain(|file| !group.files.contains(file));
+            for file in group.files.iter().filter(|file| !no_space.contains(file)) {
+                skipped.insert(
+                    file.clone(),
+                    SkipReason::MultiPart {
+                        detail: format!(
+                            "another part of multi-part '{}' didn't fit on target",
+                            group.name
+                        ),
+                    },
+                );
+            }
+        }
+        skipped.extend(no_space.into_iter().map(|file| (file, SkipReason::NoSpace)));
+        let free_up = self.config.processing.free_up;
+        let (to_process, estimates) = match free_up {
+            Some(goal) => {
+                self.order_for_free_up(to_process, &groups, &mut tasks, goal)
+                    .await?
+            }
+            None => (to_process, HashMap::new()),
+        };
+        let mut freed: u64 = 0;
+
+        let journal = self
+            .config
+            .processing
+            .journal
+            .as_deref()
+            .map(Journal::open)
+            .transpose()?;
+        let record = |file: Option<&Path>, state: JournalState| {
+            if let Some(journal) = &journal {
+                journal.record(file, state);
+            }
+        };
+        record(
+            None,
+            JournalState::RunStarted {
+                source: self.input_path.clone(),
+                target: self.target_directory.clone(),
+                files: to_process.len(),
+                dry_run: self.config.processing.dry_run,
+            },
+        );
+        let mut planned_skips: Vec<_> = skipped.iter().collect();
+        planned_skips.sort_by_key(|(file, _)| *file);
+        for (file, reason) in planned_skips {
+            record(Some(file), skipped_state(reason));
+        }
 
         let mut successful = 0;
         let mut failed = 0;
         let mut errors = HashMap::new();
+        let mut successes = HashMap::new();
+        let mut counts = OutcomeCounts::default();
 
-        for (index, file_path) in mkv_files.iter().enumerate() {
-            println!(
+        for (index, file_path) in to_process.iter().enumerate() {
+            if let Err(e) = self.control.check_cancelled() {
+                record(None, JournalState::Cancelled);
+                return Err(e);
+            }
+            // Once the target is met, only the rest of a started multi-part group runs
+            if let Some(goal) = free_up
+                && freed >= goal
+                && !continues_group(&groups, file_path, &to_process[..index])
+            {
+                if !skipped.values().any(|r| *r == SkipReason::FreeUpReached) {
+                    report!(
+                        "🏁 Freed up {} of the {} target - stopping\n",
+                        format_size(freed),
+                        format_size(goal)
+                    );
+                }
+                record(Some(file_path), skipped_state(&SkipReason::FreeUpReached));
+                skipped.insert(file_path.clone(), SkipReason::FreeUpReached);
+                continue;
+            }
+            report!(
                 "🎯 Processing file {} of {}: {}",
                 index + 1,
-                mkv_files.len(),
+                to_process.len(),
                 file_path.display()
             );
+            record(Some(file_path), JournalState::Started);
 
-            match self.process_single_file(file_path).await {
-                Ok(()) => {
+            if let Some(reason) = source_not_ready(file_path, snapshots.get(file_path)) {
+                report!("⏭️  Skipping {}: {}\n", file_path.display(), reason);
+                record(Some(file_path), skipped_state(&reason));
+                skipped.insert(file_path.clone(), reason);
+                continue;
+            }
+
+            let source_size = std::fs::metadata(file_path).map(|m| m.len()).ok();
+            match with_file(
+                file_path,
+                self.process_single_file(file_path, tasks.remove(file_path)),
+            )
+            .await
+            {
+                Ok(outcome) => {
+                    counts.record(&outcome);
+                    if let Some(reason) = outcome.skip_reason() {
+                        report!("⏭️  Skipped: {} - {}\n", file_path.display(), reason);
+                        record(Some(file_path), skipped_state(&reason));
+                        skipped.insert(file_path.clone(), reason);
+                        continue;
+                    }
+                    record(
+                        Some(file_path),
+                        JournalState::Finished {
+                            outcome: outcome.status(),
+                            outputs: outcome
+                                .output_paths()
+                                .into_iter()
+                                .map(Path::to_path_buf)
+                                .collect(),
+                        },
+                    );
                     successful += 1;
-                    println!("✅ Successfully processed: {}\n", file_path.display());
+                    let saved = saved_bytes(source_size, &outcome);
+                    // Dry runs count the estimate, real runs the actual sizes
+                    freed += saved
+                        .or_else(|| estimates.get(file_path).copied())
+                        .unwrap_or(0);
+                    successes.insert(file_path.clone(), saved);
+                    report!("✅ Successfully processed: {}\n", file_path.display());
+                    self.notifications.notify(&NotificationEvent::processed(
+                        file_path,
+                        source_size,
+                        &outcome,
+                    ));
+                }
+                Err(e) if e.is::<Cancelled>() => {
+                    record(Some(file_path), JournalState::Cancelled);
+                    record(None, JournalState::Cancelled);
+                    return Err(e);
                 }
                 Err(e) => {
                     failed += 1;
                     let error_msg = format!("{:#}", e);
+                    record(
+                        Some(file_path),
+                        JournalState::Failed {
+                            error: error_msg.clone(),
+                        },
+                    );
                     errors.insert(file_path.clone(), error_msg.clone());
-                    println!(
+                    report!(
                         "❌ Failed to process: {} - {}\n",
                         file_path.display(),
                         error_msg
                     );
+                    self.notifications
+                        .notify(&NotificationEvent::failed(file_path, &e));
                 }
             }
         }
 
+        record(
+            None,
+            JournalState::RunFinished {
+                successful,
+                failed,
+                skipped: skipped.len(),
+            },
+        );
         Ok(BatchResult {
-            total_files: mkv_files.len(),
+            total_files,
             successful,
             failed,
             errors,
+            skipped,
+            successes,
+            counts,
+            warnings: Vec::new(),
         })
     }
 
-    fn collect_mkv_files(&self) -> Result<Vec<PathBuf>> {
+    /// The manifest's analysis of the files that, like their settings, are
+    /// unchanged since the scan, placed at their target for this batch
+    fn scanned_tasks(&self) -> HashMap<PathBuf, ProcessingTask> {
+        let mut tasks = HashMap::new();
+        let mut stale = 0;
+        for entry in &self.scanned {
+            let Some(task) = &entry.task else {
+                continue;
+            };
+            let unchanged = SourceStamp::of(&entry.path)
+                .is_some_and(|stamp| stamp.size == entry.size && stamp.modified == entry.modified);
+            let located = self
+                .file_config(&entry.path)
+                .ok()
+                .filter(|config| unchanged && config_hash(config) == entry.config_hash)
+                .and_then(|config| self.locate_task(task.clone(), &config).ok());
+            match located {
+                Some(task) => {
+                    tasks.insert(entry.path.clone(), task);
+                }
+                None => stale += 1,
+            }
+        }
+        if stale > 0 {
+            report!(
+                "🔄 {} file(s) or their settings changed since the scan - analyzing them again\n",
+                stale
+            );
+        }
+        tasks
+    }
+
+    /// Leave out files the history database records as processed with the
+    /// current settings when `processing.skip_processed` is set. Returns the
+    /// files to process and the skipped ones with the reason.
+    fn separate_processed(
+        &self,
+        files: Vec<PathBuf>,
+    ) -> Result<(Vec<PathBuf>, BTreeMap<PathBuf, SkipReason>)> {
+        let Some(db_path) = &self.config.processing.history_db else {
+            return Ok((files, BTreeMap::new()));
+        };
+        if !self.config.processing.skip_processed {
+            return Ok((files, BTreeMap::new()));
+        }
+
+        let history = HistoryDb::open(db_path)?;
+        let mut to_process = Vec::new();
+        let mut processed = BTreeMap::new();
+        for file in files {
+            // A broken settings file is reported when the file is processed
+            let hash = self
+                .file_config(&file)
+                .map(|config| config_hash(&config))
+                .ok();
+            if let Some(hash) = hash
+                && history.was_processed(&file, &hash)?
+            {
+                processed.insert(file, SkipReason::AlreadyProcessed);
+            } else {
+                to_process.push(file);
+            }
+        }
+        if !processed.is_empty() {
+            report!(
+                "📚 Skipping {} file(s) already processed with these settings\n",
+                processed.len()
+            );
+        }
+        Ok((to_process, processed))
+    }
+
+    /// Leave out files that look like samples when `processing.skip_samples`
+    /// is set. Returns the files to process and the samples with the reason.
+    fn separate_samples(
+        &self,
+        files: Vec<PathBuf>,
+    ) -> (Vec<PathBuf>, BTreeMap<PathBuf, SkipReason>) {
+        let Some(minutes) = self.config.processing.skip_samples else {
+            return (files, BTreeMap::new());
+        };
+
+        let durations: Vec<(PathBuf, Option<Duration>)> = files
+            .into_iter()
+            .map(|file| {
+                let duration = media_duration(&file);
+                (file, duration)
+            })
+            .collect();
+        let samples = find_samples(&durations, Duration::from_secs(minutes * 60));
+        if !samples.is_empty() {
+            report!("🎞️  Skipping {} sample file(s)\n", samples.len());
+        }
+
+        let files = durations
+            .into_iter()
+            .map(|(file, _)| file)
+            .filter(|file| !samples.contains_key(file))
+            .collect();
+        let samples = samples
+            .into_iter()
+            .map(|(file, detail)| (file, SkipReason::Sample { detail }))
+            .collect();
+        (files, samples)
+    }
+
+    /// Check once per target directory that outputs can be written there.
+    /// Returns the files to process and the skipped ones with the reason, so an
+    /// unwritable subdirectory doesn't fail its files one by one mid-batch.
+    fn check_target_directories(
+        &self,
+        files: Vec<PathBuf>,
+    ) -> Result<(Vec<PathBuf>, BTreeMap<PathBuf, SkipReason>)> {
+        let mut checked: HashMap<PathBuf, bool> = HashMap::new();
+        let mut writable = Vec::new();
+        let mut skipped = BTreeMap::new();
+
+        for file in files {
+            let directory = self.known_target_directory(&file)?;
+            let is_writable = *checked.entry(directory.clone()).or_insert_with(|| {
+                check_directory_writable(&directory)
+                    .inspect_err(|e| {
+                        report_warn!("⚠️  Skipping files for {}:\n{:#}\n", directory.display(), e);
+                        record_warning(
+                            WarningKind::SkippedDirectory,
+                            format!("target {} is not writable", directory.display()),
+                        );
+                    })
+                    .is_ok()
+            });
+            if is_writable {
+                writable.push(file);
+            } else {
+                skipped.insert(file, SkipReason::TargetNotWritable { directory });
+            }
+        }
+
+        Ok((writable, skipped))
+    }
+
+    /// Find files that are parts of one recording (`Movie.001.mkv`, `Movie.cd2.mkv`
+    /// or linked segments) so they aren't remuxed independently. Without
+    /// `allow_ordered_chapters` every part is skipped; with it, a group is kept
+    /// in playback order when all parts share one track layout, and skipped
+    /// otherwise since they'd be trimmed differently. Returns the files to
+    /// process and the kept groups; analyzed parts are stored in `tasks`.
+    async fn separate_multi_part(
+        &self,
+        files: Vec<PathBuf>,
+        tasks: &mut HashMap<PathBuf, ProcessingTask>,
+        skipped: &mut BTreeMap<PathBuf, SkipReason>,
+    ) -> Result<(Vec<PathBuf>, Vec<PartGroup>)> {
+        let linking = files
+            .iter()
+            .filter_map(|file| Some((file.clone(), analyze_segment_linking(file)?)))
+            .collect();
+        let groups = find_part_groups(&files, &linking);
+        if groups.is_empty() {
+            return Ok((files, groups));
+        }
+
+        let mut kept = Vec::new();
+        for group in groups {
+            let kind = if group.linked {
+                "linked segments"
+            } else {
+                "multi-part"
+            };
+            if !self.config.processing.allow_ordered_chapters {
+                report!(
+                    "🧩 Skipping {} parts of {} '{}' - remuxing parts independently can break ordered playback",
+                    group.files.len(),
+                    kind,
+                    group.name
+                );
+                Self::skip_group(
+                    &group,
+                    skipped,
+                    &format!(
+                        "{} '{}' left alone so ordered playback keeps working (use --allow-ordered-chapters to process the parts as a group)",
+                        kind, group.name
+                    ),
+                );
+                continue;
+            }
+
+            for file in &group.files {
+                self.control.check_cancelled()?;
+                // Analysis errors are reported when the file is processed
+                if !tasks.contains_key(file)
+                    && let Ok(task) = self.prepare_task(file).await
+                {
+                    tasks.insert(file.clone(), task);
+                }
+            }
+            let layouts: Vec<&Vec<_>> = group
+                .files
+                .iter()
+                .filter_map(|file| tasks.get(file).map(|task| &task.streams))
+                .collect();
+            if !same_track_layout(&layouts) {
+                report!(
+                    "🧩 Skipping {} '{}' - its parts have different tracks and would be trimmed differently",
+                    kind,
+                    group.name
+                );
+                Self::skip_group(
+                    &group,
+                    skipped,
+                    &format!(
+                        "{} '{}' has parts with different tracks, which would be trimmed differently",
+                        kind, group.name
+                    ),
+                );
+                continue;
+            }
+
+            report!(
+                "🧩 Processing {} part(s) of {} '{}' as a group",
+                group.files.len(),
+                kind,
+                group.name
+            );
+            kept.push(group);
+        }
+
+        // Parts are processed back to back in playback order, where the first part was
+        let grouped: Vec<&PathBuf> = kept
+            .iter()
+            .flat_map(|group| &group.files)
+            .chain(skipped.keys())
+            .collect();
+        let mut ordered = Vec::new();
+        for file in &files {
+            if let Some(group) = kept.iter().find(|group| group.files.first() == Some(file)) {
+                ordered.extend(group.files.iter().cloned());
+            } else if !grouped.contains(&file) {
+                ordered.push(file.clone());
+            }
+        }
+        report!();
+
+        Ok((ordered, kept))
+    }
+
+    /// Mark every part of `group` as skipped for `reason`
+    fn skip_group(group: &PartGroup, skipped: &mut BTreeMap<PathBuf, SkipReason>, reason: &str) {
+        let count = group.files.len();
+        for (index, file) in group.files.iter().enumerate() {
+            skipped.insert(
+                file.clone(),
+                SkipReason::MultiPart {
+                    detail: format!("part {} of {}: {}", index + 1, count, reason),
+                },
+            );
+        }
+    }
+
+    /// Compare the projected output of the batch with the target's free space.
+    /// Returns the files to process and the files skipped to make the batch
+    /// fit; fails when it doesn't fit and neither `force` nor `fill_space` is
+    /// set. Tasks analyzed for the estimate are stored in `tasks` for reuse.
+    async fn plan_free_space(
+        &self,
+        files: Vec<PathBuf>,
+        tasks: &mut HashMap<PathBuf, ProcessingTask>,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let available_bytes = match available_space(&self.target_directory) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                report_warn!("Warning: Skipping free space check: {:#}", e);
+                return Ok((files, Vec::new()));
+            }
+        };
+
+        let mut plan = SpacePlan {
+            files: Vec::new(),
+            available_bytes,
+        };
+        for file_path in &files {
+            self.control.check_cancelled()?;
+            let task = match tasks.remove(file_path) {
+                Some(task) => Ok(task),
+                None => self.prepare_task(file_path).await,
+            };
+            let size = match task {
+                Ok(task) => {
+                    let config = self.file_config(file_path)?;
+                    let decisions = self.decide(&task, &config);
+                    let size =
+                        projected_output_size(&task, &decisions, &task.target_location, &config);
+                    tasks.insert(file_path.clone(), task);
+                    size
+                }
+                // Analysis errors are reported when the file is processed
+                Err(_) => std::fs::metadata(file_path)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0),
+            };
+            plan.files.push((file_path.clone(), size));
+        }
+
+        report!(
+            "💽 Projected output: ≈{} ({} free on target)\n",
+            format_size(plan.projected_bytes()),
+            format_size(plan.available_bytes)
+        );
+        if plan.fits() {
+            return Ok((files, Vec::new()));
+        }
+
+        if self.config.processing.fill_space {
+            let (fit, skipped) = plan.fit_to_space();
+            report!(
+                "⚠️  Batch doesn't fit the target's free space - processing {} file(s), skipping {}\n",
+                fit.len(),
+                skipped.len()
+            );
+            return Ok((fit, skipped));
+        }
+        if self.config.processing.force || self.config.processing.dry_run {
+            report!("⚠️  Batch may not fit the target's free space\n");
+            return Ok((files, Vec::new()));
+        }
+
+        Err(directory_error(
+            &self.target_directory,
+            "Plan batch",
+            &format!(
+                "Projected output (≈{}) exceeds the free space ({}).\n   💡 Use --fill-space to process what fits, or --force to start anyway",
+                format_size(plan.projected_bytes()),
+                format_size(plan.available_bytes)
+            ),
+        ))
+    }
+
+    /// Order the batch for `processing.free_up`: files with the most removable
+    /// bytes first, multi-part groups as a whole in playback order. Returns
+    /// the order and the estimated saving per file; tasks analyzed for the
+    /// estimate are stored in `tasks` for reuse.
+    async fn order_for_free_up(
+        &self,
+        files: Vec<PathBuf>,
+        groups: &[PartGroup],
+        tasks: &mut HashMap<PathBuf, ProcessingTask>,
+        goal: u64,
+    ) -> Result<(Vec<PathBuf>, HashMap<PathBuf, u64>)> {
+        let mut estimates = HashMap::new();
+        for file_path in &files {
+            self.control.check_cancelled()?;
+            let task = match tasks.remove(file_path) {
+                Some(task) => Ok(task),
+                None => self.prepare_task(file_path).await,
+            };
+            // Analysis errors are reported when the file is processed
+            let Ok(task) = task else {
+                continue;
+            };
+            let config = self.file_config(file_path)?;
+            estimates.insert(
+                file_path.clone(),
+                self.decide(&task, &config).removed_bytes(),
+            );
+            tasks.insert(file_path.clone(), task);
+        }
+
+        let mut units: Vec<Vec<PathBuf>> = Vec::new();
+        for file in files {
+            if let Some(group) = groups.iter().find(|group| group.files.contains(&file))
+                && let Some(unit) = units.iter_mut().find(|unit| group.files.contains(&unit[0]))
+            {
+                unit.push(file);
+                continue;
+            }
+            units.push(vec![file]);
+        }
+        let unit_bytes = |unit: &Vec<PathBuf>| -> u64 {
+            unit.iter().filter_map(|file| estimates.get(file)).sum()
+        };
+        units.sort_by_key(|unit| std::cmp::Reverse(unit_bytes(unit)));
+
+        let removable: u64 = estimates.values().sum();
+        report!(
+            "🧹 Free-up target {}: ≈{} removable in this batch, largest savings first\n",
+            format_size(goal),
+            format_size(removable)
+        );
+        if removable < goal {
+            report_warn!(
+                "⚠️  The batch is expected to free up only ≈{} of the {} requested\n",
+                format_size(removable),
+                format_size(goal)
+            );
+        }
+        Ok((units.concat(), estimates))
+    }
+
+    /// Stream decisions of the default language policy for `task`
+    fn decide(&self, task: &ProcessingTask, config: &Config) -> StreamDecisions {
+        let context = FileContext {
+            source_file: &task.source_file,
+            streams: &task.streams,
+            media_kind: task.media_kind(),
+            config,
+            import_context: self.import_context.as_ref(),
+        };
+        decide_streams(&context, &LanguagePolicy)
+    }
+
+    /// Settings for a file of the batch: see `directory_config()`, with the
+    /// file's own settings file (`file_settings_path()`) merged over them
+    fn file_config(&self, file_path: &Path) -> Result<Arc<Config>> {
+        if let Some(config) = self.cached_config(file_path) {
+            return Ok(config);
+        }
+
+        let inherited = self.directory_config(file_path.parent().unwrap_or(Path::new("")))?;
+        let config = match file_settings_path(file_path).filter(|path| path.is_file()) {
+            Some(settings_file) => {
+                let config = inherited.with_overrides_file(&settings_file)?;
+                report!("⚙️  File settings: {}", settings_file.display());
+                Arc::new(config)
+            }
+            None => inherited,
+        };
+
+        if let Ok(mut configs) = self.directory_configs.lock() {
+            configs.insert(file_path.to_path_buf(), config.clone());
+        }
+        Ok(config)
+    }
+
+    /// Settings for the files in `directory`: the batch's config with the
+    /// `.mkv-slimmer.yaml` files from the source directory down to `directory`
+    /// merged over it, the closest one last. A season folder as the source
+    /// directory first gets its series folder's. Directories outside the
+    /// source directory get the batch's config.
+    fn directory_config(&self, directory: &Path) -> Result<Arc<Config>> {
+        if let Some(config) = self.cached_config(directory) {
+            return Ok(config);
+        }
+
+        if !directory.starts_with(&self.input_path) {
+            return Ok(Arc::new(self.config.clone()));
+        }
+        let inherited = match directory.parent() {
+            Some(parent) if directory != self.input_path => self.directory_config(parent)?,
+            _ => Arc::new(self.series_config()?),
+        };
+        let settings_file = directory.join(DIRECTORY_CONFIG_FILE);
+        let config = if settings_file.is_file() {
+            let config = inherited.with_overrides_file(&settings_file)?;
+            report!("⚙️  Directory settings: {}", settings_file.display());
+            Arc::new(config)
+        } else {
+            inherited
+        };
+
+        if let Ok(mut configs) = self.directory_configs.lock() {
+            configs.insert(directory.to_path_buf(), config.clone());
+        }
+        Ok(config)
+    }
+
+    /// The batch's config, with the series folder's `.mkv-slimmer.yaml`
+    /// merged over it when the source directory is a season folder
+    fn series_config(&self) -> Result<Config> {
+        let series = series_directory(&self.input_path);
+        let settings_file = series.join(DIRECTORY_CONFIG_FILE);
+        if series == self.input_path || !settings_file.is_file() {
+            return Ok(self.config.clone());
+        }
+        let config = self.config.with_overrides_file(&settings_file)?;
+        report!("⚙️  Series settings: {}", settings_file.display());
+        Ok(config)
+    }
+
+    fn cached_config(&self, directory: &Path) -> Option<Arc<Config>> {
+        self.directory_configs.lock().ok()?.get(directory).cloned()
+    }
+
+    /// MKV files of the batch, and the files the filter pattern or age
+    /// filter left out
+    fn collect_mkv_files(&self) -> Result<(Vec<PathBuf>, BTreeMap<PathBuf, SkipReason>)> {
+        if let Some(files) = &self.files {
+            if self.scanned.is_empty() {
+                report!("🔁 Processing {} file(s) of a previous run", files.len());
+            } else {
+                report!("📋 Processing {} file(s) of a scan", files.len());
+            }
+            return Ok((files.clone(), BTreeMap::new()));
+        }
         let mut mkv_files = Vec::new();
+        let mut filtered = BTreeMap::new();
 
         if self.recursive {
-            self.collect_recursive(&self.input_path, &mut mkv_files)?;
+            // A target nested in the source, directly or through a symlink,
+            // must never feed its outputs back into the batch
+            let target = resolve_target_path(&self.target_directory).ok();
+            self.collect_recursive(&self.input_path, target.as_deref(), &mut mkv_files)?;
         } else {
             self.collect_non_recursive(&self.input_path, &mut mkv_files)?;
         }
 
         // Apply filter if specified
         if let Some(filter) = &self.filter_pattern {
-            mkv_files = self.apply_filter(mkv_files, filter)?;
+            mkv_files = self.apply_filter(mkv_files, filter, &mut filtered)?;
+        }
+        if self.age_filter.is_active() {
+            mkv_files = self.apply_age_filter(mkv_files, &mut filtered);
+        }
+
+        // Companion audio is merged into its video file rather than processed on its own
+        if self.config.audio.merge_external {
+            let all_files = mkv_files.clone();
+            mkv_files.retain(|file| {
+                !all_files
+                    .iter()
+                    .any(|source| is_companion_audio(source, file))
+            });
         }
 
         // Sort for consistent processing order
         mkv_files.sort();
 
-        Ok(mkv_files)
+        Ok((mkv_files, filtered))
     }
 
     fn collect_non_recursive(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
@@ -174,25 +1163,59 @@ impl BatchProcessor {
         Ok(())
     }
 
-    fn collect_recursive(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
-        let entries = std::fs::read_dir(dir)
-            .with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+    fn collect_recursive(
+        &self,
+        dir: &Path,
+        excluded: Option<&Path>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            // An unreadable subdirectory only leaves its files out
+            Err(e) if dir != self.input_path => {
+                report_warn!("⚠️  Skipping unreadable directory {}: {}", dir.display(), e);
+                record_warning(
+                    WarningKind::SkippedDirectory,
+                    format!("source {} is unreadable: {}", dir.display(), e),
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read directory: {}", dir.display()));
+            }
+        };
 
         for entry in entries {
             let entry = entry?;
             let path = entry.path();
 
+            let in_target = excluded.is_some_and(|excluded| {
+                path.canonicalize()
+                    .is_ok_and(|canonical| canonical.starts_with(excluded))
+            });
             if path.is_file() && is_valid_mkv_file(&path) {
-                files.push(path);
+                if !in_target {
+                    files.push(path);
+                }
             } else if path.is_dir() {
-                self.collect_recursive(&path, files)?;
+                if in_target {
+                    report!("🪆 Leaving the target out of the batch: {}", path.display());
+                    continue;
+                }
+                self.collect_recursive(&path, excluded, files)?;
             }
         }
 
         Ok(())
     }
 
-    fn apply_filter(&self, files: Vec<PathBuf>, pattern: &str) -> Result<Vec<PathBuf>> {
+    fn apply_filter(
+        &self,
+        files: Vec<PathBuf>,
+        pattern: &str,
+        filtered_out: &mut BTreeMap<PathBuf, SkipReason>,
+    ) -> Result<Vec<PathBuf>> {
         let mut filtered_files = Vec::new();
 
         for file in files {
@@ -213,110 +1236,507 @@ impl BatchProcessor {
                 .matches(&match_str)
             {
                 filtered_files.push(file);
+            } else {
+                filtered_out.insert(
+                    file,
+                    SkipReason::FilteredOut {
+                        filter: format!("--filter '{}'", pattern),
+                    },
+                );
             }
         }
 
         Ok(filtered_files)
     }
 
-    async fn process_single_file(&self, file_path: &Path) -> Result<()> {
-        // Calculate target path
-        let target_path = self.calculate_target_path(file_path)?;
+    /// Keep the files whose modification time passes the age filter; files
+    /// without a readable mtime are left out
+    fn apply_age_filter(
+        &self,
+        files: Vec<PathBuf>,
+        filtered_out: &mut BTreeMap<PathBuf, SkipReason>,
+    ) -> Vec<PathBuf> {
+        let now = SystemTime::now();
+        let bounds = [
+            ("--newer-than", self.age_filter.newer_than),
+            ("--older-than", self.age_filter.older_than),
+        ];
+        let filter = bounds
+            .iter()
+            .filter_map(|(flag, age)| Some(format!("{} {}", flag, format_age((*age)?))))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut kept = Vec::new();
+        for file in files {
+            let passes = match std::fs::metadata(&file).and_then(|metadata| metadata.modified()) {
+                // Modification times in the future count as brand new
+                Ok(modified) => self
+                    .age_filter
+                    .matches(now.duration_since(modified).unwrap_or_default()),
+                Err(e) => {
+                    report_warn!(
+                        "Warning: Skipping {} - can't read its modification time: {}",
+                        file.display(),
+                        e
+                    );
+                    false
+                }
+            };
+            if passes {
+                kept.push(file);
+            } else {
+                filtered_out.insert(
+                    file,
+                    SkipReason::FilteredOut {
+                        filter: filter.clone(),
+                    },
+                );
+            }
+        }
+        kept
+    }
+
+    /// Source folder of `file_path` relative to the input directory; empty
+    /// when not recursive
+    fn relative_dir(&self, file_path: &Path) -> Result<PathBuf> {
+        if !self.recursive {
+            return Ok(PathBuf::new());
+        }
+
+        let relative_path = file_path
+            .strip_prefix(&self.input_path)
+            .with_context(|| format!("Failed to strip prefix from {}", file_path.display()))?;
+
+        // Validate no path traversal components
+        for component in relative_path.components() {
+            if matches!(component, std::path::Component::ParentDir) {
+                anyhow::bail!(
+                    "Path traversal attempt detected in: {}",
+                    relative_path.display()
+                );
+            }
+        }
+
+        Ok(relative_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default())
+    }
+
+    /// Target directory for `file_path` as far as it's known before analysis:
+    /// a target template may place files by their kept languages
+    fn known_target_directory(&self, file_path: &Path) -> Result<PathBuf> {
+        let relative_dir = self.relative_dir(file_path)?;
+        Ok(match &self.config.processing.target_template {
+            Some(template) => self
+                .target_directory
+                .join(known_template_directory(template, &relative_dir)),
+            None => self.target_directory.join(relative_dir),
+        })
+    }
+
+    /// Output path for `file_path`: mirrors the source's relative location in
+    /// recursive mode unless `processing.target_template` says otherwise
+    fn calculate_target_path(&self, file_path: &Path, languages: &str) -> Result<PathBuf> {
+        let relative_dir = self.relative_dir(file_path)?;
+        let filename = file_path
+            .file_name()
+            .context("Failed to get filename")?
+            .to_string_lossy();
 
-        // Ensure target directory exists and get it for processing
-        let target_directory = target_path.parent().context(
+        match &self.config.processing.target_template {
+            Some(template) => {
+                let values = TargetPathValues {
+                    relative_dir: &relative_dir,
+                    filename: &filename,
+                    lang_summary: languages,
+                };
+                Ok(self
+                    .target_directory
+                    .join(render_target_path(template, &values)?))
+            }
+            None => Ok(self
+                .target_directory
+                .join(relative_dir)
+                .join(filename.as_ref())),
+        }
+    }
+
+    /// Target directory and, when the template renames the file, output
+    /// filename for `file_path`
+    fn target_location(
+        &self,
+        file_path: &Path,
+        languages: &str,
+    ) -> Result<(PathBuf, Option<String>)> {
+        let target_path = self.calculate_target_path(file_path, languages)?;
+        let target_directory = target_path.parent().map(Path::to_path_buf).context(
             "Target path has no parent directory - cannot determine where to place output file",
         )?;
+        let output_filename = target_path
+            .file_name()
+            .filter(|name| Some(*name) != file_path.file_name())
+            .map(|name| name.to_string_lossy().to_string());
+        Ok((target_directory, output_filename))
+    }
 
-        fs::create_dir_all(target_directory)
+    /// Analyze the streams of an MKV file into a ProcessingTask
+    async fn prepare_task(&self, file_path: &Path) -> Result<ProcessingTask> {
+        // Analysis can run before the file's turn (space and free-up planning)
+        with_file(file_path, self.analyze_task(file_path)).await
+    }
+
+    async fn analyze_task(&self, file_path: &Path) -> Result<ProcessingTask> {
+        let config = self.file_config(file_path)?;
+        let task = self.analyze_source(file_path, &config).await?;
+        self.locate_task(task, &config)
+    }
+
+    /// Streams, chapters and track IDs of a source, without a target location
+    async fn analyze_source(&self, file_path: &Path, config: &Config) -> Result<ProcessingTask> {
+        let mut streams = analyze_mkv_streams(file_path, config)
             .await
-            .with_context(|| {
-                format!(
-                    "Failed to create target directory: {}",
-                    target_directory.display()
-                )
-            })?;
+            .with_context(|| format!("Failed to analyze MKV streams: {}", file_path.display()))?;
+        if config.audio.merge_external {
+            add_companion_audio(file_path, &mut streams, config).await?;
+        }
+
+        let task = ProcessingTask::new(file_path.to_path_buf(), PathBuf::new(), streams, None)
+            .with_segment_linking(analyze_segment_linking(file_path))
+            .with_chapters(chapter_count(file_path));
+        let track_ids = resolve_track_ids(file_path, &task.streams).await;
+        Ok(task.with_track_ids(track_ids))
+    }
+
+    /// Set where the output of an analyzed `task` goes in this batch
+    fn locate_task(&self, mut task: ProcessingTask, config: &Config) -> Result<ProcessingTask> {
+        let file_path = task.source_file.clone();
+        let uses_languages = self
+            .config
+            .processing
+            .target_template
+            .as_ref()
+            .is_some_and(|template| template.contains("{lang_summary}"));
+        let languages = if uses_languages {
+            lang_summary(&task.streams, &self.decide(&task, config))
+        } else {
+            String::new()
+        };
+        (task.target_location, task.output_filename) =
+            self.target_location(&file_path, &languages)?;
+
+        Ok(task)
+    }
+
+    /// Process one file, reusing `task` when it was already analyzed for planning
+    async fn process_single_file(
+        &self,
+        file_path: &Path,
+        task: Option<ProcessingTask>,
+    ) -> Result<ProcessingOutcome> {
+        let config = self.file_config(file_path)?;
 
         // Check if file is valid MKV - if not, handle immediately
         if !is_valid_mkv_file(file_path) {
-            println!("⚠️  File is not a valid MKV file: {}", file_path.display());
-            println!("🔄 Falling back to copying original file (no processing needed)");
+            report!("⚠️  File is not a valid MKV file: {}", file_path.display());
+            report!("🔄 Falling back to copying original file (no processing needed)");
 
+            let (target_directory, output_filename) = self.target_location(file_path, "none")?;
+            create_output_directory(&target_directory, &config)?;
             return handle_non_mkv_file(
                 file_path,
-                target_directory,
-                None,
-                &self.config,
-                self.sonarr_context.as_ref(),
+                &target_directory,
+                output_filename,
+                &config,
+                self.import_context.as_ref(),
+                &self.control,
             )
             .await;
         }
 
-        // Analyze streams and create ProcessingTask
-        let streams = analyze_mkv_streams(file_path)
-            .await
-            .with_context(|| format!("Failed to analyze MKV streams: {}", file_path.display()))?;
+        // Analyze streams unless planning already did
+        let task = match task {
+            Some(task) => task,
+            None => self.prepare_task(file_path).await?,
+        };
 
-        let task = ProcessingTask::new(
-            file_path.to_path_buf(),
-            target_directory.to_path_buf(),
-            streams,
-            None, // No custom output filename in batch mode
-        );
+        // Ensure target directory exists
+        create_output_directory(&task.target_location, &config)?;
+
+        if let StreamTables::File(path) = &self.stream_tables {
+            self.append_stream_table(path, &task, &config)?;
+        }
 
-        // Process the task (without stream display for batch mode)
-        process_task(task, &self.config, self.sonarr_context.as_ref(), false).await
+        process_task(
+            task,
+            &config,
+            self.import_context.as_ref(),
+            &LanguagePolicy,
+            self.stream_tables == StreamTables::Console,
+            &self.control,
+        )
+        .await
     }
 
-    fn calculate_target_path(&self, source_file: &Path) -> Result<PathBuf> {
-        let filename = source_file.file_name().context("Failed to get filename")?;
+    /// Append the stream table of `task` to the `--show-streams` file
+    fn append_stream_table(
+        &self,
+        path: &Path,
+        task: &ProcessingTask,
+        config: &Config,
+    ) -> Result<()> {
+        let decisions = self.decide(task, config);
+        let blocks = StreamDisplayer::new(&task.streams, &decisions, task.media_kind())
+            .with_chapters(task.chapters, config.processing.chapters)
+            .with_dropped_metadata(config.tags.dropped_metadata())
+            .with_title_rewrites(title_rewrites(&task.streams, &decisions, &config.titles))
+            .render();
+        let mut text = format!("=== {} ===\n", task.source_file.display());
+        for block in blocks {
+            text.push_str(&strip_ansi(&block));
+            text.push('\n');
+        }
+        text.push('\n');
 
-        if self.recursive {
-            // Preserve directory structure
-            let relative_path = source_file
-                .strip_prefix(&self.input_path)
-                .with_context(|| {
-                    format!("Failed to strip prefix from {}", source_file.display())
-                })?;
-
-            // Validate no path traversal components
-            for component in relative_path.components() {
-                if matches!(component, std::path::Component::ParentDir) {
-                    anyhow::bail!(
-                        "Path traversal attempt detected in: {}",
-                        relative_path.display()
-                    );
-                }
-            }
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .with_context(|| format!("Failed to open stream table file: {}", path.display()))?;
+        file.write_all(text.as_bytes())
+            .with_context(|| format!("Failed to write stream table file: {}", path.display()))
+    }
+}
 
-            Ok(self.target_directory.join(relative_path))
-        } else {
-            // Simple filename in target directory
-            Ok(self.target_directory.join(filename))
+/// `path` relative to the current directory made absolute, for results read
+/// elsewhere later
+fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether `file` is a later part of a multi-part group whose earlier parts
+/// are among the files already handled
+fn continues_group(groups: &[PartGroup], file: &Path, handled: &[PathBuf]) -> bool {
+    groups.iter().any(|group| {
+        group.files.first().is_some_and(|first| first != file)
+            && group.files.iter().any(|part| part == file)
+            && handled.iter().any(|done| group.files.contains(done))
+    })
+}
+
+/// Size and modification time of a source when the batch found it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileSnapshot {
+    size: u64,
+    modified: SystemTime,
+}
+
+impl FileSnapshot {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok()?,
+        })
+    }
+}
+
+/// Journal entry of a file left out for `reason`
+fn skipped_state(reason: &SkipReason) -> JournalState {
+    JournalState::Skipped {
+        kind: reason.kind().to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Why a source can't be processed right now: it changed since `snapshot`
+/// was taken (still being written), or another process holds a lock on it
+fn source_not_ready(path: &Path, snapshot: Option<&FileSnapshot>) -> Option<SkipReason> {
+    if let Some(snapshot) = snapshot
+        && FileSnapshot::of(path).as_ref() != Some(snapshot)
+    {
+        return Some(SkipReason::StabilityCheck);
+    }
+
+    let file = std::fs::File::open(path).ok()?;
+    match file.try_lock_shared() {
+        Err(std::fs::TryLockError::WouldBlock) => Some(SkipReason::Locked),
+        _ => None,
+    }
+}
+
+/// Bytes a processed file saved: source size minus the size of its outputs.
+/// Unchanged files save nothing; dry runs have nothing to measure.
+pub(crate) fn saved_bytes(source_size: Option<u64>, outcome: &ProcessingOutcome) -> Option<u64> {
+    match outcome {
+        ProcessingOutcome::DryRun { .. } => None,
+        ProcessingOutcome::Transferred { .. }
+        | ProcessingOutcome::FlagsNormalized { .. }
+        | ProcessingOutcome::UpToDate { .. }
+        | ProcessingOutcome::BelowMinSavings { .. } => Some(0),
+        ProcessingOutcome::Remuxed { .. }
+        | ProcessingOutcome::Split { .. }
+        | ProcessingOutcome::AttachmentsRemoved { .. } => {
+            let output_size: u64 = outcome
+                .output_paths()
+                .iter()
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            source_size.map(|size| size.saturating_sub(output_size))
         }
     }
 }
 
 impl BatchResult {
+    /// Results grouped by series/season folder, for files in a Sonarr-style
+    /// layout; empty when no processed file sits in a season folder
+    pub fn by_season(&self) -> Vec<SeasonSummary> {
+        let mut groups: BTreeMap<(String, String), SeasonSummary> = BTreeMap::new();
+        let files = self
+            .successes
+            .iter()
+            .map(|(path, saved)| (path, saved.unwrap_or(0), false))
+            .chain(self.errors.keys().map(|path| (path, 0, true)));
+
+        for (path, saved, failed) in files {
+            let Some((series, season)) = series_season(path) else {
+                continue;
+            };
+            let group = groups
+                .entry((series.clone(), season.clone()))
+                .or_insert_with(|| SeasonSummary {
+                    series,
+                    season,
+                    files: 0,
+                    failed: 0,
+                    saved_bytes: 0,
+                });
+            group.files += 1;
+            group.saved_bytes += saved;
+            if failed {
+                group.failed += 1;
+            }
+        }
+
+        groups.into_values().collect()
+    }
+
+    /// Print savings and failures per series/season (nothing outside Sonarr-style layouts)
+    pub fn print_season_summary(&self) {
+        let seasons = self.by_season();
+        if seasons.is_empty() {
+            return;
+        }
+
+        report!("\n📺 By series/season:");
+        for season in seasons {
+            let marker = if season.failed == season.files {
+                "❌"
+            } else if season.failed > 0 {
+                "⚠️ "
+            } else {
+                "✅"
+            };
+            report!(
+                "   {} {} / {}: {} file(s), {} failed, saved {}",
+                marker,
+                season.series,
+                season.season,
+                season.files,
+                season.failed,
+                format_size(season.saved_bytes)
+            );
+        }
+    }
+
+    /// Counts of the batch for `--result-json` and the exit status
+    pub fn run_summary(&self, dry_run: bool) -> RunSummary {
+        RunSummary {
+            dry_run,
+            total_files: self.total_files,
+            successful: self.successful,
+            failed: self.failed,
+            skipped: self.skipped.len(),
+            skipped_by_reason: self.skipped_by_reason(),
+            retry_later: self
+                .skipped
+                .values()
+                .filter(|reason| reason.is_transient())
+                .count(),
+            counts: self.counts,
+            warnings_by_kind: warning_counts(&self.warnings),
+            failures: self
+                .errors
+                .iter()
+                .map(|(file, error)| (absolute_path(file), error.clone()))
+                .collect(),
+            batch: None,
+        }
+    }
+
+    /// Number of skipped files per `SkipReason::kind()`
+    pub fn skipped_by_reason(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for reason in self.skipped.values() {
+            *counts.entry(reason.kind().to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Print the skipped files: counts per reason, then each file except the
+    /// filtered-out ones (usually most of the library)
+    pub fn print_skipped(&self, indent: &str) {
+        for (kind, count) in self.skipped_by_reason() {
+            report!("{}{}: {}", indent, kind.replace('_', " "), count);
+        }
+        for (file, reason) in self
+            .skipped
+            .iter()
+            .filter(|(_, reason)| !matches!(reason, SkipReason::FilteredOut { .. }))
+        {
+            report!("{}{} - {}", indent, file.display(), reason);
+        }
+    }
+
+    /// Print the batch's warnings by kind, so they aren't lost in the scrollback
+    pub fn print_warnings(&self) {
+        print_warning_summary(&self.warnings);
+    }
+
     pub fn print_summary(&self) {
-        println!("📊 Batch Processing Summary:");
-        println!("   Total files: {}", self.total_files);
-        println!("   Successful: {}", self.successful);
-        println!("   Failed: {}", self.failed);
+        report!("📊 Batch Processing Summary:");
+        report!("   Total files: {}", self.total_files);
+        report!("   Successful: {}", self.successful);
+        report!("   Failed: {}", self.failed);
+
+        if !self.skipped.is_empty() {
+            report!("   Skipped: {}", self.skipped.len());
+        }
 
         if !self.errors.is_empty() {
-            println!("\n❌ Failed files:");
+            report!("\n❌ Failed files:");
             for (file, error) in &self.errors {
-                println!("   {}: {}", file.display(), error);
+                report!("   {}: {}", file.display(), error);
             }
         }
 
+        if !self.skipped.is_empty() {
+            report!("\n⏭️  Skipped files:");
+            self.print_skipped("   ");
+        }
+
+        self.print_season_summary();
+
         if self.successful == self.total_files {
-            println!("\n🎉 All files processed successfully!");
+            report!("\n🎉 All files processed successfully!");
         } else if self.successful > 0 {
-            println!("\n⚠️  Batch completed with some failures");
+            report!("\n⚠️  Batch completed with some failures");
         } else {
-            println!("\n💥 Batch processing failed completely");
+            report!("\n💥 Batch processing failed completely");
         }
+
+        self.print_warnings();
     }
 }
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::mkvtoolnix::run_tool;
+use crate::error::{file_validation_error, processing_error};
+use crate::models::MkvmergeIdentify;
+use crate::utils::require_mkvtoolnix_tool;
+
+/// Default export location: `Movie.mkv` → `Movie.chapters.xml` next to the file
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::chapters::default_chapters_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(
+///     default_chapters_path(Path::new("/movies/Movie.mkv")).unwrap(),
+///     PathBuf::from("/movies/Movie.chapters.xml")
+/// );
+/// ```
+pub fn default_chapters_path(file: &Path) -> Result<PathBuf> {
+    let stem = file
+        .file_stem()
+        .context("Could not extract filename from source path")?
+        .to_string_lossy();
+    Ok(file.with_file_name(format!("{}.chapters.xml", stem)))
+}
+
+/// Write the chapters of `file` to `output` as Matroska chapters XML (mkvextract)
+pub fn export_chapters(file: &Path, output: &Path) -> Result<()> {
+    if !file.is_file() {
+        return Err(file_validation_error(file, "File does not exist"));
+    }
+    require_mkvtoolnix_tool("mkvextract")?;
+
+    let mut cmd = Command::new("mkvextract");
+    cmd.arg(file).arg("chapters").arg(output);
+    run_tool(&mut cmd, file, "Chapter export")?;
+
+    // mkvextract succeeds without writing anything when there are no chapters
+    if !output.is_file() {
+        return Err(processing_error(
+            file,
+            "Chapter export",
+            "File has no chapters",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replace the chapters of `file` in place with those from `chapters_xml` (mkvpropedit)
+pub fn import_chapters(file: &Path, chapters_xml: &Path) -> Result<()> {
+    if !file.is_file() {
+        return Err(file_validation_error(file, "File does not exist"));
+    }
+    if !chapters_xml.is_file() {
+        return Err(file_validation_error(
+            chapters_xml,
+            "Chapters file does not exist",
+        ));
+    }
+    require_mkvtoolnix_tool("mkvpropedit")?;
+
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(file).arg("--chapters").arg(chapters_xml);
+    run_tool(&mut cmd, file, "Chapter import")
+}
+
+/// Number of chapters of `file_path` (summed over its editions), read with
+/// the matroska parser or, for files it can't read, `mkvmerge -J`. None when
+/// neither can tell.
+pub fn chapter_count(file_path: &Path) -> Option<usize> {
+    if let Some(mkv) = std::fs::File::open(file_path)
+        .ok()
+        .and_then(|file| matroska::Matroska::open(file).ok())
+    {
+        return Some(
+            mkv.chapters
+                .iter()
+                .map(|edition| edition.chapters.len())
+                .sum(),
+        );
+    }
+
+    let output = Command::new("mkvmerge")
+        .arg("-J")
+        .arg(file_path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let identify: MkvmergeIdentify = serde_json::from_slice(&output.stdout).ok()?;
+    Some(
+        identify
+            .chapters
+            .iter()
+            .map(|edition| edition.num_entries)
+            .sum(),
+    )
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::mkvtoolnix::run_tool;
+use crate::config::Config;
+use crate::models::{ProcessingTask, StreamType};
+use crate::utils::tool_available;
+use crate::{report, report_warn};
+
+/// Text subtitle codecs (ffprobe names) whose payload is subject to a charset
+const TEXT_SUBTITLE_CODECS: &[&str] = &["subrip", "srt", "ass", "ssa", "webvtt", "text"];
+
+/// Whether `codec` (ffprobe name) is a text-based subtitle format
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::charset::is_text_subtitle;
+///
+/// assert!(is_text_subtitle("subrip"));
+/// assert!(is_text_subtitle("ASS"));
+/// assert!(!is_text_subtitle("hdmv_pgs_subtitle"));
+/// ```
+pub fn is_text_subtitle(codec: &str) -> bool {
+    TEXT_SUBTITLE_CODECS.contains(&codec.to_lowercase().as_str())
+}
+
+/// Usual legacy encoding for subtitles in a language, used when a track turns
+/// out not to be UTF-8 and the config names no charset for its language
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::charset::legacy_charset;
+///
+/// assert_eq!(legacy_charset("hun"), "windows-1250");
+/// assert_eq!(legacy_charset("rus"), "windows-1251");
+/// assert_eq!(legacy_charset("eng"), "windows-1252");
+/// ```
+pub fn legacy_charset(language: &str) -> &'static str {
+    match language {
+        "alb" | "bos" | "cze" | "hrv" | "hun" | "pol" | "rum" | "slo" | "slv" => "windows-1250",
+        "bel" | "bul" | "mac" | "rus" | "srp" | "ukr" => "windows-1251",
+        "gre" => "windows-1253",
+        "tur" => "windows-1254",
+        "heb" => "windows-1255",
+        "ara" | "per" | "urd" => "windows-1256",
+        "est" | "lav" | "lit" => "windows-1257",
+        "vie" => "windows-1258",
+        "chi" => "GB18030",
+        "jpn" => "Shift_JIS",
+        "kor" => "EUC-KR",
+        "tha" => "TIS-620",
+        _ => "windows-1252",
+    }
+}
+
+/// Find kept text subtitle tracks of the source that aren't valid UTF-8 and
+/// return the charset to convert each from, as `(track ID, charset)` pairs for
+/// mkvmerge's `--sub-charset`. Tracks are extracted with mkvextract to check
+/// their contents; the charset comes from `subtitles.charsets` by language,
+/// falling back to `legacy_charset()`.
+pub fn detect_subtitle_charsets(
+    task: &ProcessingTask,
+    streams_to_keep: &[u32],
+    config: &Config,
+) -> Result<Vec<(u32, String)>> {
+    let text_tracks: Vec<_> = task
+        .streams
+        .iter()
+        .filter(|s| s.external.is_none() && streams_to_keep.contains(&s.index))
+        .filter(|s| s.stream_type == StreamType::Subtitle)
+        .filter(|s| is_text_subtitle(&s.codec))
+        .collect();
+    if text_tracks.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !tool_available("mkvextract") {
+        report_warn!(
+            "⚠️  mkvextract not found - subtitle charsets left as they are (install MKVToolNix to fix them)"
+        );
+        return Ok(Vec::new());
+    }
+
+    let extracted: Vec<(u32, PathBuf)> = text_tracks
+        .iter()
+        .map(|s| (s.index, extraction_path(&task.source_file, s.index)))
+        .collect();
+    let mut cmd = Command::new("mkvextract");
+    cmd.arg(&task.source_file).arg("tracks");
+    for (index, path) in &extracted {
+        let mut spec = std::ffi::OsString::from(format!("{}:", task.track_ids.track_id(*index)));
+        spec.push(path);
+        cmd.arg(spec);
+    }
+    let result = run_tool(&mut cmd, &task.source_file, "Subtitle charset detection");
+
+    let mut charsets = Vec::new();
+    for ((index, path), stream) in extracted.iter().zip(&text_tracks) {
+        if result.is_ok()
+            && let Ok(contents) = std::fs::read(path)
+            && !is_utf8(&contents)
+        {
+            let language = stream.effective_language();
+            let charset = config
+                .subtitles
+                .charsets
+                .get(language)
+                .cloned()
+                .unwrap_or_else(|| legacy_charset(language).to_string());
+            report!(
+                "🔤 Subtitle #{} ({}) is not UTF-8, converting from {}",
+                index,
+                language,
+                charset
+            );
+            charsets.push((*index, charset));
+        }
+        let _ = std::fs::remove_file(path);
+    }
+    result?;
+
+    Ok(charsets)
+}
+
+/// Whether subtitle text is valid UTF-8 (a byte order mark is allowed)
+fn is_utf8(contents: &[u8]) -> bool {
+    let contents = contents.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(contents);
+    std::str::from_utf8(contents).is_ok()
+}
+
+/// Temporary file a track is extracted to for inspection
+fn extraction_path(source_file: &Path, index: u32) -> PathBuf {
+    let stem = source_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "mkv-slimmer-{}-{}.{}.sub",
+        std::process::id(),
+        stem,
+        index
+    ))
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::analyzer::analyze_mkv_streams;
+use crate::config::{Config, SyncOffset};
+use crate::models::{ExternalTrack, MediaKind, StreamInfo, StreamType};
+use crate::report;
+use crate::utils::normalize_language;
+
+/// Check whether `candidate` is a companion audio file of `source`
+///
+/// Companions sit in the same directory and share the source's file stem,
+/// optionally followed by a language code: `Movie.mkv` → `Movie.mka`,
+/// `Movie.eng.mka`, `Movie.Commentary.eng.mka`. Only video files have companions.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::external::is_companion_audio;
+/// use std::path::Path;
+///
+/// let source = Path::new("/movies/Movie.mkv");
+/// assert!(is_companion_audio(source, Path::new("/movies/Movie.eng.mka")));
+/// assert!(!is_companion_audio(source, Path::new("/movies/Movie 2.eng.mka")));
+/// assert!(!is_companion_audio(source, Path::new("/other/Movie.eng.mka")));
+/// ```
+pub fn is_companion_audio(source: &Path, candidate: &Path) -> bool {
+    if candidate == source || candidate.parent() != source.parent() {
+        return false;
+    }
+    if MediaKind::from_path(source) != MediaKind::Video
+        || MediaKind::from_path(candidate) != MediaKind::AudioOnly
+    {
+        return false;
+    }
+
+    let (Some(source_stem), Some(candidate_stem)) = (
+        source.file_stem().map(|s| s.to_string_lossy()),
+        candidate.file_stem().map(|s| s.to_string_lossy()),
+    ) else {
+        return false;
+    };
+
+    candidate_stem == source_stem
+        || candidate_stem
+            .strip_prefix(source_stem.as_ref())
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Language code encoded in a companion filename (`Movie.eng.mka` → `eng`)
+///
+/// Only the last dot-separated part before the extension is considered, and
+/// only when it looks like an ISO 639 code (2-3 ASCII letters).
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::external::companion_language;
+/// use std::path::Path;
+///
+/// assert_eq!(companion_language(Path::new("Movie.eng.mka")), Some("eng".to_string()));
+/// assert_eq!(companion_language(Path::new("Movie.Commentary.mka")), None);
+/// assert_eq!(companion_language(Path::new("Movie.mka")), None);
+/// ```
+pub fn companion_language(companion: &Path) -> Option<String> {
+    let stem = companion.file_stem()?.to_string_lossy();
+    let (_, code) = stem.rsplit_once('.')?;
+    let is_language_code =
+        (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphabetic());
+    is_language_code.then(|| normalize_language(code))
+}
+
+/// Find companion audio files of `source`, sorted by filename
+pub fn find_companion_audio(source: &Path) -> Result<Vec<PathBuf>> {
+    let directory = source
+        .parent()
+        .context("Could not determine source directory")?;
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read directory: {}", directory.display()))?;
+
+    let mut companions = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && is_companion_audio(source, &path) {
+            companions.push(path);
+        }
+    }
+    companions.sort();
+
+    Ok(companions)
+}
+
+/// Analyze the companion audio files of `source` and append their audio tracks
+/// to `streams`, so they go through the same keep/default logic as the source's
+/// own tracks. Returns the number of tracks added.
+pub async fn add_companion_audio(
+    source: &Path,
+    streams: &mut Vec<StreamInfo>,
+    config: &Config,
+) -> Result<usize> {
+    let mut next_index = streams.iter().map(|s| s.index + 1).max().unwrap_or(0);
+    let mut added = 0;
+
+    for companion in find_companion_audio(source)? {
+        let language = companion_language(&companion);
+        let companion_streams =
+            analyze_mkv_streams(&companion, config)
+                .await
+                .with_context(|| {
+                    format!("Failed to analyze companion audio: {}", companion.display())
+                })?;
+
+        for mut stream in companion_streams
+            .into_iter()
+            .filter(|s| s.stream_type == StreamType::Audio)
+        {
+            stream.external = Some(ExternalTrack {
+                path: companion.clone(),
+                track_id: stream.index,
+            });
+            stream.index = next_index;
+            if language.is_some() {
+                stream.language = language.clone();
+            }
+            next_index += 1;
+            added += 1;
+            streams.push(stream);
+        }
+
+        report!("🔗 Found companion audio: {}", companion.display());
+    }
+
+    Ok(added)
+}
+
+/// Sidecar holding per-file sync offsets: `Movie.mkv` → `Movie.sync.yaml`
+pub fn sync_sidecar_path(source: &Path) -> Option<PathBuf> {
+    let stem = source.file_stem()?.to_string_lossy();
+    Some(source.with_file_name(format!("{}.sync.yaml", stem)))
+}
+
+/// Sync offsets that apply to the external tracks of `source`
+///
+/// Entries from the sidecar next to the source are added to the configured
+/// ones; offsets set in the config or on the command line take precedence.
+pub fn resolve_sync_offsets(
+    source: &Path,
+    config: &Config,
+) -> Result<BTreeMap<String, SyncOffset>> {
+    let mut offsets = BTreeMap::new();
+
+    if let Some(sidecar) = sync_sidecar_path(source)
+        && sidecar.is_file()
+    {
+        let content = std::fs::read_to_string(&sidecar)
+            .with_context(|| format!("Failed to read sync sidecar: {}", sidecar.display()))?;
+        offsets = serde_yaml::from_str::<BTreeMap<String, SyncOffset>>(&content)
+            .with_context(|| format!("Failed to parse sync sidecar: {}", sidecar.display()))?;
+    }
+
+    offsets.extend(
+        config
+            .processing
+            .sync_offsets
+            .iter()
+            .map(|(key, offset)| (key.clone(), *offset)),
+    );
+
+    Ok(offsets)
+}
+
+/// Offset for an external stream: an entry for its filename wins over one for its language
+pub fn sync_offset_for(
+    stream: &StreamInfo,
+    external: &ExternalTrack,
+    offsets: &BTreeMap<String, SyncOffset>,
+) -> Option<SyncOffset> {
+    let filename = external.path.file_name()?.to_string_lossy();
+    offsets
+        .get(filename.as_ref())
+        .or_else(|| {
+            offsets
+                .iter()
+                .find(|(key, _)| normalize_language(key) == stream.effective_language())
+                .map(|(_, offset)| offset)
+        })
+        .copied()
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::analyzer::{analyze_mkv_streams, resolve_track_ids};
+use super::backup::backup_file;
+use super::mkvtoolnix::run_tool;
+use crate::config::Config;
+use crate::error::{config_error, file_validation_error};
+use crate::models::{LanguageSource, StreamInfo, StreamType, TrackRef};
+use crate::utils::{
+    collect_mkv_files, is_valid_mkv_file, language_from_title, normalize_language,
+    require_mkvtoolnix_tool,
+};
+use crate::{report, report_warn};
+
+/// Language corrections for a library: glob patterns, relative to the scanned
+/// directory, mapped to stream index (or `uid:<track UID>`) → language
+///
+/// ```yaml
+/// "Show/Season 1/*.mkv":
+///   2: jpn
+/// "Movie.mkv":
+///   uid:9283746512: eng
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct LanguageMapping {
+    pub patterns: BTreeMap<String, BTreeMap<TrackRef, String>>,
+}
+
+impl LanguageMapping {
+    /// Load and validate a mapping file
+    pub fn from_yaml(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read language mapping: {}", path.display()))?;
+        let mapping: LanguageMapping = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse language mapping: {}", path.display()))?;
+
+        for pattern in mapping.patterns.keys() {
+            glob::Pattern::new(pattern).map_err(|e| {
+                config_error(
+                    "Language mapping",
+                    &format!("Invalid pattern '{}': {}", pattern, e),
+                )
+            })?;
+        }
+
+        Ok(mapping)
+    }
+
+    /// Corrections for the file at `relative` (its path below the scanned
+    /// directory). Entries of every matching pattern are merged.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::core::fix_languages::LanguageMapping;
+    /// use mkv_slimmer::models::TrackRef;
+    /// use std::collections::BTreeMap;
+    /// use std::path::Path;
+    ///
+    /// let mut mapping = LanguageMapping::default();
+    /// mapping.patterns.insert(
+    ///     "Show/*/*.mkv".to_string(),
+    ///     BTreeMap::from([(TrackRef::Index(1), "jpn".to_string())]),
+    /// );
+    ///
+    /// let fixes = mapping.overrides_for(Path::new("Show/Season 1/E01.mkv"));
+    /// assert_eq!(fixes.get(&TrackRef::Index(1)).map(String::as_str), Some("jpn"));
+    /// assert!(mapping.overrides_for(Path::new("Movie.mkv")).is_empty());
+    /// ```
+    pub fn overrides_for(&self, relative: &Path) -> BTreeMap<TrackRef, String> {
+        let mut overrides = BTreeMap::new();
+        for (pattern, entries) in &self.patterns {
+            if glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches_path(relative)) {
+                overrides.extend(
+                    entries
+                        .iter()
+                        .map(|(track, lang)| (*track, normalize_language(lang))),
+                );
+            }
+        }
+        overrides
+    }
+}
+
+/// A track whose language tag gets corrected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageFix {
+    /// Stream index
+    pub index: u32,
+    /// Language the file currently has for the track
+    pub from: String,
+    /// Language written to the track
+    pub to: String,
+    /// Where the new language came from
+    pub source: LanguageSource,
+}
+
+impl std::fmt::Display for LanguageFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let origin = match self.source {
+            LanguageSource::Tag => "tag",
+            LanguageSource::Title => "title",
+            LanguageSource::Override => "mapping",
+            LanguageSource::Import => "media info",
+        };
+        write!(
+            f,
+            "#{} {} → {} ({})",
+            self.index, self.from, self.to, origin
+        )
+    }
+}
+
+/// Counts reported by `fix_languages`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageFixSummary {
+    /// MKV files analyzed
+    pub files_scanned: usize,
+    /// Files that had (or, in a dry run, would have) tracks corrected
+    pub files_changed: usize,
+    /// Tracks corrected across all files
+    pub tracks_changed: usize,
+    /// Files that couldn't be analyzed or edited
+    pub failed: Vec<PathBuf>,
+}
+
+/// Language corrections for one file's audio and subtitle streams: mapping
+/// overrides first, then (with `infer`) title inference for untagged tracks.
+/// Tracks already carrying the target language are left out.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::fix_languages::planned_fixes;
+/// use mkv_slimmer::models::{LanguageSource, StreamInfo, StreamType, TrackRef};
+/// use std::collections::BTreeMap;
+///
+/// let mut titled = StreamInfo::new(1, StreamType::Audio);
+/// titled.title = Some("Japanese".to_string());
+/// let mut subtitle = StreamInfo::new(2, StreamType::Subtitle);
+/// subtitle.language = Some("eng".to_string());
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), titled, subtitle];
+///
+/// let overrides = BTreeMap::from([(TrackRef::Index(2), "hun".to_string())]);
+/// let fixes = planned_fixes(&streams, &overrides, true);
+/// assert_eq!(fixes.len(), 2);
+/// assert_eq!((fixes[0].to.as_str(), fixes[0].source), ("jpn", LanguageSource::Title));
+/// assert_eq!(fixes[1].to_string(), "#2 eng → hun (mapping)");
+/// ```
+pub fn planned_fixes(
+    streams: &[StreamInfo],
+    overrides: &BTreeMap<TrackRef, String>,
+    infer: bool,
+) -> Vec<LanguageFix> {
+    streams
+        .iter()
+        .filter(|s| matches!(s.stream_type, StreamType::Audio | StreamType::Subtitle))
+        .filter_map(|stream| {
+            let current = stream.effective_language();
+            let language = overrides
+                .iter()
+                .find(|(track, _)| track.matches(stream))
+                .map(|(_, language)| language);
+            let (to, source) = match language {
+                Some(language) => (language.as_str(), LanguageSource::Override),
+                None if infer && current == "und" => (
+                    stream.title.as_deref().and_then(language_from_title)?,
+                    LanguageSource::Title,
+                ),
+                None => return None,
+            };
+            (to != current).then(|| LanguageFix {
+                index: stream.index,
+                from: current.to_string(),
+                to: to.to_string(),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Correct track language tags in place (mkvpropedit, no remux) for `input`,
+/// an MKV file or a directory of them. Files that fail are reported and
+/// skipped; a dry run only prints the planned corrections.
+pub async fn fix_languages(
+    input: &Path,
+    mapping: Option<&LanguageMapping>,
+    infer: bool,
+    recursive: bool,
+    dry_run: bool,
+    config: &Config,
+) -> Result<LanguageFixSummary> {
+    let (root, files) = if input.is_dir() {
+        (input, collect_mkv_files(input, recursive)?)
+    } else if is_valid_mkv_file(input) {
+        (
+            input.parent().unwrap_or(Path::new("")),
+            vec![input.to_path_buf()],
+        )
+    } else {
+        return Err(file_validation_error(
+            input,
+            "Input must be an MKV file or a directory",
+        ));
+    };
+    if !dry_run {
+        require_mkvtoolnix_tool("mkvpropedit")?;
+    }
+
+    // Corrections are planned against the languages the files actually carry
+    let mut config = config.clone();
+    config.processing.infer_languages = false;
+    config.processing.language_overrides.clear();
+    config.processing.strict = false;
+
+    let mut summary = LanguageFixSummary::default();
+    for file in files {
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        let overrides = mapping
+            .map(|mapping| mapping.overrides_for(relative))
+            .unwrap_or_default();
+
+        match fix_file_languages(&file, &overrides, infer, dry_run, &config).await {
+            Ok(fixes) => {
+                summary.files_scanned += 1;
+                if fixes.is_empty() {
+                    continue;
+                }
+                let fixes: Vec<String> = fixes.iter().map(ToString::to_string).collect();
+                report!("🏷️  {}: {}", relative.display(), fixes.join(", "));
+                summary.files_changed += 1;
+                summary.tracks_changed += fixes.len();
+            }
+            Err(e) => {
+                report_warn!("⚠️  {}: {:#}", file.display(), e);
+                summary.failed.push(file);
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+async fn fix_file_languages(
+    file: &Path,
+    overrides: &BTreeMap<TrackRef, String>,
+    infer: bool,
+    dry_run: bool,
+    config: &Config,
+) -> Result<Vec<LanguageFix>> {
+    let streams = analyze_mkv_streams(file, config).await?;
+    let fixes = planned_fixes(&streams, overrides, infer);
+    if fixes.is_empty() || dry_run {
+        return Ok(fixes);
+    }
+
+    let track_ids = resolve_track_ids(file, &streams).await;
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(file);
+    for fix in &fixes {
+        cmd.arg("--edit")
+            .arg(track_ids.propedit_selector(fix.index))
+            .arg("--set")
+            .arg(format!("language={}", fix.to));
+    }
+    backup_file(file, false, config)?;
+    run_tool(&mut cmd, file, "Language fix")?;
+
+    Ok(fixes)
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::error::{directory_error, file_validation_error};
+use crate::report_warn;
+use crate::utils::{collect_mkv_files, is_valid_mkv_file};
+
+/// MIME types Matroska muxers use for font attachments
+const FONT_MIME_TYPES: &[&str] = &[
+    "application/x-truetype-font",
+    "application/x-font-ttf",
+    "application/x-font-otf",
+    "application/vnd.ms-opentype",
+    "application/font-sfnt",
+    "application/font-woff",
+    "font/ttf",
+    "font/otf",
+    "font/sfnt",
+    "font/collection",
+    "font/woff",
+    "font/woff2",
+];
+
+/// Extensions recognised as fonts when the MIME type is generic
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "woff", "woff2"];
+
+/// Counts reported by `extract_fonts`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontExtraction {
+    /// MKV files whose attachments were read
+    pub files_scanned: usize,
+    /// Font files written to the output directory
+    pub fonts_written: usize,
+    /// Font attachments skipped because identical data was already written
+    pub duplicates: usize,
+}
+
+/// Write the font attachments of `input` (an MKV file or a directory of them)
+/// into `output`, keeping one copy of each distinct font. A font whose name is
+/// taken by a different font gets its content hash appended to the name.
+pub fn extract_fonts(input: &Path, output: &Path, recursive: bool) -> Result<FontExtraction> {
+    let files = if input.is_dir() {
+        collect_mkv_files(input, recursive)?
+    } else if is_valid_mkv_file(input) {
+        vec![input.to_path_buf()]
+    } else {
+        return Err(file_validation_error(
+            input,
+            "Input must be an MKV file or a directory",
+        ));
+    };
+
+    std::fs::create_dir_all(output)
+        .map_err(|e| directory_error(output, "create font directory", &e.to_string()))?;
+
+    let mut written: HashMap<u64, PathBuf> = HashMap::new();
+    let mut result = FontExtraction::default();
+
+    for file in files {
+        let mkv = match std::fs::File::open(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|handle| matroska::Matroska::open(handle).map_err(anyhow::Error::from))
+        {
+            Ok(mkv) => mkv,
+            Err(e) => {
+                report_warn!("Warning: Could not read {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        result.files_scanned += 1;
+
+        for attachment in mkv.attachments.iter().filter(|a| is_font(a)) {
+            let hash = content_hash(&attachment.data);
+            if written.contains_key(&hash) {
+                result.duplicates += 1;
+                continue;
+            }
+
+            let path = font_output_path(output, &attachment.name, hash, &attachment.data)?;
+            if !path.exists() {
+                std::fs::write(&path, &attachment.data)
+                    .with_context(|| format!("Failed to write font: {}", path.display()))?;
+                result.fonts_written += 1;
+            } else {
+                result.duplicates += 1;
+            }
+            written.insert(hash, path);
+        }
+    }
+
+    Ok(result)
+}
+
+fn is_font(attachment: &matroska::Attachment) -> bool {
+    let mime_type = attachment.mime_type.to_lowercase();
+    if FONT_MIME_TYPES.contains(&mime_type.as_str()) {
+        return true;
+    }
+    Path::new(&attachment.name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| FONT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Where a font is written: its attachment name, unless a different font
+/// already uses that name (`Font.ttf` → `Font-1a2b3c4d.ttf`). Returns an
+/// existing path when the same font is already there from an earlier run.
+fn font_output_path(output: &Path, name: &str, hash: u64, data: &[u8]) -> Result<PathBuf> {
+    // Attachment names come from the file, so never let them leave `output`
+    let file_name = Path::new(name)
+        .file_name()
+        .context("Font attachment has no usable name")?;
+    let path = output.join(file_name);
+    if !path.exists() || std::fs::read(&path).is_ok_and(|existing| existing == data) {
+        return Ok(path);
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let renamed = match path.extension() {
+        Some(ext) => format!("{}-{:08x}.{}", stem, hash as u32, ext.to_string_lossy()),
+        None => format!("{}-{:08x}", stem, hash as u32),
+    };
+    Ok(output.join(renamed))
+}
@@ -0,0 +1,335 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, RemovedManifest};
+use crate::models::{ProcessingOutcome, StreamInfo};
+
+/// One processed file as recorded in the history database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Source file as it was when processed
+    pub source: SourceStamp,
+    /// First output file
+    pub output: Option<PathBuf>,
+    /// `ProcessingOutcome` status (`remuxed`, `transferred`, ...)
+    pub status: String,
+    /// Removed streams as `type:language`, e.g. `audio:fre,subtitle:ger`
+    pub removed_tracks: String,
+    /// Track UIDs of the removed streams, comma-separated in the order of
+    /// `removed_tracks` (empty for streams whose UID wasn't known), so a
+    /// record names the same tracks however a later analysis numbers them
+    pub removed_uids: String,
+    pub removed_count: u64,
+    pub bytes_saved: u64,
+    /// `config_hash()` of the settings the file was processed with
+    pub config_hash: String,
+    /// When the file was processed (seconds since the Unix epoch)
+    pub processed_at: u64,
+}
+
+/// A source file identified by canonical path, size and modification time
+/// (seconds since the Unix epoch); size and time tell a replaced file from
+/// the processed one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceStamp {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: u64,
+}
+
+impl SourceStamp {
+    /// Stamp of an existing file; taken before processing, since a move takes
+    /// the source away
+    pub fn of(file: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(file).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        Some(SourceStamp {
+            path: file.canonicalize().ok()?,
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// Totals over history entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryTotals {
+    pub files: u64,
+    pub remuxed: u64,
+    pub tracks_removed: u64,
+    pub bytes_saved: u64,
+}
+
+/// SQLite database of processed files (`processing.history_db`)
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+impl HistoryDb {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create history directory: {}", parent.display())
+            })?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open history database: {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed (
+                id INTEGER PRIMARY KEY,
+                source TEXT NOT NULL,
+                source_size INTEGER NOT NULL,
+                source_modified INTEGER NOT NULL,
+                output TEXT,
+                status TEXT NOT NULL,
+                removed_tracks TEXT NOT NULL,
+                removed_uids TEXT NOT NULL DEFAULT '',
+                removed_count INTEGER NOT NULL,
+                bytes_saved INTEGER NOT NULL,
+                config_hash TEXT NOT NULL,
+                processed_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS processed_source ON processed (source);",
+        )
+        .with_context(|| format!("Failed to set up history database: {}", path.display()))?;
+        // Databases created before track UIDs were recorded
+        if conn
+            .prepare("SELECT removed_uids FROM processed LIMIT 0")
+            .is_err()
+        {
+            conn.execute(
+                "ALTER TABLE processed ADD COLUMN removed_uids TEXT NOT NULL DEFAULT ''",
+                [],
+            )
+            .with_context(|| format!("Failed to upgrade history database: {}", path.display()))?;
+        }
+        Ok(HistoryDb { conn })
+    }
+
+    /// Add an entry
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT INTO processed (source, source_size, source_modified, output, status,
+                    removed_tracks, removed_uids, removed_count, bytes_saved, config_hash,
+                    processed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    entry.source.path.to_string_lossy(),
+                    entry.source.size as i64,
+                    entry.source.modified as i64,
+                    entry
+                        .output
+                        .as_ref()
+                        .map(|output| output.to_string_lossy().to_string()),
+                    entry.status,
+                    entry.removed_tracks,
+                    entry.removed_uids,
+                    entry.removed_count as i64,
+                    entry.bytes_saved as i64,
+                    entry.config_hash,
+                    entry.processed_at as i64,
+                ],
+            )
+            .context("Failed to record file in history")?;
+        Ok(())
+    }
+
+    /// Whether `file`, unchanged since, was processed with settings hashing
+    /// to `config_hash`
+    pub fn was_processed(&self, file: &Path, config_hash: &str) -> Result<bool> {
+        let Some(stamp) = SourceStamp::of(file) else {
+            return Ok(false);
+        };
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM processed WHERE source = ?1 AND source_size = ?2
+                    AND source_modified = ?3 AND config_hash = ?4 LIMIT 1",
+                params![
+                    stamp.path.to_string_lossy(),
+                    stamp.size as i64,
+                    stamp.modified as i64,
+                    config_hash
+                ],
+                |_| Ok(()),
+            )
+            .optional()
+            .context("Failed to query history")?;
+        Ok(found.is_some())
+    }
+
+    /// Entries whose source path contains `pattern` (all without one), newest
+    /// first, at most `limit`
+    pub fn entries(&self, pattern: Option<&str>, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT source, source_size, source_modified, output, status, removed_tracks,
+                    removed_uids, removed_count, bytes_saved, config_hash, processed_at
+                 FROM processed WHERE instr(source, ?1) > 0
+                 ORDER BY processed_at DESC, id DESC LIMIT ?2",
+            )
+            .context("Failed to query history")?;
+        let rows = statement
+            .query_map(
+                params![pattern.unwrap_or(""), limit.min(i64::MAX as usize) as i64],
+                |row| {
+                    Ok(HistoryEntry {
+                        source: SourceStamp {
+                            path: PathBuf::from(row.get::<_, String>(0)?),
+                            size: row.get::<_, i64>(1)? as u64,
+                            modified: row.get::<_, i64>(2)? as u64,
+                        },
+                        output: row.get::<_, Option<String>>(3)?.map(PathBuf::from),
+                        status: row.get(4)?,
+                        removed_tracks: row.get(5)?,
+                        removed_uids: row.get(6)?,
+                        removed_count: row.get::<_, i64>(7)? as u64,
+                        bytes_saved: row.get::<_, i64>(8)? as u64,
+                        config_hash: row.get(9)?,
+                        processed_at: row.get::<_, i64>(10)? as u64,
+                    })
+                },
+            )
+            .context("Failed to query history")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read history")
+    }
+
+    /// Totals over the entries whose source path contains `pattern`
+    pub fn totals(&self, pattern: Option<&str>) -> Result<HistoryTotals> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*), COUNT(*) FILTER (WHERE status IN ('remuxed', 'split')),
+                    COALESCE(SUM(removed_count), 0),
+                    COALESCE(SUM(bytes_saved), 0)
+                 FROM processed WHERE instr(source, ?1) > 0",
+                params![pattern.unwrap_or("")],
+                |row| {
+                    Ok(HistoryTotals {
+                        files: row.get::<_, i64>(0)? as u64,
+                        remuxed: row.get::<_, i64>(1)? as u64,
+                        tracks_removed: row.get::<_, i64>(2)? as u64,
+                        bytes_saved: row.get::<_, i64>(3)? as u64,
+                    })
+                },
+            )
+            .context("Failed to query history")
+    }
+}
+
+/// Stable hash of the settings that shape outputs. Run-mode switches (dry
+/// run, backups, logs, the journal, the history and marker tags themselves)
+/// are left out, so changing them doesn't make processed files look new.
+pub fn config_hash(config: &Config) -> String {
+    let mut config = config.clone();
+    config.processing.dry_run = false;
+    config.processing.skip_processed = false;
+    config.processing.marker_tags = false;
+    config.processing.skip_marked = false;
+    config.processing.history_db = None;
+    config.processing.journal = None;
+    config.processing.backup_dir = None;
+    config.processing.log_dir = None;
+    config.processing.options_dir = None;
+    config.processing.cross_check = false;
+    // A sidecar listing removed tracks leaves the output itself unchanged
+    if config.processing.removed_manifest == Some(RemovedManifest::Sidecar) {
+        config.processing.removed_manifest = None;
+    }
+    config.notifications.clear();
+    // What a profile changes is in the settings themselves
+    config.profiles.clear();
+    let json = serde_json::to_string(&config).unwrap_or_default();
+
+    // FNV-1a: stable across builds, unlike std's hashers
+    let hash = json.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Record a finished file in `processing.history_db`; dry runs aren't
+/// recorded. `source` is the source's stamp from before processing.
+pub fn record_processed(
+    source: Option<SourceStamp>,
+    streams: &[StreamInfo],
+    outcome: &ProcessingOutcome,
+    config: &Config,
+) -> Result<()> {
+    let Some(db_path) = &config.processing.history_db else {
+        return Ok(());
+    };
+    let Some(source) = source else {
+        return Ok(());
+    };
+    if matches!(outcome, ProcessingOutcome::DryRun { .. }) {
+        return Ok(());
+    }
+
+    let removed: Vec<&StreamInfo> = outcome
+        .decisions()
+        .map(|decisions| {
+            streams
+                .iter()
+                .filter(|s| decisions.get(s.index).is_some_and(|d| !d.is_kept()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let removed_tracks: Vec<String> = removed
+        .iter()
+        .map(|s| {
+            format!(
+                "{}:{}",
+                s.stream_type.to_string().to_lowercase(),
+                s.effective_language()
+            )
+        })
+        .collect();
+    let removed_uids: Vec<String> = removed
+        .iter()
+        .map(|s| s.uid.map(|uid| uid.to_string()).unwrap_or_default())
+        .collect();
+    let bytes_saved = match outcome {
+        ProcessingOutcome::Remuxed { .. }
+        | ProcessingOutcome::Split { .. }
+        | ProcessingOutcome::AttachmentsRemoved { .. } => {
+            let output_size: u64 = outcome
+                .output_paths()
+                .iter()
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            source.size.saturating_sub(output_size)
+        }
+        _ => 0,
+    };
+    let status = outcome.status();
+
+    let entry = HistoryEntry {
+        source,
+        output: outcome
+            .output_paths()
+            .first()
+            .map(|path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf())),
+        status,
+        removed_count: removed_tracks.len() as u64,
+        removed_tracks: removed_tracks.join(","),
+        removed_uids: removed_uids.join(","),
+        bytes_saved,
+        config_hash: config_hash(config),
+        processed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs()),
+    };
+    HistoryDb::open(db_path)?.record(&entry)
+}
@@ -0,0 +1,605 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::models::{KnownTrack, MkvmergeId, MkvmergeIdentify, StreamInfo, StreamType, TrackIdMap};
+use crate::report_warn;
+use crate::utils::{WarningKind, record_warning};
+
+/// Matroska codec IDs and the matching ffprobe codec names, which the rest of
+/// the analysis (WebM checks, size weights, charset detection) is keyed on.
+/// Entries ending in `/` match any codec ID with that prefix.
+const CODEC_NAMES: &[(&str, &str)] = &[
+    ("V_MPEG4/ISO/AVC", "h264"),
+    ("V_MPEGH/ISO/HEVC", "hevc"),
+    ("V_AV1", "av1"),
+    ("V_VP8", "vp8"),
+    ("V_VP9", "vp9"),
+    ("V_MPEG4/ISO/", "mpeg4"),
+    ("V_MPEG2", "mpeg2video"),
+    ("V_MPEG1", "mpeg1video"),
+    ("V_THEORA", "theora"),
+    ("V_MJPEG", "mjpeg"),
+    ("A_AAC", "aac"),
+    ("A_AAC/", "aac"),
+    ("A_AC3", "ac3"),
+    ("A_EAC3", "eac3"),
+    ("A_DTS", "dts"),
+    ("A_DTS/", "dts"),
+    ("A_TRUEHD", "truehd"),
+    ("A_MLP", "mlp"),
+    ("A_FLAC", "flac"),
+    ("A_ALAC", "alac"),
+    ("A_OPUS", "opus"),
+    ("A_VORBIS", "vorbis"),
+    ("A_MPEG/L3", "mp3"),
+    ("A_MPEG/L2", "mp2"),
+    ("A_PCM/INT/LIT", "pcm_s16le"),
+    ("S_TEXT/UTF8", "subrip"),
+    ("S_TEXT/ASS", "ass"),
+    ("S_TEXT/SSA", "ssa"),
+    ("S_ASS", "ass"),
+    ("S_SSA", "ssa"),
+    ("S_TEXT/WEBVTT", "webvtt"),
+    ("S_HDMV/PGS", "hdmv_pgs_subtitle"),
+    ("S_HDMV/TEXTST", "hdmv_text_subtitle"),
+    ("S_VOBSUB", "dvd_subtitle"),
+    ("S_DVBSUB", "dvb_subtitle"),
+];
+
+/// ffprobe-style codec name for a Matroska codec ID
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::identify::codec_name_for_id;
+///
+/// assert_eq!(codec_name_for_id("V_MPEGH/ISO/HEVC"), "hevc");
+/// assert_eq!(codec_name_for_id("A_AAC/MPEG4/LC"), "aac");
+/// assert_eq!(codec_name_for_id("S_TEXT/UTF8"), "subrip");
+/// assert_eq!(codec_name_for_id("X_CUSTOM"), "x_custom");
+/// ```
+pub fn codec_name_for_id(codec_id: &str) -> String {
+    CODEC_NAMES
+        .iter()
+        .find(|(id, _)| {
+            if id.ends_with('/') {
+                codec_id.starts_with(id)
+            } else {
+                codec_id == *id
+            }
+        })
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| codec_id.to_lowercase())
+}
+
+/// Parse a statistics DURATION tag (`00:01:31.010000000`) into seconds
+pub fn parse_duration_tag(duration_str: &str) -> Option<f64> {
+    let parts: Vec<&str> = duration_str.split(':').collect();
+    if parts.len() == 3
+        && let (Ok(hours), Ok(minutes)) = (parts[0].parse::<f64>(), parts[1].parse::<f64>())
+        && let Ok(seconds) = parts[2].parse::<f64>()
+    {
+        return Some(hours * 3600.0 + minutes * 60.0 + seconds);
+    }
+    None
+}
+
+/// Run `mkvmerge -J`; None (with a warning) when it isn't available or fails
+pub async fn get_mkvmerge_data(file_path: &Path) -> Option<MkvmergeIdentify> {
+    let json = get_mkvmerge_json(file_path).await?;
+    match serde_json::from_value(json) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            report_warn!("Warning: Could not parse mkvmerge identification: {}", e);
+            record_warning(
+                WarningKind::Analysis,
+                format!("could not parse mkvmerge identification: {}", e),
+            );
+            None
+        }
+    }
+}
+
+/// Raw `mkvmerge -J` output as JSON; None (with a warning) when it isn't
+/// available or fails
+pub async fn get_mkvmerge_json(file_path: &Path) -> Option<serde_json::Value> {
+    let output = Command::new("mkvmerge").arg("-J").arg(file_path).output();
+
+    match output {
+        Ok(output) if output.status.success() => match serde_json::from_slice(&output.stdout) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                report_warn!("Warning: Could not parse mkvmerge identification: {}", e);
+                record_warning(
+                    WarningKind::Analysis,
+                    format!("could not parse mkvmerge identification: {}", e),
+                );
+                None
+            }
+        },
+        Ok(_) => {
+            report_warn!("Warning: mkvmerge could not identify the file");
+            record_warning(
+                WarningKind::Analysis,
+                "mkvmerge could not identify the file",
+            );
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Tracks and attachments of a file as mkvmerge numbers them, from
+/// `mkvmerge -J` or, without it, the matroska parser (track IDs follow the
+/// track entry order). None when neither can read the file.
+pub async fn known_tracks(file_path: &Path) -> Option<Vec<KnownTrack>> {
+    if let Some(identify) = get_mkvmerge_data(file_path).await {
+        let tracks = identify.tracks.iter().map(|track| KnownTrack {
+            stream_type: match track.track_type.as_str() {
+                "video" => StreamType::Video,
+                "audio" => StreamType::Audio,
+                "subtitles" => StreamType::Subtitle,
+                _ => StreamType::Unknown,
+            },
+            mkvmerge_id: MkvmergeId::Track(track.id),
+            track_number: track.properties.number,
+            uid: track.properties.uid,
+        });
+        let attachments = identify.attachments.iter().map(|attachment| KnownTrack {
+            stream_type: StreamType::Attachment,
+            mkvmerge_id: MkvmergeId::Attachment(attachment.id),
+            track_number: None,
+            uid: None,
+        });
+        return Some(tracks.chain(attachments).collect());
+    }
+
+    let mkv = get_matroska_data(file_path).await?;
+    let tracks = mkv
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(position, track)| KnownTrack {
+            stream_type: match track.tracktype {
+                matroska::Tracktype::Video => StreamType::Video,
+                matroska::Tracktype::Audio => StreamType::Audio,
+                matroska::Tracktype::Subtitle => StreamType::Subtitle,
+                _ => StreamType::Unknown,
+            },
+            mkvmerge_id: MkvmergeId::Track(position as u32),
+            track_number: Some(track.number),
+            uid: Some(track.uid),
+        });
+    let attachments = (1..=mkv.attachments.len() as u32).map(|id| KnownTrack {
+        stream_type: StreamType::Attachment,
+        mkvmerge_id: MkvmergeId::Attachment(id),
+        track_number: None,
+        uid: None,
+    });
+    Some(tracks.chain(attachments).collect())
+}
+
+/// Parse the file with the matroska crate; None (with a warning) on failure
+pub async fn get_matroska_data(file_path: &Path) -> Option<matroska::Matroska> {
+    match std::fs::File::open(file_path) {
+        Ok(file) => match matroska::Matroska::open(file) {
+            Ok(mkv) => Some(mkv),
+            Err(e) => {
+                report_warn!("Warning: Could not parse with matroska crate: {}", e);
+                record_warning(
+                    WarningKind::Analysis,
+                    format!("could not parse with matroska crate: {}", e),
+                );
+                None
+            }
+        },
+        Err(e) => {
+            report_warn!("Warning: Could not open file for matroska parsing: {}", e);
+            None
+        }
+    }
+}
+
+/// Streams as reported by `mkvmerge -J`: tracks in ID order, then attachments,
+/// indexed the same way ffprobe numbers them
+pub fn streams_from_mkvmerge(identify: &MkvmergeIdentify) -> Vec<StreamInfo> {
+    let mut streams: Vec<StreamInfo> = identify
+        .tracks
+        .iter()
+        .map(|track| {
+            let stream_type = match track.track_type.as_str() {
+                "video" => StreamType::Video,
+                "audio" => StreamType::Audio,
+                "subtitles" => StreamType::Subtitle,
+                _ => StreamType::Unknown,
+            };
+            let properties = &track.properties;
+
+            let mut info = StreamInfo::new(track.id, stream_type);
+            info.uid = properties.uid;
+            info.codec = match &properties.codec_id {
+                Some(codec_id) => codec_name_for_id(codec_id),
+                None => track.codec.clone().unwrap_or_else(|| "unknown".to_string()),
+            };
+            // mkvmerge's codec name tells the variant (`DTS-HD Master Audio`)
+            if stream_type == StreamType::Audio {
+                info.profile = track.codec.clone();
+            }
+            info.language = properties.language.clone();
+            info.title = properties.track_name.clone();
+            info.default = properties.default_track.unwrap_or(false);
+            info.forced = properties.forced_track.unwrap_or(false);
+            info.size_bytes = properties
+                .tag_number_of_bytes
+                .as_deref()
+                .and_then(|bytes| bytes.parse().ok());
+            info.duration_seconds = properties
+                .tag_duration
+                .as_deref()
+                .and_then(parse_duration_tag);
+            info.bitrate = properties
+                .tag_bps
+                .as_deref()
+                .and_then(|bps| bps.parse().ok());
+
+            match stream_type {
+                StreamType::Video => {
+                    info.resolution = properties.pixel_dimensions.clone();
+                    info.framerate = properties
+                        .default_duration
+                        .filter(|&ns| ns > 0)
+                        .map(|ns| 1_000_000_000.0 / ns as f64);
+                }
+                StreamType::Audio => {
+                    info.channels = properties.audio_channels;
+                    info.sample_rate = properties.audio_sampling_frequency;
+                }
+                StreamType::Subtitle => info.subtitle_format = Some(info.codec.clone()),
+                _ => {}
+            }
+            info
+        })
+        .collect();
+
+    let first_attachment = streams.len() as u32;
+    streams.extend(
+        identify
+            .attachments
+            .iter()
+            .enumerate()
+            .map(|(position, attachment)| {
+                let mut info =
+                    StreamInfo::new(first_attachment + position as u32, StreamType::Attachment);
+                info.codec = attachment
+                    .content_type
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string());
+                info.title = attachment.file_name.clone();
+                info.size_bytes = attachment.size;
+                info.cover_art = attachment
+                    .file_name
+                    .as_deref()
+                    .is_some_and(is_cover_art_name);
+                info
+            }),
+    );
+
+    streams
+}
+
+/// Streams as read by the matroska crate's own EBML parser: tracks in file
+/// order, then attachments. Needs no external tools.
+pub fn streams_from_matroska(mkv: &matroska::Matroska) -> Vec<StreamInfo> {
+    let mut streams: Vec<StreamInfo> = mkv
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(position, track)| {
+            let stream_type = match track.tracktype {
+                matroska::Tracktype::Video => StreamType::Video,
+                matroska::Tracktype::Audio => StreamType::Audio,
+                matroska::Tracktype::Subtitle => StreamType::Subtitle,
+                _ => StreamType::Unknown,
+            };
+
+            let mut info = StreamInfo::new(position as u32, stream_type);
+            info.uid = Some(track.uid);
+            info.codec = codec_name_for_id(&track.codec_id);
+            info.language = track.language.as_ref().map(|language| language.to_string());
+            info.title = track.name.clone();
+            info.default = track.default;
+            info.forced = track.forced;
+
+            let statistic = |name: &str| track_statistic(mkv, track.uid, name);
+            info.size_bytes = statistic("NUMBER_OF_BYTES").and_then(|v| v.parse().ok());
+            info.duration_seconds = statistic("DURATION").and_then(|v| parse_duration_tag(&v));
+            info.bitrate = statistic("BPS").and_then(|v| v.parse().ok());
+
+            match &track.settings {
+                matroska::Settings::Video(video) => {
+                    info.resolution = Some(format!("{}x{}", video.pixel_width, video.pixel_height));
+                    info.framerate = track
+                        .default_duration
+                        .filter(|duration| !duration.is_zero())
+                        .map(|duration| 1.0 / duration.as_secs_f64());
+                }
+                matroska::Settings::Audio(audio) => {
+                    info.channels = Some(audio.channels as u32);
+                    info.sample_rate = Some(audio.sample_rate as u32);
+                }
+                matroska::Settings::None => {}
+            }
+            if stream_type == StreamType::Subtitle {
+                info.subtitle_format = Some(info.codec.clone());
+            }
+            info
+        })
+        .collect();
+
+    let first_attachment = streams.len() as u32;
+    streams.extend(
+        mkv.attachments
+            .iter()
+            .enumerate()
+            .map(|(position, attachment)| {
+                let mut info =
+                    StreamInfo::new(first_attachment + position as u32, StreamType::Attachment);
+                info.codec = attachment.mime_type.clone();
+                info.title = Some(attachment.name.clone());
+                info.size_bytes = Some(attachment.data.len() as u64);
+                info.cover_art = is_cover_art_name(&attachment.name);
+                info
+            }),
+    );
+
+    streams
+}
+
+/// Whether an attachment file name is one the Matroska spec reserves for
+/// cover art (`cover`, `small_cover`, `cover_land`, `small_cover_land`, as
+/// JPEG or PNG)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::identify::is_cover_art_name;
+///
+/// assert!(is_cover_art_name("cover.jpg"));
+/// assert!(is_cover_art_name("Small_Cover_Land.PNG"));
+/// assert!(!is_cover_art_name("cover.ttf"));
+/// assert!(!is_cover_art_name("poster.jpg"));
+/// ```
+pub fn is_cover_art_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    matches!(
+        stem,
+        "cover" | "small_cover" | "cover_land" | "small_cover_land"
+    ) && matches!(extension, "jpg" | "jpeg" | "png")
+}
+
+/// Value of a statistics tag (NUMBER_OF_BYTES, DURATION, BPS) targeting a track
+fn track_statistic(mkv: &matroska::Matroska, track_uid: u64, name: &str) -> Option<String> {
+    mkv.tags
+        .iter()
+        .filter(|tag| {
+            tag.targets
+                .as_ref()
+                .is_some_and(|targets| targets.track_uids.contains(&track_uid))
+        })
+        .flat_map(|tag| &tag.simple)
+        .find(|simple| simple.name == name)
+        .and_then(|simple| match &simple.value {
+            Some(matroska::TagValue::String(value)) => Some(value.clone()),
+            _ => None,
+        })
+}
+
+/// Fill the fields `stream` is missing from `fallback`, a lower-precedence
+/// source's view of the same stream. Flags are left as the primary source
+/// reported them.
+pub fn fill_missing_fields(stream: &mut StreamInfo, fallback: &StreamInfo) {
+    if stream.codec == "unknown" {
+        stream.codec = fallback.codec.clone();
+    }
+    if stream.profile.is_none() {
+        stream.profile = fallback.profile.clone();
+    }
+    if stream.language.as_deref().is_none_or(str::is_empty) {
+        stream.language = fallback.language.clone();
+    }
+    if stream.title.is_none() {
+        stream.title = fallback.title.clone();
+    }
+    if stream.size_bytes.is_none() {
+        stream.size_bytes = fallback.size_bytes;
+    }
+    if stream.duration_seconds.is_none() {
+        stream.duration_seconds = fallback.duration_seconds;
+    }
+    if stream.resolution.is_none() {
+        stream.resolution = fallback.resolution.clone();
+    }
+    if stream.framerate.is_none() {
+        stream.framerate = fallback.framerate;
+    }
+    if stream.hdr.is_none() {
+        stream.hdr = fallback.hdr;
+    }
+    if stream.channels.is_none() {
+        stream.channels = fallback.channels;
+    }
+    if stream.sample_rate.is_none() {
+        stream.sample_rate = fallback.sample_rate;
+    }
+    if stream.bitrate.is_none() {
+        stream.bitrate = fallback.bitrate;
+    }
+    if stream.subtitle_format.is_none() {
+        stream.subtitle_format = fallback.subtitle_format.clone();
+    }
+}
+
+/// Give the streams without a track UID (ffprobe reads none) the UIDs of
+/// `reference`, a source that read them, pairing the streams of each type in
+/// order as `TrackIdMap::resolve()` does
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::identify::fill_missing_uids;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// // ffprobe skipped a data track mkvmerge numbers 1
+/// let mut streams = vec![StreamInfo::new(0, StreamType::Video), StreamInfo::new(1, StreamType::Audio)];
+/// let mut reference = vec![
+///     StreamInfo::new(0, StreamType::Video),
+///     StreamInfo::new(1, StreamType::Unknown),
+///     StreamInfo::new(2, StreamType::Audio),
+/// ];
+/// for (uid, stream) in (100..).zip(&mut reference) {
+///     stream.uid = Some(uid);
+/// }
+/// fill_missing_uids(&mut streams, &reference);
+/// assert_eq!(streams[0].uid, Some(100));
+/// assert_eq!(streams[1].uid, Some(102));
+/// ```
+pub fn fill_missing_uids(streams: &mut [StreamInfo], reference: &[StreamInfo]) {
+    let known: Vec<KnownTrack> = reference
+        .iter()
+        .map(|s| KnownTrack {
+            stream_type: s.container_type(),
+            mkvmerge_id: MkvmergeId::Track(s.index),
+            track_number: None,
+            uid: s.uid,
+        })
+        .collect();
+    let uids = TrackIdMap::resolve(streams, &known);
+    for stream in streams.iter_mut().filter(|s| s.uid.is_none()) {
+        stream.uid = uids.uid(stream.index);
+    }
+}
+
+/// A point on which ffprobe and `mkvmerge -J` disagree about a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceConflict {
+    /// Stream the difference is about; None for file-level differences
+    pub stream: Option<u32>,
+    /// What differs (`audio tracks`, `language`, `default flag`, ...)
+    pub field: String,
+    pub ffprobe: String,
+    pub mkvmerge: String,
+}
+
+impl std::fmt::Display for SourceConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(index) = self.stream {
+            write!(f, "stream #{} ", index)?;
+        }
+        write!(
+            f,
+            "{}: ffprobe={}, mkvmerge={}",
+            self.field, self.ffprobe, self.mkvmerge
+        )
+    }
+}
+
+/// Compare the ffprobe and `mkvmerge -J` views of a file: track counts per
+/// type, then the type, language and default/forced flags of each stream.
+/// Disagreements usually point at broken headers.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::identify::detect_source_conflicts;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let mut ffprobe = vec![StreamInfo::new(0, StreamType::Video), StreamInfo::new(1, StreamType::Audio)];
+/// let mut mkvmerge = ffprobe.clone();
+/// ffprobe[1].language = Some("eng".to_string());
+/// mkvmerge[1].language = Some("jpn".to_string());
+///
+/// let conflicts = detect_source_conflicts(&ffprobe, &mkvmerge);
+/// assert_eq!(conflicts.len(), 1);
+/// assert_eq!(conflicts[0].to_string(), "stream #1 language: ffprobe=eng, mkvmerge=jpn");
+///
+/// mkvmerge.pop();
+/// assert_eq!(detect_source_conflicts(&ffprobe, &mkvmerge)[0].field, "audio tracks");
+/// ```
+pub fn detect_source_conflicts(
+    ffprobe: &[StreamInfo],
+    mkvmerge: &[StreamInfo],
+) -> Vec<SourceConflict> {
+    let mut conflicts = Vec::new();
+
+    for stream_type in [
+        StreamType::Video,
+        StreamType::Audio,
+        StreamType::Subtitle,
+        StreamType::Attachment,
+    ] {
+        // ffprobe lists cover art as video; count it where mkvmerge does
+        let count = |streams: &[StreamInfo]| {
+            streams
+                .iter()
+                .filter(|s| s.container_type() == stream_type)
+                .count()
+        };
+        let (ffprobe_count, mkvmerge_count) = (count(ffprobe), count(mkvmerge));
+        if ffprobe_count != mkvmerge_count {
+            let field = match stream_type {
+                StreamType::Attachment => "attachments".to_string(),
+                _ => format!("{} tracks", stream_type.to_string().to_lowercase()),
+            };
+            conflicts.push(SourceConflict {
+                stream: None,
+                field,
+                ffprobe: ffprobe_count.to_string(),
+                mkvmerge: mkvmerge_count.to_string(),
+            });
+        }
+    }
+
+    for stream in ffprobe {
+        let Some(other) = mkvmerge.iter().find(|s| s.index == stream.index) else {
+            continue;
+        };
+        let mut differs = |field: &str, ffprobe: String, mkvmerge: String| {
+            if ffprobe != mkvmerge {
+                conflicts.push(SourceConflict {
+                    stream: Some(stream.index),
+                    field: field.to_string(),
+                    ffprobe,
+                    mkvmerge,
+                });
+            }
+        };
+
+        if stream.container_type() != other.container_type() {
+            differs(
+                "type",
+                stream.stream_type.to_string(),
+                other.stream_type.to_string(),
+            );
+            continue;
+        }
+        if stream.container_type() == StreamType::Attachment {
+            continue;
+        }
+        differs(
+            "language",
+            stream.effective_language().to_string(),
+            other.effective_language().to_string(),
+        );
+        differs(
+            "default flag",
+            stream.default.to_string(),
+            other.default.to_string(),
+        );
+        differs(
+            "forced flag",
+            stream.forced.to_string(),
+            other.forced.to_string(),
+        );
+    }
+
+    conflicts
+}
@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::analyzer::{get_ffprobe_data, merge_probe_data};
+use super::identify::get_mkvmerge_json;
+use crate::config::Config;
+use crate::error::{directory_error, file_validation_error};
+use crate::models::StreamInfo;
+use crate::report_warn;
+use crate::utils::is_valid_mkv_file;
+
+/// Everything the analyzer saw for one file: the merged streams it decided on
+/// and the raw probe output they were built from
+#[derive(Debug, Clone)]
+pub struct Inspection {
+    /// Streams as merged from ffprobe, mkvmerge and the matroska parser
+    pub streams: Vec<StreamInfo>,
+    /// Raw `ffprobe -show_format -show_streams` JSON, None if it didn't run
+    pub ffprobe: Option<serde_json::Value>,
+    /// Raw `mkvmerge -J` JSON, None if it didn't run
+    pub mkvmerge: Option<serde_json::Value>,
+}
+
+impl Inspection {
+    /// Output sections as (name, pretty-printed JSON) pairs; sources that
+    /// didn't run are `null`
+    pub fn sections(&self) -> Result<Vec<(&'static str, String)>> {
+        Ok(vec![
+            ("streams", serde_json::to_string_pretty(&self.streams)?),
+            ("ffprobe", serde_json::to_string_pretty(&self.ffprobe)?),
+            ("mkvmerge", serde_json::to_string_pretty(&self.mkvmerge)?),
+        ])
+    }
+
+    /// Write each section to `<stem>.<section>.json` in `output_dir` and
+    /// return the written paths
+    pub fn write_to(&self, source_file: &Path, output_dir: &Path) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(output_dir)
+            .map_err(|e| directory_error(output_dir, "create", &e.to_string()))?;
+
+        let stem = source_file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "inspect".to_string());
+
+        let mut written = Vec::new();
+        for (name, json) in self.sections()? {
+            let path = output_dir.join(format!("{}.{}.json", stem, name));
+            std::fs::write(&path, json + "\n")
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+/// Probe `file` the same way processing does and keep the raw tool output
+/// alongside the merged streams
+pub async fn inspect_file(file: &Path, config: &Config) -> Result<Inspection> {
+    if !is_valid_mkv_file(file) {
+        return Err(file_validation_error(file, "Not an MKV file"));
+    }
+
+    let ffprobe = get_ffprobe_data(file).await.unwrap_or_else(|e| {
+        report_warn!("Warning: {:#}", e);
+        None
+    });
+    let mkvmerge = get_mkvmerge_json(file).await;
+    let mkvmerge_data = mkvmerge
+        .clone()
+        .and_then(|json| serde_json::from_value(json).ok());
+    // Inspecting is how incomplete analyses get debugged, so never fail on them
+    let mut config = config.clone();
+    config.processing.strict = false;
+    let streams = merge_probe_data(file, ffprobe.clone(), mkvmerge_data, &config).await?;
+
+    Ok(Inspection {
+        streams,
+        ffprobe,
+        mkvmerge,
+    })
+}
@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::report_warn;
+use crate::utils::format_utc_date;
+
+/// One line of the batch journal: a state transition of a run or of one of
+/// its files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// When the transition happened (seconds since the Unix epoch)
+    pub time: u64,
+    /// Run the entry belongs to (`<start time>-<process id>`), since runs
+    /// append to the same journal
+    pub run: String,
+    /// Source file the entry is about; None for run-level entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(flatten)]
+    pub state: JournalState,
+}
+
+/// State a run or file entered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JournalState {
+    /// The batch found its files and is about to process them
+    RunStarted {
+        source: PathBuf,
+        target: PathBuf,
+        files: usize,
+        dry_run: bool,
+    },
+    /// Processing of the file began
+    Started,
+    /// The file was left out (`SkipReason` kind and description)
+    Skipped { kind: String, reason: String },
+    /// The file was processed (`ProcessingOutcome` status)
+    Finished {
+        outcome: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        outputs: Vec<PathBuf>,
+    },
+    /// Processing of the file failed
+    Failed { error: String },
+    /// The run was cancelled (while the file was processed, if any)
+    Cancelled,
+    /// The batch completed
+    RunFinished {
+        successful: usize,
+        failed: usize,
+        skipped: usize,
+    },
+}
+
+/// Append-only journal of a batch (`processing.journal`). Every entry is
+/// written as a single line and synced to disk before processing goes on,
+/// so after a crash or power loss the journal tells which file was being
+/// worked on; see `journal show`.
+pub struct Journal {
+    path: PathBuf,
+    run: String,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// Open (or create) the journal at `path` for appending a new run. A
+    /// last line cut off by a crash is ended first, so the new run's entries
+    /// start on a line of their own.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create journal directory: {}", parent.display())
+            })?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open journal: {}", path.display()))?;
+        end_last_line(&mut file)
+            .with_context(|| format!("Failed to repair journal: {}", path.display()))?;
+        Ok(Journal {
+            path: path.to_path_buf(),
+            run: format!("{}-{}", now(), std::process::id()),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append a state transition of the run (`file` None) or of a file. A
+    /// journal that can't be written is warned about but doesn't stop the
+    /// batch.
+    pub fn record(&self, file: Option<&Path>, state: JournalState) {
+        let entry = JournalEntry {
+            time: now(),
+            run: self.run.clone(),
+            file: file.map(Path::to_path_buf),
+            state,
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut line| {
+                line.push('\n');
+                let mut file = self
+                    .file
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("journal lock poisoned"))?;
+                // One write per line, so a crash can only cut off the last one
+                file.write_all(line.as_bytes())?;
+                file.sync_data()?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            report_warn!(
+                "⚠️  Failed to write journal {}: {:#}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Terminate a non-empty journal whose last line has no newline
+fn end_last_line(file: &mut File) -> Result<()> {
+    if file.metadata()?.len() == 0 {
+        return Ok(());
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    if last[0] != b'\n' {
+        file.write_all(b"\n")?;
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+/// Entries of the journal at `path` and the number of lines that couldn't
+/// be read (typically a last line cut off by a crash)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::journal::{Journal, JournalState, journal_runs, read_journal};
+///
+/// let path = std::env::temp_dir().join(format!("journal-doctest-{}.jsonl", std::process::id()));
+/// // A run that crashed in the middle of writing an entry
+/// std::fs::write(
+///     &path,
+///     "{\"time\":1,\"run\":\"1-1\",\"file\":\"/media/a.mkv\",\"state\":\"started\"}\n{\"time\":2,\"ru",
+/// )
+/// .unwrap();
+///
+/// let journal = Journal::open(&path).unwrap();
+/// journal.record(None, JournalState::Cancelled);
+/// drop(journal);
+///
+/// let (entries, unreadable) = read_journal(&path).unwrap();
+/// std::fs::remove_file(&path).unwrap();
+/// assert_eq!(unreadable, 1);
+/// assert_eq!(entries.len(), 2);
+/// let runs = journal_runs(&entries);
+/// assert_eq!(runs.len(), 2);
+/// assert_eq!(runs[1].finished.as_ref().unwrap().state, JournalState::Cancelled);
+/// ```
+pub fn read_journal(path: &Path) -> Result<(Vec<JournalEntry>, usize)> {
+    let content = std::fs::read(path)
+        .with_context(|| format!("Failed to read journal: {}", path.display()))?;
+    let mut entries = Vec::new();
+    let mut unreadable = 0;
+    for line in content.split(|&b| b == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => unreadable += 1,
+        }
+    }
+    Ok((entries, unreadable))
+}
+
+/// What the journal says about one run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalRun {
+    pub run: String,
+    /// The `RunStarted` entry, if it was written
+    pub started: Option<JournalEntry>,
+    /// Last entry of every file, in the order the files first appear
+    pub files: Vec<JournalEntry>,
+    /// The `RunFinished` entry, or the `Cancelled` entry of a cancelled run;
+    /// None if the run crashed, lost power or was killed
+    pub finished: Option<JournalEntry>,
+}
+
+impl JournalRun {
+    /// Files whose processing started but never finished, failed or was
+    /// cancelled
+    pub fn interrupted(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.files
+            .iter()
+            .filter(|entry| entry.state == JournalState::Started)
+    }
+}
+
+/// Reconstruct the runs of a journal, in the order they started
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::journal::{JournalEntry, JournalState, journal_runs};
+/// use std::path::PathBuf;
+///
+/// let entry = |file: Option<&str>, state: JournalState| JournalEntry {
+///     time: 1_700_000_000,
+///     run: "1700000000-42".to_string(),
+///     file: file.map(PathBuf::from),
+///     state,
+/// };
+/// let entries = vec![
+///     entry(
+///         None,
+///         JournalState::RunStarted {
+///             source: "/media/in".into(),
+///             target: "/media/out".into(),
+///             files: 2,
+///             dry_run: false,
+///         },
+///     ),
+///     entry(Some("/media/in/a.mkv"), JournalState::Started),
+///     entry(
+///         Some("/media/in/a.mkv"),
+///         JournalState::Finished { outcome: "remuxed".to_string(), outputs: vec![] },
+///     ),
+///     entry(Some("/media/in/b.mkv"), JournalState::Started),
+///     // Power loss
+/// ];
+///
+/// let runs = journal_runs(&entries);
+/// assert_eq!(runs.len(), 1);
+/// assert_eq!(runs[0].files.len(), 2);
+/// assert!(runs[0].finished.is_none());
+/// let interrupted: Vec<_> = runs[0].interrupted().collect();
+/// assert_eq!(interrupted.len(), 1);
+/// assert_eq!(interrupted[0].file, Some(PathBuf::from("/media/in/b.mkv")));
+/// ```
+pub fn journal_runs(entries: &[JournalEntry]) -> Vec<JournalRun> {
+    let mut runs: Vec<JournalRun> = Vec::new();
+    for entry in entries {
+        let index = match runs.iter().position(|run| run.run == entry.run) {
+            Some(index) => index,
+            None => {
+                runs.push(JournalRun {
+                    run: entry.run.clone(),
+                    started: None,
+                    files: Vec::new(),
+                    finished: None,
+                });
+                runs.len() - 1
+            }
+        };
+        let run = &mut runs[index];
+        match (&entry.state, &entry.file) {
+            (JournalState::RunStarted { .. }, _) => run.started = Some(entry.clone()),
+            (JournalState::RunFinished { .. }, _) | (JournalState::Cancelled, None) => {
+                run.finished = Some(entry.clone())
+            }
+            (_, Some(file)) => {
+                match run
+                    .files
+                    .iter_mut()
+                    .find(|known| known.file.as_ref() == Some(file))
+                {
+                    Some(known) => *known = entry.clone(),
+                    None => run.files.push(entry.clone()),
+                }
+            }
+            (_, None) => {}
+        }
+    }
+    runs
+}
+
+/// `YYYY-MM-DD HH:MM:SS` (UTC) for a time in seconds since the Unix epoch
+pub fn format_journal_time(timestamp: u64) -> String {
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        format_utc_date(timestamp),
+        timestamp / 3_600 % 24,
+        timestamp / 60 % 60,
+        timestamp % 60
+    )
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
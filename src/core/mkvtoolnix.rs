@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::processing_error;
+
+/// Run an MKVToolNix command, turning a failure into a processing error that
+/// carries the tool's output
+pub fn run_tool(cmd: &mut Command, file: &Path, stage: &str) -> Result<()> {
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to execute {:?}", cmd.get_program()))?;
+
+    if !output.status.success() {
+        return Err(processing_error(
+            file,
+            stage,
+            &format!(
+                "{:?} failed with exit code {:?}:\n{}\n{}",
+                cmd.get_program(),
+                output.status.code(),
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// An mkvmerge command line kept as its arguments, so it can be printed,
+/// saved as an option file or pointed at another output before it runs
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::mkvtoolnix::MkvmergeCommand;
+/// use std::path::Path;
+///
+/// let mut cmd = MkvmergeCommand::new();
+/// cmd.arg("-o").arg("/out/Movie (2020).mkv").arg("--title").arg("");
+/// cmd.arg("/in/Movie.mkv");
+///
+/// assert_eq!(
+///     cmd.command_line(),
+///     "mkvmerge -o '/out/Movie (2020).mkv' --title '' /in/Movie.mkv"
+/// );
+/// let partial = cmd.with_output(Path::new("/out/.Movie.mkv.partial"));
+/// assert_eq!(partial.args()[1], "/out/.Movie.mkv.partial");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MkvmergeCommand {
+    args: Vec<OsString>,
+}
+
+impl MkvmergeCommand {
+    pub const PROGRAM: &str = "mkvmerge";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an argument
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// The same command writing to `output` instead of its `-o` path
+    pub fn with_output(&self, output: &Path) -> Self {
+        let mut redirected = Self::new();
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            redirected.arg(arg);
+            if arg == "-o" && args.next().is_some() {
+                redirected.arg(output);
+            }
+        }
+        redirected
+    }
+
+    /// Arguments as they go into a JSON option file (`mkvmerge @FILE`), or
+    /// None when one of them isn't UTF-8
+    pub fn option_args(&self) -> Option<Vec<String>> {
+        self.args
+            .iter()
+            .map(|arg| arg.to_str().map(str::to_string))
+            .collect()
+    }
+
+    /// The process to run
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(Self::PROGRAM);
+        cmd.args(&self.args);
+        cmd
+    }
+
+    /// The command as it would be typed into a POSIX shell
+    pub fn command_line(&self) -> String {
+        command_line(&self.to_command())
+    }
+}
+
+/// The program and arguments of `cmd` as they would be typed into a POSIX
+/// shell, each quoted where needed
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote `arg` for a POSIX shell: left as is when it only holds characters
+/// no shell treats specially, single-quoted otherwise
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::mkvtoolnix::shell_quote;
+/// use std::ffi::OsStr;
+///
+/// assert_eq!(shell_quote(OsStr::new("--language")), "--language");
+/// assert_eq!(shell_quote(OsStr::new("2:ger")), "2:ger");
+/// assert_eq!(shell_quote(OsStr::new("Show – S01E02")), "'Show – S01E02'");
+/// assert_eq!(shell_quote(OsStr::new("Director's Cut")), r"'Director'\''s Cut'");
+/// assert_eq!(shell_quote(OsStr::new("")), "''");
+/// ```
+pub fn shell_quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:,=+@%".contains(c));
+    if plain {
+        arg.into_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
@@ -1,6 +1,42 @@
+pub mod analyze;
 pub mod analyzer;
+pub mod attachments;
+pub mod audit;
+pub mod backup;
 pub mod batch;
+pub mod chapters;
+pub mod charset;
+pub mod external;
+pub mod fix_languages;
+pub mod fonts;
+pub mod history;
+pub mod identify;
+pub mod inspect;
+pub mod journal;
+pub mod mkvtoolnix;
+pub mod notify;
+pub mod partial;
+pub mod parts;
+pub mod policy;
 pub mod processor;
+pub mod progress;
+pub mod removed;
+pub mod samples;
+pub mod sanity;
+pub mod schema;
+pub mod scrub;
+pub mod slimmer;
+pub mod space;
+pub mod tags;
+pub mod template;
+pub mod throughput;
+pub mod titles;
+pub mod up_to_date;
+pub mod verify;
 
 pub use batch::BatchProcessor;
+pub use policy::{FileContext, LanguagePolicy, StreamPolicy};
 pub use processor::{handle_non_mkv_file, process_task};
+pub use progress::{ProcessControl, ProgressCallback, ProgressEvent};
+pub use slimmer::{ProcessingHooks, Slimmer, SlimmerBuilder};
+pub use tokio_util::sync::CancellationToken;
@@ -0,0 +1,485 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::batch::saved_bytes;
+use crate::config::{NotificationRules, NotifierConfig, NotifierKind, NotifyOn};
+use crate::error::config_error;
+use crate::models::{ProcessingOutcome, RunSummary};
+use crate::report_warn;
+use crate::utils::format_size;
+
+/// Placeholders a notification template may use
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "event",
+    "file",
+    "outcome",
+    "saved",
+    "error",
+    "total",
+    "successful",
+    "failed",
+    "skipped",
+];
+
+/// Something that happened during a run that notifications report
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    /// A file was remuxed, transferred or left as it was
+    FileProcessed {
+        file: PathBuf,
+        /// What happened to it (`remuxed`, `transferred`, ...)
+        outcome: String,
+        /// Bytes the file saved; None in dry runs or when unknown
+        saved_bytes: Option<u64>,
+    },
+    /// Processing a file failed
+    FileFailed { file: PathBuf, error: String },
+    /// The run is over
+    RunFinished(Box<RunSummary>),
+}
+
+impl NotificationEvent {
+    /// Event for `file`, `source_size` bytes before processing, ending in `outcome`
+    pub fn processed(file: &Path, source_size: Option<u64>, outcome: &ProcessingOutcome) -> Self {
+        NotificationEvent::FileProcessed {
+            file: file.to_path_buf(),
+            outcome: outcome_label(outcome).to_string(),
+            saved_bytes: saved_bytes(source_size, outcome),
+        }
+    }
+
+    /// Event for `file` failing with `error`
+    pub fn failed(file: &Path, error: &anyhow::Error) -> Self {
+        NotificationEvent::FileFailed {
+            file: file.to_path_buf(),
+            error: format!("{:#}", error),
+        }
+    }
+
+    /// Name of the event, as the `{event}` placeholder and the webhook's `event`
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotificationEvent::FileProcessed { .. } => "processed",
+            NotificationEvent::FileFailed { .. } => "failed",
+            NotificationEvent::RunFinished(_) => "finished",
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            NotificationEvent::FileProcessed { .. } => "mkv-slimmer: file processed".to_string(),
+            NotificationEvent::FileFailed { .. } => "mkv-slimmer: file failed".to_string(),
+            NotificationEvent::RunFinished(summary) if summary.dry_run => {
+                "mkv-slimmer: dry run finished".to_string()
+            }
+            NotificationEvent::RunFinished(_) => "mkv-slimmer: run finished".to_string(),
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            NotificationEvent::FileProcessed { .. } => "{file}: {outcome}, saved {saved}",
+            NotificationEvent::FileFailed { .. } => "{file} failed: {error}",
+            NotificationEvent::RunFinished(_) => {
+                "{successful} of {total} file(s) processed, {failed} failed, {skipped} skipped"
+            }
+        }
+    }
+
+    /// Value of a template placeholder; empty where the event has none
+    fn value(&self, placeholder: &str) -> String {
+        match (self, placeholder) {
+            (_, "event") => self.name().to_string(),
+            (
+                NotificationEvent::FileProcessed { file, .. }
+                | NotificationEvent::FileFailed { file, .. },
+                "file",
+            ) => file.display().to_string(),
+            (NotificationEvent::FileProcessed { outcome, .. }, "outcome") => outcome.clone(),
+            (NotificationEvent::FileProcessed { saved_bytes, .. }, "saved") => {
+                saved_bytes.map_or_else(|| "?".to_string(), format_size)
+            }
+            (NotificationEvent::FileFailed { error, .. }, "error") => error.clone(),
+            (NotificationEvent::RunFinished(summary), "total") => summary.total_files.to_string(),
+            (NotificationEvent::RunFinished(summary), "successful") => {
+                summary.successful.to_string()
+            }
+            (NotificationEvent::RunFinished(summary), "failed") => summary.failed.to_string(),
+            (NotificationEvent::RunFinished(summary), "skipped") => summary.skipped.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether a backend with `on` is sent this event
+    fn concerns(&self, on: NotifyOn) -> bool {
+        matches!(
+            (self, on),
+            (NotificationEvent::FileProcessed { .. }, NotifyOn::File)
+                | (
+                    NotificationEvent::FileFailed { .. },
+                    NotifyOn::File | NotifyOn::Failures
+                )
+                | (NotificationEvent::RunFinished(_), NotifyOn::Batch)
+        )
+    }
+}
+
+/// What happened to a file, in a few words
+fn outcome_label(outcome: &ProcessingOutcome) -> &'static str {
+    match outcome {
+        ProcessingOutcome::Remuxed { .. } => "remuxed",
+        ProcessingOutcome::Split { .. } => "split by language",
+        ProcessingOutcome::Transferred { .. } => "transferred unchanged",
+        ProcessingOutcome::FlagsNormalized { .. } => "flags normalized",
+        ProcessingOutcome::AttachmentsRemoved { .. } => "attachments removed",
+        ProcessingOutcome::UpToDate { .. } => "already up to date",
+        ProcessingOutcome::BelowMinSavings { .. } => "kept original (below minimum savings)",
+        ProcessingOutcome::DryRun {
+            needs_remux: true, ..
+        } => "would be remuxed",
+        ProcessingOutcome::DryRun {
+            needs_remux: false, ..
+        } => "would be transferred",
+    }
+}
+
+/// Fill the `{placeholder}`s of `template` from `event`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::notify::{NotificationEvent, render_notification};
+/// use std::path::PathBuf;
+///
+/// let event = NotificationEvent::FileFailed {
+///     file: PathBuf::from("/tv/Show/E01.mkv"),
+///     error: "disk full".to_string(),
+/// };
+/// assert_eq!(
+///     render_notification("{event}: {file} ({error}{outcome})", &event),
+///     "failed: /tv/Show/E01.mkv (disk full)"
+/// );
+/// ```
+pub fn render_notification(template: &str, event: &NotificationEvent) -> String {
+    TEMPLATE_PLACEHOLDERS
+        .iter()
+        .fold(template.to_string(), |rendered, placeholder| {
+            rendered.replace(&format!("{{{}}}", placeholder), &event.value(placeholder))
+        })
+}
+
+/// Fail for templates using placeholders `render_notification()` doesn't know
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::notify::validate_notification_template;
+///
+/// assert!(validate_notification_template("{file}: {outcome}").is_ok());
+/// assert!(validate_notification_template("{series_title} done").is_err());
+/// ```
+pub fn validate_notification_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| end + start) else {
+            break;
+        };
+        let placeholder = &rest[start + 1..end];
+        if !TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+            return Err(config_error(
+                "Notification template",
+                &format!(
+                    "Unknown placeholder '{{{}}}' in '{}'. Use one of: {}",
+                    placeholder,
+                    template,
+                    TEMPLATE_PLACEHOLDERS
+                        .iter()
+                        .map(|p| format!("{{{}}}", p))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+/// A rendered message for a notifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// `NotificationEvent::name()` of the event
+    pub event: &'static str,
+    pub title: String,
+    pub message: String,
+}
+
+/// A service notifications are delivered to
+///
+/// `Notifications` decides which events reach a notifier and renders them;
+/// a notifier only delivers the result. Library users can add their own with
+/// `Notifications::with_notifier`.
+///
+/// # Examples
+/// ```rust
+/// use anyhow::Result;
+/// use mkv_slimmer::config::NotificationRules;
+/// use mkv_slimmer::core::notify::{Notification, NotificationEvent, Notifications, Notifier};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Collect(Arc<Mutex<Vec<String>>>);
+///
+/// impl Notifier for Collect {
+///     fn name(&self) -> String {
+///         "collect".to_string()
+///     }
+///
+///     fn send(&self, notification: &Notification) -> Result<()> {
+///         self.0.lock().unwrap().push(notification.message.clone());
+///         Ok(())
+///     }
+/// }
+///
+/// let messages = Arc::new(Mutex::new(Vec::new()));
+/// let notifications = Notifications::default()
+///     .with_notifier(Collect(messages.clone()), NotificationRules::default());
+///
+/// // The default rules only report finished runs
+/// notifications.notify(&NotificationEvent::FileFailed {
+///     file: "a.mkv".into(),
+///     error: "broken".to_string(),
+/// });
+/// notifications.notify(&NotificationEvent::RunFinished(Box::default()));
+/// assert_eq!(
+///     *messages.lock().unwrap(),
+///     vec!["0 of 0 file(s) processed, 0 failed, 0 skipped".to_string()]
+/// );
+/// ```
+pub trait Notifier: Send + Sync {
+    /// Name used when a delivery fails
+    fn name(&self) -> String;
+
+    /// Deliver `notification`
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Runs a shell command per message
+pub struct CommandNotifier {
+    command: String,
+}
+
+impl CommandNotifier {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn name(&self) -> String {
+        format!("command '{}'", self.command)
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("MKV_SLIMMER_EVENT", notification.event)
+            .env("MKV_SLIMMER_TITLE", &notification.title)
+            .env("MKV_SLIMMER_MESSAGE", &notification.message)
+            .status()
+            .context("Failed to run the command")?;
+        if !status.success() {
+            anyhow::bail!("The command exited with {}", status);
+        }
+        Ok(())
+    }
+}
+
+/// POSTs each message as JSON to a URL, using curl
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let body = serde_json::json!({
+            "event": notification.event,
+            "title": notification.title,
+            "message": notification.message,
+        });
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--max-time", "30"])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data-binary", "@-"])
+            .arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .context("Failed to run curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(body.to_string().as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("curl exited with {}", status);
+        }
+        Ok(())
+    }
+}
+
+/// A notifier with the rules deciding what it is sent
+struct Route {
+    notifier: Box<dyn Notifier>,
+    rules: NotificationRules,
+    state: Mutex<RouteState>,
+}
+
+#[derive(Default)]
+struct RouteState {
+    last_sent: Option<Instant>,
+    /// Messages left out by `min_interval_secs` since the last one sent
+    suppressed: usize,
+}
+
+impl Route {
+    fn notify(&self, event: &NotificationEvent) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let finished = matches!(event, NotificationEvent::RunFinished(_));
+        if !event.concerns(self.rules.on) {
+            // Messages a per-file backend left out are summed up at the end
+            if !(finished && state.suppressed > 0) {
+                return;
+            }
+        } else if !finished
+            && let (Some(secs), Some(last_sent)) = (self.rules.min_interval_secs, state.last_sent)
+            && last_sent.elapsed() < Duration::from_secs(secs)
+        {
+            state.suppressed += 1;
+            return;
+        }
+
+        let template = match &self.rules.template {
+            Some(template) if event.concerns(self.rules.on) => template.as_str(),
+            _ => event.default_template(),
+        };
+        let mut message = render_notification(template, event);
+        if state.suppressed > 0 {
+            message.push_str(&format!(
+                "\n({} more notification(s) left out by the rate limit)",
+                state.suppressed
+            ));
+        }
+        let notification = Notification {
+            event: event.name(),
+            title: event.title(),
+            message,
+        };
+        match self.notifier.send(&notification) {
+            Ok(()) => {
+                state.last_sent = Some(Instant::now());
+                state.suppressed = 0;
+            }
+            Err(e) => report_warn!(
+                "Warning: Notification via {} failed: {:#}",
+                self.notifier.name(),
+                e
+            ),
+        }
+    }
+}
+
+/// Delivers the events of a run to every notifier whose rules ask for them
+#[derive(Default)]
+pub struct Notifications {
+    routes: Vec<Route>,
+}
+
+impl Notifications {
+    /// The backends of the `notifications` config
+    pub fn from_config(configs: &[NotifierConfig]) -> Self {
+        configs
+            .iter()
+            .fold(Notifications::default(), |notifications, config| {
+                let notifier: Box<dyn Notifier> = match &config.kind {
+                    NotifierKind::Command { command } => Box::new(CommandNotifier::new(command)),
+                    NotifierKind::Webhook { url } => Box::new(WebhookNotifier::new(url)),
+                };
+                notifications.with_boxed(notifier, config.rules.clone())
+            })
+    }
+
+    /// Also deliver to `notifier`, as `rules` say
+    pub fn with_notifier(
+        self,
+        notifier: impl Notifier + 'static,
+        rules: NotificationRules,
+    ) -> Self {
+        self.with_boxed(Box::new(notifier), rules)
+    }
+
+    fn with_boxed(mut self, notifier: Box<dyn Notifier>, rules: NotificationRules) -> Self {
+        self.routes.push(Route {
+            notifier,
+            rules,
+            state: Mutex::new(RouteState::default()),
+        });
+        self
+    }
+
+    /// Whether there is no notifier at all
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Send `event` to the notifiers that want it. Failed deliveries are
+    /// reported as warnings and never fail the run.
+    pub fn notify(&self, event: &NotificationEvent) {
+        for route in &self.routes {
+            route.notify(event);
+        }
+    }
+}
+
+/// Fail for notification backends that can't work
+pub fn validate_notifications(configs: &[NotifierConfig]) -> Result<()> {
+    for config in configs {
+        match &config.kind {
+            NotifierKind::Command { command } if command.trim().is_empty() => {
+                return Err(config_error(
+                    "Notifications",
+                    "A command notification needs a non-empty 'command'",
+                ));
+            }
+            NotifierKind::Webhook { url }
+                if !(url.starts_with("http://") || url.starts_with("https://")) =>
+            {
+                return Err(config_error(
+                    "Notifications",
+                    &format!(
+                        "Invalid webhook URL '{}'. Use an http:// or https:// URL",
+                        url
+                    ),
+                ));
+            }
+            _ => {}
+        }
+        if let Some(template) = &config.rules.template {
+            validate_notification_template(template)?;
+        }
+    }
+    Ok(())
+}
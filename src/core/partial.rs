@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::progress::{ProcessControl, ProgressEvent};
+use crate::error::Cancelled;
+use crate::utils::format_size;
+use crate::{report, report_warn};
+
+/// Prefix of the temporary files written to the system temp directory
+/// (`mkv-slimmer-<pid>-...`)
+const TEMP_PREFIX: &str = "mkv-slimmer-";
+
+/// Marker in the name of partial outputs (`<output>.mkv-slimmer-<pid>.part`)
+const PARTIAL_MARKER: &str = ".mkv-slimmer-";
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// Without /proc, files untouched this long count as orphaned
+const ORPHAN_AGE: Duration = Duration::from_secs(24 * 3_600);
+
+/// Size of the chunks copies are read and written in; progress is reported
+/// after each one
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Where an output is written until it is complete: next to it (same
+/// filesystem, so the final rename is atomic) with this process's ID, so a
+/// crashed run's leftovers can be told from outputs still being written
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::partial::partial_path;
+/// use std::path::Path;
+///
+/// let partial = partial_path(Path::new("/media/Movie.mkv"));
+/// assert_eq!(partial.parent(), Some(Path::new("/media")));
+/// assert_eq!(
+///     partial.file_name().unwrap().to_string_lossy(),
+///     format!("Movie.mkv.mkv-slimmer-{}.part", std::process::id())
+/// );
+/// ```
+pub fn partial_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(
+        "{}{}{}",
+        PARTIAL_MARKER,
+        std::process::id(),
+        PARTIAL_SUFFIX
+    ));
+    output_path.with_file_name(name)
+}
+
+/// Move a complete partial output to its final path, replacing what is there
+pub fn finish_partial(partial: &Path, output_path: &Path) -> Result<()> {
+    std::fs::rename(partial, output_path).with_context(|| {
+        format!(
+            "Failed to move finished output into place: {}",
+            output_path.display()
+        )
+    })
+}
+
+/// Copy `source` to `output_path` through a partial file, so an interrupted
+/// copy never leaves a truncated file at the output path. The copy runs in
+/// chunks, reporting `ProgressEvent::Copying` to `control` and stopping once it
+/// is cancelled.
+pub async fn copy_via_partial(
+    source: &Path,
+    output_path: &Path,
+    control: &ProcessControl,
+) -> Result<()> {
+    let partial = partial_path(output_path);
+    let result = match copy_chunked(source, &partial, control).await {
+        Ok(()) => finish_partial(&partial, output_path),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+async fn copy_chunked(source: &Path, destination: &Path, control: &ProcessControl) -> Result<()> {
+    let mut reader = tokio::fs::File::open(source)
+        .await
+        .with_context(|| format!("Failed to open {}", source.display()))?;
+    let metadata = reader.metadata().await?;
+    let total = metadata.len();
+    let mut writer = tokio::fs::File::create(destination)
+        .await
+        .with_context(|| format!("Failed to create {}", destination.display()))?;
+
+    let mut buffer = vec![0; COPY_CHUNK_SIZE];
+    let mut copied = 0;
+    control.report(ProgressEvent::Copying { copied, total });
+    loop {
+        if control.cancellation().is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .await
+            .with_context(|| format!("Failed to write {}", destination.display()))?;
+        copied += read as u64;
+        control.report(ProgressEvent::Copying {
+            copied,
+            total: total.max(copied),
+        });
+    }
+    writer.flush().await?;
+
+    // Like std::fs::copy, the copy gets the source's permission bits
+    tokio::fs::set_permissions(destination, metadata.permissions()).await?;
+    Ok(())
+}
+
+/// Process ID of a partial output or temporary file of mkv-slimmer, from its name
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::partial::owner_pid;
+/// use std::path::Path;
+///
+/// assert_eq!(owner_pid(Path::new("/media/Movie.mkv.mkv-slimmer-42.part")), Some(42));
+/// assert_eq!(owner_pid(Path::new("/tmp/mkv-slimmer-42-Movie.mkv.options.json")), Some(42));
+/// assert_eq!(owner_pid(Path::new("/media/Movie.mkv")), None);
+/// ```
+pub fn owner_pid(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let pid = match name.strip_suffix(PARTIAL_SUFFIX) {
+        Some(rest) => rest.rsplit_once(PARTIAL_MARKER)?.1,
+        None => name.strip_prefix(TEMP_PREFIX)?.split_once('-')?.0,
+    };
+    pid.parse().ok()
+}
+
+/// Whether a file left by process `pid` belongs to a run that is gone. With
+/// /proc the process is looked up; elsewhere a file counts as orphaned once it
+/// hasn't changed for a day.
+fn is_orphaned(pid: u32, path: &Path) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    if Path::new("/proc/self").exists() {
+        return !Path::new("/proc").join(pid.to_string()).exists();
+    }
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= ORPHAN_AGE)
+}
+
+/// Partial outputs below `target` (in subdirectories too with `recursive`)
+/// and temporary files in the system temp directory that crashed or killed
+/// runs left behind, with their sizes
+pub fn find_orphans(target: &Path, recursive: bool) -> Vec<(PathBuf, u64)> {
+    let mut candidates = Vec::new();
+    collect_partials(target, recursive, &mut candidates);
+    if let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) {
+        candidates.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(TEMP_PREFIX))
+        }));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|path| owner_pid(path).is_some_and(|pid| is_orphaned(pid, path)))
+        .filter_map(|path| {
+            let metadata = std::fs::symlink_metadata(&path).ok()?;
+            metadata.is_file().then_some((path, metadata.len()))
+        })
+        .collect()
+}
+
+fn collect_partials(directory: &Path, recursive: bool, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if recursive {
+                collect_partials(&path, recursive, found);
+            }
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(PARTIAL_SUFFIX) && name.contains(PARTIAL_MARKER))
+        {
+            found.push(path);
+        }
+    }
+}
+
+/// Remove the orphaned partial outputs and temporary files `find_orphans()`
+/// reports and say how much space that reclaimed (in dry runs, what would be
+/// removed). Files that can't be removed are reported and left.
+pub fn clean_orphans(target: &Path, recursive: bool, dry_run: bool) {
+    let orphans = find_orphans(target, recursive);
+    if orphans.is_empty() {
+        return;
+    }
+
+    let mut removed = 0;
+    let mut reclaimed = 0;
+    for (path, size) in &orphans {
+        if dry_run {
+            report!("🧹 Dry run: Would remove orphaned file: {}", path.display());
+        } else if let Err(e) = std::fs::remove_file(path) {
+            report_warn!(
+                "⚠️  Could not remove orphaned file {}: {}",
+                path.display(),
+                e
+            );
+            continue;
+        } else {
+            report!("🧹 Removed orphaned file: {}", path.display());
+        }
+        removed += 1;
+        reclaimed += size;
+    }
+
+    if dry_run {
+        report!(
+            "🧹 {} orphaned partial/temp file(s) from interrupted runs would be removed ({})\n",
+            removed,
+            format_size(reclaimed)
+        );
+    } else {
+        report!(
+            "🧹 Removed {} orphaned partial/temp file(s) from interrupted runs, reclaiming {}\n",
+            removed,
+            format_size(reclaimed)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::models::{SegmentLinking, StreamInfo, StreamType};
+
+/// Parts of one recording split across several files, in playback order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartGroup {
+    /// Name shown in messages: the shared base name, or the first linked file
+    pub name: String,
+    pub files: Vec<PathBuf>,
+    /// Whether the parts were found through linked segments rather than names
+    pub linked: bool,
+}
+
+/// Base name and part number of a file named like a split part: `Movie.001.mkv`,
+/// `Movie-002.mkv` (mkvmerge `--split`), `Movie.part1.mkv`, `Movie pt2.mkv` or
+/// `Movie.cd1.mkv`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::parts::split_part_name;
+/// use std::path::Path;
+///
+/// assert_eq!(split_part_name(Path::new("/m/Movie.001.mkv")), Some(("Movie".to_string(), 1)));
+/// assert_eq!(split_part_name(Path::new("Movie-002.mkv")), Some(("Movie".to_string(), 2)));
+/// assert_eq!(split_part_name(Path::new("Movie CD2.mkv")), Some(("Movie".to_string(), 2)));
+/// assert_eq!(split_part_name(Path::new("Show - S01E01.mkv")), None);
+/// assert_eq!(split_part_name(Path::new("Movie (2020).mkv")), None);
+/// ```
+pub fn split_part_name(path: &Path) -> Option<(String, u32)> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let separator = stem.rfind(['.', '-', '_', ' '])?;
+    let (base, suffix) = (stem[..separator].trim_end(), &stem[separator + 1..]);
+    if base.is_empty() {
+        return None;
+    }
+
+    let lower = suffix.to_lowercase();
+    let number = ["part", "pt", "cd"]
+        .iter()
+        .find_map(|prefix| lower.strip_prefix(prefix))
+        .filter(|number| !number.is_empty() && number.len() <= 2)
+        // Bare numbers only count in mkvmerge's zero-padded style
+        .or_else(|| (lower.len() == 3).then_some(lower.as_str()))?;
+    if !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((base.to_string(), number.parse().ok()?))
+}
+
+/// Multi-part recordings among `files`: files sharing a split-part base name
+/// in the same folder (two or more parts), and files whose segments link to a
+/// previous/next segment. `linking` holds the segment linking of the files
+/// that have any.
+pub fn find_part_groups(
+    files: &[PathBuf],
+    linking: &HashMap<PathBuf, SegmentLinking>,
+) -> Vec<PartGroup> {
+    let mut by_name: BTreeMap<(PathBuf, String), Vec<(u32, PathBuf)>> = BTreeMap::new();
+    for file in files {
+        if let Some((base, part)) = split_part_name(file) {
+            let folder = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            by_name
+                .entry((folder, base))
+                .or_default()
+                .push((part, file.clone()));
+        }
+    }
+
+    let mut groups: Vec<PartGroup> = by_name
+        .into_iter()
+        .filter(|(_, parts)| parts.len() > 1)
+        .map(|((_, base), mut parts)| {
+            parts.sort();
+            PartGroup {
+                name: base,
+                files: parts.into_iter().map(|(_, file)| file).collect(),
+                linked: false,
+            }
+        })
+        .collect();
+
+    let grouped: Vec<&PathBuf> = groups.iter().flat_map(|g| &g.files).collect();
+    let linked: Vec<(&PathBuf, &SegmentLinking)> = files
+        .iter()
+        .filter(|file| !grouped.contains(file))
+        .filter_map(|file| Some((file, linking.get(file)?)))
+        .filter(|(_, l)| l.previous_uid.is_some() || l.next_uid.is_some())
+        .collect();
+    groups.extend(linked_chains(&linked));
+
+    groups
+}
+
+/// Chains of files connected through previous/next segment UIDs, each
+/// starting with a file whose previous segment isn't in the batch
+fn linked_chains(linked: &[(&PathBuf, &SegmentLinking)]) -> Vec<PartGroup> {
+    let by_uid: HashMap<&str, usize> = linked
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, l))| Some((l.segment_uid.as_deref()?, i)))
+        .collect();
+    let is_first = |l: &SegmentLinking| {
+        l.previous_uid
+            .as_deref()
+            .is_none_or(|uid| !by_uid.contains_key(uid))
+    };
+
+    let mut visited = vec![false; linked.len()];
+    let mut chains = Vec::new();
+    let starts: Vec<usize> = (0..linked.len())
+        .filter(|&i| is_first(linked[i].1))
+        .chain(0..linked.len())
+        .collect();
+    for start in starts {
+        if visited[start] {
+            continue;
+        }
+        let mut files = Vec::new();
+        let mut current = Some(start);
+        while let Some(i) = current.filter(|&i| !visited[i]) {
+            visited[i] = true;
+            files.push(linked[i].0.clone());
+            current = linked[i]
+                .1
+                .next_uid
+                .as_deref()
+                .and_then(|uid| by_uid.get(uid).copied());
+        }
+        chains.push(PartGroup {
+            name: linked[start]
+                .0
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            files,
+            linked: true,
+        });
+    }
+    chains
+}
+
+/// What has to match between parts for them to get the same track selection:
+/// type, codec and language of every stream, in order
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::parts::same_track_layout;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let part = vec![StreamInfo::new(0, StreamType::Video), StreamInfo::new(1, StreamType::Audio)];
+/// let mut other = part.clone();
+/// assert!(same_track_layout(&[&part, &other]));
+///
+/// other[1].language = Some("jpn".to_string());
+/// assert!(!same_track_layout(&[&part, &other]));
+/// ```
+pub fn same_track_layout(parts: &[&Vec<StreamInfo>]) -> bool {
+    let layout = |streams: &Vec<StreamInfo>| -> Vec<(StreamType, String, String)> {
+        streams
+            .iter()
+            .map(|s| {
+                (
+                    s.stream_type,
+                    s.codec.clone(),
+                    s.effective_language().to_string(),
+                )
+            })
+            .collect()
+    };
+    parts
+        .split_first()
+        .is_none_or(|(first, rest)| rest.iter().all(|part| layout(part) == layout(first)))
+}
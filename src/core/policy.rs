@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use crate::config::Config;
+use crate::models::{
+    Decision, DecisionReason, ImportContext, MediaKind, StreamAction, StreamInfo, StreamType,
+};
+
+/// Per-file information available to a `StreamPolicy` when deciding on a stream
+pub struct FileContext<'a> {
+    /// File the streams belong to
+    pub source_file: &'a Path,
+    /// All streams of the file, for policies that compare streams with each other
+    pub streams: &'a [StreamInfo],
+    /// Kind of container, which determines the stream types that are filtered
+    pub media_kind: MediaKind,
+    /// Active configuration
+    pub config: &'a Config,
+    /// Sonarr/Radarr context of the import, if running as an import script
+    pub import_context: Option<&'a ImportContext>,
+}
+
+/// Decides whether individual streams are kept or removed
+///
+/// The default implementation is `LanguagePolicy`. Library users can supply
+/// their own policy to `process_task` (or `Slimmer::builder().policy(..)`) to
+/// replace the keep/remove logic while reusing default-flag handling and the
+/// mkvmerge integration.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::{FileContext, StreamPolicy};
+/// use mkv_slimmer::models::{Decision, DecisionReason, StreamInfo, StreamType};
+///
+/// /// Keeps everything except commentary tracks
+/// struct NoCommentary;
+///
+/// impl StreamPolicy for NoCommentary {
+///     fn decide(&self, stream: &StreamInfo, _context: &FileContext) -> Decision {
+///         let is_commentary = stream
+///             .title
+///             .as_deref()
+///             .is_some_and(|t| t.to_lowercase().contains("commentary"));
+///         if stream.stream_type == StreamType::Audio && is_commentary {
+///             Decision::remove(DecisionReason::Custom("commentary".to_string()))
+///         } else {
+///             Decision::keep(DecisionReason::AlwaysKept)
+///         }
+///     }
+/// }
+/// ```
+pub trait StreamPolicy: Send + Sync {
+    /// Decide what happens to `stream`
+    fn decide(&self, stream: &StreamInfo, context: &FileContext) -> Decision;
+}
+
+/// Default policy: keeps video, attachments and unknown streams (cover art
+/// unless `attachments.remove_cover_art` is set), and keeps audio/subtitle
+/// streams whose language (and title prefix) match the config.
+/// Stream types the container kind doesn't filter (e.g. subtitles in an .mka)
+/// are kept as-is.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LanguagePolicy;
+
+impl StreamPolicy for LanguagePolicy {
+    fn decide(&self, stream: &StreamInfo, context: &FileContext) -> Decision {
+        let config = context.config;
+        if stream.cover_art && config.attachments.remove_cover_art {
+            return Decision::remove(DecisionReason::CoverArt);
+        }
+        match stream.stream_type {
+            // Always keep video streams, attachments (fonts, etc.) and
+            // unknown streams to be safe
+            StreamType::Video | StreamType::Attachment | StreamType::Unknown => {
+                Decision::keep(DecisionReason::AlwaysKept)
+            }
+            StreamType::Audio if !context.media_kind.filters_audio() => {
+                Decision::keep(DecisionReason::AlwaysKept)
+            }
+            StreamType::Subtitle if !context.media_kind.filters_subtitles() => {
+                Decision::keep(DecisionReason::AlwaysKept)
+            }
+            StreamType::Audio => {
+                let lang = stream.effective_language();
+                if config.audio.keep_languages.iter().any(|l| l == lang) {
+                    Decision::keep(DecisionReason::LanguageMatch)
+                } else {
+                    Decision::remove(DecisionReason::NotPreferred)
+                }
+            }
+            StreamType::Subtitle => {
+                let lang = stream.effective_language();
+                // Check if any preference matches this subtitle, preferring
+                // title-specific matches for the reported reason
+                let mut decision = Decision::remove(DecisionReason::NotPreferred);
+                for pref in config.subtitles.keep_languages.iter().filter(|pref| {
+                    pref.language == lang && pref.matches_title(stream.title.as_deref())
+                }) {
+                    if pref.title_prefix.is_some() {
+                        return Decision::keep(DecisionReason::TitleMatch);
+                    }
+                    decision = Decision::keep(DecisionReason::LanguageMatch);
+                }
+                if decision.action == StreamAction::Remove && keeps_forced_flag(stream, config) {
+                    return Decision::keep(DecisionReason::ForcedKept);
+                }
+                decision
+            }
+        }
+    }
+}
+
+/// Whether `stream` is a forced subtitle that `subtitles.keep_forced` retains
+/// with its forced flag, instead of clearing the flag like on other tracks
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::policy::keeps_forced_flag;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let mut config = Config::default();
+/// let mut forced = StreamInfo::new(2, StreamType::Subtitle);
+/// forced.forced = true;
+/// assert!(!keeps_forced_flag(&forced, &config));
+///
+/// config.subtitles.keep_forced = true;
+/// assert!(keeps_forced_flag(&forced, &config));
+/// assert!(!keeps_forced_flag(&StreamInfo::new(3, StreamType::Subtitle), &config));
+/// ```
+pub fn keeps_forced_flag(stream: &StreamInfo, config: &Config) -> bool {
+    config.subtitles.keep_forced && stream.stream_type == StreamType::Subtitle && stream.forced
+}
@@ -1,10 +1,15 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
-use super::analyzer::{handle_no_processing_needed_task, process_mkv_streams};
+use super::analyzer::{decide_streams, handle_no_processing_needed_task, process_mkv_streams};
+use super::history::{SourceStamp, record_processed};
+use super::policy::{FileContext, StreamPolicy};
+use super::progress::ProcessControl;
+use super::titles::title_rewrites;
 use crate::config::Config;
 use crate::display::StreamDisplayer;
-use crate::models::{ProcessingTask, SonarrContext};
+use crate::models::{ImportContext, ProcessingOutcome, ProcessingTask};
+use crate::{report, report_warn};
 
 /// Processes a single MKV file using a ProcessingTask with configuration
 ///
@@ -15,45 +20,73 @@ use crate::models::{ProcessingTask, SonarrContext};
 /// # Arguments
 /// * `task` - Pre-analyzed processing task containing file info and streams
 /// * `config` - Configuration for stream filtering and processing behavior  
-/// * `sonarr_context` - Optional Sonarr context for automated processing
+/// * `import_context` - Optional Sonarr/Radarr context for automated processing
+/// * `policy` - Decides which streams are kept (`LanguagePolicy` for the config-driven default)
 /// * `display_streams` - Whether to show stream information (for interactive mode)
+/// * `control` - Progress callback and cancellation token for the run
 ///
 /// # Returns
-/// `Ok(())` if processing completed successfully, `Err` with context on failure
+/// The `ProcessingOutcome` describing what was written, `Err` with context on failure
 ///
 /// # Examples
-/// ```rust
-/// use mkv_slimmer::core::{process_task, ProcessingTask};
+/// ```rust,no_run
 /// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::{LanguagePolicy, ProcessControl, process_task};
+/// use mkv_slimmer::models::ProcessingTask;
 /// use std::path::PathBuf;
 ///
-/// # tokio_test::block_on(async {
+/// # async fn run() -> anyhow::Result<()> {
 /// let task = ProcessingTask::new(
 ///     PathBuf::from("input.mkv"),
-///     PathBuf::from("output.mkv")
+///     PathBuf::from("/output"),
+///     Vec::new(),
+///     None,
 /// );
 /// let config = Config::default();
 ///
-/// let result = process_task(task, &config, None, true).await;
-/// # });
+/// process_task(task, &config, None, &LanguagePolicy, true, &ProcessControl::default()).await?;
+/// # Ok(())
+/// # }
 /// ```
 pub async fn process_task(
     task: ProcessingTask,
     config: &Config,
-    sonarr_context: Option<&SonarrContext>,
+    import_context: Option<&ImportContext>,
+    policy: &dyn StreamPolicy,
     display_streams: bool,
-) -> Result<()> {
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
+    // Decide once; display and processing describe the same outcome
+    let context = FileContext {
+        source_file: &task.source_file,
+        streams: &task.streams,
+        media_kind: task.media_kind(),
+        config,
+        import_context,
+    };
+    let decisions = decide_streams(&context, policy);
+
     // Display streams in interactive mode (not in batch mode)
     if display_streams {
-        let displayer = StreamDisplayer::new(&task.streams, config);
+        let displayer = StreamDisplayer::new(&task.streams, &decisions, task.media_kind())
+            .with_chapters(task.chapters, config.processing.chapters)
+            .with_dropped_metadata(config.tags.dropped_metadata())
+            .with_title_rewrites(title_rewrites(&task.streams, &decisions, &config.titles));
         displayer
             .display()
             .context("Failed to display stream information")?;
-        println!("\n🎬 Processing streams...");
+        report!("\n🎬 Processing streams...");
     }
 
+    // Stamp the source now: a move takes it away
+    let source = config
+        .processing
+        .history_db
+        .as_ref()
+        .and_then(|_| SourceStamp::of(&task.source_file));
+
     // Process the streams using the task
-    process_mkv_streams(&task, config, sonarr_context)
+    let outcome = process_mkv_streams(&task, decisions, config, import_context, control)
         .await
         .with_context(|| {
             format!(
@@ -62,7 +95,11 @@ pub async fn process_task(
             )
         })?;
 
-    Ok(())
+    // The file is done either way; a history that can't be written only warns
+    if let Err(e) = record_processed(source, &task.streams, &outcome, config) {
+        report_warn!("⚠️  {:#}", e);
+    }
+    Ok(outcome)
 }
 
 /// Handle non-MKV files by copying/hardlinking immediately
@@ -72,8 +109,9 @@ pub async fn handle_non_mkv_file(
     target_directory: &Path,
     output_filename: Option<String>,
     config: &Config,
-    sonarr_context: Option<&SonarrContext>,
-) -> Result<()> {
+    import_context: Option<&ImportContext>,
+    control: &ProcessControl,
+) -> Result<ProcessingOutcome> {
     // Create a minimal task for file operations
     let task = ProcessingTask::new(
         source_file.to_path_buf(),
@@ -82,11 +120,9 @@ pub async fn handle_non_mkv_file(
         output_filename,
     );
 
-    handle_no_processing_needed_task(&task, config, sonarr_context)
+    handle_no_processing_needed_task(&task, config, import_context, control)
         .await
-        .with_context(|| format!("Failed to copy non-MKV file: {}", source_file.display()))?;
-
-    Ok(())
+        .with_context(|| format!("Failed to copy non-MKV file: {}", source_file.display()))
 }
 
 // Legacy analyze_and_process_mkv_file removed - batch.rs now uses ProcessingTask directly
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::Cancelled;
+
+/// Stage reached while processing a file, reported to the progress callback
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// Stream analysis of the source file has started
+    AnalysisStarted,
+    /// The file is being remuxed or transferred to its target
+    ProcessingStarted,
+    /// mkvmerge progress of the current output, 0-100
+    Remuxing { percent: u8 },
+    /// Bytes copied so far of a `total`-byte copy of the source to its target
+    Copying { copied: u64, total: u64 },
+    /// Processing finished successfully
+    Finished,
+}
+
+/// Callback invoked whenever processing reaches a new stage
+pub type ProgressCallback = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Progress reporting and cancellation shared by every stage of a run
+///
+/// The default control reports nothing and is never cancelled.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::{CancellationToken, ProcessControl, ProgressEvent};
+///
+/// let token = CancellationToken::new();
+/// let control = ProcessControl::default()
+///     .with_progress(|event| println!("{:?}", event))
+///     .with_cancellation(token.clone());
+///
+/// control.report(ProgressEvent::AnalysisStarted);
+/// assert!(control.check_cancelled().is_ok());
+/// token.cancel();
+/// assert!(control.check_cancelled().is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct ProcessControl {
+    progress: Option<ProgressCallback>,
+    cancellation: CancellationToken,
+}
+
+impl ProcessControl {
+    /// Report events to `callback`
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(ProgressEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Abort the run once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Forward an event to the progress callback, if any
+    pub fn report(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress(event);
+        }
+    }
+
+    /// Token that aborts this run when cancelled
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Fail with `Cancelled` if the run has been cancelled
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::models::{DecisionReason, StreamDecisions, StreamInfo, StreamType, Versioned};
+
+/// Global tag holding the removed tracks as JSON (`removed_manifest: tag`)
+pub const REMOVED_TRACKS_TAG: &str = "MKV_SLIMMER_REMOVED";
+
+/// A track of the source that a remux left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RemovedTrack {
+    /// Index of the track in the source
+    pub index: u32,
+    /// Matroska track UID in the source, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u64>,
+    #[serde(rename = "type")]
+    pub stream_type: StreamType,
+    pub codec: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Size of the track (possibly estimated), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    pub reason: DecisionReason,
+}
+
+/// Sidecar written next to an output (`removed_manifest: sidecar`), as a
+/// `Versioned` document
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RemovedManifestFile {
+    /// Source the output was made from
+    pub source: PathBuf,
+    pub removed: Vec<RemovedTrack>,
+}
+
+/// Tracks of the source file that `decisions` leave out. External tracks
+/// that weren't merged were never part of the source, so they aren't listed.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::removed::removed_tracks;
+/// use mkv_slimmer::models::{
+///     DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamInfo, StreamType,
+/// };
+///
+/// let mut commentary = StreamInfo::new(2, StreamType::Audio);
+/// commentary.codec = "ac3".to_string();
+/// commentary.language = Some("eng".to_string());
+/// commentary.title = Some("Commentary".to_string());
+/// commentary.size_bytes = Some(350_000_000);
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), commentary];
+/// let decision = |index: u32, stream_type: StreamType, action: StreamAction| StreamDecision {
+///     index,
+///     stream_type,
+///     action,
+///     default: false,
+///     reason: DecisionReason::NotPreferred,
+///     size_bytes: None,
+/// };
+/// let decisions = StreamDecisions {
+///     streams: vec![
+///         decision(0, StreamType::Video, StreamAction::Keep),
+///         decision(2, StreamType::Audio, StreamAction::Remove),
+///     ],
+///     ..Default::default()
+/// };
+///
+/// let removed = removed_tracks(&streams, &decisions);
+/// assert_eq!(removed.len(), 1);
+/// assert_eq!(removed[0].title.as_deref(), Some("Commentary"));
+/// assert_eq!(removed[0].size_bytes, Some(350_000_000));
+/// ```
+pub fn removed_tracks(streams: &[StreamInfo], decisions: &StreamDecisions) -> Vec<RemovedTrack> {
+    streams
+        .iter()
+        .filter(|s| s.external.is_none())
+        .filter_map(|s| {
+            let decision = decisions.get(s.index).filter(|d| !d.is_kept())?;
+            Some(RemovedTrack {
+                index: s.index,
+                uid: s.uid,
+                stream_type: s.stream_type,
+                codec: s.codec.clone(),
+                language: s.language.clone(),
+                title: s.title.clone(),
+                size_bytes: decision.size_bytes.or(s.size_bytes),
+                reason: decision.reason.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Location of the removed-tracks sidecar of an output: `<output>.removed.json`
+pub fn removed_manifest_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".removed.json");
+    PathBuf::from(path)
+}
+
+/// Write the removed-tracks sidecar of `output_path`, returning its path
+pub fn write_removed_manifest(
+    source: &Path,
+    output_path: &Path,
+    removed: &[RemovedTrack],
+) -> Result<PathBuf> {
+    let manifest = RemovedManifestFile {
+        source: source.to_path_buf(),
+        removed: removed.to_vec(),
+    };
+    let path = removed_manifest_path(output_path);
+    let json = serde_json::to_string_pretty(&Versioned::new(manifest))?;
+    std::fs::write(&path, json + "\n").with_context(|| {
+        format!(
+            "Failed to write removed-tracks manifest: {}",
+            path.display()
+        )
+    })?;
+    Ok(path)
+}
+
+/// The removed tracks as the value of `REMOVED_TRACKS_TAG`
+pub fn removed_tracks_tag(removed: &[RemovedTrack]) -> String {
+    serde_json::to_string(removed).unwrap_or_default()
+}
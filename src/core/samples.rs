@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::utils::format_age;
+
+/// How many times the sample limit another file in the folder has to run for
+/// a short file next to it to count as a sample
+const LONG_SIBLING_FACTOR: u32 = 3;
+
+/// Whether the file name (or its folder) marks it as a sample: a `sample`
+/// word in the name, or a `Sample`/`Samples` folder
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::samples::has_sample_name;
+/// use std::path::Path;
+///
+/// assert!(has_sample_name(Path::new("Movie.2020.1080p-sample.mkv")));
+/// assert!(has_sample_name(Path::new("/movies/Movie (2020)/Sample/movie.mkv")));
+/// assert!(!has_sample_name(Path::new("Sampler.Season.1.E01.mkv")));
+/// ```
+pub fn has_sample_name(path: &Path) -> bool {
+    let is_sample = |word: &str| word.eq_ignore_ascii_case("sample");
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let folder = path
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    stem.split(|c: char| !c.is_alphanumeric()).any(is_sample)
+        || is_sample(&folder)
+        || folder.eq_ignore_ascii_case("samples")
+}
+
+/// Playback duration stored in the Matroska segment info
+pub fn media_duration(path: &Path) -> Option<Duration> {
+    let file = std::fs::File::open(path).ok()?;
+    matroska::Matroska::open(file).ok()?.info.duration
+}
+
+/// Files of a batch that look like samples, with the reason. `files` pairs
+/// each file with its duration (None when unknown); files shorter than
+/// `max_duration` are samples when their name says so, or when another file in
+/// the same folder runs at least three times as long as `max_duration`.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::samples::find_samples;
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+///
+/// let minutes = |m: u64| Some(Duration::from_secs(m * 60));
+/// let files = vec![
+///     (PathBuf::from("/m/Movie/Movie.mkv"), minutes(110)),
+///     (PathBuf::from("/m/Movie/Movie.sample.mkv"), minutes(1)),
+///     (PathBuf::from("/m/Movie/trailer.mkv"), minutes(2)),
+///     (PathBuf::from("/m/Short/Short.mkv"), minutes(7)),
+/// ];
+/// let samples = find_samples(&files, Duration::from_secs(10 * 60));
+/// assert_eq!(samples.len(), 2);
+/// assert!(samples.contains_key(&PathBuf::from("/m/Movie/trailer.mkv")));
+/// // Short films on their own stay in the batch
+/// assert!(!samples.contains_key(&PathBuf::from("/m/Short/Short.mkv")));
+/// ```
+pub fn find_samples(
+    files: &[(PathBuf, Option<Duration>)],
+    max_duration: Duration,
+) -> BTreeMap<PathBuf, String> {
+    let long_duration = max_duration * LONG_SIBLING_FACTOR;
+    let mut samples = BTreeMap::new();
+
+    for (file, duration) in files {
+        if duration.is_some_and(|duration| duration >= max_duration) {
+            continue;
+        }
+        if has_sample_name(file) {
+            samples.insert(file.clone(), "sample: named like a sample".to_string());
+            continue;
+        }
+
+        let Some(duration) = duration else {
+            continue;
+        };
+        let longest_sibling = files
+            .iter()
+            .filter(|(other, _)| other != file && other.parent() == file.parent())
+            .filter_map(|(_, duration)| *duration)
+            .max()
+            .filter(|longest| *longest >= long_duration);
+        if let Some(longest) = longest_sibling {
+            samples.insert(
+                file.clone(),
+                format!(
+                    "sample: runs {} while another file in the folder runs {}",
+                    format_age(Duration::from_secs(duration.as_secs())),
+                    format_age(Duration::from_secs(longest.as_secs() / 60 * 60))
+                ),
+            );
+        }
+    }
+
+    samples
+}
@@ -0,0 +1,223 @@
+use super::policy::FileContext;
+use crate::config::NoMatchPolicy;
+use crate::models::{StreamDecisions, StreamInfo, StreamType};
+
+/// Problems that make the planned output obviously wrong for this file: no
+/// video left, no audio left, or no subtitles left while the kept audio is in a
+/// language the user doesn't list for subtitles (foreign audio without
+/// subtitles). Empty when the output looks sane.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::analyzer::decide_streams;
+/// use mkv_slimmer::core::sanity::degenerate_output_problems;
+/// use mkv_slimmer::core::{FileContext, LanguagePolicy};
+/// use mkv_slimmer::models::{MediaKind, StreamInfo, StreamType};
+/// use std::path::Path;
+///
+/// let mut audio = StreamInfo::new(1, StreamType::Audio);
+/// audio.language = Some("jpn".to_string());
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), audio];
+///
+/// let mut config = Config::default();
+/// config.audio.keep_languages = vec!["eng".to_string()];
+/// let context = FileContext {
+///     source_file: Path::new("movie.mkv"),
+///     streams: &streams,
+///     media_kind: MediaKind::Video,
+///     config: &config,
+///     import_context: None,
+/// };
+///
+/// let decisions = decide_streams(&context, &LanguagePolicy);
+/// let problems = degenerate_output_problems(&context, &decisions);
+/// assert_eq!(problems, vec!["all 1 audio track(s) would be removed (jpn)".to_string()]);
+/// ```
+pub fn degenerate_output_problems(
+    context: &FileContext,
+    decisions: &StreamDecisions,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let is_kept = |stream: &&StreamInfo| decisions.get(stream.index).is_some_and(|d| d.is_kept());
+    let of_type = |stream_type: StreamType| -> (Vec<&StreamInfo>, Vec<&StreamInfo>) {
+        context
+            .streams
+            .iter()
+            .filter(|s| s.container_type() == stream_type)
+            .partition(is_kept)
+    };
+
+    let (kept_video, removed_video) = of_type(StreamType::Video);
+    if kept_video.is_empty() && !removed_video.is_empty() {
+        problems.push(format!(
+            "all {} video track(s) would be removed",
+            removed_video.len()
+        ));
+    }
+
+    let (kept_audio, removed_audio) = of_type(StreamType::Audio);
+    if context.media_kind.filters_audio() && kept_audio.is_empty() && !removed_audio.is_empty() {
+        problems.push(format!(
+            "all {} audio track(s) would be removed ({})",
+            removed_audio.len(),
+            languages(&removed_audio).join(", ")
+        ));
+    }
+
+    let (kept_subtitles, removed_subtitles) = of_type(StreamType::Subtitle);
+    if context.media_kind.filters_subtitles()
+        && kept_subtitles.is_empty()
+        && !removed_subtitles.is_empty()
+    {
+        let subtitle_languages = &context.config.subtitles.keep_languages;
+        let unsubtitled: Vec<&str> = languages(&kept_audio)
+            .into_iter()
+            .filter(|lang| *lang != "und")
+            .filter(|lang| !subtitle_languages.iter().any(|pref| pref.language == *lang))
+            .collect();
+        if !unsubtitled.is_empty() {
+            problems.push(format!(
+                "all {} subtitle track(s) would be removed, but the kept audio ({}) isn't in the subtitle languages",
+                removed_subtitles.len(),
+                unsubtitled.join(", ")
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Distinct languages of `streams`, in stream order
+fn languages<'a>(streams: &[&'a StreamInfo]) -> Vec<&'a str> {
+    let mut languages = Vec::new();
+    for stream in streams {
+        let lang = stream.effective_language();
+        if !languages.contains(&lang) {
+            languages.push(lang);
+        }
+    }
+    languages
+}
+
+/// Gaps in the analysis that processing would otherwise paper over: streams of
+/// Unknown type and audio tracks without a language tag. `processing.strict`
+/// fails the file when this isn't empty.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::sanity::incomplete_analysis_problems;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let mut tagged = StreamInfo::new(1, StreamType::Audio);
+/// tagged.language = Some("eng".to_string());
+/// let streams = vec![
+///     StreamInfo::new(0, StreamType::Video),
+///     tagged,
+///     StreamInfo::new(2, StreamType::Audio),
+///     StreamInfo::new(3, StreamType::Unknown),
+/// ];
+///
+/// assert_eq!(
+///     incomplete_analysis_problems(&streams),
+///     vec![
+///         "stream #2 is audio without a language tag".to_string(),
+///         "stream #3 has an unknown type".to_string(),
+///     ]
+/// );
+/// ```
+pub fn incomplete_analysis_problems(streams: &[StreamInfo]) -> Vec<String> {
+    streams
+        .iter()
+        .filter_map(|stream| match stream.stream_type {
+            StreamType::Unknown => Some(format!("stream #{} has an unknown type", stream.index)),
+            StreamType::Audio if stream.effective_language() == "und" => Some(format!(
+                "stream #{} is audio without a language tag",
+                stream.index
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Stream types whose `no_match_policy` is `fail` and of which nothing
+/// matched the preferences. Checked alongside `degenerate_output_problems`.
+/// Forced subtitles kept by `subtitles.keep_forced` aren't a match.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::{Config, NoMatchPolicy, SubtitlePreference};
+/// use mkv_slimmer::core::analyzer::decide_streams;
+/// use mkv_slimmer::core::sanity::no_match_failures;
+/// use mkv_slimmer::core::{FileContext, LanguagePolicy};
+/// use mkv_slimmer::models::{MediaKind, StreamInfo, StreamType};
+/// use std::path::Path;
+///
+/// let mut audio = StreamInfo::new(1, StreamType::Audio);
+/// audio.language = Some("eng".to_string());
+/// let mut forced = StreamInfo::new(2, StreamType::Subtitle);
+/// forced.language = Some("fre".to_string());
+/// forced.forced = true;
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), audio, forced];
+///
+/// let mut config = Config::default();
+/// config.audio.keep_languages = vec!["eng".to_string()];
+/// config.subtitles.keep_languages = vec![SubtitlePreference::parse("eng").unwrap()];
+/// config.subtitles.keep_forced = true;
+/// config.subtitles.no_match_policy = NoMatchPolicy::Fail;
+/// let context = FileContext {
+///     source_file: Path::new("movie.mkv"),
+///     streams: &streams,
+///     media_kind: MediaKind::Video,
+///     config: &config,
+///     import_context: None,
+/// };
+///
+/// // The forced French subtitle is kept, but no subtitle matched
+/// let decisions = decide_streams(&context, &LanguagePolicy);
+/// assert!(decisions.get(2).unwrap().is_kept());
+/// assert_eq!(
+///     no_match_failures(&context, &decisions),
+///     vec!["none of the 1 subtitle track(s) match the preferences (no_match_policy: fail)".to_string()]
+/// );
+/// ```
+pub fn no_match_failures(context: &FileContext, decisions: &StreamDecisions) -> Vec<String> {
+    let checks = [
+        (
+            StreamType::Audio,
+            "audio",
+            context.media_kind.filters_audio(),
+            context.config.audio.no_match_policy,
+        ),
+        (
+            StreamType::Subtitle,
+            "subtitle",
+            context.media_kind.filters_subtitles(),
+            context.config.subtitles.no_match_policy,
+        ),
+    ];
+
+    checks
+        .into_iter()
+        .filter(|(_, _, filtered, policy)| *filtered && *policy == NoMatchPolicy::Fail)
+        .filter_map(|(stream_type, label, _, _)| {
+            let of_type: Vec<&StreamInfo> = context
+                .streams
+                .iter()
+                .filter(|s| s.stream_type == stream_type)
+                .collect();
+            let none_kept = !of_type.iter().any(|s| {
+                decisions
+                    .get(s.index)
+                    .is_some_and(|d| d.satisfies_no_match_policy())
+            });
+            (!of_type.is_empty() && none_kept).then(|| {
+                format!(
+                    "none of the {} {} track(s) match the preferences (no_match_policy: fail)",
+                    of_type.len(),
+                    label
+                )
+            })
+        })
+        .collect()
+}
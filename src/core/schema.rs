@@ -0,0 +1,116 @@
+use anyhow::Result;
+use schemars::schema_for;
+use std::str::FromStr;
+
+use crate::core::audit::UntaggedReport;
+use crate::core::removed::RemovedManifestFile;
+use crate::error::config_error;
+use crate::models::{RunSummary, ScanManifest, Versioned};
+use crate::utils::console::ConsoleLine;
+
+/// JSON document whose schema the `schema` subcommand exports
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::schema::SchemaDocument;
+/// use mkv_slimmer::models::SCHEMA_VERSION;
+///
+/// let document: SchemaDocument = "result".parse().unwrap();
+/// let schema = document.schema();
+/// assert_eq!(schema["properties"]["schema_version"]["type"], "integer");
+/// assert!(schema["properties"]["failures"].is_object());
+/// assert_eq!(schema["x-schema-version"], SCHEMA_VERSION);
+/// assert!("plan".parse::<SchemaDocument>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDocument {
+    /// The `--result-json` summary of a run
+    Result,
+    /// A line of `--console json` output
+    Events,
+    /// The `audit untagged --output` report
+    AuditUntagged,
+    /// The manifest written by `scan`
+    Manifest,
+    /// The `<output>.removed.json` sidecar of `removed_manifest: sidecar`
+    Removed,
+}
+
+impl SchemaDocument {
+    pub const ALL: [SchemaDocument; 5] = [
+        SchemaDocument::Result,
+        SchemaDocument::Events,
+        SchemaDocument::AuditUntagged,
+        SchemaDocument::Manifest,
+        SchemaDocument::Removed,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaDocument::Result => "result",
+            SchemaDocument::Events => "events",
+            SchemaDocument::AuditUntagged => "audit-untagged",
+            SchemaDocument::Manifest => "manifest",
+            SchemaDocument::Removed => "removed",
+        }
+    }
+
+    /// What the document is, as the description of its schema
+    pub fn description(&self) -> &'static str {
+        match self {
+            SchemaDocument::Result => "Summary of a processing run, written by --result-json",
+            SchemaDocument::Events => "A message of --console json output, one per line",
+            SchemaDocument::AuditUntagged => {
+                "Untagged audio/subtitle tracks, written by audit untagged --output"
+            }
+            SchemaDocument::Manifest => {
+                "Files found by scan, with their analysis, read by process --manifest"
+            }
+            SchemaDocument::Removed => {
+                "Tracks a remux left out, written next to the output by --removed-manifest sidecar"
+            }
+        }
+    }
+
+    /// JSON Schema of the document as currently written, tagged with
+    /// `x-schema-version`
+    pub fn schema(&self) -> serde_json::Value {
+        let schema = match self {
+            SchemaDocument::Result => schema_for!(Versioned<RunSummary>),
+            SchemaDocument::Events => schema_for!(Versioned<ConsoleLine<'static>>),
+            SchemaDocument::AuditUntagged => schema_for!(Versioned<UntaggedReport>),
+            SchemaDocument::Manifest => schema_for!(Versioned<ScanManifest>),
+            SchemaDocument::Removed => schema_for!(Versioned<RemovedManifestFile>),
+        };
+        let mut schema = schema.to_value();
+        if let Some(object) = schema.as_object_mut() {
+            object.insert(
+                "title".to_string(),
+                format!("mkv-slimmer {}", self.name()).into(),
+            );
+            object.insert("description".to_string(), self.description().into());
+            object.insert(
+                "x-schema-version".to_string(),
+                crate::models::SCHEMA_VERSION.into(),
+            );
+        }
+        schema
+    }
+}
+
+impl FromStr for SchemaDocument {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        SchemaDocument::ALL
+            .into_iter()
+            .find(|document| document.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = SchemaDocument::ALL.iter().map(|d| d.name()).collect();
+                config_error(
+                    "Schema",
+                    &format!("Unknown document '{}'. Use one of: {}", s, names.join(", ")),
+                )
+            })
+    }
+}
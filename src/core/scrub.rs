@@ -0,0 +1,50 @@
+use anyhow::Result;
+use std::path::Path;
+use std::process::Command;
+
+use super::mkvtoolnix::{MkvmergeCommand, run_tool};
+use crate::report_warn;
+use crate::utils::tool_available;
+
+/// Muxing/writing application recorded in scrubbed outputs instead of the
+/// mkvmerge/libebml version strings of the machine that did the remux
+pub const SCRUBBED_APPLICATION: &str = "mkv-slimmer";
+
+/// Add the mkvmerge options that keep processing metadata out of the output:
+/// no "date written", no statistics tags (they carry a writing date and app),
+/// and segment/track UIDs derived from the output filename instead of random
+/// ones, so reruns produce identical files
+pub fn add_scrub_args(cmd: &mut MkvmergeCommand, output_path: &Path) {
+    let seed = output_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| SCRUBBED_APPLICATION.to_string());
+
+    cmd.arg("--no-date")
+        .arg("--disable-track-statistics-tags")
+        .arg("--deterministic")
+        .arg(seed);
+}
+
+/// Overwrite the MUXING_APP/WRITING_APP strings of a written output in place
+/// (mkvpropedit), since mkvmerge always records its own version there
+pub fn scrub_application_info(output_path: &Path) -> Result<()> {
+    // The remux already happened; don't fail it over the application strings
+    if !tool_available("mkvpropedit") {
+        report_warn!(
+            "⚠️  mkvpropedit not found - muxing/writing application of {} left as mkvmerge wrote it",
+            output_path.display()
+        );
+        return Ok(());
+    }
+
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(output_path)
+        .arg("--edit")
+        .arg("info")
+        .arg("--set")
+        .arg(format!("muxing-application={}", SCRUBBED_APPLICATION))
+        .arg("--set")
+        .arg(format!("writing-application={}", SCRUBBED_APPLICATION));
+    run_tool(&mut cmd, output_path, "Metadata scrub")
+}
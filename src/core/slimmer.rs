@@ -0,0 +1,309 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+use super::analyzer::{
+    analyze_mkv_streams, analyze_segment_linking, check_import_languages, resolve_track_ids,
+};
+use super::chapters::chapter_count;
+use super::external::add_companion_audio;
+use super::policy::{LanguagePolicy, StreamPolicy};
+use super::processor::{handle_non_mkv_file, process_task};
+use super::progress::{ProcessControl, ProgressEvent};
+use crate::config::Config;
+use crate::models::{ImportContext, ProcessingOutcome, ProcessingTask};
+use crate::report;
+use crate::utils::{is_valid_mkv_file, output_import_markers, validate_source_target_paths};
+
+/// Hooks invoked around the processing of a file
+///
+/// Both methods default to doing nothing. Returning an error from
+/// `before_process` aborts the run before anything is written.
+pub trait ProcessingHooks: Send + Sync {
+    /// Called after analysis, before the file is remuxed or transferred
+    fn before_process(&self, _task: &ProcessingTask) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once the output has been written (or simulated in dry-run mode)
+    fn after_process(&self, _task: &ProcessingTask, _outcome: &ProcessingOutcome) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Where the output of a `Slimmer` run is placed
+#[derive(Debug, Clone)]
+enum Target {
+    /// Output keeps the source filename inside this directory
+    Directory(PathBuf),
+    /// Output is written to exactly this path
+    File(PathBuf),
+    /// Output replaces the source
+    InPlace,
+}
+
+/// Builder for a `Slimmer` run, see `Slimmer::builder()`
+#[derive(Default)]
+pub struct SlimmerBuilder {
+    source: Option<PathBuf>,
+    target: Option<Target>,
+    config: Option<Config>,
+    import_context: Option<ImportContext>,
+    hooks: Option<Box<dyn ProcessingHooks>>,
+    policy: Option<Box<dyn StreamPolicy>>,
+    control: ProcessControl,
+    display_streams: bool,
+}
+
+impl SlimmerBuilder {
+    /// MKV file to process
+    pub fn source(mut self, source: impl Into<PathBuf>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Directory the output is written to, keeping the source filename
+    pub fn target_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.target = Some(Target::Directory(directory.into()));
+        self
+    }
+
+    /// Exact path of the output file
+    pub fn target_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.target = Some(Target::File(file.into()));
+        self
+    }
+
+    /// Replace the source with the output instead of writing it elsewhere
+    pub fn in_place(mut self) -> Self {
+        self.target = Some(Target::InPlace);
+        self
+    }
+
+    /// Configuration to use (defaults to `Config::default()`)
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Sonarr or Radarr context controlling transfer mode and `[MoveStatus]` output
+    pub fn import_context(mut self, import_context: impl Into<ImportContext>) -> Self {
+        self.import_context = Some(import_context.into());
+        self
+    }
+
+    /// Hooks invoked before and after processing
+    pub fn hooks(mut self, hooks: impl ProcessingHooks + 'static) -> Self {
+        self.hooks = Some(Box::new(hooks));
+        self
+    }
+
+    /// Policy deciding which streams are kept (defaults to `LanguagePolicy`)
+    pub fn policy(mut self, policy: impl StreamPolicy + 'static) -> Self {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Callback receiving `ProgressEvent`s as processing advances
+    pub fn on_progress(mut self, callback: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        self.control = self.control.with_progress(callback);
+        self
+    }
+
+    /// Token that aborts the run when cancelled; a running mkvmerge is killed
+    /// and its partial output removed
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.control = self.control.with_cancellation(token);
+        self
+    }
+
+    /// Print the stream tables before processing (off by default)
+    pub fn display_streams(mut self, display_streams: bool) -> Self {
+        self.display_streams = display_streams;
+        self
+    }
+
+    /// Validate the builder and create the `Slimmer`
+    pub fn build(self) -> Result<Slimmer> {
+        let source = self
+            .source
+            .context("Slimmer requires a source file - call .source() on the builder")?;
+        let target = self.target.context(
+            "Slimmer requires a target - call .target_directory(), .target_file() or .in_place() on the builder",
+        )?;
+
+        Ok(Slimmer {
+            source,
+            target,
+            config: self.config.unwrap_or_default(),
+            import_context: self.import_context,
+            hooks: self.hooks,
+            policy: self.policy.unwrap_or_else(|| Box::new(LanguagePolicy)),
+            control: self.control,
+            display_streams: self.display_streams,
+        })
+    }
+}
+
+/// Programmatic entry point for processing a single MKV file
+///
+/// Wraps path validation, analysis, stream selection and output writing in
+/// the same way the CLI does, yielding a typed `ProcessingOutcome`.
+///
+/// # Examples
+/// ```rust,no_run
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::Slimmer;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let outcome = Slimmer::builder()
+///     .source("/downloads/movie.mkv")
+///     .target_directory("/movies")
+///     .config(Config::default())
+///     .on_progress(|event| println!("{:?}", event))
+///     .build()?
+///     .run()
+///     .await?;
+/// for path in outcome.output_paths() {
+///     println!("Wrote {}", path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Slimmer {
+    source: PathBuf,
+    target: Target,
+    config: Config,
+    import_context: Option<ImportContext>,
+    hooks: Option<Box<dyn ProcessingHooks>>,
+    policy: Box<dyn StreamPolicy>,
+    control: ProcessControl,
+    display_streams: bool,
+}
+
+impl Slimmer {
+    /// Start configuring a new run
+    pub fn builder() -> SlimmerBuilder {
+        SlimmerBuilder::default()
+    }
+
+    /// Configuration used by this run
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Analyze and process the source file
+    pub async fn run(&self) -> Result<ProcessingOutcome> {
+        let (target_directory, output_filename) = match &self.target {
+            Target::Directory(directory) => (directory.clone(), None),
+            Target::File(file) => {
+                let parent_dir = file
+                    .parent()
+                    .context("Could not determine parent directory from target file path")?;
+                let filename = file
+                    .file_name()
+                    .context("Could not extract filename from target path")?
+                    .to_string_lossy()
+                    .to_string();
+                (parent_dir.to_path_buf(), Some(filename))
+            }
+            Target::InPlace => {
+                let source_dir = self
+                    .source
+                    .parent()
+                    .context("Could not determine source directory")?;
+                let filename = self
+                    .source
+                    .file_name()
+                    .context("Could not extract filename from source path")?
+                    .to_string_lossy()
+                    .to_string();
+                (source_dir.to_path_buf(), Some(filename))
+            }
+        };
+
+        if !matches!(self.target, Target::InPlace) {
+            // Validate source and target paths are not nested within each other
+            let source_dir = self
+                .source
+                .parent()
+                .context("Could not determine source directory")?;
+            validate_source_target_paths(source_dir, &target_directory)
+                .context("Source and target path validation failed")?;
+        }
+
+        // Check if file is valid MKV - if not, handle immediately
+        if !is_valid_mkv_file(&self.source) {
+            report!(
+                "⚠️  File is not a valid MKV file: {}",
+                self.source.display()
+            );
+            report!("🔄 Falling back to copying original file (no processing needed)");
+
+            self.control.report(ProgressEvent::ProcessingStarted);
+            let outcome = handle_non_mkv_file(
+                &self.source,
+                &target_directory,
+                output_filename,
+                &self.config,
+                self.import_context.as_ref(),
+                &self.control,
+            )
+            .await?;
+            self.control.report(ProgressEvent::Finished);
+            return Ok(outcome);
+        }
+
+        self.control.check_cancelled()?;
+        self.control.report(ProgressEvent::AnalysisStarted);
+        let mut streams = analyze_mkv_streams(&self.source, &self.config)
+            .await
+            .with_context(|| format!("Failed to analyze MKV streams: {}", self.source.display()))?;
+        if let Some(import_context) = &self.import_context {
+            check_import_languages(&mut streams, import_context, &self.config);
+        }
+        if self.config.audio.merge_external {
+            add_companion_audio(&self.source, &mut streams, &self.config).await?;
+        }
+        let track_ids = resolve_track_ids(&self.source, &streams).await;
+        let task = ProcessingTask::new(
+            self.source.clone(),
+            target_directory,
+            streams,
+            output_filename,
+        )
+        .with_segment_linking(analyze_segment_linking(&self.source))
+        .with_chapters(chapter_count(&self.source))
+        .with_track_ids(track_ids);
+
+        if let Some(hooks) = &self.hooks {
+            hooks.before_process(&task)?;
+        }
+
+        self.control.check_cancelled()?;
+        self.control.report(ProgressEvent::ProcessingStarted);
+        let outcome = process_task(
+            task.clone(),
+            &self.config,
+            self.import_context.as_ref(),
+            self.policy.as_ref(),
+            self.display_streams,
+            &self.control,
+        )
+        .await?;
+
+        if self.import_context.is_some()
+            && self.config.processing.import_markers
+            && let Some(decisions) = outcome.decisions()
+        {
+            output_import_markers(&task.streams, decisions);
+        }
+
+        if let Some(hooks) = &self.hooks {
+            hooks.after_process(&task, &outcome)?;
+        }
+        self.control.report(ProgressEvent::Finished);
+
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use super::analyzer::{attachment_removal_fast_path, language_retags};
+use super::titles::title_rewrites;
+use super::up_to_date::flags_differ;
+use crate::config::{Config, ContainerFormat};
+use crate::error::processing_error;
+use crate::models::{ProcessingTask, StreamDecisions};
+use crate::report_warn;
+use crate::utils::format_size;
+
+/// Share of the estimated output size a remux needs free on top of it, for
+/// container overhead (cues, headers) and estimation error
+const REMUX_HEADROOM_PERCENT: u64 = 2;
+
+/// Projected disk usage of a batch on the target filesystem
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpacePlan {
+    /// Projected bytes written for each file, in processing order
+    pub files: Vec<(PathBuf, u64)>,
+    /// Free space available on the target filesystem
+    pub available_bytes: u64,
+}
+
+impl SpacePlan {
+    /// Total projected bytes written by the batch
+    pub fn projected_bytes(&self) -> u64 {
+        self.files.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Returns true if the whole batch fits into the available space
+    pub fn fits(&self) -> bool {
+        self.projected_bytes() <= self.available_bytes
+    }
+
+    /// Split the batch into files that fit the available space and files that
+    /// don't. Files are picked largest first so as much of the space as
+    /// possible is used; both lists keep the processing order.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::core::space::SpacePlan;
+    /// use std::path::PathBuf;
+    ///
+    /// let plan = SpacePlan {
+    ///     files: vec![
+    ///         (PathBuf::from("a.mkv"), 400),
+    ///         (PathBuf::from("b.mkv"), 700),
+    ///         (PathBuf::from("c.mkv"), 300),
+    ///     ],
+    ///     available_bytes: 1000,
+    /// };
+    /// assert!(!plan.fits());
+    ///
+    /// let (fit, skipped) = plan.fit_to_space();
+    /// assert_eq!(fit, vec![PathBuf::from("b.mkv"), PathBuf::from("c.mkv")]);
+    /// assert_eq!(skipped, vec![PathBuf::from("a.mkv")]);
+    /// ```
+    pub fn fit_to_space(&self) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut by_size: Vec<&(PathBuf, u64)> = self.files.iter().collect();
+        by_size.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+        let mut remaining = self.available_bytes;
+        let mut chosen: Vec<&Path> = Vec::new();
+        for (path, size) in by_size {
+            if *size <= remaining {
+                remaining -= size;
+                chosen.push(path);
+            }
+        }
+
+        self.files
+            .iter()
+            .map(|(path, _)| path.clone())
+            .partition(|path| chosen.contains(&path.as_path()))
+    }
+}
+
+/// Free space on the filesystem `path` lives on. The path itself doesn't need
+/// to exist yet; its nearest existing ancestor is checked.
+pub fn available_space(path: &Path) -> Result<u64> {
+    let existing = existing_ancestor(path);
+    fs4::available_space(existing)
+        .with_context(|| format!("Failed to query free space: {}", existing.display()))
+}
+
+/// Bytes processing `task` is expected to write to `target_directory`.
+///
+/// A remux writes the kept streams (external tracks included). An unchanged
+/// file costs nothing when it can be hard linked or renamed within the same
+/// filesystem, and its full size otherwise. Files that only need other flags
+/// are copied before editing, so they cost their full size, as do files that
+/// only lose attachments (deleted with mkvpropedit). A remux whose
+/// removed streams save less than `processing.min_savings` counts as unchanged.
+pub fn projected_output_size(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    target_directory: &Path,
+    config: &Config,
+) -> u64 {
+    let source_size = std::fs::metadata(&task.source_file)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    // Flags-only outputs are edited copies, never hard links
+    if config.processing.flags_only {
+        return source_size;
+    }
+
+    let other_changes = task.streams.iter().any(|s| s.external.is_some())
+        || config.processing.scrub
+        || !language_retags(&task.streams, decisions, config).is_empty()
+        || !title_rewrites(&task.streams, decisions, &config.titles).is_empty()
+        || config.processing.container == ContainerFormat::Webm
+        || !config.attachments.add.is_empty();
+    let needs_remux = decisions.removed_count() > 0 || other_changes;
+
+    let below_min_savings = config
+        .processing
+        .min_savings
+        .zip(decisions.known_removed_bytes())
+        .is_some_and(|(floor, removed)| {
+            !floor.is_met(source_size, source_size.saturating_sub(removed))
+        });
+
+    if !needs_remux || below_min_savings {
+        return if !below_min_savings && flags_differ(&task.streams, decisions, config) {
+            source_size
+        } else if same_filesystem(&task.source_file, target_directory) {
+            0
+        } else {
+            source_size
+        };
+    }
+
+    // Attachments deleted by mkvpropedit are deleted from a full copy
+    if !other_changes && attachment_removal_fast_path(task, decisions, config, None).is_some() {
+        return source_size;
+    }
+
+    estimated_remux_size(task, decisions, source_size)
+}
+
+/// Expected size of a remux of `task`: its kept streams (external tracks
+/// included), or the whole source when their sizes aren't known
+fn estimated_remux_size(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    source_size: u64,
+) -> u64 {
+    let kept_size: u64 = task
+        .streams
+        .iter()
+        .filter(|s| decisions.get(s.index).is_some_and(|d| d.is_kept()))
+        .filter_map(|s| s.size_bytes)
+        .sum();
+    if kept_size > 0 {
+        kept_size
+    } else {
+        source_size
+    }
+}
+
+/// Fail before mkvmerge runs when the filesystem of `output_path` doesn't
+/// have room for the expected output, rather than leaving mkvmerge to stop
+/// midway with a write error. Dry runs and `processing.force` only warn;
+/// when free space can't be queried the check is skipped.
+pub fn check_remux_space(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    output_path: &Path,
+    config: &Config,
+) -> Result<()> {
+    let estimate = estimated_remux_size(task, decisions, source_size(task));
+    let needed = estimate + estimate / 100 * REMUX_HEADROOM_PERCENT;
+    check_free_space(task, needed, output_path, config)
+}
+
+/// Like `check_remux_space()`, for a full copy of the source to
+/// `output_path`
+pub fn check_copy_space(task: &ProcessingTask, output_path: &Path, config: &Config) -> Result<()> {
+    check_free_space(task, source_size(task), output_path, config)
+}
+
+fn source_size(task: &ProcessingTask) -> u64 {
+    std::fs::metadata(&task.source_file)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+/// Fail (or, in dry runs and with `processing.force`, warn) when fewer than
+/// `needed` bytes are free for `output_path`
+fn check_free_space(
+    task: &ProcessingTask,
+    needed: u64,
+    output_path: &Path,
+    config: &Config,
+) -> Result<()> {
+    let directory = output_path.parent().unwrap_or(Path::new("."));
+    let Ok(available) = available_space(directory) else {
+        return Ok(());
+    };
+    if needed <= available {
+        return Ok(());
+    }
+
+    let shortage = format!(
+        "The output needs about {} but only {} is free on the filesystem of {}",
+        format_size(needed),
+        format_size(available),
+        existing_ancestor(directory).display()
+    );
+    if config.processing.dry_run || config.processing.force {
+        report_warn!("⚠️  Free space check: {}", shortage);
+        return Ok(());
+    }
+    Err(processing_error(
+        &task.source_file,
+        "Free space check",
+        &format!(
+            "{}.\n   💡 Free up space on the target, choose another target, or use --force to try anyway",
+            shortage
+        ),
+    ))
+}
+
+/// `path` itself or its nearest ancestor that exists
+pub(crate) fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(Path::new("."))
+}
+
+/// Whether `target` (or, when it doesn't exist yet, the directory it would
+/// be created in) is on the filesystem of `source`
+pub(crate) fn same_filesystem(source: &Path, target: &Path) -> bool {
+    match (
+        std::fs::metadata(source),
+        std::fs::metadata(existing_ancestor(target)),
+    ) {
+        (Ok(source), Ok(target)) => source.dev() == target.dev(),
+        _ => false,
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::history::config_hash;
+use super::mkvtoolnix::run_tool;
+use crate::config::{Config, TagConfig};
+use crate::error::{file_validation_error, processing_error};
+use crate::models::ImportContext;
+use crate::utils::{format_utc_date, require_mkvtoolnix_tool};
+
+/// Default export location: `Movie.mkv` → `Movie.tags.xml` next to the file
+pub fn default_tags_path(file: &Path) -> Result<PathBuf> {
+    let stem = file
+        .file_stem()
+        .context("Could not extract filename from source path")?
+        .to_string_lossy();
+    Ok(file.with_file_name(format!("{}.tags.xml", stem)))
+}
+
+/// Write the global tags of `file` to `output` as Matroska tags XML (mkvextract)
+pub fn export_tags(file: &Path, output: &Path) -> Result<()> {
+    if !file.is_file() {
+        return Err(file_validation_error(file, "File does not exist"));
+    }
+    require_mkvtoolnix_tool("mkvextract")?;
+
+    let mut cmd = Command::new("mkvextract");
+    cmd.arg(file).arg("tags").arg(output);
+    run_tool(&mut cmd, file, "Tag export")?;
+
+    // mkvextract succeeds without writing anything when there are no tags
+    if !output.is_file() {
+        return Err(processing_error(file, "Tag export", "File has no tags"));
+    }
+
+    Ok(())
+}
+
+/// Replace the global tags of `file` in place with those from `tags_xml` (mkvpropedit)
+pub fn import_tags(file: &Path, tags_xml: &Path) -> Result<()> {
+    if !tags_xml.is_file() {
+        return Err(file_validation_error(tags_xml, "Tags file does not exist"));
+    }
+    let mut value = std::ffi::OsString::from("global:");
+    value.push(tags_xml);
+    set_global_tags(file, &value, "Tag import")
+}
+
+/// Remove all global tags of `file` in place (mkvpropedit)
+pub fn strip_tags(file: &Path) -> Result<()> {
+    set_global_tags(file, "global:".as_ref(), "Tag strip")
+}
+
+fn set_global_tags(file: &Path, value: &std::ffi::OsStr, stage: &str) -> Result<()> {
+    if !file.is_file() {
+        return Err(file_validation_error(file, "File does not exist"));
+    }
+    require_mkvtoolnix_tool("mkvpropedit")?;
+
+    let mut cmd = Command::new("mkvpropedit");
+    cmd.arg(file).arg("--tags").arg(value);
+    run_tool(&mut cmd, file, stage)
+}
+
+/// Fill the `{placeholder}`s of the configured tag template from the Sonarr
+/// or Radarr context. Tags referencing a value that isn't available are left out.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::TagConfig;
+/// use mkv_slimmer::core::tags::render_tag_template;
+/// use mkv_slimmer::models::{ImportContext, SonarrContext};
+///
+/// let mut config = TagConfig::default();
+/// config.template.insert("SHOW".to_string(), "{series_title}".to_string());
+/// config.template.insert("SEASON".to_string(), "{season}".to_string());
+///
+/// let context = ImportContext::from(SonarrContext {
+///     series_title: Some("Show".to_string()),
+///     ..Default::default()
+/// });
+/// let tags = render_tag_template(&config, Some(&context));
+/// assert_eq!(tags.get("SHOW").map(String::as_str), Some("Show"));
+/// assert!(!tags.contains_key("SEASON"));
+/// ```
+pub fn render_tag_template(
+    config: &TagConfig,
+    import_context: Option<&ImportContext>,
+) -> BTreeMap<String, String> {
+    config
+        .template
+        .iter()
+        .filter_map(|(name, template)| {
+            render_placeholders(template, import_context).map(|value| (name.clone(), value))
+        })
+        .collect()
+}
+
+/// Fill the `{placeholder}`s of a single template string from the Sonarr
+/// or Radarr context, or None if any of them has no value. `{name:02}`
+/// zero-pads the numbers of a value to two digits.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::tags::render_placeholders;
+/// use mkv_slimmer::models::{ImportContext, SonarrContext};
+///
+/// let context = ImportContext::from(SonarrContext {
+///     series_title: Some("Show".to_string()),
+///     episode_file_season_number: Some("1".to_string()),
+///     episode_file_episode_numbers: Some("2".to_string()),
+///     episode_file_episode_titles: Some("Pilot".to_string()),
+///     ..Default::default()
+/// });
+/// assert_eq!(
+///     render_placeholders("{series_title} – S{season:02}E{episode:02} – {episode_title}", Some(&context))
+///         .as_deref(),
+///     Some("Show – S01E02 – Pilot")
+/// );
+/// assert_eq!(render_placeholders("{air_date}", Some(&context)), None);
+/// assert_eq!(render_placeholders("{series_title}", None), None);
+/// ```
+pub fn render_placeholders(
+    template: &str,
+    import_context: Option<&ImportContext>,
+) -> Option<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start;
+        rendered.push_str(&rest[..start]);
+        match rest[start + 1..end].split_once(':') {
+            Some((name, width)) => {
+                let value = import_context?.template_value(name)?;
+                rendered.push_str(&zero_pad_numbers(value, width.parse().ok()?));
+            }
+            None => rendered.push_str(import_context?.template_value(&rest[start + 1..end])?),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Some(rendered)
+}
+
+/// Pad the comma-separated numbers of a value (`2,3`, Sonarr's episode
+/// numbers of a multi-episode file) with zeros to `width` digits
+fn zero_pad_numbers(value: &str, width: usize) -> String {
+    value
+        .split(',')
+        .map(|part| match part.trim().parse::<u64>() {
+            Ok(number) => format!("{:0width$}", number, width = width),
+            Err(_) => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Global tag names of the mkv-slimmer marker (`processing.marker_tags`)
+pub const MARKER_VERSION_TAG: &str = "MKV_SLIMMER_VERSION";
+pub const MARKER_DATE_TAG: &str = "MKV_SLIMMER_DATE";
+pub const MARKER_CONFIG_TAG: &str = "MKV_SLIMMER_CONFIG";
+
+/// What the marker tags of a remuxed output say about how it was made
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::tags::SlimmerMarker;
+///
+/// let marker = SlimmerMarker {
+///     version: "0.1.0".to_string(),
+///     date: Some("2024-02-29".to_string()),
+///     config_hash: "0123456789abcdef".to_string(),
+/// };
+/// assert_eq!(SlimmerMarker::from_tags(&marker.tags()), Some(marker));
+///
+/// // Without the settings hash there is nothing to compare against
+/// assert_eq!(SlimmerMarker::from_tags(&Default::default()), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlimmerMarker {
+    pub version: String,
+    /// UTC date of the remux; left out of scrubbed outputs
+    pub date: Option<String>,
+    /// `config_hash()` of the settings the output was made with
+    pub config_hash: String,
+}
+
+impl SlimmerMarker {
+    /// Marker for an output made now with `config`
+    pub fn current(config: &Config) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        SlimmerMarker {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            date: (!config.processing.scrub).then(|| format_utc_date(now)),
+            config_hash: config_hash(config),
+        }
+    }
+
+    /// The marker as global tags
+    pub fn tags(&self) -> BTreeMap<String, String> {
+        let mut tags = BTreeMap::new();
+        tags.insert(MARKER_VERSION_TAG.to_string(), self.version.clone());
+        if let Some(date) = &self.date {
+            tags.insert(MARKER_DATE_TAG.to_string(), date.clone());
+        }
+        tags.insert(MARKER_CONFIG_TAG.to_string(), self.config_hash.clone());
+        tags
+    }
+
+    /// Read a marker back from the global tags of a file
+    pub fn from_tags(tags: &BTreeMap<String, String>) -> Option<Self> {
+        Some(SlimmerMarker {
+            version: tags.get(MARKER_VERSION_TAG).cloned().unwrap_or_default(),
+            date: tags.get(MARKER_DATE_TAG).cloned(),
+            config_hash: tags.get(MARKER_CONFIG_TAG)?.clone(),
+        })
+    }
+}
+
+/// Location of the generated tags file used for a remux: `<output>.tags.xml`
+pub fn global_tags_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".tags.xml");
+    PathBuf::from(path)
+}
+
+/// Matroska tags XML holding `tags` as global simple tags
+pub fn global_tags_xml(tags: &BTreeMap<String, String>) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE Tags SYSTEM \"matroskatags.dtd\">\n<Tags>\n  <Tag>\n    <Targets />\n",
+    );
+    for (name, value) in tags {
+        xml.push_str(&format!(
+            "    <Simple>\n      <Name>{}</Name>\n      <String>{}</String>\n    </Simple>\n",
+            escape_xml(name),
+            escape_xml(value)
+        ));
+    }
+    xml.push_str("  </Tag>\n</Tags>\n");
+    xml
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::config_error;
+use crate::models::{StreamDecisions, StreamInfo, StreamType};
+
+/// Placeholders a target template may use
+pub const TARGET_TEMPLATE_TOKENS: [&str; 3] = ["relative_dir", "filename", "lang_summary"];
+
+/// Values filled into a target template for one source file
+#[derive(Debug, Clone, Copy)]
+pub struct TargetPathValues<'a> {
+    /// Source folder relative to the batch input directory (empty when not recursive)
+    pub relative_dir: &'a Path,
+    /// Source file name, extension included
+    pub filename: &'a str,
+    /// Kept audio languages (see `lang_summary()`)
+    pub lang_summary: &'a str,
+}
+
+/// Check that a target template only uses known placeholders and stays
+/// relative to the target directory
+pub fn validate_target_template(template: &str) -> Result<()> {
+    let invalid = |reason: String| config_error("Target template", &reason);
+
+    if template.trim().is_empty() {
+        return Err(invalid("Template is empty".to_string()));
+    }
+    if template.starts_with('/') {
+        return Err(invalid(format!(
+            "'{}' must be relative to the target directory",
+            template
+        )));
+    }
+    if template.split('/').any(|component| component == "..") {
+        return Err(invalid(format!("'{}' must not contain '..'", template)));
+    }
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| end + start)
+            .ok_or_else(|| invalid(format!("Unclosed '{{' in '{}'", template)))?;
+        let token = &rest[start + 1..end];
+        if !TARGET_TEMPLATE_TOKENS.contains(&token) {
+            return Err(invalid(format!(
+                "Unknown placeholder '{{{}}}'. Use {}",
+                token,
+                TARGET_TEMPLATE_TOKENS
+                    .map(|t| format!("{{{}}}", t))
+                    .join(", ")
+            )));
+        }
+        rest = &rest[end + 1..];
+    }
+
+    Ok(())
+}
+
+/// Output path below the target directory for a (validated) template. A
+/// template without `{filename}` names the directory and the source file name
+/// is appended; empty components (e.g. an empty `{relative_dir}`) drop out.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::template::{TargetPathValues, render_target_path};
+/// use std::path::{Path, PathBuf};
+///
+/// let values = TargetPathValues {
+///     relative_dir: Path::new("Show/Season 01"),
+///     filename: "E01.mkv",
+///     lang_summary: "eng+jpn",
+/// };
+/// assert_eq!(
+///     render_target_path("{relative_dir}/slimmed/{filename}", &values).unwrap(),
+///     PathBuf::from("Show/Season 01/slimmed/E01.mkv")
+/// );
+/// assert_eq!(
+///     render_target_path("{lang_summary}", &values).unwrap(),
+///     PathBuf::from("eng+jpn/E01.mkv")
+/// );
+///
+/// let flat = TargetPathValues { relative_dir: Path::new(""), ..values };
+/// assert_eq!(
+///     render_target_path("{relative_dir}/{filename}", &flat).unwrap(),
+///     PathBuf::from("E01.mkv")
+/// );
+/// ```
+pub fn render_target_path(template: &str, values: &TargetPathValues) -> Result<PathBuf> {
+    let relative_dir = values.relative_dir.to_string_lossy();
+    let rendered = template
+        .replace("{relative_dir}", &relative_dir)
+        .replace("{filename}", values.filename)
+        .replace("{lang_summary}", values.lang_summary);
+
+    let mut path = PathBuf::new();
+    for component in rendered.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        path.push(component);
+    }
+    if !template.contains("{filename}") {
+        path.push(values.filename);
+    }
+
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(config_error(
+            "Target template",
+            &format!(
+                "'{}' renders to '{}', which leaves the target directory",
+                template,
+                path.display()
+            ),
+        ));
+    }
+    Ok(path)
+}
+
+/// The directory part of a template that is known before the file is analyzed:
+/// everything up to the first component using `{lang_summary}`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::template::known_template_directory;
+/// use std::path::{Path, PathBuf};
+///
+/// let dir = known_template_directory("slimmed/{relative_dir}/{lang_summary}/{filename}", Path::new("Show"));
+/// assert_eq!(dir, PathBuf::from("slimmed/Show"));
+/// ```
+pub fn known_template_directory(template: &str, relative_dir: &Path) -> PathBuf {
+    let mut components: Vec<&str> = template.split('/').collect();
+    if template.contains("{filename}") {
+        components.pop();
+    }
+
+    let relative_dir = relative_dir.to_string_lossy();
+    let mut path = PathBuf::new();
+    for component in components
+        .into_iter()
+        .take_while(|component| !component.contains("{lang_summary}"))
+    {
+        let rendered = component.replace("{relative_dir}", &relative_dir);
+        for part in rendered.split('/').filter(|c| !c.is_empty() && *c != ".") {
+            path.push(part);
+        }
+    }
+    path
+}
+
+/// Languages of the kept audio tracks in stream order, joined with `+`
+/// (`eng+jpn`); `none` when no audio is kept
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::template::lang_summary;
+/// use mkv_slimmer::models::{
+///     DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamInfo, StreamType,
+/// };
+///
+/// let mut japanese = StreamInfo::new(1, StreamType::Audio);
+/// japanese.language = Some("jpn".to_string());
+/// let streams = vec![StreamInfo::new(0, StreamType::Video), japanese];
+///
+/// let decisions = StreamDecisions {
+///     streams: vec![StreamDecision {
+///         index: 1,
+///         stream_type: StreamType::Audio,
+///         action: StreamAction::Keep,
+///         default: true,
+///         reason: DecisionReason::LanguageMatch,
+///         size_bytes: None,
+///     }],
+///     ..Default::default()
+/// };
+/// assert_eq!(lang_summary(&streams, &decisions), "jpn");
+/// assert_eq!(lang_summary(&streams, &StreamDecisions::default()), "none");
+/// ```
+pub fn lang_summary(streams: &[StreamInfo], decisions: &StreamDecisions) -> String {
+    let mut languages: Vec<&str> = Vec::new();
+    for stream in streams
+        .iter()
+        .filter(|s| s.stream_type == StreamType::Audio)
+        .filter(|s| decisions.get(s.index).is_some_and(|d| d.is_kept()))
+    {
+        let language = stream.effective_language();
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+
+    if languages.is_empty() {
+        "none".to_string()
+    } else {
+        languages.join("+")
+    }
+}
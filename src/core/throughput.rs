@@ -0,0 +1,140 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::config::ThroughputConfig;
+use crate::utils::{WarningKind, record_warning};
+use crate::{report, report_warn};
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// Watches the progress of one remux and flags a rate that stays below
+/// `min_mb_per_sec` for `sustained_secs`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::ThroughputConfig;
+/// use mkv_slimmer::core::throughput::ThroughputMonitor;
+/// use std::time::{Duration, Instant};
+///
+/// let settings = ThroughputConfig::new(10.0);
+/// let start = Instant::now();
+/// // 1 GiB source: 1% is ~10 MiB
+/// let mut monitor = ThroughputMonitor::new(&settings, 1 << 30, start);
+///
+/// // 1% in 10 seconds is ~1 MB/s, but not yet for a minute
+/// assert_eq!(monitor.record_percent(1, start + Duration::from_secs(10)), None);
+/// let rate = monitor.record_percent(7, start + Duration::from_secs(70)).unwrap();
+/// assert!(rate < 10.0);
+/// // Warns once per remux
+/// assert_eq!(monitor.record_percent(8, start + Duration::from_secs(200)), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThroughputMonitor {
+    settings: ThroughputConfig,
+    total_bytes: u64,
+    last_sample: (Instant, u64),
+    slow_since: Option<Instant>,
+    warned: bool,
+}
+
+impl ThroughputMonitor {
+    /// Monitor an operation over `total_bytes` that started at `started`
+    pub fn new(settings: &ThroughputConfig, total_bytes: u64, started: Instant) -> Self {
+        Self {
+            settings: settings.clone(),
+            total_bytes,
+            last_sample: (started, 0),
+            slow_since: None,
+            warned: false,
+        }
+    }
+
+    /// Settings the monitor checks against
+    pub fn settings(&self) -> &ThroughputConfig {
+        &self.settings
+    }
+
+    /// Record progress (0-100) reached at `now`. Returns the rate in MB/s the
+    /// first time it has stayed below the minimum for the sustained period.
+    pub fn record_percent(&mut self, percent: u8, now: Instant) -> Option<f64> {
+        let done = self.total_bytes / 100 * u64::from(percent.min(100));
+        let (last_time, last_done) = self.last_sample;
+        let elapsed = now.saturating_duration_since(last_time);
+        if elapsed.is_zero() {
+            return None;
+        }
+        self.last_sample = (now, done);
+
+        let rate = done.saturating_sub(last_done) as f64 / BYTES_PER_MB / elapsed.as_secs_f64();
+        if rate >= self.settings.min_mb_per_sec {
+            self.slow_since = None;
+            return None;
+        }
+        let slow_since = *self.slow_since.get_or_insert(last_time);
+        let sustained = Duration::from_secs(self.settings.sustained_secs);
+        if self.warned || now.saturating_duration_since(slow_since) < sustained {
+            return None;
+        }
+        self.warned = true;
+        Some(rate)
+    }
+}
+
+/// Rate in MB/s of a finished transfer of `bytes` that took `elapsed`, when
+/// it was below the minimum for at least the sustained period
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::ThroughputConfig;
+/// use mkv_slimmer::core::throughput::slow_transfer_rate;
+/// use std::time::Duration;
+///
+/// let settings = ThroughputConfig::new(50.0);
+/// let mb = 1024 * 1024;
+/// assert!(slow_transfer_rate(&settings, 600 * mb, Duration::from_secs(120)).is_some());
+/// // Too short to count as sustained
+/// assert!(slow_transfer_rate(&settings, 10 * mb, Duration::from_secs(2)).is_none());
+/// ```
+pub fn slow_transfer_rate(
+    settings: &ThroughputConfig,
+    bytes: u64,
+    elapsed: Duration,
+) -> Option<f64> {
+    if elapsed < Duration::from_secs(settings.sustained_secs) {
+        return None;
+    }
+    let rate = bytes as f64 / BYTES_PER_MB / elapsed.as_secs_f64();
+    (rate < settings.min_mb_per_sec).then_some(rate)
+}
+
+/// Print a degraded-storage warning for `operation` on `path` and run the
+/// configured notification command
+pub fn warn_slow_throughput(settings: &ThroughputConfig, operation: &str, path: &Path, rate: f64) {
+    let message = format!(
+        "{} of {} ran at {:.1} MB/s for over {}s (minimum {} MB/s) - storage may be degraded",
+        operation,
+        path.display(),
+        rate,
+        settings.sustained_secs,
+        settings.min_mb_per_sec
+    );
+    report!("🐢 {}", message);
+    record_warning(
+        WarningKind::SlowTransfer,
+        format!("{} ran at {:.1} MB/s", operation, rate),
+    );
+
+    if let Some(command) = &settings.notify_command {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("MKV_SLIMMER_WARNING", &message)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => report_warn!("Warning: Throughput notification exited with {}", status),
+            Err(e) => report_warn!("Warning: Failed to run throughput notification: {}", e),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+
+use crate::config::TitleConfig;
+use crate::error::config_error;
+use crate::models::{StreamDecisions, StreamInfo};
+use crate::utils::language_name;
+
+/// Placeholders a track title template may use
+pub const TITLE_PLACEHOLDERS: [&str; 5] =
+    ["language", "language_name", "channels", "codec", "title"];
+
+/// Check that a track title template only uses known placeholders
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::titles::validate_title_template;
+///
+/// assert!(validate_title_template("{language_name} {channels}ch {codec}").is_ok());
+/// assert!(validate_title_template("").is_ok());
+/// assert!(validate_title_template("{release_group}").is_err());
+/// ```
+pub fn validate_title_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| end + start) else {
+            return Err(config_error(
+                "Track titles",
+                &format!("Unclosed '{{' in '{}'", template),
+            ));
+        };
+        let placeholder = &rest[start + 1..end];
+        if !TITLE_PLACEHOLDERS.contains(&placeholder) {
+            return Err(config_error(
+                "Track titles",
+                &format!(
+                    "Unknown placeholder '{{{}}}' in '{}'. Use one of: {}",
+                    placeholder,
+                    template,
+                    TITLE_PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                ),
+            ));
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+/// Title `template` filled in for `stream`, or None if one of its
+/// placeholders has no value for it (e.g. `{channels}` of a subtitle)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::titles::render_title;
+/// use mkv_slimmer::models::{StreamInfo, StreamType};
+///
+/// let mut stream = StreamInfo::new(1, StreamType::Audio);
+/// stream.codec = "eac3".to_string();
+/// stream.language = Some("ger".to_string());
+/// stream.channels = Some(6);
+/// stream.title = Some("GER DL 5.1 by RlsGrp".to_string());
+///
+/// assert_eq!(
+///     render_title("{language_name} {channels}ch {codec}", &stream).as_deref(),
+///     Some("German 6ch EAC3")
+/// );
+/// assert_eq!(render_title("{language}", &stream).as_deref(), Some("ger"));
+/// stream.channels = None;
+/// assert_eq!(render_title("{channels}ch", &stream), None);
+/// ```
+pub fn render_title(template: &str, stream: &StreamInfo) -> Option<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start;
+        rendered.push_str(&rest[..start]);
+        let value = match &rest[start + 1..end] {
+            "language" => stream.effective_language().to_string(),
+            "language_name" => language_name(stream.effective_language())?.to_string(),
+            "channels" => stream.channels?.to_string(),
+            "codec" => stream.codec.to_uppercase(),
+            "title" => stream.title.clone()?,
+            _ => return None,
+        };
+        rendered.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Some(rendered.trim().to_string())
+}
+
+/// Kept streams whose title `config` changes, with the new title (empty to
+/// clear it)
+pub fn title_rewrites(
+    streams: &[StreamInfo],
+    decisions: &StreamDecisions,
+    config: &TitleConfig,
+) -> Vec<(u32, String)> {
+    streams
+        .iter()
+        .filter(|s| decisions.get(s.index).is_some_and(|d| d.is_kept()))
+        .filter_map(|s| {
+            let title = render_title(config.template(s.container_type())?, s)?;
+            (s.title.as_deref().unwrap_or("") != title).then_some((s.index, title))
+        })
+        .collect()
+}
+
+/// Whether a track of an already processed output still has a title `config`
+/// would rewrite. Templates using `{title}` build on the existing title, so
+/// they can't be checked this way and are skipped.
+pub fn has_stale_titles(output_streams: &[StreamInfo], config: &TitleConfig) -> bool {
+    output_streams.iter().any(|s| {
+        config
+            .template(s.container_type())
+            .filter(|template| !template.contains("{title}"))
+            .and_then(|template| render_title(template, s))
+            .is_some_and(|title| s.title.as_deref().unwrap_or("") != title)
+    })
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use super::analyzer::analyze_mkv_streams;
+use super::chapters::chapter_count;
+use super::policy::keeps_forced_flag;
+use super::titles::has_stale_titles;
+use crate::config::{ChapterMode, Config};
+use crate::models::{LanguageSource, StreamDecisions, StreamInfo, StreamType};
+
+/// The properties of an output track that a rerun would reproduce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackSignature {
+    pub stream_type: StreamType,
+    pub codec: String,
+    /// None when the output keeps whatever tag the source had, which isn't
+    /// known once a language was inferred or overridden
+    pub language: Option<String>,
+    pub default: bool,
+    pub forced: bool,
+}
+
+impl TrackSignature {
+    /// Whether `actual` (read from an existing output) matches this expectation
+    pub fn matches(&self, actual: &TrackSignature) -> bool {
+        self.stream_type == actual.stream_type
+            && self.codec == actual.codec
+            && self
+                .language
+                .as_ref()
+                .is_none_or(|language| actual.language.as_ref() == Some(language))
+            && self.default == actual.default
+            && self.forced == actual.forced
+    }
+}
+
+/// Video, audio and subtitle tracks a remux with `decisions` writes, in output
+/// order. Audio and subtitle flags follow the decisions (forced is cleared
+/// unless `subtitles.keep_forced` keeps it); video tracks keep the source's flags.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::up_to_date::expected_tracks;
+/// use mkv_slimmer::models::{
+///     DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamInfo, StreamType,
+/// };
+///
+/// let mut audio = StreamInfo::new(1, StreamType::Audio);
+/// audio.language = Some("jpn".to_string());
+/// audio.forced = true;
+/// let streams = vec![audio, StreamInfo::new(2, StreamType::Subtitle)];
+///
+/// let decisions = StreamDecisions {
+///     streams: vec![StreamDecision {
+///         index: 1,
+///         stream_type: StreamType::Audio,
+///         action: StreamAction::Keep,
+///         default: true,
+///         reason: DecisionReason::LanguageMatch,
+///         size_bytes: None,
+///     }],
+///     ..Default::default()
+/// };
+/// let tracks = expected_tracks(&streams, &decisions, &Config::default());
+/// assert_eq!(tracks.len(), 1);
+/// assert_eq!(tracks[0].language.as_deref(), Some("jpn"));
+/// assert!(tracks[0].default && !tracks[0].forced);
+/// ```
+pub fn expected_tracks(
+    streams: &[StreamInfo],
+    decisions: &StreamDecisions,
+    config: &Config,
+) -> Vec<TrackSignature> {
+    streams
+        .iter()
+        .filter(|s| is_track(s.container_type()))
+        .filter_map(|stream| {
+            let decision = decisions.get(stream.index).filter(|d| d.is_kept())?;
+            let language_written = stream.language_source == LanguageSource::Tag
+                || stream.external.is_some()
+                || config.processing.tag_languages;
+            let (default, forced) = match stream.stream_type {
+                StreamType::Video => (stream.default, stream.forced),
+                _ => (decision.default, keeps_forced_flag(stream, config)),
+            };
+            Some(TrackSignature {
+                stream_type: stream.stream_type,
+                codec: stream.codec.clone(),
+                language: language_written.then(|| stream.effective_language().to_string()),
+                default,
+                forced,
+            })
+        })
+        .collect()
+}
+
+/// Signatures of the video, audio and subtitle tracks of an analyzed file
+pub fn actual_tracks(streams: &[StreamInfo]) -> Vec<TrackSignature> {
+    streams
+        .iter()
+        .filter(|s| is_track(s.container_type()))
+        .map(|stream| TrackSignature {
+            stream_type: stream.stream_type,
+            codec: stream.codec.clone(),
+            language: Some(stream.effective_language().to_string()),
+            default: stream.default,
+            forced: stream.forced,
+        })
+        .collect()
+}
+
+/// Whether keeping every track of `streams` with `decisions` still changes
+/// the default/forced flag of an audio or subtitle track, which mkvpropedit
+/// can do without a remux
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::config::Config;
+/// use mkv_slimmer::core::up_to_date::flags_differ;
+/// use mkv_slimmer::models::{
+///     DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamInfo, StreamType,
+/// };
+///
+/// let streams = vec![StreamInfo::new(0, StreamType::Audio)];
+/// let mut decisions = StreamDecisions {
+///     streams: vec![StreamDecision {
+///         index: 0,
+///         stream_type: StreamType::Audio,
+///         action: StreamAction::Keep,
+///         default: true,
+///         reason: DecisionReason::LanguageMatch,
+///         size_bytes: None,
+///     }],
+///     ..Default::default()
+/// };
+/// assert!(flags_differ(&streams, &decisions, &Config::default()));
+///
+/// decisions.streams[0].default = false;
+/// assert!(!flags_differ(&streams, &decisions, &Config::default()));
+/// ```
+pub fn flags_differ(streams: &[StreamInfo], decisions: &StreamDecisions, config: &Config) -> bool {
+    expected_tracks(streams, decisions, config)
+        .iter()
+        .zip(actual_tracks(streams))
+        .any(|(expected, actual)| {
+            expected.default != actual.default || expected.forced != actual.forced
+        })
+}
+
+/// Whether `output_path` already holds what remuxing `streams` with
+/// `decisions` would write: the same kept tracks, languages and flags. A
+/// missing or unreadable output is never up to date.
+pub async fn output_is_up_to_date(
+    output_path: &Path,
+    streams: &[StreamInfo],
+    decisions: &StreamDecisions,
+    config: &Config,
+) -> bool {
+    if !output_path.is_file() {
+        return false;
+    }
+    // Chapters left behind from before `processing.chapters: strip`
+    if config.processing.chapters == ChapterMode::Strip
+        && chapter_count(output_path).is_some_and(|count| count > 0)
+    {
+        return false;
+    }
+
+    // Read the output as it is: inference and overrides describe the source
+    let mut output_config = config.clone();
+    output_config.processing.infer_languages = false;
+    output_config.processing.language_overrides.clear();
+    output_config.processing.strict = false;
+    let Ok(output_streams) = analyze_mkv_streams(output_path, &output_config).await else {
+        return false;
+    };
+    if has_stale_titles(&output_streams, &config.titles) {
+        return false;
+    }
+
+    let expected = expected_tracks(streams, decisions, config);
+    let actual = actual_tracks(&output_streams);
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(&actual)
+            .all(|(expected, actual)| expected.matches(actual))
+}
+
+fn is_track(stream_type: StreamType) -> bool {
+    matches!(
+        stream_type,
+        StreamType::Video | StreamType::Audio | StreamType::Subtitle
+    )
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use super::analyzer::analyze_mkv_streams;
+use super::samples::media_duration;
+use crate::config::Config;
+use crate::models::{ProcessingTask, StreamDecisions, StreamInfo, StreamType};
+use crate::report_warn;
+use crate::utils::{WarningKind, record_warning};
+
+/// Largest difference between source and output durations that isn't
+/// reported, unless `processing.duration_tolerance_secs` sets another
+pub const DEFAULT_DURATION_TOLERANCE_SECS: f64 = 2.0;
+
+/// A duration of a remuxed output that differs from the source's
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationMismatch {
+    /// What was compared: `container`, `audio track 2`, ...
+    pub what: String,
+    /// Duration in the source, in seconds
+    pub source: f64,
+    /// Duration in the output, in seconds
+    pub output: f64,
+}
+
+impl std::fmt::Display for DurationMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} runs {} in the output but {} in the source ({:+.1}s)",
+            self.what,
+            format_timestamp(self.output),
+            format_timestamp(self.source),
+            self.output - self.source
+        )
+    }
+}
+
+/// Kept source tracks whose duration differs from the matching output track
+/// by more than `tolerance` seconds. Tracks are matched per type in order,
+/// which is how mkvmerge writes them; tracks from external files (which may
+/// be shifted by sync offsets) and tracks without a known duration are
+/// skipped.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::core::verify::track_duration_mismatches;
+/// use mkv_slimmer::models::{
+///     DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamInfo, StreamType,
+/// };
+///
+/// let stream = |index: u32, stream_type: StreamType, seconds: f64| {
+///     let mut stream = StreamInfo::new(index, stream_type);
+///     stream.duration_seconds = Some(seconds);
+///     stream
+/// };
+/// let keep = |index: u32, stream_type: StreamType, action: StreamAction| StreamDecision {
+///     index,
+///     stream_type,
+///     action,
+///     default: false,
+///     reason: DecisionReason::LanguageMatch,
+///     size_bytes: None,
+/// };
+/// let source = vec![
+///     stream(0, StreamType::Video, 2700.0),
+///     stream(1, StreamType::Audio, 2700.0),
+///     stream(2, StreamType::Audio, 2700.0),
+/// ];
+/// let decisions = StreamDecisions {
+///     streams: vec![
+///         keep(0, StreamType::Video, StreamAction::Keep),
+///         keep(1, StreamType::Audio, StreamAction::Remove),
+///         keep(2, StreamType::Audio, StreamAction::Keep),
+///     ],
+///     ..Default::default()
+/// };
+/// // The write stopped 15 minutes into the file
+/// let output = vec![stream(0, StreamType::Video, 2700.4), stream(1, StreamType::Audio, 900.0)];
+///
+/// let mismatches = track_duration_mismatches(&source, &decisions, &output, 2.0);
+/// assert_eq!(mismatches.len(), 1);
+/// assert_eq!(mismatches[0].what, "audio track #2");
+/// assert_eq!(
+///     mismatches[0].to_string(),
+///     "audio track #2 runs 0:15:00.0 in the output but 0:45:00.0 in the source (-1800.0s)"
+/// );
+/// ```
+pub fn track_duration_mismatches(
+    source_streams: &[StreamInfo],
+    decisions: &StreamDecisions,
+    output_streams: &[StreamInfo],
+    tolerance: f64,
+) -> Vec<DurationMismatch> {
+    let mut mismatches = Vec::new();
+    for stream_type in [StreamType::Video, StreamType::Audio, StreamType::Subtitle] {
+        let kept = source_streams.iter().filter(|s| {
+            s.container_type() == stream_type && decisions.get(s.index).is_some_and(|d| d.is_kept())
+        });
+        let written = output_streams
+            .iter()
+            .filter(|s| s.container_type() == stream_type);
+        for (source, output) in kept.zip(written) {
+            if source.external.is_some() {
+                continue;
+            }
+            let (Some(source_secs), Some(output_secs)) =
+                (source.duration_seconds, output.duration_seconds)
+            else {
+                continue;
+            };
+            if (source_secs - output_secs).abs() > tolerance {
+                mismatches.push(DurationMismatch {
+                    what: format!(
+                        "{} track #{}",
+                        stream_type.to_string().to_lowercase(),
+                        source.index
+                    ),
+                    source: source_secs,
+                    output: output_secs,
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+/// Compare the container and track durations of a freshly remuxed output
+/// with the source's and warn about differences beyond the tolerance,
+/// which usually mean the output was cut short (an I/O error mid-write).
+/// Returns the mismatches found.
+pub async fn verify_output_durations(
+    task: &ProcessingTask,
+    decisions: &StreamDecisions,
+    output_path: &Path,
+    config: &Config,
+) -> Vec<DurationMismatch> {
+    let tolerance = config
+        .processing
+        .duration_tolerance_secs
+        .unwrap_or(DEFAULT_DURATION_TOLERANCE_SECS);
+    let mut mismatches = Vec::new();
+
+    if let (Some(source), Some(output)) = (
+        media_duration(&task.source_file),
+        media_duration(output_path),
+    ) && (source.as_secs_f64() - output.as_secs_f64()).abs() > tolerance
+    {
+        mismatches.push(DurationMismatch {
+            what: "container".to_string(),
+            source: source.as_secs_f64(),
+            output: output.as_secs_f64(),
+        });
+    }
+
+    // Read the output as it is: inference and overrides describe the source
+    let mut output_config = config.clone();
+    output_config.processing.infer_languages = false;
+    output_config.processing.language_overrides.clear();
+    output_config.processing.strict = false;
+    if let Ok(output_streams) = analyze_mkv_streams(output_path, &output_config).await {
+        mismatches.extend(track_duration_mismatches(
+            &task.streams,
+            decisions,
+            &output_streams,
+            tolerance,
+        ));
+    }
+
+    for mismatch in &mismatches {
+        report_warn!("⚠️  Duration check: {}", mismatch);
+        record_warning(WarningKind::DurationMismatch, mismatch.to_string());
+    }
+    mismatches
+}
+
+/// `h:mm:ss.s` for a number of seconds
+fn format_timestamp(seconds: f64) -> String {
+    let tenths = (seconds.max(0.0) * 10.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{}",
+        tenths / 36_000,
+        tenths / 600 % 60,
+        tenths / 10 % 60,
+        tenths % 10
+    )
+}
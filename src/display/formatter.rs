@@ -3,102 +3,147 @@ use colored::*;
 use std::collections::HashMap;
 use tabled::{Table, settings::Style};
 
-use crate::config::Config;
-use crate::models::{StreamInfo, StreamType};
-use crate::utils::format_size;
+use crate::config::ChapterMode;
+use crate::models::{
+    DecisionReason, LanguageSource, MediaKind, StreamDecisions, StreamInfo, StreamType,
+};
+use crate::report;
+use crate::utils::{format_percent, format_size};
 
 use super::tables::{AttachmentStreamRow, AudioStreamRow, SubtitleStreamRow, VideoStreamRow};
 
+/// Add a formatted block to the output of `render()`
+macro_rules! emit_line {
+    ($out:expr, $($arg:tt)*) => {
+        $out.push(format!($($arg)*))
+    };
+}
+
 pub struct StreamDisplayer<'a> {
     streams: &'a [StreamInfo],
-    config: &'a Config,
+    decisions: &'a StreamDecisions,
+    media_kind: MediaKind,
     grouped_streams: HashMap<StreamType, Vec<&'a StreamInfo>>,
+    chapters: Option<usize>,
+    chapter_mode: ChapterMode,
+    dropped_metadata: Vec<&'static str>,
+    title_rewrites: Vec<(u32, String)>,
 }
 
 impl<'a> StreamDisplayer<'a> {
-    pub fn new(streams: &'a [StreamInfo], config: &'a Config) -> Self {
+    pub fn new(
+        streams: &'a [StreamInfo],
+        decisions: &'a StreamDecisions,
+        media_kind: MediaKind,
+    ) -> Self {
         let mut grouped_streams = HashMap::new();
 
+        // Cover art is listed with the video streams, however it was reported
         for stream in streams {
+            let table = if stream.cover_art {
+                StreamType::Video
+            } else {
+                stream.stream_type
+            };
             grouped_streams
-                .entry(stream.stream_type)
+                .entry(table)
                 .or_insert_with(Vec::new)
                 .push(stream);
         }
 
         Self {
             streams,
-            config,
+            decisions,
+            media_kind,
             grouped_streams,
+            chapters: None,
+            chapter_mode: ChapterMode::Keep,
+            dropped_metadata: Vec::new(),
+            title_rewrites: Vec::new(),
         }
     }
 
-    /// Find the preferred default audio stream (returns stream index)
-    /// Uses the first language from keep_languages that exists in the streams
-    fn get_preferred_default_audio_stream(&self) -> Option<u32> {
-        let audio_streams = self.grouped_streams.get(&StreamType::Audio)?;
+    /// Note the source metadata the remux leaves out in the summary
+    /// (`TagConfig::dropped_metadata()`)
+    pub fn with_dropped_metadata(mut self, dropped: Vec<&'static str>) -> Self {
+        self.dropped_metadata = dropped;
+        self
+    }
 
-        for keep_lang in &self.config.audio.keep_languages {
-            for stream in audio_streams {
-                let lang = stream.effective_language();
-                if lang == keep_lang {
-                    return Some(stream.index);
-                }
-            }
-        }
-        None
+    /// List the track titles the remux rewrites in the summary
+    /// (`core::titles::title_rewrites()`)
+    pub fn with_title_rewrites(mut self, rewrites: Vec<(u32, String)>) -> Self {
+        self.title_rewrites = rewrites;
+        self
     }
 
-    /// Find the preferred default subtitle stream (returns stream index)
-    /// Uses the first preference from keep_languages that exists in the streams
-    fn get_preferred_default_subtitle_stream(&self) -> Option<u32> {
-        let subtitle_streams = self.grouped_streams.get(&StreamType::Subtitle)?;
+    /// Show the number of chapters, and whether they're removed, in the
+    /// summary (nothing is shown when the count is unknown)
+    pub fn with_chapters(mut self, chapters: Option<usize>, mode: ChapterMode) -> Self {
+        self.chapters = chapters;
+        self.chapter_mode = mode;
+        self
+    }
 
-        for pref in &self.config.subtitles.keep_languages {
-            for stream in subtitle_streams {
-                let lang = stream.effective_language();
-                if lang == &pref.language && pref.matches_title(stream.title.as_deref()) {
-                    return Some(stream.index);
-                }
-            }
+    /// Print the tables and summary through the console output layer
+    pub fn display(&self) -> Result<()> {
+        for block in self.render() {
+            report!("{}", block);
         }
-        None
+        Ok(())
     }
 
-    pub fn display(&self) -> Result<()> {
+    /// The tables and summary as the blocks `display()` prints, with colors
+    pub fn render(&self) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let out = &mut blocks;
+        if self.media_kind == MediaKind::AudioOnly {
+            emit_line!(
+                out,
+                "\n{}",
+                "🎧 Audio-only container: only audio language rules apply".bold()
+            );
+        } else if self.media_kind == MediaKind::SubtitlesOnly {
+            emit_line!(
+                out,
+                "\n{}",
+                "💬 Subtitle-only container: only subtitle language rules apply".bold()
+            );
+        }
+
         // Display video streams
         if let Some(streams) = self.grouped_streams.get(&StreamType::Video) {
-            self.display_video_streams(streams)?;
+            self.display_video_streams(streams, out);
         }
 
         // Display audio streams
         if let Some(streams) = self.grouped_streams.get(&StreamType::Audio) {
-            self.display_audio_streams(streams)?;
+            self.display_audio_streams(streams, out);
         }
 
         // Display subtitle streams
         if let Some(streams) = self.grouped_streams.get(&StreamType::Subtitle) {
-            self.display_subtitle_streams(streams)?;
+            self.display_subtitle_streams(streams, out);
         }
 
         // Display attachments
         if let Some(streams) = self.grouped_streams.get(&StreamType::Attachment) {
-            self.display_attachment_streams(streams)?;
+            self.display_attachment_streams(streams, out);
         }
 
         // Display summary
-        self.display_summary()?;
+        self.display_summary(out);
 
-        Ok(())
+        blocks
     }
 
-    fn display_video_streams(&self, streams: &[&StreamInfo]) -> Result<()> {
-        println!("\n{}", "🎬 Video Streams:".bold().cyan());
+    fn display_video_streams(&self, streams: &[&StreamInfo], out: &mut Vec<String>) {
+        emit_line!(out, "\n{}", "🎬 Video Streams:".bold().cyan());
 
         let rows: Vec<VideoStreamRow> = streams
             .iter()
             .map(|stream| VideoStreamRow {
-                index: stream.index.to_string(),
+                index: self.format_index(stream),
                 codec: stream.codec.clone(),
                 resolution: stream.resolution.clone().unwrap_or_else(|| "?".to_string()),
                 fps: stream
@@ -109,27 +154,23 @@ impl<'a> StreamDisplayer<'a> {
                     .hdr
                     .map(|h| if h { "Yes" } else { "No" }.to_string())
                     .unwrap_or_else(|| "No".to_string()),
-                size: stream
-                    .size_mb()
-                    .map(|s| format!("{:.1} MB", s))
-                    .unwrap_or_else(|| "?".to_string()),
+                size: self.format_stream_size(stream),
                 status: self.get_stream_status(stream),
             })
             .collect();
 
         let table = Table::new(rows).with(Style::rounded()).to_string();
 
-        println!("{}", table);
-        Ok(())
+        emit_line!(out, "{}", table);
     }
 
-    fn display_audio_streams(&self, streams: &[&StreamInfo]) -> Result<()> {
-        println!("\n{}", "🎵 Audio Streams:".bold().cyan());
+    fn display_audio_streams(&self, streams: &[&StreamInfo], out: &mut Vec<String>) {
+        emit_line!(out, "\n{}", "🎵 Audio Streams:".bold().cyan());
 
         let rows: Vec<AudioStreamRow> = streams
             .iter()
             .map(|stream| AudioStreamRow {
-                index: stream.index.to_string(),
+                index: self.format_index(stream),
                 codec: stream.codec.clone(),
                 language: self.format_language(&stream.language),
                 channels: stream
@@ -140,10 +181,7 @@ impl<'a> StreamDisplayer<'a> {
                     .sample_rate
                     .map(|sr| format!("{} Hz", sr))
                     .unwrap_or_else(|| "?".to_string()),
-                size: stream
-                    .size_mb()
-                    .map(|s| format!("{:.1} MB", s))
-                    .unwrap_or_else(|| "?".to_string()),
+                size: self.format_stream_size(stream),
                 default: if stream.default { "Yes" } else { "No" }.to_string(),
                 status: self.get_stream_status(stream),
             })
@@ -151,17 +189,16 @@ impl<'a> StreamDisplayer<'a> {
 
         let table = Table::new(rows).with(Style::rounded()).to_string();
 
-        println!("{}", table);
-        Ok(())
+        emit_line!(out, "{}", table);
     }
 
-    fn display_subtitle_streams(&self, streams: &[&StreamInfo]) -> Result<()> {
-        println!("\n{}", "📄 Subtitle Streams:".bold().cyan());
+    fn display_subtitle_streams(&self, streams: &[&StreamInfo], out: &mut Vec<String>) {
+        emit_line!(out, "\n{}", "📄 Subtitle Streams:".bold().cyan());
 
         let rows: Vec<SubtitleStreamRow> = streams
             .iter()
             .map(|stream| SubtitleStreamRow {
-                index: stream.index.to_string(),
+                index: self.format_index(stream),
                 format: stream
                     .subtitle_format
                     .clone()
@@ -177,12 +214,11 @@ impl<'a> StreamDisplayer<'a> {
 
         let table = Table::new(rows).with(Style::rounded()).to_string();
 
-        println!("{}", table);
-        Ok(())
+        emit_line!(out, "{}", table);
     }
 
-    fn display_attachment_streams(&self, streams: &[&StreamInfo]) -> Result<()> {
-        println!("\n{}", "📎 Attachments:".bold().cyan());
+    fn display_attachment_streams(&self, streams: &[&StreamInfo], out: &mut Vec<String>) {
+        emit_line!(out, "\n{}", "📎 Attachments:".bold().cyan());
 
         // Group attachments by type for cleaner display
         let mut type_counts: HashMap<String, usize> = HashMap::new();
@@ -193,51 +229,44 @@ impl<'a> StreamDisplayer<'a> {
 
         // If we have many of the same type, show a summary
         if streams.len() > 10 && type_counts.len() < streams.len() {
-            println!("Attachment Summary:");
+            emit_line!(out, "Attachment Summary:");
             for (attachment_type, count) in type_counts {
-                println!("  {} files: {}", attachment_type, count);
+                emit_line!(out, "  {} files: {}", attachment_type, count);
             }
-            println!("\nFirst few attachments:");
+            emit_line!(out, "\nFirst few attachments:");
 
             let limited_streams: Vec<_> = streams.iter().take(5).collect();
             let rows: Vec<AttachmentStreamRow> = limited_streams
                 .iter()
                 .map(|stream| AttachmentStreamRow {
-                    index: stream.index.to_string(),
+                    index: self.format_index(stream),
                     attachment_type: self.get_attachment_type(&stream.codec),
                     title: stream.title.clone().unwrap_or_else(|| "".to_string()),
-                    size: stream
-                        .size_mb()
-                        .map(|s| format!("{:.1} MB", s))
-                        .unwrap_or_else(|| "?".to_string()),
+                    size: self.format_stream_size(stream),
                 })
                 .collect();
 
             let table = Table::new(rows).with(Style::rounded()).to_string();
 
-            println!("{}", table);
+            emit_line!(out, "{}", table);
             if streams.len() > 5 {
-                println!("... and {} more attachments", streams.len() - 5);
+                emit_line!(out, "... and {} more attachments", streams.len() - 5);
             }
         } else {
             let rows: Vec<AttachmentStreamRow> = streams
                 .iter()
                 .map(|stream| AttachmentStreamRow {
-                    index: stream.index.to_string(),
+                    index: self.format_index(stream),
                     attachment_type: self.get_attachment_type(&stream.codec),
                     title: stream.title.clone().unwrap_or_else(|| "".to_string()),
-                    size: stream
-                        .size_mb()
-                        .map(|s| format!("{:.1} MB", s))
-                        .unwrap_or_else(|| "?".to_string()),
+                    size: self.format_stream_size(stream),
                 })
                 .collect();
 
             let table = Table::new(rows).with(Style::rounded()).to_string();
 
-            println!("{}", table);
+            emit_line!(out, "{}", table);
         }
-        Ok(())
     }
 
     fn get_attachment_type(&self, codec: &str) -> String {
@@ -262,66 +291,85 @@ impl<'a> StreamDisplayer<'a> {
     }
 
     fn get_stream_status(&self, stream: &StreamInfo) -> String {
-        match stream.stream_type {
-            StreamType::Video => {
-                // Always keep all video streams
-                "KEEP".green().to_string()
-            }
-            StreamType::Audio => {
-                let lang = stream.effective_language();
-                if self.config.audio.keep_languages.iter().any(|l| l == lang) {
-                    let preferred_default_index = self.get_preferred_default_audio_stream();
-                    if preferred_default_index == Some(stream.index) {
-                        "KEEP (default)".yellow().to_string()
-                    } else {
-                        "KEEP".green().to_string()
-                    }
-                } else {
-                    "REMOVE".red().to_string()
-                }
-            }
-            StreamType::Subtitle => {
-                let lang = stream.effective_language();
-                // Check if any preference matches this subtitle
-                let matches_preference = self.config.subtitles.keep_languages.iter().any(|pref| {
-                    pref.language == lang && pref.matches_title(stream.title.as_deref())
-                });
-
-                if matches_preference {
-                    let mut status_parts = Vec::new();
-
-                    let preferred_default_index = self.get_preferred_default_subtitle_stream();
-                    if preferred_default_index == Some(stream.index) {
-                        status_parts.push("default");
-                    }
-
-                    // Add title match indicator if there was a specific title requirement
-                    if stream.title.is_some() {
-                        if self.config.subtitles.keep_languages.iter().any(|pref| {
-                            pref.language == lang
-                                && pref.title_prefix.is_some()
-                                && pref.matches_title(stream.title.as_deref())
-                        }) {
-                            status_parts.push("title match");
-                        }
-                    }
-
-                    if !status_parts.is_empty() {
-                        format!("KEEP ({})", status_parts.join(", "))
-                            .yellow()
-                            .to_string()
-                    } else {
-                        "KEEP".green().to_string()
-                    }
-                } else {
-                    "REMOVE".red().to_string()
-                }
-            }
-            StreamType::Attachment => {
-                // Always keep all attachment streams
-                "KEEP".green().to_string()
-            }
-            _ => "UNKNOWN".dimmed().to_string(),
+        let Some(decision) = self.decisions.get(stream.index) else {
+            return "UNKNOWN".dimmed().to_string();
+        };
+        if decision.reason == DecisionReason::DownmixDuplicate {
+            return "REMOVE (downmix duplicate)".red().to_string();
+        }
+        if decision.reason == DecisionReason::ChannelsNotPreferred {
+            return "REMOVE (channel count)".red().to_string();
+        }
+        if decision.reason == DecisionReason::CodecRemoved {
+            return "REMOVE (codec)".red().to_string();
+        }
+        if decision.reason == DecisionReason::CodecNotPreferred {
+            return "REMOVE (lower-ranked codec)".red().to_string();
+        }
+        if decision.reason == DecisionReason::CoverArt {
+            return "REMOVE (cover art)".red().to_string();
+        }
+        if !decision.is_kept() {
+            return match language_note(stream) {
+                Some(note) => format!("REMOVE ({})", note).red().to_string(),
+                None => "REMOVE".red().to_string(),
+            };
+        }
+
+        let mut status_parts = Vec::new();
+        if stream.cover_art {
+            status_parts.push("cover art");
+        }
+        if decision.default {
+            status_parts.push("default");
+        }
+        if decision.reason == DecisionReason::TitleMatch {
+            status_parts.push("title match");
+        }
+        if decision.reason == DecisionReason::FlagsOnly {
+            status_parts.push("not preferred");
+        }
+        if decision.reason == DecisionReason::ForcedKept {
+            status_parts.push("forced");
+        }
+        if decision.reason == DecisionReason::NoMatchFallback {
+            status_parts.push("no match fallback");
+        }
+        if let Some(note) = language_note(stream) {
+            status_parts.push(note);
+        }
+
+        if status_parts.is_empty() {
+            "KEEP".green().to_string()
+        } else {
+            format!("KEEP ({})", status_parts.join(", "))
+                .yellow()
+                .to_string()
+        }
+    }
+
+    /// Format a stream index, marking streams merged in from external files
+    fn format_index(&self, stream: &StreamInfo) -> String {
+        match &stream.external {
+            Some(external) => format!(
+                "{} (ext: {})",
+                stream.index,
+                external
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            ),
+            None => stream.index.to_string(),
+        }
+    }
+
+    /// Format a stream size, prefixing heuristic estimates with "≈"
+    fn format_stream_size(&self, stream: &StreamInfo) -> String {
+        match stream.size_bytes {
+            Some(size) if stream.size_estimated => format!("≈{}", format_size(size)),
+            Some(size) => format_size(size),
+            None => "?".to_string(),
         }
     }
 
@@ -331,39 +379,92 @@ impl<'a> StreamDisplayer<'a> {
             .unwrap_or_else(|| "none".dimmed().to_string())
     }
 
-    fn display_summary(&self) -> Result<()> {
-        println!("\n{}", "📊 Summary:".bold());
+    fn display_summary(&self, out: &mut Vec<String>) {
+        emit_line!(out, "\n{}", "📊 Summary:".bold());
 
         let total_size: u64 = self.streams.iter().filter_map(|s| s.size_bytes).sum();
 
-        let mut keep_size = 0u64;
-        let mut remove_count = 0;
-
-        for stream in self.streams {
-            let status = self.get_stream_status(stream);
-            if !status.contains("REMOVE") {
-                keep_size += stream.size_bytes.unwrap_or(0);
-            } else {
-                remove_count += 1;
-            }
-        }
+        let keep_size: u64 = self
+            .streams
+            .iter()
+            .filter(|s| self.decisions.get(s.index).is_some_and(|d| d.is_kept()))
+            .filter_map(|s| s.size_bytes)
+            .sum();
+        let remove_count = self.decisions.removed_count();
 
         if total_size > 0 {
             let savings = total_size - keep_size;
             let savings_pct = (savings as f64 / total_size as f64) * 100.0;
-
-            println!("Original size: {}", format_size(total_size));
-            println!("After processing: {}", format_size(keep_size));
-            println!(
-                "Space savings: {} ({:.1}%)",
+            let has_estimates = self.streams.iter().any(|s| s.size_estimated);
+            let approx = if has_estimates { "≈" } else { "" };
+
+            emit_line!(out, "Original size: {}{}", approx, format_size(total_size));
+            emit_line!(
+                out,
+                "After processing: {}{}",
+                approx,
+                format_size(keep_size)
+            );
+            emit_line!(
+                out,
+                "Space savings: {}{} ({})",
+                approx,
                 format_size(savings),
-                savings_pct
+                format_percent(savings_pct)
             );
-            println!("Streams to remove: {}", remove_count);
+            emit_line!(out, "Streams to remove: {}", remove_count);
+            if has_estimates {
+                emit_line!(
+                    out,
+                    "{}",
+                    "≈ Some stream sizes are estimated from the container size".dimmed()
+                );
+            }
         } else {
-            println!("Unable to calculate size information");
+            emit_line!(out, "Unable to calculate size information");
         }
 
-        Ok(())
+        match self.chapters {
+            Some(0) => emit_line!(out, "Chapters: none"),
+            Some(count) if self.chapter_mode == ChapterMode::Strip => {
+                emit_line!(out, "Chapters: {} ({})", count, "REMOVE".red())
+            }
+            Some(count) => emit_line!(out, "Chapters: {}", count),
+            None => {}
+        }
+        if !self.dropped_metadata.is_empty() {
+            emit_line!(
+                out,
+                "Metadata to drop: {}",
+                self.dropped_metadata.join(", ")
+            );
+        }
+        if !self.title_rewrites.is_empty() {
+            emit_line!(out, "Track titles to rewrite:");
+            for (index, title) in &self.title_rewrites {
+                let old = self
+                    .streams
+                    .iter()
+                    .find(|s| s.index == *index)
+                    .and_then(|s| s.title.as_deref())
+                    .unwrap_or("");
+                let new = if title.is_empty() {
+                    "(cleared)".dimmed().to_string()
+                } else {
+                    format!("'{}'", title)
+                };
+                emit_line!(out, "  {}: '{}' → {}", index, old, new);
+            }
+        }
+    }
+}
+
+/// Status note for streams whose language didn't come from the file's tags
+fn language_note(stream: &StreamInfo) -> Option<&'static str> {
+    match stream.language_source {
+        LanguageSource::Tag => None,
+        LanguageSource::Title => Some("language from title"),
+        LanguageSource::Override => Some("language set by user"),
+        LanguageSource::Import => Some("language from media info"),
     }
 }
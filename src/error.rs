@@ -62,6 +62,20 @@ pub fn path_safety_error(source: &Path, target: &Path, issue: &str) -> anyhow::E
     )
 }
 
+/// Returned when a run is aborted through its `CancellationToken`
+///
+/// Embedders can detect it with `error.downcast_ref::<Cancelled>()`.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "⏹️  Processing cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
 /// Provides suggestions based on common error scenarios
 pub fn suggest_solution(error: &str) -> Option<&'static str> {
     if error.contains("Permission denied") {
@@ -0,0 +1,7 @@
+pub mod cli;
+pub mod config;
+pub mod core;
+pub mod display;
+pub mod error;
+pub mod models;
+pub mod utils;
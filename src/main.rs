@@ -1,125 +1,174 @@
-mod cli;
-mod config;
-mod core;
-mod display;
-mod error;
-mod models;
-mod utils;
-
 use anyhow::{Context, Result};
 use std::path::Path;
-
-use cli::{ProcessingSettings, TargetType, prepare_processing_settings, print_configuration_info};
-use core::analyzer::analyze_mkv_streams;
-use core::{BatchProcessor, handle_non_mkv_file, process_task};
-use models::{ProcessingTask, StreamInfo};
-use utils::{is_valid_mkv_file, validate_source_target_paths};
+use std::sync::Arc;
+
+use mkv_slimmer::cli::{
+    CliCommand, ProcessingSettings, TargetType, cancel_on_ctrl_c, prepare_processing_settings,
+    print_configuration_info, remux_progress_bar, run_analyze_command, run_audit_command,
+    run_chapters_command, run_fix_languages_command, run_fonts_command, run_history_command,
+    run_inspect_command, run_journal_command, run_process_manifest_command,
+    run_retry_failed_command, run_rollback_command, run_scan_command, run_schema_command,
+    run_tags_command, write_result_json,
+};
+use mkv_slimmer::core::notify::{NotificationEvent, Notifications};
+use mkv_slimmer::core::partial::clean_orphans;
+use mkv_slimmer::core::{BatchProcessor, ProcessControl, Slimmer};
+use mkv_slimmer::models::{MediaKind, OutcomeCounts, RunSummary};
+use mkv_slimmer::report;
+use mkv_slimmer::utils::{validate_source_target_paths, with_file};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = match CliCommand::parse()? {
+        CliCommand::Process(args) => *args,
+        CliCommand::Chapters(command) => return run_chapters_command(command).await,
+        CliCommand::Tags(command) => return run_tags_command(command).await,
+        CliCommand::Fonts(command) => return run_fonts_command(command).await,
+        CliCommand::Inspect(command) => return run_inspect_command(command).await,
+        CliCommand::Analyze(command) => return run_analyze_command(command).await,
+        CliCommand::FixLanguages(command) => return run_fix_languages_command(command).await,
+        CliCommand::Audit(command) => return run_audit_command(command).await,
+        CliCommand::Journal(command) => return run_journal_command(command).await,
+        CliCommand::Rollback(command) => return run_rollback_command(command).await,
+        CliCommand::RetryFailed(command) => return run_retry_failed_command(command).await,
+        CliCommand::Scan(command) => return run_scan_command(command).await,
+        CliCommand::ProcessManifest(command) => {
+            return run_process_manifest_command(command).await;
+        }
+        CliCommand::History(command) => return run_history_command(command).await,
+        CliCommand::Schema(command) => return run_schema_command(command).await,
+    };
+
     // Get processed settings from CLI
-    let settings = prepare_processing_settings().await?;
+    let settings = prepare_processing_settings(args).await?;
 
-    if settings.input_is_file {
+    let result_json = settings.result_json.clone();
+    let notifications = Arc::new(Notifications::from_config(&settings.config.notifications));
+    let summary = if settings.input_is_file {
         // Process single file
-        process_single_file(settings).await?;
+        process_single_file(settings, &notifications).await?
     } else {
         // Process directory
-        process_directory(settings).await?;
+        process_directory(settings, notifications.clone()).await?
+    };
+    notifications.notify(&NotificationEvent::RunFinished(Box::new(summary.clone())));
+
+    if let Some(path) = result_json {
+        write_result_json(&path, &summary)?;
+    }
+    if summary.changes_pending() {
+        report!(
+            "📋 Dry run: {} file(s) would be remuxed",
+            summary.counts.remux
+        );
+        std::process::exit(summary.exit_code());
+    }
+    if summary.retry_later > 0 {
+        report!(
+            "🔁 {} file(s) skipped for now - a later run may process them",
+            summary.retry_later
+        );
+        std::process::exit(summary.exit_code());
     }
 
     Ok(())
 }
 
-async fn process_single_file(settings: ProcessingSettings) -> Result<()> {
-    // Handle different target types to determine output location
-    let (target_directory, output_filename) = match settings.target_type {
-        TargetType::File => {
-            // File → File: use parent directory and extract filename
-            let parent_dir = settings
-                .target_path
-                .parent()
-                .context("Could not determine parent directory from target file path")?;
-            let filename = settings
-                .target_path
-                .file_name()
-                .context("Could not extract filename from target path")?
-                .to_string_lossy()
-                .to_string();
-            (parent_dir, Some(filename))
-        }
-        TargetType::Directory => {
-            // File → Directory: current behavior
-            (settings.target_path.as_path(), None)
-        }
-    };
-
-    // Validate source and target paths are not nested within each other
-    let source_dir = settings
-        .input_path
-        .parent()
-        .context("Could not determine source directory")?;
-    validate_source_target_paths(source_dir, target_directory)
-        .context("Source and target path validation failed")?;
-
+async fn process_single_file(
+    settings: ProcessingSettings,
+    notifications: &Notifications,
+) -> Result<RunSummary> {
+    let dry_run = settings.config.processing.dry_run;
     // Display processing info
-    println!("📁 Analyzing: {}", settings.input_path.display());
+    report!("📁 Analyzing: {}", settings.input_path.display());
     match settings.target_type {
+        TargetType::File if settings.in_place => {
+            report!("📄 Target: in place (replaces the input)");
+        }
         TargetType::File => {
-            println!("📄 Target file: {}", settings.target_path.display());
+            report!("📄 Target file: {}", settings.target_path.display());
         }
         TargetType::Directory => {
-            println!("📂 Target directory: {}", settings.target_path.display());
+            report!("📂 Target directory: {}", settings.target_path.display());
         }
     }
-    print_configuration_info(&settings.config);
-
-    // Check if file is valid MKV - if not, handle immediately
-    if !is_valid_mkv_file(&settings.input_path) {
-        println!(
-            "⚠️  File is not a valid MKV file: {}",
-            settings.input_path.display()
-        );
-        println!("🔄 Falling back to copying original file (no processing needed)");
-
-        handle_non_mkv_file(
-            &settings.input_path,
-            &target_directory.to_path_buf(),
-            output_filename,
-            &settings.config,
-            settings.sonarr_context.as_ref(),
-        )
-        .await?;
-
-        return Ok(());
+    print_configuration_info(
+        &settings.config,
+        Some(MediaKind::from_path(&settings.input_path)),
+    );
+    let output_directory = match settings.target_type {
+        TargetType::File => settings
+            .target_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new(".")),
+        TargetType::Directory => settings.target_path.as_path(),
+    };
+    clean_orphans(output_directory, false, dry_run);
+
+    let input_path = settings.input_path.clone();
+    let mut builder = Slimmer::builder()
+        .source(settings.input_path)
+        .config(settings.config)
+        .display_streams(true)
+        .on_progress(remux_progress_bar())
+        .cancellation_token(cancel_on_ctrl_c());
+    builder = match settings.target_type {
+        TargetType::File if settings.in_place => builder.in_place(),
+        TargetType::File => builder.target_file(settings.target_path),
+        TargetType::Directory => builder.target_directory(settings.target_path),
+    };
+    if let Some(import_context) = settings.import_context {
+        builder = builder.import_context(import_context);
     }
 
-    // Create ProcessingTask with stream analysis
-    let task = create_processing_task(
-        settings.input_path,
-        target_directory.to_path_buf(),
-        output_filename,
-    )
-    .await?;
-
-    // Process the task
-    process_task(
-        task,
-        &settings.config,
-        settings.sonarr_context.as_ref(),
-        true,
-    )
-    .await
+    let source_size = std::fs::metadata(&input_path).map(|m| m.len()).ok();
+    let outcome = match with_file(&input_path, builder.build()?.run()).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            notifications.notify(&NotificationEvent::failed(&input_path, &e));
+            // The run ends here, so batch notifications hear about it now
+            let summary = RunSummary {
+                dry_run,
+                total_files: 1,
+                failed: 1,
+                failures: [(input_path.clone(), format!("{:#}", e))].into(),
+                ..Default::default()
+            };
+            notifications.notify(&NotificationEvent::RunFinished(Box::new(summary)));
+            return Err(e);
+        }
+    };
+    notifications.notify(&NotificationEvent::processed(
+        &input_path,
+        source_size,
+        &outcome,
+    ));
+
+    let mut counts = OutcomeCounts::default();
+    counts.record(&outcome);
+    Ok(RunSummary {
+        dry_run,
+        total_files: 1,
+        successful: 1,
+        counts,
+        ..Default::default()
+    })
 }
 
-async fn process_directory(settings: ProcessingSettings) -> Result<()> {
+async fn process_directory(
+    settings: ProcessingSettings,
+    notifications: Arc<Notifications>,
+) -> Result<RunSummary> {
+    let dry_run = settings.config.processing.dry_run;
     // Validate source and target paths are not nested within each other
     validate_source_target_paths(&settings.input_path, &settings.target_path)
         .context("Source and target path validation failed")?;
 
-    println!("📁 Source directory: {}", settings.input_path.display());
-    println!("📂 Target directory: {}", settings.target_path.display());
-    print_configuration_info(&settings.config);
+    report!("📁 Source directory: {}", settings.input_path.display());
+    report!("📂 Target directory: {}", settings.target_path.display());
+    print_configuration_info(&settings.config, None);
+    clean_orphans(&settings.target_path, settings.recursive, dry_run);
 
     let batch_processor = BatchProcessor::new(
         settings.input_path,
@@ -127,45 +176,39 @@ async fn process_directory(settings: ProcessingSettings) -> Result<()> {
         settings.recursive,
         settings.filter_pattern,
         settings.config,
-        settings.sonarr_context,
-    );
+        settings.import_context,
+    )
+    .with_control(
+        ProcessControl::default()
+            .with_progress(remux_progress_bar())
+            .with_cancellation(cancel_on_ctrl_c()),
+    )
+    .with_age_filter(settings.age_filter)
+    .with_stream_tables(settings.stream_tables)
+    .with_notifications(notifications);
 
     let result = batch_processor.process().await?;
+    let batch = batch_processor.batch_run();
 
-    println!("\n🎯 Batch Processing Results:");
-    println!("📊 Total files processed: {}", result.total_files);
-    println!("✅ Successful: {}", result.successful);
+    report!("\n🎯 Batch Processing Results:");
+    report!("📊 Total files processed: {}", result.total_files);
+    report!("✅ Successful: {}", result.successful);
     if result.failed > 0 {
-        println!("❌ Failed: {}", result.failed);
-        println!("\nErrors encountered:");
+        report!("❌ Failed: {}", result.failed);
+        report!("\nErrors encountered:");
         for (file, error) in &result.errors {
-            println!("  {} - {}", file.display(), error);
+            report!("  {} - {}", file.display(), error);
         }
     }
+    if !result.skipped.is_empty() {
+        report!("⏭️  Skipped: {}", result.skipped.len());
+        result.print_skipped("  ");
+    }
+    result.print_season_summary();
+    result.print_warnings();
 
-    Ok(())
-}
-
-/// Create a ProcessingTask by analyzing the MKV file streams
-async fn create_processing_task(
-    source_file: std::path::PathBuf,
-    target_location: std::path::PathBuf,
-    output_filename: Option<String>,
-) -> Result<ProcessingTask> {
-    // Analyze streams using the new analyzer functions
-    let streams = analyze_mkv_streams_local(&source_file)
-        .await
-        .with_context(|| format!("Failed to analyze MKV streams: {}", source_file.display()))?;
-
-    Ok(ProcessingTask::new(
-        source_file,
-        target_location,
-        streams,
-        output_filename,
-    ))
-}
-
-/// Analyze MKV file streams and return StreamInfo vector
-async fn analyze_mkv_streams_local(file_path: &Path) -> Result<Vec<StreamInfo>> {
-    analyze_mkv_streams(file_path).await
+    Ok(RunSummary {
+        batch: Some(batch),
+        ..result.run_summary(dry_run)
+    })
 }
@@ -0,0 +1,271 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::stream::StreamType;
+
+/// What will happen to a stream when the file is processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamAction {
+    /// Stream is carried over into the output file
+    Keep,
+    /// Stream is dropped from the output file
+    Remove,
+}
+
+/// Why a stream was kept or removed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionReason {
+    /// Stream type isn't filtered for this file (video, attachments, unknown
+    /// streams, or non-audio streams in an audio-only container)
+    AlwaysKept,
+    /// Language is listed in the keep preferences
+    LanguageMatch,
+    /// Language and title prefix match a subtitle preference
+    TitleMatch,
+    /// Language (or title) doesn't match any keep preference
+    NotPreferred,
+    /// Stream type can't be stored in the output container (attachments in WebM)
+    UnsupportedByContainer,
+    /// Not preferred, but kept because only flags are normalized (`--flags-only`)
+    FlagsOnly,
+    /// Not preferred, but kept because no track of its type matched and the
+    /// type's `no_match_policy` keeps something
+    NoMatchFallback,
+    /// Not preferred, but a forced subtitle kept by `subtitles.keep_forced`
+    ForcedKept,
+    /// Matches, but a track of the same language has at least
+    /// `audio.min_channels` channels
+    DownmixDuplicate,
+    /// Matches, but a track of the same language has exactly
+    /// `audio.prefer_channels` channels
+    ChannelsNotPreferred,
+    /// Matches, but the subtitle codec is listed in `subtitles.remove_codecs`
+    /// or missing from `subtitles.keep_codecs`
+    CodecRemoved,
+    /// Matches, but `audio.tracks_per_language` tracks of the same language
+    /// rank higher in `audio.codec_preference`
+    CodecNotPreferred,
+    /// Embedded cover picture, removed by `attachments.remove_cover_art`
+    CoverArt,
+    /// Decided by a custom stream policy, with its own explanation
+    Custom(String),
+}
+
+/// Outcome of a stream policy for one stream, before default flags are assigned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decision {
+    /// Whether the stream is kept or removed
+    pub action: StreamAction,
+    /// Why the stream was kept or removed
+    pub reason: DecisionReason,
+}
+
+impl Decision {
+    /// Keep the stream for the given reason
+    pub fn keep(reason: DecisionReason) -> Self {
+        Self {
+            action: StreamAction::Keep,
+            reason,
+        }
+    }
+
+    /// Remove the stream for the given reason
+    pub fn remove(reason: DecisionReason) -> Self {
+        Self {
+            action: StreamAction::Remove,
+            reason,
+        }
+    }
+}
+
+/// Keep/remove decision for a single stream, shared by processing, display and
+/// any serialized output so they all describe a file with the same schema
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::{DecisionReason, StreamAction, StreamDecision, StreamType};
+///
+/// let decision = StreamDecision {
+///     index: 1,
+///     stream_type: StreamType::Audio,
+///     action: StreamAction::Keep,
+///     default: true,
+///     reason: DecisionReason::LanguageMatch,
+///     size_bytes: Some(1024),
+/// };
+/// assert!(decision.is_kept());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamDecision {
+    /// Index of the stream this decision applies to
+    pub index: u32,
+    /// Type of the stream this decision applies to
+    pub stream_type: StreamType,
+    /// Whether the stream is kept or removed
+    pub action: StreamAction,
+    /// Whether the stream will be flagged as default in the output
+    pub default: bool,
+    /// Why the stream was kept or removed
+    pub reason: DecisionReason,
+    /// Size of the stream in bytes, if known (see `StreamInfo::size_bytes`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+}
+
+impl StreamDecision {
+    /// Returns true if the stream is carried over into the output
+    pub fn is_kept(&self) -> bool {
+        self.action == StreamAction::Keep
+    }
+
+    /// Whether the stream is kept in a way that satisfies its type's
+    /// `no_match_policy`: forced subtitles kept regardless of language don't
+    pub fn satisfies_no_match_policy(&self) -> bool {
+        self.is_kept() && self.reason != DecisionReason::ForcedKept
+    }
+}
+
+/// Decisions for all streams of a file, computed once and shared by display,
+/// the mkvmerge command builder and the processing outcome
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::{DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamType};
+///
+/// let decisions = StreamDecisions {
+///     streams: vec![
+///         StreamDecision {
+///             index: 0,
+///             stream_type: StreamType::Audio,
+///             action: StreamAction::Keep,
+///             default: true,
+///             reason: DecisionReason::LanguageMatch,
+///             size_bytes: Some(3000),
+///         },
+///         StreamDecision {
+///             index: 1,
+///             stream_type: StreamType::Audio,
+///             action: StreamAction::Remove,
+///             default: false,
+///             reason: DecisionReason::NotPreferred,
+///             size_bytes: Some(2000),
+///         },
+///     ],
+///     default_audio: Some(0),
+///     default_subtitle: None,
+///     ..Default::default()
+/// };
+/// assert_eq!(decisions.kept_indices(), vec![0]);
+/// assert_eq!(decisions.removed_count(), 1);
+/// assert_eq!(decisions.removed_bytes(), 2000);
+///
+/// let audio = &decisions.type_stats()[0];
+/// assert_eq!((audio.kept, audio.removed), (1, 1));
+/// assert_eq!((audio.bytes_kept, audio.bytes_removed), (3000, 2000));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamDecisions {
+    /// One decision per stream, in stream order
+    pub streams: Vec<StreamDecision>,
+    /// Kept audio stream flagged as default in the output
+    pub default_audio: Option<u32>,
+    /// Kept subtitle stream flagged as default in the output
+    pub default_subtitle: Option<u32>,
+    /// Per-type totals of `streams` (see `type_stats`), stored so serialized
+    /// results carry them without re-deriving
+    #[serde(default)]
+    pub stats: Vec<StreamTypeStats>,
+}
+
+/// Kept/removed totals for one stream type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamTypeStats {
+    /// Stream type these totals cover
+    pub stream_type: StreamType,
+    /// Number of streams carried over into the output
+    pub kept: usize,
+    /// Number of streams dropped from the output
+    pub removed: usize,
+    /// Known bytes of the kept streams
+    pub bytes_kept: u64,
+    /// Known bytes of the removed streams
+    pub bytes_removed: u64,
+}
+
+impl StreamDecisions {
+    /// Decision for the stream with the given index
+    pub fn get(&self, index: u32) -> Option<&StreamDecision> {
+        self.streams.iter().find(|d| d.index == index)
+    }
+
+    /// Indices of the streams carried over into the output, in stream order
+    pub fn kept_indices(&self) -> Vec<u32> {
+        self.streams
+            .iter()
+            .filter(|d| d.is_kept())
+            .map(|d| d.index)
+            .collect()
+    }
+
+    /// Number of streams dropped from the output
+    pub fn removed_count(&self) -> usize {
+        self.streams.iter().filter(|d| !d.is_kept()).count()
+    }
+
+    /// Known bytes of the streams dropped from the output
+    pub fn removed_bytes(&self) -> u64 {
+        self.streams
+            .iter()
+            .filter(|d| !d.is_kept())
+            .filter_map(|d| d.size_bytes)
+            .sum()
+    }
+
+    /// Bytes of the streams dropped from the output, or None when the size
+    /// of one of them is unknown
+    pub fn known_removed_bytes(&self) -> Option<u64> {
+        self.streams
+            .iter()
+            .filter(|d| !d.is_kept())
+            .map(|d| d.size_bytes)
+            .sum()
+    }
+
+    /// Kept/removed counts and bytes per stream type, for the types present,
+    /// in video, audio, subtitle, attachment, unknown order
+    pub fn type_stats(&self) -> Vec<StreamTypeStats> {
+        [
+            StreamType::Video,
+            StreamType::Audio,
+            StreamType::Subtitle,
+            StreamType::Attachment,
+            StreamType::Unknown,
+        ]
+        .into_iter()
+        .filter_map(|stream_type| {
+            let of_type: Vec<&StreamDecision> = self
+                .streams
+                .iter()
+                .filter(|d| d.stream_type == stream_type)
+                .collect();
+            if of_type.is_empty() {
+                return None;
+            }
+            let (kept, removed): (Vec<&StreamDecision>, Vec<&StreamDecision>) =
+                of_type.into_iter().partition(|d| d.is_kept());
+            let bytes = |decisions: &[&StreamDecision]| -> u64 {
+                decisions.iter().filter_map(|d| d.size_bytes).sum()
+            };
+            Some(StreamTypeStats {
+                stream_type,
+                kept: kept.len(),
+                removed: removed.len(),
+                bytes_kept: bytes(&kept),
+                bytes_removed: bytes(&removed),
+            })
+        })
+        .collect()
+    }
+}
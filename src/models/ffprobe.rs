@@ -11,6 +11,7 @@ pub struct FFProbeStream {
     pub codec_type: Option<String>,
     pub codec_name: Option<String>,
     pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
     pub width: Option<i64>,
     pub height: Option<i64>,
     pub r_frame_rate: Option<String>,
@@ -40,6 +41,8 @@ pub struct FFProbeTags {
 pub struct FFProbeDisposition {
     pub default: Option<i64>,
     pub forced: Option<i64>,
+    /// 1 for pictures attached to the file (cover art) rather than played
+    pub attached_pic: Option<i64>,
     // Allow any other disposition fields to be present without failing
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
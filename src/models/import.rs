@@ -0,0 +1,171 @@
+use super::radarr::RadarrContext;
+use super::sonarr::SonarrContext;
+use super::stream::StreamType;
+use crate::utils::{language_from_title, language_name, normalize_language};
+
+/// Context of the *arr application that runs mkv-slimmer as its custom
+/// import script. Both speak the same protocol: the transfer mode decides how
+/// unchanged files reach the destination, and `[MoveStatus]` lines report back.
+#[derive(Debug, Clone)]
+pub enum ImportContext {
+    Sonarr(Box<SonarrContext>),
+    Radarr(Box<RadarrContext>),
+}
+
+impl ImportContext {
+    /// Name of the application, for messages
+    pub fn app_name(&self) -> &'static str {
+        match self {
+            ImportContext::Sonarr(_) => "Sonarr",
+            ImportContext::Radarr(_) => "Radarr",
+        }
+    }
+
+    /// The `*_transfermode` the application asked for (`Move`, `Copy`,
+    /// `HardLink`, `HardLinkOrCopy`)
+    pub fn transfer_mode(&self) -> Option<&str> {
+        match self {
+            ImportContext::Sonarr(context) => context.transfer_mode.as_deref(),
+            ImportContext::Radarr(context) => context.transfer_mode.as_deref(),
+        }
+    }
+
+    /// Value for a tag template or attachment placeholder
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{ImportContext, RadarrContext, SonarrContext};
+    ///
+    /// let sonarr = ImportContext::from(SonarrContext {
+    ///     series_title: Some("Show".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(sonarr.template_value("series_title"), Some("Show"));
+    ///
+    /// let radarr = ImportContext::from(RadarrContext {
+    ///     movie_title: Some("Movie".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(radarr.template_value("movie_title"), Some("Movie"));
+    /// assert_eq!(radarr.template_value("series_title"), None);
+    /// ```
+    pub fn template_value(&self, placeholder: &str) -> Option<&str> {
+        match self {
+            ImportContext::Sonarr(context) => context.template_value(placeholder),
+            ImportContext::Radarr(context) => context.template_value(placeholder),
+        }
+    }
+
+    /// Languages the application's media info lists for the audio or
+    /// subtitle tracks of the imported file (`eng / jpn` → `["eng", "jpn"]`).
+    /// None when it didn't report them or for other stream types.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{ImportContext, SonarrContext, StreamType};
+    ///
+    /// let context = ImportContext::from(SonarrContext {
+    ///     episode_file_media_info_audio_languages: Some("eng / JPN".to_string()),
+    ///     episode_file_media_info_subtitles: Some(String::new()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     context.media_info_languages(StreamType::Audio),
+    ///     Some(vec!["eng".to_string(), "jpn".to_string()])
+    /// );
+    /// assert_eq!(context.media_info_languages(StreamType::Subtitle), Some(Vec::new()));
+    /// assert_eq!(context.media_info_languages(StreamType::Video), None);
+    /// ```
+    pub fn media_info_languages(&self, stream_type: StreamType) -> Option<Vec<String>> {
+        let list = match (self, stream_type) {
+            (ImportContext::Sonarr(context), StreamType::Audio) => {
+                &context.episode_file_media_info_audio_languages
+            }
+            (ImportContext::Sonarr(context), StreamType::Subtitle) => {
+                &context.episode_file_media_info_subtitles
+            }
+            (ImportContext::Radarr(context), StreamType::Audio) => {
+                &context.movie_file_media_info_audio_languages
+            }
+            (ImportContext::Radarr(context), StreamType::Subtitle) => {
+                &context.movie_file_media_info_subtitles
+            }
+            _ => return None,
+        };
+        list.as_deref().map(|list| {
+            list.split('/')
+                .map(normalize_language)
+                .filter(|language| !language.is_empty())
+                .collect()
+        })
+    }
+
+    /// ISO 639-2 code of the series' or movie's original language, which the
+    /// application reports as a code (`jpn`) or an English name (`Japanese`)
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{ImportContext, RadarrContext, SonarrContext};
+    ///
+    /// let sonarr = ImportContext::from(SonarrContext {
+    ///     series_original_language: Some("Japanese".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(sonarr.original_language().as_deref(), Some("jpn"));
+    ///
+    /// let radarr = ImportContext::from(RadarrContext {
+    ///     movie_original_language: Some("FRE".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(radarr.original_language().as_deref(), Some("fre"));
+    /// ```
+    pub fn original_language(&self) -> Option<String> {
+        let language = match self {
+            ImportContext::Sonarr(context) => &context.series_original_language,
+            ImportContext::Radarr(context) => &context.movie_original_language,
+        };
+        let language = language.as_deref()?;
+        let code = normalize_language(language);
+        if language_name(&code).is_some() {
+            return Some(code);
+        }
+        language_from_title(language).map(str::to_string)
+    }
+
+    /// Tags of the series or movie (`anime|kids` → `["anime", "kids"]`)
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{ImportContext, RadarrContext};
+    ///
+    /// let context = ImportContext::from(RadarrContext {
+    ///     movie_tags: Some("4k|kids".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(context.tags(), vec!["4k", "kids"]);
+    /// ```
+    pub fn tags(&self) -> Vec<String> {
+        let tags = match self {
+            ImportContext::Sonarr(context) => &context.series_tags,
+            ImportContext::Radarr(context) => &context.movie_tags,
+        };
+        tags.as_deref()
+            .unwrap_or_default()
+            .split('|')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+}
+
+impl From<SonarrContext> for ImportContext {
+    fn from(context: SonarrContext) -> Self {
+        ImportContext::Sonarr(Box::new(context))
+    }
+}
+
+impl From<RadarrContext> for ImportContext {
+    fn from(context: RadarrContext) -> Self {
+        ImportContext::Radarr(Box::new(context))
+    }
+}
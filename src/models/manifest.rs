@@ -0,0 +1,87 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::task::ProcessingTask;
+
+/// Files of a library found by `scan`, with their analysis, for
+/// `process --manifest`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScanManifest {
+    /// Source directory the files were collected from
+    pub source: PathBuf,
+    pub recursive: bool,
+    /// When the scan finished, in seconds since the Unix epoch
+    pub scanned_at: u64,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// A scanned file and what processing it would do
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch; with the size, it
+    /// tells whether the file changed since the scan
+    pub modified: u64,
+    /// `config_hash()` of the settings the file was analyzed with, its
+    /// directory settings included
+    pub config_hash: String,
+    /// Streams processing would remove
+    pub removed_streams: usize,
+    /// Known bytes of the streams processing would remove
+    pub removed_bytes: u64,
+    /// Why the file couldn't be analyzed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The analysis, reused by `process --manifest` while the file and its
+    /// settings are unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub task: Option<ProcessingTask>,
+}
+
+impl ManifestEntry {
+    /// Whether processing would remove streams from the file
+    pub fn is_candidate(&self) -> bool {
+        self.removed_streams > 0
+    }
+}
+
+impl ScanManifest {
+    /// The files processing would remove streams from
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{ManifestEntry, ScanManifest};
+    /// use std::path::PathBuf;
+    ///
+    /// let entry = |name: &str, removed_streams: usize| ManifestEntry {
+    ///     path: PathBuf::from(name),
+    ///     size: 1000,
+    ///     modified: 0,
+    ///     config_hash: String::new(),
+    ///     removed_streams,
+    ///     removed_bytes: 100 * removed_streams as u64,
+    ///     error: None,
+    ///     task: None,
+    /// };
+    /// let manifest = ScanManifest {
+    ///     source: PathBuf::from("/tv"),
+    ///     recursive: true,
+    ///     scanned_at: 0,
+    ///     files: vec![entry("a.mkv", 2), entry("b.mkv", 0)],
+    /// };
+    /// let candidates: Vec<_> = manifest.candidates().map(|e| e.path.clone()).collect();
+    /// assert_eq!(candidates, vec![PathBuf::from("a.mkv")]);
+    /// assert_eq!(manifest.removable_bytes(), 200);
+    /// ```
+    pub fn candidates(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.files.iter().filter(|entry| entry.is_candidate())
+    }
+
+    /// Known bytes processing would remove across all files
+    pub fn removable_bytes(&self) -> u64 {
+        self.files.iter().map(|entry| entry.removed_bytes).sum()
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Kind of Matroska container being processed, derived from the file extension
+///
+/// Audio-only containers (.mka) only have their audio tracks filtered and
+/// subtitle-only containers (.mks) only their subtitle tracks; any other streams
+/// they carry (cover art, stray tracks) are left untouched.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::MediaKind;
+/// use std::path::Path;
+///
+/// assert_eq!(MediaKind::from_path(Path::new("movie.mkv")), MediaKind::Video);
+/// assert_eq!(MediaKind::from_path(Path::new("movie.eng.mka")), MediaKind::AudioOnly);
+/// assert_eq!(MediaKind::from_path(Path::new("movie.MKS")), MediaKind::SubtitlesOnly);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    /// Regular video file (.mkv, .webm)
+    Video,
+    /// Audio-only Matroska file (.mka)
+    AudioOnly,
+    /// Subtitle-only Matroska file (.mks)
+    SubtitlesOnly,
+}
+
+impl MediaKind {
+    /// Determine the kind from the file extension (case-insensitive)
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("mka") => MediaKind::AudioOnly,
+            Some("mks") => MediaKind::SubtitlesOnly,
+            _ => MediaKind::Video,
+        }
+    }
+
+    /// Whether audio language preferences apply to files of this kind
+    pub fn filters_audio(&self) -> bool {
+        *self != MediaKind::SubtitlesOnly
+    }
+
+    /// Whether subtitle preferences apply to files of this kind
+    pub fn filters_subtitles(&self) -> bool {
+        *self != MediaKind::AudioOnly
+    }
+}
@@ -0,0 +1,67 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Output of `mkvmerge -J` (identification in JSON format)
+#[derive(Deserialize)]
+pub struct MkvmergeIdentify {
+    #[serde(default)]
+    pub tracks: Vec<MkvmergeTrack>,
+    #[serde(default)]
+    pub attachments: Vec<MkvmergeAttachment>,
+    /// One entry per edition
+    #[serde(default)]
+    pub chapters: Vec<MkvmergeChapters>,
+}
+
+#[derive(Deserialize)]
+pub struct MkvmergeTrack {
+    /// mkvmerge track ID (0-based, in track entry order)
+    pub id: u32,
+    /// `video`, `audio` or `subtitles`
+    #[serde(rename = "type")]
+    pub track_type: String,
+    /// Human-readable codec name (`AVC/H.264/MPEG-4p10`)
+    pub codec: Option<String>,
+    #[serde(default)]
+    pub properties: MkvmergeTrackProperties,
+}
+
+#[derive(Deserialize, Default)]
+pub struct MkvmergeTrackProperties {
+    /// TrackNumber element of the track entry
+    pub number: Option<u64>,
+    /// TrackUID element of the track entry
+    pub uid: Option<u64>,
+    pub codec_id: Option<String>,
+    pub language: Option<String>,
+    pub track_name: Option<String>,
+    pub default_track: Option<bool>,
+    pub forced_track: Option<bool>,
+    pub pixel_dimensions: Option<String>,
+    /// Frame duration in nanoseconds
+    pub default_duration: Option<u64>,
+    pub audio_channels: Option<u32>,
+    pub audio_sampling_frequency: Option<u32>,
+    pub tag_number_of_bytes: Option<String>,
+    pub tag_duration: Option<String>,
+    pub tag_bps: Option<String>,
+    // Allow any other properties to be present without failing
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct MkvmergeChapters {
+    /// Chapters of the edition
+    #[serde(default)]
+    pub num_entries: usize,
+}
+
+#[derive(Deserialize)]
+pub struct MkvmergeAttachment {
+    /// mkvmerge attachment ID (1-based)
+    pub id: u32,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub size: Option<u64>,
+}
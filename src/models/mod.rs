@@ -1,9 +1,36 @@
+pub mod decision;
 pub mod ffprobe;
+pub mod import;
+pub mod manifest;
+pub mod media;
+pub mod mkvmerge;
+pub mod outcome;
+pub mod radarr;
+pub mod schema;
+pub mod segment;
 pub mod sonarr;
 pub mod stream;
 pub mod task;
+pub mod track_ids;
 
+pub use decision::{
+    Decision, DecisionReason, StreamAction, StreamDecision, StreamDecisions, StreamTypeStats,
+};
 pub use ffprobe::{FFProbeDisposition, FFProbeOutput, FFProbeStream, FFProbeTags};
+pub use import::ImportContext;
+pub use manifest::{ManifestEntry, ScanManifest};
+pub use media::MediaKind;
+pub use mkvmerge::{
+    MkvmergeAttachment, MkvmergeChapters, MkvmergeIdentify, MkvmergeTrack, MkvmergeTrackProperties,
+};
+pub use outcome::{
+    BatchRun, EXIT_CHANGES_PENDING, EXIT_RETRY_LATER, OutcomeCounts, ProcessingOutcome, RunSummary,
+    SkipReason, TransferMethod,
+};
+pub use radarr::RadarrContext;
+pub use schema::{SCHEMA_VERSION, Versioned};
+pub use segment::SegmentLinking;
 pub use sonarr::SonarrContext;
-pub use stream::{StreamInfo, StreamType};
+pub use stream::{ExternalTrack, LanguageSource, StreamInfo, StreamType};
 pub use task::ProcessingTask;
+pub use track_ids::{KnownTrack, MkvmergeId, TrackIdMap, TrackIds, TrackRef};
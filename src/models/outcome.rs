@@ -0,0 +1,371 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::decision::StreamDecisions;
+use crate::config::Config;
+use crate::utils::format_size;
+
+/// How an unchanged file was placed at its target location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMethod {
+    /// File was renamed (or copied and deleted across filesystems)
+    Moved,
+    /// File was copied
+    Copied,
+    /// File was hard linked
+    HardLinked,
+    /// Nothing was transferred: the source is its own output (`--in-place`)
+    InPlace,
+}
+
+/// Result of processing a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ProcessingOutcome {
+    /// mkvmerge wrote a new file with the kept streams
+    Remuxed {
+        output_path: PathBuf,
+        decisions: StreamDecisions,
+        /// Saved mkvmerge output, when `processing.log_dir` is set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        log_path: Option<PathBuf>,
+    },
+    /// mkvmerge wrote one file per subtitle language (subtitle-only containers)
+    Split {
+        output_paths: Vec<PathBuf>,
+        decisions: StreamDecisions,
+        /// Saved mkvmerge output of each run, when `processing.log_dir` is set
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        log_paths: Vec<PathBuf>,
+    },
+    /// Nothing needed to change; the source was moved, copied or linked as-is
+    Transferred {
+        output_path: PathBuf,
+        method: TransferMethod,
+    },
+    /// All streams were kept; the source was copied or moved and its
+    /// default/forced flags normalized in place (mkvpropedit)
+    FlagsNormalized {
+        output_path: PathBuf,
+        method: TransferMethod,
+        decisions: StreamDecisions,
+    },
+    /// Only attachments were removed; the source was copied or moved and
+    /// they were deleted from it in place (mkvpropedit)
+    AttachmentsRemoved {
+        output_path: PathBuf,
+        method: TransferMethod,
+        decisions: StreamDecisions,
+    },
+    /// An existing output already has the kept tracks and flags a remux
+    /// would write, so it was left alone
+    UpToDate {
+        output_path: PathBuf,
+        decisions: StreamDecisions,
+    },
+    /// The remux saved less than `processing.min_savings`; its output was
+    /// discarded and the source moved, copied or linked instead
+    BelowMinSavings {
+        output_path: PathBuf,
+        method: TransferMethod,
+        saved_bytes: u64,
+    },
+    /// Dry run: nothing was written
+    DryRun {
+        output_path: PathBuf,
+        /// Whether a real run would have remuxed the file
+        needs_remux: bool,
+    },
+}
+
+impl ProcessingOutcome {
+    /// Name of the outcome as used in the result JSON (`remuxed`,
+    /// `transferred`, ...)
+    pub fn status(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.get("status")?.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Paths of the (would-be) output files
+    pub fn output_paths(&self) -> Vec<&Path> {
+        match self {
+            ProcessingOutcome::Remuxed { output_path, .. }
+            | ProcessingOutcome::Transferred { output_path, .. }
+            | ProcessingOutcome::FlagsNormalized { output_path, .. }
+            | ProcessingOutcome::AttachmentsRemoved { output_path, .. }
+            | ProcessingOutcome::UpToDate { output_path, .. }
+            | ProcessingOutcome::BelowMinSavings { output_path, .. }
+            | ProcessingOutcome::DryRun { output_path, .. } => vec![output_path.as_path()],
+            ProcessingOutcome::Split { output_paths, .. } => {
+                output_paths.iter().map(|p| p.as_path()).collect()
+            }
+        }
+    }
+
+    /// Stream decisions the output was written with, for outcomes that
+    /// applied them
+    pub fn decisions(&self) -> Option<&StreamDecisions> {
+        match self {
+            ProcessingOutcome::Remuxed { decisions, .. }
+            | ProcessingOutcome::Split { decisions, .. }
+            | ProcessingOutcome::FlagsNormalized { decisions, .. }
+            | ProcessingOutcome::AttachmentsRemoved { decisions, .. }
+            | ProcessingOutcome::UpToDate { decisions, .. } => Some(decisions),
+            _ => None,
+        }
+    }
+
+    /// Why the file counts as skipped in a batch: its output was already up
+    /// to date, or the remux wasn't worth keeping
+    pub fn skip_reason(&self) -> Option<SkipReason> {
+        match self {
+            ProcessingOutcome::UpToDate { .. } => Some(SkipReason::UpToDate),
+            ProcessingOutcome::BelowMinSavings { saved_bytes, .. } => {
+                Some(SkipReason::BelowMinSavings {
+                    saved_bytes: *saved_bytes,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why a file of a batch wasn't processed, or was left as it was
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::SkipReason;
+///
+/// let reason = SkipReason::FilteredOut { filter: "--newer-than 7d".to_string() };
+/// assert_eq!(reason.kind(), "filtered_out");
+/// assert_eq!(reason.to_string(), "filtered out by --newer-than 7d");
+/// assert!(SkipReason::Locked.is_transient());
+/// assert!(!SkipReason::UpToDate.is_transient());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Excluded by the `--filter` pattern or an age filter
+    FilteredOut { filter: String },
+    /// Looks like a sample (`processing.skip_samples`)
+    Sample { detail: String },
+    /// Part of a multi-part recording that isn't processed
+    MultiPart { detail: String },
+    /// Outputs can't be written to the target directory
+    TargetNotWritable { directory: PathBuf },
+    /// Left out so the batch fits the target's free space
+    NoSpace,
+    /// Another process holds a lock on the file
+    Locked,
+    /// The batch had already saved the `processing.free_up` target
+    FreeUpReached,
+    /// The file changed after it was found, so it's probably still being written
+    StabilityCheck,
+    /// An existing output already has the planned tracks
+    UpToDate,
+    /// The remux saved less than `processing.min_savings`
+    BelowMinSavings { saved_bytes: u64 },
+    /// The history database records the unchanged file as processed with
+    /// the same settings (`processing.skip_processed`)
+    AlreadyProcessed,
+}
+
+impl SkipReason {
+    /// Name of the reason as used in the result JSON
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SkipReason::FilteredOut { .. } => "filtered_out",
+            SkipReason::Sample { .. } => "sample",
+            SkipReason::MultiPart { .. } => "multi_part",
+            SkipReason::TargetNotWritable { .. } => "target_not_writable",
+            SkipReason::NoSpace => "no_space",
+            SkipReason::Locked => "locked",
+            SkipReason::FreeUpReached => "free_up_reached",
+            SkipReason::StabilityCheck => "stability_check",
+            SkipReason::UpToDate => "up_to_date",
+            SkipReason::BelowMinSavings { .. } => "below_min_savings",
+            SkipReason::AlreadyProcessed => "already_processed",
+        }
+    }
+
+    /// Whether a later run may process the file without any change to the
+    /// library or the settings
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            SkipReason::TargetNotWritable { .. }
+                | SkipReason::NoSpace
+                | SkipReason::Locked
+                | SkipReason::StabilityCheck
+        )
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::FilteredOut { filter } => write!(f, "filtered out by {}", filter),
+            SkipReason::Sample { detail } | SkipReason::MultiPart { detail } => {
+                write!(f, "{}", detail)
+            }
+            SkipReason::TargetNotWritable { directory } => {
+                write!(f, "target directory not writable: {}", directory.display())
+            }
+            SkipReason::NoSpace => write!(f, "not enough free space on target"),
+            SkipReason::Locked => write!(f, "locked by another process"),
+            SkipReason::FreeUpReached => write!(f, "free-up target already reached"),
+            SkipReason::StabilityCheck => {
+                write!(
+                    f,
+                    "changed since it was found - probably still being written"
+                )
+            }
+            SkipReason::UpToDate => write!(f, "existing output is up to date"),
+            SkipReason::BelowMinSavings { saved_bytes } => write!(
+                f,
+                "remux would only save {} - original kept",
+                format_size(*saved_bytes)
+            ),
+            SkipReason::AlreadyProcessed => write!(f, "already processed with the same settings"),
+        }
+    }
+}
+
+/// Exit status of a dry run that found files to remux
+pub const EXIT_CHANGES_PENDING: i32 = 8;
+
+/// Exit status of a run that skipped files for reasons a later run may not
+/// hit (locked, still being written, no space, target not writable)
+pub const EXIT_RETRY_LATER: i32 = 9;
+
+/// Processed files by what happened to them (in a dry run: what would happen)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OutcomeCounts {
+    /// Files remuxed, split included
+    pub remux: usize,
+    /// Unchanged files moved, copied or linked, flags-only and
+    /// attachment-only edits included
+    pub transfer: usize,
+    /// Existing outputs that already matched the planned tracks
+    pub up_to_date: usize,
+}
+
+impl OutcomeCounts {
+    /// Count one processed file
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{OutcomeCounts, ProcessingOutcome};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut counts = OutcomeCounts::default();
+    /// counts.record(&ProcessingOutcome::DryRun {
+    ///     output_path: PathBuf::from("/out/a.mkv"),
+    ///     needs_remux: true,
+    /// });
+    /// assert_eq!(counts.remux, 1);
+    /// ```
+    pub fn record(&mut self, outcome: &ProcessingOutcome) {
+        match outcome {
+            ProcessingOutcome::Remuxed { .. }
+            | ProcessingOutcome::Split { .. }
+            | ProcessingOutcome::DryRun {
+                needs_remux: true, ..
+            } => self.remux += 1,
+            ProcessingOutcome::Transferred { .. }
+            | ProcessingOutcome::FlagsNormalized { .. }
+            | ProcessingOutcome::AttachmentsRemoved { .. }
+            | ProcessingOutcome::BelowMinSavings { .. }
+            | ProcessingOutcome::DryRun {
+                needs_remux: false, ..
+            } => self.transfer += 1,
+            ProcessingOutcome::UpToDate { .. } => self.up_to_date += 1,
+        }
+    }
+}
+
+/// What a batch ran with, kept in its result so `retry-failed` can process
+/// the failed files to the same targets with the same settings
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchRun {
+    /// Source directory the batch collected files from
+    pub source: PathBuf,
+    /// Target directory outputs were placed below
+    pub target: PathBuf,
+    pub recursive: bool,
+    /// Effective configuration, CLI overrides included, in the format of
+    /// `settings.yaml`
+    #[schemars(with = "serde_json::Value")]
+    pub config: Config,
+}
+
+/// Result of a processing run, written by `--result-json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RunSummary {
+    pub dry_run: bool,
+    pub total_files: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// Skipped files by `SkipReason::kind()`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skipped_by_reason: BTreeMap<String, usize>,
+    /// Skipped files a later run may process (`SkipReason::is_transient()`)
+    #[serde(default)]
+    pub retry_later: usize,
+    #[serde(flatten)]
+    pub counts: OutcomeCounts,
+    /// Warnings of a batch by `WarningKind::name()`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub warnings_by_kind: BTreeMap<String, usize>,
+    /// Error of each failed file
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<PathBuf, String>,
+    /// Settings of a batch run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchRun>,
+}
+
+impl RunSummary {
+    /// Whether a dry run found files that still need remuxing. Transfers of
+    /// unchanged files don't count: they would be redone on every run.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{EXIT_CHANGES_PENDING, EXIT_RETRY_LATER, OutcomeCounts, RunSummary};
+    ///
+    /// let mut summary = RunSummary {
+    ///     dry_run: true,
+    ///     counts: OutcomeCounts { remux: 2, ..Default::default() },
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(summary.exit_code(), EXIT_CHANGES_PENDING);
+    ///
+    /// summary.counts.remux = 0;
+    /// summary.counts.transfer = 3;
+    /// assert_eq!(summary.exit_code(), 0);
+    ///
+    /// summary.retry_later = 1;
+    /// assert_eq!(summary.exit_code(), EXIT_RETRY_LATER);
+    /// ```
+    pub fn changes_pending(&self) -> bool {
+        self.dry_run && self.counts.remux > 0
+    }
+
+    /// Process exit status: `EXIT_CHANGES_PENDING` when changes are pending,
+    /// else `EXIT_RETRY_LATER` when files were skipped for transient reasons, 0 otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.changes_pending() {
+            EXIT_CHANGES_PENDING
+        } else if self.retry_later > 0 {
+            EXIT_RETRY_LATER
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,96 @@
+/// Radarr environment context containing the variables Radarr passes to a
+/// custom import script. All fields are stored as raw strings like
+/// `SonarrContext`.
+#[derive(Debug, Clone, Default)]
+pub struct RadarrContext {
+    // File Paths
+    pub source_path: Option<String>,
+    pub destination_path: Option<String>,
+
+    // Instance Information
+    pub instance_name: Option<String>,
+    pub application_url: Option<String>,
+    pub transfer_mode: Option<String>,
+
+    // Movie Metadata
+    pub movie_id: Option<String>,
+    pub movie_title: Option<String>,
+    pub movie_year: Option<String>,
+    pub movie_path: Option<String>,
+    pub movie_imdb_id: Option<String>,
+    pub movie_tmdb_id: Option<String>,
+    pub movie_overview: Option<String>,
+    pub movie_original_language: Option<String>,
+    pub movie_genres: Option<String>,
+    pub movie_tags: Option<String>,
+    pub movie_physical_release_date: Option<String>,
+
+    // Quality and Media Information
+    pub movie_file_quality: Option<String>,
+    pub movie_file_quality_version: Option<String>,
+    pub movie_file_release_group: Option<String>,
+    pub movie_file_scene_name: Option<String>,
+    pub movie_file_media_info_audio_channels: Option<String>,
+    pub movie_file_media_info_audio_codec: Option<String>,
+    pub movie_file_media_info_audio_languages: Option<String>,
+    pub movie_file_media_info_languages: Option<String>,
+    pub movie_file_media_info_height: Option<String>,
+    pub movie_file_media_info_width: Option<String>,
+    pub movie_file_media_info_subtitles: Option<String>,
+    pub movie_file_media_info_video_codec: Option<String>,
+    pub movie_file_media_info_video_dynamic_range_type: Option<String>,
+
+    // Custom Formats
+    pub movie_file_custom_format: Option<String>,
+    pub movie_file_custom_format_score: Option<String>,
+
+    // Download Information
+    pub download_client: Option<String>,
+    pub download_client_type: Option<String>,
+    pub download_id: Option<String>,
+
+    // Deleted Files (for upgrades)
+    pub deleted_relative_paths: Option<String>,
+    pub deleted_paths: Option<String>,
+    pub deleted_date_added: Option<String>,
+    pub deleted_recycle_bin_paths: Option<String>,
+}
+
+impl RadarrContext {
+    /// Value for a tag template placeholder (`{movie_title}` → `movie_title`)
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::RadarrContext;
+    ///
+    /// let context = RadarrContext {
+    ///     movie_title: Some("Movie".to_string()),
+    ///     movie_year: Some("1999".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(context.template_value("movie_title"), Some("Movie"));
+    /// assert_eq!(context.template_value("year"), Some("1999"));
+    /// assert_eq!(context.template_value("series_title"), None);
+    /// ```
+    pub fn template_value(&self, placeholder: &str) -> Option<&str> {
+        let value = match placeholder {
+            "movie_title" => &self.movie_title,
+            "year" => &self.movie_year,
+            "movie_path" => &self.movie_path,
+            "movie_genres" => &self.movie_genres,
+            "overview" => &self.movie_overview,
+            "release_date" => &self.movie_physical_release_date,
+            "imdb_id" => &self.movie_imdb_id,
+            "tmdb_id" => &self.movie_tmdb_id,
+            "release_group" => &self.movie_file_release_group,
+            "quality" => &self.movie_file_quality,
+            _ => return None,
+        };
+        value.as_deref().filter(|v| !v.is_empty())
+    }
+
+    /// Check if any Radarr environment variables were found
+    pub fn is_present(&self) -> bool {
+        self.source_path.is_some() || self.instance_name.is_some() || self.movie_id.is_some()
+    }
+}
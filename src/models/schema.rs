@@ -0,0 +1,64 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::config_error;
+
+/// Version of the JSON documents mkv-slimmer writes (`--result-json`, the
+/// `--console json` lines and `audit untagged` reports).
+///
+/// Within a version, documents only gain optional fields, so readers should
+/// ignore fields they don't know. Removing, renaming or retyping a field, or
+/// giving an existing one a new meaning, bumps the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A JSON document tagged with the version of its schema
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::{RunSummary, SCHEMA_VERSION, Versioned};
+///
+/// let json = serde_json::to_value(Versioned::new(RunSummary::default())).unwrap();
+/// assert_eq!(json["schema_version"], SCHEMA_VERSION);
+/// assert_eq!(json["total_files"], 0);
+///
+/// // Documents from before versioning read as version 0
+/// let old: Versioned<RunSummary> = serde_json::from_str(
+///     r#"{"dry_run":false,"total_files":1,"successful":1,"failed":0,"skipped":0,"remux":1,"transfer":0,"up_to_date":0}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(old.schema_version, 0);
+/// assert!(old.check_version("report").is_ok());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<T> {
+    /// Schema version the document was written with (0: before versioning)
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub document: T,
+}
+
+impl<T> Versioned<T> {
+    /// `document` tagged with the current `SCHEMA_VERSION`
+    pub fn new(document: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            document,
+        }
+    }
+
+    /// Fail for documents written with a newer schema than this build reads
+    pub fn check_version(&self, what: &str) -> Result<()> {
+        if self.schema_version > SCHEMA_VERSION {
+            return Err(config_error(
+                "Schema version",
+                &format!(
+                    "The {} uses schema version {}, but this version of mkv-slimmer reads up to {}. Upgrade mkv-slimmer to read it",
+                    what, self.schema_version, SCHEMA_VERSION
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
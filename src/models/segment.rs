@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// Segment linking found in a Matroska file (ordered chapters, linked segments)
+///
+/// Files like these only play correctly while their segment UIDs and chapter
+/// structure stay intact, so remuxing them needs an explicit opt-in.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::SegmentLinking;
+///
+/// let linking = SegmentLinking {
+///     ordered_chapters: true,
+///     ..Default::default()
+/// };
+/// assert!(linking.is_linked());
+/// assert_eq!(linking.describe(), "ordered chapters");
+/// assert!(!SegmentLinking::default().is_linked());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentLinking {
+    /// UID of this segment as 32 hex digits
+    pub segment_uid: Option<String>,
+    /// UID of the segment played before this one
+    pub previous_uid: Option<String>,
+    /// UID of the segment played after this one
+    pub next_uid: Option<String>,
+    /// An edition is flagged as ordered
+    pub ordered_chapters: bool,
+    /// Chapters play parts of other segments
+    pub external_chapters: bool,
+}
+
+impl SegmentLinking {
+    /// Whether playback depends on the segment structure of the file
+    pub fn is_linked(&self) -> bool {
+        self.ordered_chapters
+            || self.external_chapters
+            || self.previous_uid.is_some()
+            || self.next_uid.is_some()
+    }
+
+    /// Human-readable list of the linking features in use
+    pub fn describe(&self) -> String {
+        let mut features = Vec::new();
+        if self.ordered_chapters {
+            features.push("ordered chapters");
+        }
+        if self.external_chapters {
+            features.push("chapters referencing other segments");
+        }
+        if self.previous_uid.is_some() || self.next_uid.is_some() {
+            features.push("linked previous/next segments");
+        }
+        features.join(", ")
+    }
+}
@@ -67,6 +67,40 @@ pub struct SonarrContext {
 }
 
 impl SonarrContext {
+    /// Value for a tag template placeholder (`{series_title}` → `series_title`)
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::SonarrContext;
+    ///
+    /// let context = SonarrContext {
+    ///     series_title: Some("Show".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(context.template_value("series_title"), Some("Show"));
+    /// assert_eq!(context.template_value("season"), None);
+    /// ```
+    pub fn template_value(&self, placeholder: &str) -> Option<&str> {
+        let value = match placeholder {
+            "series_title" => &self.series_title,
+            "series_type" => &self.series_type,
+            "series_path" => &self.series_path,
+            "series_genres" => &self.series_genres,
+            "season" => &self.episode_file_season_number,
+            "episode" => &self.episode_file_episode_numbers,
+            "episode_title" => &self.episode_file_episode_titles,
+            "air_date" => &self.episode_file_episode_air_dates,
+            "overview" => &self.episode_file_episode_overviews,
+            "imdb_id" => &self.series_imdb_id,
+            "tvdb_id" => &self.series_tvdb_id,
+            "tmdb_id" => &self.series_tmdb_id,
+            "release_group" => &self.episode_file_release_group,
+            "quality" => &self.episode_file_quality,
+            _ => return None,
+        };
+        value.as_deref().filter(|v| !v.is_empty())
+    }
+
     /// Check if any Sonarr environment variables were found
     pub fn is_present(&self) -> bool {
         self.source_path.is_some() || self.instance_name.is_some() || self.series_id.is_some()
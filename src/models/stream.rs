@@ -1,3 +1,7 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
 /// Represents the different types of streams found in MKV files
 ///
 /// MKV files can contain multiple stream types, each serving different purposes:
@@ -5,7 +9,8 @@
 /// - Audio streams contain sound tracks in different languages
 /// - Subtitle streams provide text overlays in different languages
 /// - Attachment streams contain fonts, cover art, or other embedded files
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum StreamType {
     /// Video stream containing visual content
     Video,
@@ -31,6 +36,30 @@ impl std::fmt::Display for StreamType {
     }
 }
 
+/// Where a stream's language code came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageSource {
+    /// The file's own language tag (or none at all)
+    #[default]
+    Tag,
+    /// Inferred from a language name in the track title
+    Title,
+    /// Set by the user (`processing.language_overrides`)
+    Override,
+    /// Reported by Sonarr/Radarr (`processing.trust_import_languages`)
+    Import,
+}
+
+/// Location of a stream that lives in a separate file and is muxed into the output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalTrack {
+    /// File the stream is read from
+    pub path: PathBuf,
+    /// mkvmerge track ID of the stream within that file
+    pub track_id: u32,
+}
+
 /// Contains detailed information about a single stream within an MKV file
 ///
 /// This struct aggregates metadata from multiple sources (ffprobe, matroska parser)
@@ -46,16 +75,30 @@ impl std::fmt::Display for StreamType {
 /// assert_eq!(video_stream.index, 0);
 /// assert_eq!(video_stream.stream_type, StreamType::Video);
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamInfo {
-    /// Zero-based index of the stream within the MKV file
+    /// Zero-based index of the stream within the MKV file. For external streams
+    /// this only identifies the stream within the combined track set.
     pub index: u32,
+    /// Matroska TrackUID, if known: unlike the index it doesn't depend on how
+    /// the analyzer numbered the streams
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u64>,
+    /// Set for streams merged in from a separate file (e.g. companion audio)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external: Option<ExternalTrack>,
     /// The type of stream (video, audio, subtitle, attachment, unknown)
     pub stream_type: StreamType,
     /// Codec name (e.g., "h264", "aac", "subrip")
     pub codec: String,
+    /// Codec profile or variant (e.g., "DTS-HD MA", "LC"), if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
     /// Language code if available (e.g., "eng", "jpn", "fre")
     pub language: Option<String>,
+    /// Where `language` came from; anything but `Tag` corrects the file's metadata
+    #[serde(default)]
+    pub language_source: LanguageSource,
     /// Human-readable title or description of the stream
     pub title: Option<String>,
     /// Whether this stream is marked as default for its type
@@ -64,6 +107,8 @@ pub struct StreamInfo {
     pub forced: bool,
     /// Size of the stream in bytes, if calculable
     pub size_bytes: Option<u64>,
+    /// Whether `size_bytes` is a heuristic estimate rather than a measured value
+    pub size_estimated: bool,
     /// Duration of the stream in seconds
     pub duration_seconds: Option<f64>,
 
@@ -86,6 +131,12 @@ pub struct StreamInfo {
     // Subtitle-specific fields
     /// Subtitle format (e.g., "subrip", "ass", "vobsub")
     pub subtitle_format: Option<String>,
+
+    /// Whether the stream is an embedded cover picture: an attachment named
+    /// like Matroska cover art, which ffprobe reports as an mjpeg/png video
+    /// stream with the `attached_pic` disposition
+    #[serde(default)]
+    pub cover_art: bool,
 }
 
 impl StreamInfo {
@@ -110,13 +161,18 @@ impl StreamInfo {
     pub fn new(index: u32, stream_type: StreamType) -> Self {
         Self {
             index,
+            uid: None,
+            external: None,
             stream_type,
             codec: "unknown".to_string(),
+            profile: None,
             language: None,
+            language_source: LanguageSource::Tag,
             title: None,
             default: false,
             forced: false,
             size_bytes: None,
+            size_estimated: false,
             duration_seconds: None,
             resolution: None,
             framerate: None,
@@ -125,6 +181,7 @@ impl StreamInfo {
             sample_rate: None,
             bitrate: None,
             subtitle_format: None,
+            cover_art: false,
         }
     }
 
@@ -170,4 +227,53 @@ impl StreamInfo {
             .filter(|s| !s.is_empty())
             .unwrap_or("und")
     }
+
+    /// Type of the element the stream is stored as in the file: cover art is
+    /// an attachment even where ffprobe reports it as a video stream
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{StreamInfo, StreamType};
+    ///
+    /// let mut cover = StreamInfo::new(3, StreamType::Video);
+    /// assert_eq!(cover.container_type(), StreamType::Video);
+    /// cover.cover_art = true;
+    /// assert_eq!(cover.container_type(), StreamType::Attachment);
+    /// ```
+    pub fn container_type(&self) -> StreamType {
+        if self.cover_art {
+            StreamType::Attachment
+        } else {
+            self.stream_type
+        }
+    }
+
+    /// Audio format as `audio.codec_preference` names it: the codec, except
+    /// that DTS-HD (MA, HRA or DTS:X) is `dts-hd` and PCM variants are `pcm`
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::{StreamInfo, StreamType};
+    ///
+    /// let mut stream = StreamInfo::new(1, StreamType::Audio);
+    /// stream.codec = "dts".to_string();
+    /// assert_eq!(stream.audio_format(), "dts");
+    ///
+    /// stream.profile = Some("DTS-HD MA".to_string());
+    /// assert_eq!(stream.audio_format(), "dts-hd");
+    ///
+    /// stream.codec = "pcm_s24le".to_string();
+    /// assert_eq!(stream.audio_format(), "pcm");
+    /// ```
+    pub fn audio_format(&self) -> String {
+        let codec = self.codec.to_lowercase();
+        let profile = self.profile.as_deref().unwrap_or_default().to_lowercase();
+        if codec == "dts" && (profile.contains("dts-hd") || profile.contains("dts:x")) {
+            "dts-hd".to_string()
+        } else if codec.starts_with("pcm_") {
+            "pcm".to_string()
+        } else {
+            codec
+        }
+    }
 }
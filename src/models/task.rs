@@ -1,16 +1,29 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use super::media::MediaKind;
+use super::segment::SegmentLinking;
 use super::stream::StreamInfo;
+use super::track_ids::TrackIdMap;
 
 /// Lightweight struct holding file-specific processing information
 /// Global info (config, sonarr context) is passed separately to processing functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingTask {
     pub source_file: PathBuf,
     pub target_location: PathBuf,
     pub streams: Vec<StreamInfo>,
     pub output_filename: Option<String>,
+    /// Set when the source uses ordered chapters or linked segments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_linking: Option<SegmentLinking>,
+    /// mkvmerge IDs of the source's streams
+    #[serde(default)]
+    pub track_ids: TrackIdMap,
+    /// Number of chapters in the source; None when it couldn't be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapters: Option<usize>,
 }
 
 impl ProcessingTask {
@@ -20,14 +33,60 @@ impl ProcessingTask {
         streams: Vec<StreamInfo>,
         output_filename: Option<String>,
     ) -> Self {
+        let track_ids = TrackIdMap::from_streams(&streams);
         Self {
             source_file,
             target_location,
             streams,
             output_filename,
+            segment_linking: None,
+            track_ids,
+            chapters: None,
         }
     }
 
+    /// Record how the source's streams map to mkvmerge IDs
+    pub fn with_track_ids(mut self, track_ids: TrackIdMap) -> Self {
+        self.track_ids = track_ids;
+        self
+    }
+
+    /// Record the segment linking found in the source (None if it has none)
+    pub fn with_segment_linking(mut self, segment_linking: Option<SegmentLinking>) -> Self {
+        self.segment_linking = segment_linking;
+        self
+    }
+
+    /// Record the number of chapters found in the source
+    pub fn with_chapters(mut self, chapters: Option<usize>) -> Self {
+        self.chapters = chapters;
+        self
+    }
+
+    /// Whether the source has chapters that `processing.chapters: strip`
+    /// would remove
+    pub fn has_chapters(&self) -> bool {
+        self.chapters.is_some_and(|count| count > 0)
+    }
+
+    /// Whether the output replaces the source itself (`--in-place`)
+    ///
+    /// # Examples
+    /// ```rust
+    /// use mkv_slimmer::models::ProcessingTask;
+    /// use std::path::PathBuf;
+    ///
+    /// let source = PathBuf::from("/movies/Movie.mkv");
+    /// let in_place = ProcessingTask::new(source.clone(), "/movies".into(), Vec::new(), None);
+    /// assert!(in_place.is_in_place());
+    /// let copy = ProcessingTask::new(source, "/slim".into(), Vec::new(), None);
+    /// assert!(!copy.is_in_place());
+    /// ```
+    pub fn is_in_place(&self) -> bool {
+        self.generate_output_path()
+            .is_ok_and(|output_path| output_path == self.source_file)
+    }
+
     /// Generate the full output path for this processing task
     pub fn generate_output_path(&self) -> Result<PathBuf> {
         let output_path = match &self.output_filename {
@@ -45,6 +104,11 @@ impl ProcessingTask {
         Ok(output_path)
     }
 
+    /// Kind of container being processed, derived from the source extension
+    pub fn media_kind(&self) -> MediaKind {
+        MediaKind::from_path(&self.source_file)
+    }
+
     /// Get the source filename for display purposes
     pub fn source_filename(&self) -> String {
         self.source_file
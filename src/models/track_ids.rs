@@ -0,0 +1,315 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::stream::{StreamInfo, StreamType};
+use crate::error::config_error;
+
+/// A stream named in the settings: by its index in the analysis (`2`) or by
+/// its Matroska track UID (`uid:123456789`), which stays valid whichever
+/// analyzer numbered the streams
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::{StreamInfo, StreamType, TrackRef};
+///
+/// let mut stream = StreamInfo::new(2, StreamType::Audio);
+/// stream.uid = Some(123456789);
+///
+/// assert!("2".parse::<TrackRef>().unwrap().matches(&stream));
+/// assert!("uid:123456789".parse::<TrackRef>().unwrap().matches(&stream));
+/// assert!(!"uid:2".parse::<TrackRef>().unwrap().matches(&stream));
+/// assert_eq!(TrackRef::Uid(42).to_string(), "uid:42");
+/// assert!("audio".parse::<TrackRef>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawTrackRef", into = "String")]
+pub enum TrackRef {
+    Index(u32),
+    Uid(u64),
+}
+
+/// Stream indices may be written as bare numbers in YAML
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTrackRef {
+    Index(u32),
+    Text(String),
+}
+
+impl TrackRef {
+    /// Whether `stream` is the stream this names
+    pub fn matches(&self, stream: &StreamInfo) -> bool {
+        match self {
+            TrackRef::Index(index) => stream.index == *index,
+            TrackRef::Uid(uid) => stream.uid == Some(*uid),
+        }
+    }
+}
+
+impl TryFrom<RawTrackRef> for TrackRef {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawTrackRef) -> Result<Self> {
+        match raw {
+            RawTrackRef::Index(index) => Ok(TrackRef::Index(index)),
+            RawTrackRef::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<TrackRef> for String {
+    fn from(track: TrackRef) -> Self {
+        track.to_string()
+    }
+}
+
+impl std::fmt::Display for TrackRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackRef::Index(index) => write!(f, "{}", index),
+            TrackRef::Uid(uid) => write!(f, "uid:{}", uid),
+        }
+    }
+}
+
+impl std::str::FromStr for TrackRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let parsed = match trimmed.strip_prefix("uid:") {
+            Some(uid) => uid.trim().parse().ok().map(TrackRef::Uid),
+            None => trimmed.parse().ok().map(TrackRef::Index),
+        };
+        parsed.ok_or_else(|| {
+            config_error(
+                "Track",
+                &format!(
+                    "Invalid track '{}'. Use a stream index or uid:<track UID>",
+                    s
+                ),
+            )
+        })
+    }
+}
+
+/// How mkvmerge addresses a stream of its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MkvmergeId {
+    /// Track ID (0-based, track entry order): `--audio-tracks`,
+    /// `--default-track-flag`, mkvextract `tracks`; mkvpropedit's `track:N`
+    /// is the ID + 1
+    Track(u32),
+    /// Attachment ID (1-based): `--attachments`
+    Attachment(u32),
+}
+
+/// A track or attachment as a tool other than the analyzer numbers it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownTrack {
+    pub stream_type: StreamType,
+    pub mkvmerge_id: MkvmergeId,
+    /// TrackNumber element of the track entry, if known
+    pub track_number: Option<u64>,
+    /// TrackUID element of the track entry, if known
+    pub uid: Option<u64>,
+}
+
+/// The IDs one analyzed stream has for mkvmerge and in the Matroska file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackIds {
+    pub mkvmerge: MkvmergeId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u64>,
+}
+
+/// Stream index (as the analysis numbered it) → mkvmerge track/attachment ID
+/// and Matroska track number and UID, for the source file's own streams.
+/// ffprobe skips tracks it can't read and numbers attachments after the
+/// tracks, so its indices don't always match what mkvmerge selects by.
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::models::{KnownTrack, MkvmergeId, StreamInfo, StreamType, TrackIdMap};
+///
+/// // ffprobe didn't list mkvmerge's track 1 (a data track), so its audio is #1
+/// let streams = vec![
+///     StreamInfo::new(0, StreamType::Video),
+///     StreamInfo::new(1, StreamType::Audio),
+///     StreamInfo::new(2, StreamType::Attachment),
+/// ];
+/// let known = [
+///     KnownTrack { stream_type: StreamType::Video, mkvmerge_id: MkvmergeId::Track(0), track_number: Some(1), uid: Some(11) },
+///     KnownTrack { stream_type: StreamType::Unknown, mkvmerge_id: MkvmergeId::Track(1), track_number: Some(2), uid: Some(12) },
+///     KnownTrack { stream_type: StreamType::Audio, mkvmerge_id: MkvmergeId::Track(2), track_number: Some(3), uid: Some(13) },
+///     KnownTrack { stream_type: StreamType::Attachment, mkvmerge_id: MkvmergeId::Attachment(1), track_number: None, uid: None },
+/// ];
+/// let map = TrackIdMap::resolve(&streams, &known);
+/// assert_eq!(map.track_id(1), 2);
+/// assert_eq!(map.track_number(1), Some(3));
+/// assert_eq!(map.uid(1), Some(13));
+/// assert_eq!(map.attachment_id(2), Some(1));
+/// assert!(map.differs());
+///
+/// // Streams that know their UID are paired by it, whatever their order
+/// let mut swapped = vec![StreamInfo::new(0, StreamType::Audio), StreamInfo::new(1, StreamType::Audio)];
+/// swapped[0].uid = Some(22);
+/// swapped[1].uid = Some(21);
+/// let known = [
+///     KnownTrack { stream_type: StreamType::Audio, mkvmerge_id: MkvmergeId::Track(0), track_number: Some(1), uid: Some(21) },
+///     KnownTrack { stream_type: StreamType::Audio, mkvmerge_id: MkvmergeId::Track(1), track_number: Some(2), uid: Some(22) },
+/// ];
+/// let map = TrackIdMap::resolve(&swapped, &known);
+/// assert_eq!(map.track_id(0), 1);
+/// assert_eq!(map.track_id(1), 0);
+///
+/// // Without a resolved map, stream indices are used as they are
+/// assert_eq!(TrackIdMap::from_streams(&streams).track_id(1), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackIdMap {
+    /// Stored as `[index, ids]` pairs: integer map keys don't survive the
+    /// `#[serde(flatten)]` of `Versioned` documents in JSON
+    #[serde(with = "index_pairs")]
+    ids: BTreeMap<u32, TrackIds>,
+}
+
+impl TrackIdMap {
+    /// Pair the analyzed streams with `known` tracks: a stream whose UID is
+    /// known is the known track with that UID; otherwise the n-th remaining
+    /// stream of a type is the n-th remaining known track of that type, the
+    /// n-th attachment (cover art included) the n-th known attachment.
+    /// Streams without a counterpart stay unmapped.
+    pub fn resolve(streams: &[StreamInfo], known: &[KnownTrack]) -> Self {
+        let mut ids = BTreeMap::new();
+        let track_ids = |track: &KnownTrack| TrackIds {
+            mkvmerge: track.mkvmerge_id,
+            track_number: track.track_number,
+            uid: track.uid,
+        };
+        for stream_type in [
+            StreamType::Video,
+            StreamType::Audio,
+            StreamType::Subtitle,
+            StreamType::Attachment,
+        ] {
+            let mut of_type: Vec<&StreamInfo> = streams
+                .iter()
+                .filter(|s| s.container_type() == stream_type && s.external.is_none())
+                .collect();
+            of_type.sort_by_key(|s| s.index);
+            let mut counterparts: Vec<&KnownTrack> = known
+                .iter()
+                .filter(|k| k.stream_type == stream_type)
+                .collect();
+
+            of_type.retain(|stream| {
+                let Some(position) = counterparts
+                    .iter()
+                    .position(|track| stream.uid.is_some() && track.uid == stream.uid)
+                else {
+                    return true;
+                };
+                ids.insert(stream.index, track_ids(counterparts.remove(position)));
+                false
+            });
+            for (stream, track) in of_type.into_iter().zip(counterparts) {
+                ids.insert(stream.index, track_ids(track));
+            }
+        }
+        TrackIdMap { ids }
+    }
+
+    /// The map that holds when the analysis numbered streams like mkvmerge:
+    /// track IDs are the stream indices, attachments are counted from 1
+    pub fn from_streams(streams: &[StreamInfo]) -> Self {
+        let mut attachments = 0;
+        let known: Vec<KnownTrack> = streams
+            .iter()
+            .filter(|s| s.external.is_none())
+            .map(|s| {
+                let mkvmerge_id = if s.container_type() == StreamType::Attachment {
+                    attachments += 1;
+                    MkvmergeId::Attachment(attachments)
+                } else {
+                    MkvmergeId::Track(s.index)
+                };
+                KnownTrack {
+                    stream_type: s.container_type(),
+                    mkvmerge_id,
+                    track_number: None,
+                    uid: s.uid,
+                }
+            })
+            .collect();
+        Self::resolve(streams, &known)
+    }
+
+    /// mkvmerge track ID of a stream; the stream index when it isn't mapped
+    pub fn track_id(&self, index: u32) -> u32 {
+        match self.ids.get(&index).map(|ids| ids.mkvmerge) {
+            Some(MkvmergeId::Track(id)) => id,
+            _ => index,
+        }
+    }
+
+    /// mkvmerge attachment ID of an attachment stream
+    pub fn attachment_id(&self, index: u32) -> Option<u32> {
+        match self.ids.get(&index)?.mkvmerge {
+            MkvmergeId::Attachment(id) => Some(id),
+            MkvmergeId::Track(_) => None,
+        }
+    }
+
+    /// Matroska track number of a stream, if known
+    pub fn track_number(&self, index: u32) -> Option<u64> {
+        self.ids.get(&index)?.track_number
+    }
+
+    /// Matroska track UID of a stream, if known
+    pub fn uid(&self, index: u32) -> Option<u64> {
+        self.ids.get(&index)?.uid
+    }
+
+    /// mkvpropedit selector of a track: `track:=UID` when its UID is known,
+    /// which can't pick the wrong track, else its position (`track:N`,
+    /// the mkvmerge track ID + 1)
+    pub fn propedit_selector(&self, index: u32) -> String {
+        match self.uid(index) {
+            Some(uid) => format!("track:={}", uid),
+            None => format!("track:{}", self.track_id(index) + 1),
+        }
+    }
+
+    /// Whether any track ID differs from its stream index
+    pub fn differs(&self) -> bool {
+        self.ids
+            .iter()
+            .any(|(index, ids)| matches!(ids.mkvmerge, MkvmergeId::Track(id) if id != *index))
+    }
+}
+
+mod index_pairs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    use super::TrackIds;
+
+    pub fn serialize<S: Serializer>(
+        ids: &BTreeMap<u32, TrackIds>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<u32, TrackIds>, D::Error> {
+        let pairs: Vec<(u32, TrackIds)> = Vec::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
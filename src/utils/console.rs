@@ -0,0 +1,150 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::config_error;
+use crate::models::Versioned;
+
+/// How console messages are written
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::ConsoleMode;
+///
+/// assert_eq!("json".parse::<ConsoleMode>().unwrap(), ConsoleMode::Json);
+/// assert_eq!("Quiet".parse::<ConsoleMode>().unwrap(), ConsoleMode::Quiet);
+/// assert!("verbose".parse::<ConsoleMode>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConsoleMode {
+    /// Status lines as they are (default)
+    #[default]
+    Human,
+    /// Only warnings and errors
+    Quiet,
+    /// One JSON object per line with level, file and message
+    Json,
+}
+
+impl std::str::FromStr for ConsoleMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "human" => Ok(ConsoleMode::Human),
+            "quiet" => Ok(ConsoleMode::Quiet),
+            "json" => Ok(ConsoleMode::Json),
+            _ => Err(config_error(
+                "Console output",
+                &format!("Unknown mode '{}'. Use 'human', 'quiet' or 'json'", s),
+            )),
+        }
+    }
+}
+
+/// Severity of a console message; warnings and errors go to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warning,
+}
+
+/// A message as written in JSON mode, one per line
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConsoleLine<'a> {
+    pub level: Level,
+    /// File the message is about, during batch processing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    pub message: &'a str,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Held while a message is written, so lines from concurrent tasks don't interleave
+static CONSOLE: Mutex<()> = Mutex::new(());
+
+tokio::task_local! {
+    static CURRENT_FILE: PathBuf;
+}
+
+/// Switch the console mode for the rest of the process
+pub fn set_console_mode(mode: ConsoleMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn console_mode() -> ConsoleMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => ConsoleMode::Quiet,
+        2 => ConsoleMode::Json,
+        _ => ConsoleMode::Human,
+    }
+}
+
+/// Run `future` with its messages attributed to `file`
+pub async fn with_file<F: Future>(file: &Path, future: F) -> F::Output {
+    CURRENT_FILE.scope(file.to_path_buf(), future).await
+}
+
+/// The file the running task's messages are attributed to
+pub fn current_file() -> Option<PathBuf> {
+    CURRENT_FILE.try_with(|file| file.clone()).ok()
+}
+
+/// Write a message in the current mode. Use the `report!` and `report_warn!`
+/// macros instead of calling this directly.
+pub fn emit(level: Level, message: fmt::Arguments) {
+    let mode = console_mode();
+    if mode == ConsoleMode::Quiet && level == Level::Info {
+        return;
+    }
+
+    let _console = CONSOLE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match (mode, level) {
+        (ConsoleMode::Json, _) => {
+            let message = message.to_string();
+            let message = message.trim();
+            if message.is_empty() {
+                return;
+            }
+            let line = Versioned::new(ConsoleLine {
+                level,
+                file: current_file(),
+                message,
+            });
+            if let Ok(json) = serde_json::to_string(&line) {
+                println!("{}", json);
+            }
+        }
+        (_, Level::Info) => println!("{}", message),
+        (_, Level::Warning) => eprintln!("{}", message),
+    }
+}
+
+/// Print a status line through the console output layer (stdout; hidden in
+/// quiet mode)
+#[macro_export]
+macro_rules! report {
+    () => {
+        $crate::utils::console::emit($crate::utils::console::Level::Info, format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::utils::console::emit($crate::utils::console::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Print a warning or error through the console output layer (stderr)
+#[macro_export]
+macro_rules! report_warn {
+    ($($arg:tt)*) => {
+        $crate::utils::console::emit($crate::utils::console::Level::Warning, format_args!($($arg)*))
+    };
+}
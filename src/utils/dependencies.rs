@@ -1,22 +1,146 @@
 use crate::error::dependency_error;
 use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Command;
 
-/// Check for required external dependencies
-pub fn check_dependencies() -> Result<Vec<String>> {
-    let mut missing = Vec::new();
+const MKVTOOLNIX_SUGGESTION: &str = "Install MKVToolNix from https://mkvtoolnix.download/ or use your package manager (apt install mkvtoolnix, brew install mkvtoolnix, etc.)";
 
-    // Check for ffprobe (optional but recommended)
-    if which::which("ffprobe").is_err() {
-        missing.push("ffprobe".to_string());
+/// An external tool and what happens without it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolInfo {
+    pub name: &'static str,
+    /// Features that are limited or fail when the tool is missing
+    pub needed_for: &'static str,
+    pub install: &'static str,
+}
+
+/// Tools that are optional: processing works without them, some features don't
+pub const OPTIONAL_TOOLS: [ToolInfo; 3] = [
+    ToolInfo {
+        name: "ffprobe",
+        needed_for: "files mkvmerge can't identify are analyzed from Matroska data alone; --prefer-source ffprobe has no effect",
+        install: "Install ffmpeg (apt install ffmpeg, brew install ffmpeg, etc.)",
+    },
+    ToolInfo {
+        name: "mkvpropedit",
+        needed_for: "flag-only changes need a full remux; --flags-only, --scrub metadata, fix-languages, chapters/tags import and tags strip are unavailable",
+        install: MKVTOOLNIX_SUGGESTION,
+    },
+    ToolInfo {
+        name: "mkvextract",
+        needed_for: "subtitle charsets aren't fixed; chapters/tags export is unavailable",
+        install: MKVTOOLNIX_SUGGESTION,
+    },
+];
+
+/// Where an external tool was found and the version it reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolStatus {
+    pub name: &'static str,
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+impl ToolStatus {
+    pub fn is_available(&self) -> bool {
+        self.path.is_some()
     }
+}
+
+impl std::fmt::Display for ToolStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.path, &self.version) {
+            (None, _) => write!(f, "{} missing", self.name),
+            (Some(_), Some(version)) => write!(f, "{} {}", self.name, version),
+            (Some(_), None) => write!(f, "{} (unknown version)", self.name),
+        }
+    }
+}
+
+/// Whether `tool` is on the PATH
+pub fn tool_available(tool: &str) -> bool {
+    which::which(tool).is_ok()
+}
 
+/// Find `name` on the PATH and ask it for its version
+pub fn detect_tool(name: &'static str) -> ToolStatus {
+    let path = which::which(name).ok();
+    let version = path.as_ref().and_then(|path| {
+        let flag = if name == "ffprobe" {
+            "-version"
+        } else {
+            "--version"
+        };
+        let output = Command::new(path).arg(flag).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_tool_version(&String::from_utf8_lossy(&output.stdout))
+    });
+    ToolStatus {
+        name,
+        path,
+        version,
+    }
+}
+
+/// The MKVToolNix tools and ffprobe as found on the PATH
+pub fn detect_tools() -> Vec<ToolStatus> {
+    ["mkvmerge", "mkvpropedit", "mkvextract", "ffprobe"]
+        .into_iter()
+        .map(detect_tool)
+        .collect()
+}
+
+/// Version number from the first line of a tool's version output
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::parse_tool_version;
+///
+/// assert_eq!(
+///     parse_tool_version("mkvpropedit v81.0 ('Milliontown') 64-bit\n").as_deref(),
+///     Some("v81.0")
+/// );
+/// assert_eq!(
+///     parse_tool_version("ffprobe version 6.1.1-3ubuntu5 Copyright (c) 2007-2023\n").as_deref(),
+///     Some("6.1.1-3ubuntu5")
+/// );
+/// assert_eq!(parse_tool_version(""), None);
+/// ```
+pub fn parse_tool_version(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    let mut words = line.split_whitespace().skip(1);
+    let word = words.next()?;
+    let version = if word == "version" {
+        words.next()?
+    } else {
+        word
+    };
+    version
+        .trim_start_matches('v')
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| version.to_string())
+}
+
+/// Check for required external dependencies; returns the optional tools
+/// that are missing
+pub fn check_dependencies() -> Result<Vec<ToolInfo>> {
     // Check for mkvmerge (required for actual modifications)
-    if which::which("mkvmerge").is_err() {
-        return Err(dependency_error(
-            "mkvmerge",
-            "Install MKVToolNix from https://mkvtoolnix.download/ or use your package manager (apt install mkvtoolnix, brew install mkvtoolnix, etc.)",
-        ));
+    if !tool_available("mkvmerge") {
+        return Err(dependency_error("mkvmerge", MKVTOOLNIX_SUGGESTION));
     }
 
-    Ok(missing)
+    Ok(OPTIONAL_TOOLS
+        .into_iter()
+        .filter(|tool| !tool_available(tool.name))
+        .collect())
+}
+
+/// Fail with installation advice if an MKVToolNix tool is not on the PATH
+pub fn require_mkvtoolnix_tool(tool: &str) -> Result<()> {
+    if !tool_available(tool) {
+        return Err(dependency_error(tool, MKVTOOLNIX_SUGGESTION));
+    }
+    Ok(())
 }
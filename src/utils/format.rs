@@ -1,7 +1,139 @@
-/// Formats a byte count into a human-readable size string
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::config_error;
+
+/// Units sizes are shown in
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::SizeUnits;
+///
+/// assert_eq!("si".parse::<SizeUnits>().unwrap(), SizeUnits::Si);
+/// assert!("metric".parse::<SizeUnits>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// Powers of 1024: KB, MB, GB, TB (default)
+    #[default]
+    Binary,
+    /// Powers of 1000: kB, MB, GB, TB
+    Si,
+    /// Exact byte counts, for scripts
+    Bytes,
+}
+
+impl std::str::FromStr for SizeUnits {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "binary" => Ok(SizeUnits::Binary),
+            "si" => Ok(SizeUnits::Si),
+            "bytes" => Ok(SizeUnits::Bytes),
+            _ => Err(config_error(
+                "Size units",
+                &format!("Unknown units '{}'. Use 'binary', 'si' or 'bytes'", s),
+            )),
+        }
+    }
+}
+
+/// Thousands separator, which also decides the decimal mark
+/// (`1,234.5`, `1.234,5`, `1 234,5`, `1'234.5`)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::DigitGrouping;
 ///
-/// Converts bytes into appropriate units (B, KB, MB, GB, TB) with one decimal place.
-/// Uses binary (1024) conversion rather than decimal (1000).
+/// assert_eq!("period".parse::<DigitGrouping>().unwrap(), DigitGrouping::Period);
+/// assert!("dot".parse::<DigitGrouping>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DigitGrouping {
+    /// No separator, `.` as decimal mark (default)
+    #[default]
+    None,
+    Comma,
+    Period,
+    Space,
+    Apostrophe,
+}
+
+impl DigitGrouping {
+    fn separator(self) -> Option<char> {
+        match self {
+            DigitGrouping::None => None,
+            DigitGrouping::Comma => Some(','),
+            DigitGrouping::Period => Some('.'),
+            DigitGrouping::Space => Some(' '),
+            DigitGrouping::Apostrophe => Some('\''),
+        }
+    }
+
+    fn decimal_mark(self) -> char {
+        match self {
+            DigitGrouping::Period | DigitGrouping::Space => ',',
+            _ => '.',
+        }
+    }
+}
+
+impl std::str::FromStr for DigitGrouping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(DigitGrouping::None),
+            "comma" => Ok(DigitGrouping::Comma),
+            "period" => Ok(DigitGrouping::Period),
+            "space" => Ok(DigitGrouping::Space),
+            "apostrophe" => Ok(DigitGrouping::Apostrophe),
+            _ => Err(config_error(
+                "Digit grouping",
+                &format!(
+                    "Unknown grouping '{}'. Use 'none', 'comma', 'period', 'space' or 'apostrophe'",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// How `format_size` and `format_number` write numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub units: SizeUnits,
+    pub grouping: DigitGrouping,
+}
+
+static SIZE_UNITS_SETTING: AtomicU8 = AtomicU8::new(0);
+static GROUPING_SETTING: AtomicU8 = AtomicU8::new(0);
+
+/// Switch the number format for the rest of the process
+pub fn set_number_format(format: NumberFormat) {
+    SIZE_UNITS_SETTING.store(format.units as u8, Ordering::Relaxed);
+    GROUPING_SETTING.store(format.grouping as u8, Ordering::Relaxed);
+}
+
+pub fn number_format() -> NumberFormat {
+    let units = match SIZE_UNITS_SETTING.load(Ordering::Relaxed) {
+        1 => SizeUnits::Si,
+        2 => SizeUnits::Bytes,
+        _ => SizeUnits::Binary,
+    };
+    let grouping = match GROUPING_SETTING.load(Ordering::Relaxed) {
+        1 => DigitGrouping::Comma,
+        2 => DigitGrouping::Period,
+        3 => DigitGrouping::Space,
+        4 => DigitGrouping::Apostrophe,
+        _ => DigitGrouping::None,
+    };
+    NumberFormat { units, grouping }
+}
+
+/// Formats a byte count into a human-readable size string in the process's
+/// number format (binary units with one decimal place unless changed)
 ///
 /// # Arguments
 /// * `size_bytes` - The size in bytes to format
@@ -19,14 +151,269 @@
 /// assert_eq!(format_size(1048576), "1.0 MB");
 /// ```
 pub fn format_size(size_bytes: u64) -> String {
-    const SIZE_UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    format_size_with(size_bytes, number_format())
+}
+
+/// Formats a byte count in the given number format
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::{DigitGrouping, NumberFormat, SizeUnits, format_size_with};
+///
+/// let si = NumberFormat { units: SizeUnits::Si, ..Default::default() };
+/// assert_eq!(format_size_with(1500, si), "1.5 kB");
+///
+/// let bytes = NumberFormat { units: SizeUnits::Bytes, grouping: DigitGrouping::Comma };
+/// assert_eq!(format_size_with(1234567, bytes), "1,234,567 B");
+///
+/// let german = NumberFormat { units: SizeUnits::Binary, grouping: DigitGrouping::Period };
+/// assert_eq!(format_size_with(1536, german), "1,5 KB");
+/// ```
+pub fn format_size_with(size_bytes: u64, format: NumberFormat) -> String {
+    let (base, units): (f64, &[&str]) = match format.units {
+        SizeUnits::Binary => (1024.0, &["B", "KB", "MB", "GB", "TB"]),
+        SizeUnits::Si => (1000.0, &["B", "kB", "MB", "GB", "TB"]),
+        SizeUnits::Bytes => return format!("{} B", group_digits(size_bytes, format.grouping)),
+    };
     let mut size_value = size_bytes as f64;
     let mut current_unit_index = 0;
 
-    while size_value >= 1024.0 && current_unit_index < SIZE_UNITS.len() - 1 {
-        size_value /= 1024.0;
+    while size_value >= base && current_unit_index < units.len() - 1 {
+        size_value /= base;
         current_unit_index += 1;
     }
 
-    format!("{:.1} {}", size_value, SIZE_UNITS[current_unit_index])
+    format!(
+        "{} {}",
+        format_decimal(size_value, format.grouping),
+        units[current_unit_index]
+    )
+}
+
+/// Formats a count with the process's thousands separator
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::format_number;
+///
+/// assert_eq!(format_number(1234), "1234");
+/// ```
+pub fn format_number(value: u64) -> String {
+    group_digits(value, number_format().grouping)
+}
+
+/// Formats a percentage with one decimal place and the process's decimal mark
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::format_percent;
+///
+/// assert_eq!(format_percent(12.34), "12.3%");
+/// ```
+pub fn format_percent(value: f64) -> String {
+    format!("{}%", format_decimal(value, number_format().grouping))
+}
+
+fn group_digits(value: u64, grouping: DigitGrouping) -> String {
+    let digits = value.to_string();
+    let Some(separator) = grouping.separator() else {
+        return digits;
+    };
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// One decimal place, with grouping and decimal mark applied
+fn format_decimal(value: f64, grouping: DigitGrouping) -> String {
+    let tenths = (value * 10.0).round() as u64;
+    format!(
+        "{}{}{}",
+        group_digits(tenths / 10, grouping),
+        grouping.decimal_mark(),
+        tenths % 10
+    )
+}
+
+/// Units accepted by `parse_size`, largest first (always binary)
+const SIZE_UNITS: &[(&str, f64)] = &[
+    ("T", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("G", 1024.0 * 1024.0 * 1024.0),
+    ("M", 1024.0 * 1024.0),
+    ("K", 1024.0),
+];
+
+/// Parses a size such as `500G`, `1.5TB`, `700MiB` or a bare byte count
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::parse_size;
+///
+/// assert_eq!(parse_size("500G").unwrap(), 500 * 1024 * 1024 * 1024);
+/// assert_eq!(parse_size("1.5 KB").unwrap(), 1536);
+/// assert_eq!(parse_size("700MiB").unwrap(), 700 * 1024 * 1024);
+/// assert_eq!(parse_size("4096").unwrap(), 4096);
+/// assert!(parse_size("lots").is_err());
+/// ```
+pub fn parse_size(s: &str) -> anyhow::Result<u64> {
+    let upper = s.trim().to_uppercase();
+    let without_bytes = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, scale) = SIZE_UNITS
+        .iter()
+        .find_map(|(unit, scale)| Some((without_bytes.strip_suffix(unit)?, *scale)))
+        .unwrap_or((without_bytes, 1.0));
+
+    match number.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 => Ok((value * scale).round() as u64),
+        _ => Err(config_error(
+            "Size",
+            &format!("Invalid size '{}'. Use e.g. '500G', '1.5TB' or '700MB'", s),
+        )),
+    }
+}
+
+/// Units accepted by `parse_age`, largest first
+const AGE_UNITS: &[(&str, u64)] = &[
+    ("w", 604_800),
+    ("d", 86_400),
+    ("h", 3_600),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Parses a file age such as `30m`, `12h`, `7d` or `2w` (a bare number is days)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::parse_age;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_age("7d").unwrap(), Duration::from_secs(7 * 86_400));
+/// assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3_600));
+/// assert_eq!(parse_age("2").unwrap(), Duration::from_secs(2 * 86_400));
+/// assert!(parse_age("soon").is_err());
+/// ```
+pub fn parse_age(s: &str) -> anyhow::Result<Duration> {
+    let trimmed = s.trim();
+    let (number, seconds) = AGE_UNITS
+        .iter()
+        .find_map(|(unit, seconds)| Some((trimmed.strip_suffix(unit)?, *seconds)))
+        .unwrap_or((trimmed, 86_400));
+
+    let value: u64 = number.trim().parse().map_err(|_| {
+        config_error(
+            "File age",
+            &format!("Invalid age '{}'. Use e.g. '30m', '12h', '7d' or '2w'", s),
+        )
+    })?;
+    Ok(Duration::from_secs(value * seconds))
+}
+
+/// Formats an age in the largest unit that divides it evenly (days at most)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::format_age;
+/// use std::time::Duration;
+///
+/// assert_eq!(format_age(Duration::from_secs(14 * 86_400)), "14d");
+/// assert_eq!(format_age(Duration::from_secs(90 * 60)), "90m");
+/// ```
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    let (unit, size) = AGE_UNITS[1..]
+        .iter()
+        .find(|(_, size)| seconds.is_multiple_of(*size))
+        .copied()
+        .unwrap_or(("s", 1));
+    format!("{}{}", seconds / size, unit)
+}
+
+/// Formats the time since `timestamp` (seconds since the Unix epoch) in whole
+/// minutes, hours or days, rounded down (at least one minute)
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::format_elapsed;
+/// use std::time::{SystemTime, UNIX_EPOCH};
+///
+/// let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+/// assert_eq!(format_elapsed(now - 2 * 86_400 - 3_600), "2d");
+/// assert_eq!(format_elapsed(now), "1m");
+/// ```
+pub fn format_elapsed(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let age = now.saturating_sub(timestamp);
+    let unit = [86_400, 3_600, 60]
+        .into_iter()
+        .find(|unit| age >= *unit)
+        .unwrap_or(60);
+    format_age(Duration::from_secs((age / unit).max(1) * unit))
+}
+
+/// Formats a time (seconds since the Unix epoch) as a UTC date, `YYYY-MM-DD`
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::format_utc_date;
+///
+/// assert_eq!(format_utc_date(0), "1970-01-01");
+/// assert_eq!(format_utc_date(1_709_208_000), "2024-02-29");
+/// ```
+pub fn format_utc_date(timestamp: u64) -> String {
+    // Civil date from days since the epoch (proleptic Gregorian calendar)
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// `text` without ANSI escape sequences (colors), for writing to files
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::strip_ansi;
+///
+/// assert_eq!(strip_ansi("\u{1b}[31mREMOVE\u{1b}[0m (codec)"), "REMOVE (codec)");
+/// assert_eq!(strip_ansi("plain"), "plain");
+/// ```
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI sequences end with a letter, e.g. ESC [ 1 ; 31 m
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
 }
@@ -1,9 +1,38 @@
+pub mod console;
 pub mod dependencies;
 pub mod format;
+pub mod language_codes;
+pub mod languages;
+pub mod permissions;
+pub mod radarr;
 pub mod sonarr;
 pub mod validation;
+pub mod warnings;
 
-pub use dependencies::check_dependencies;
-pub use format::format_size;
-pub use sonarr::{SonarrMoveStatus, collect_sonarr_environment, output_sonarr_move_status};
-pub use validation::{is_valid_mkv_file, validate_source_target_paths};
+pub use console::{ConsoleMode, console_mode, set_console_mode, with_file};
+pub use dependencies::{
+    OPTIONAL_TOOLS, ToolInfo, ToolStatus, check_dependencies, detect_tool, detect_tools,
+    parse_tool_version, require_mkvtoolnix_tool, tool_available,
+};
+pub use format::{
+    DigitGrouping, NumberFormat, SizeUnits, format_age, format_elapsed, format_number,
+    format_percent, format_size, format_size_with, format_utc_date, number_format, parse_age,
+    parse_size, set_number_format, strip_ansi,
+};
+pub use language_codes::{normalize_language, normalize_languages};
+pub use languages::{LANGUAGES, language_from_title, language_name, suggest_language};
+pub use permissions::{
+    OWNER_OF_TARGET, apply_output_permissions, check_directory_writable, create_output_directory,
+    resolve_group, resolve_user,
+};
+pub use radarr::collect_radarr_environment;
+pub use sonarr::{
+    SonarrMoveStatus, collect_sonarr_environment, output_import_markers, output_sonarr_move_status,
+};
+pub use validation::{
+    collect_mkv_files, is_valid_mkv_file, resolve_target_path, validate_source_target_paths,
+};
+pub use warnings::{
+    CollectedWarning, WarningKind, collect_warnings, print_warning_summary, record_warning,
+    warning_counts,
+};
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::{config_error, directory_error};
+
+/// `processing.output_owner` value that copies the owner of the directory
+/// an output is written to
+pub const OWNER_OF_TARGET: &str = "target";
+
+/// Resolve a group name (from `/etc/group`) or numeric group ID
+pub fn resolve_group(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse::<u32>() {
+        return Ok(gid);
+    }
+
+    let groups = std::fs::read_to_string("/etc/group").unwrap_or_default();
+    groups
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?.parse::<u32>().ok()?;
+            Some((name, gid))
+        })
+        .find(|(name, _)| *name == group)
+        .map(|(_, gid)| gid)
+        .ok_or_else(|| {
+            config_error(
+                "Output group",
+                &format!("Unknown group '{}'. Use a group name or numeric ID", group),
+            )
+        })
+}
+
+/// Resolve a user name (from `/etc/passwd`) or numeric user ID
+pub fn resolve_user(user: &str) -> Result<u32> {
+    if let Ok(uid) = user.parse::<u32>() {
+        return Ok(uid);
+    }
+
+    let users = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+    users
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse::<u32>().ok()?;
+            Some((name, uid))
+        })
+        .find(|(name, _)| *name == user)
+        .map(|(_, uid)| uid)
+        .ok_or_else(|| {
+            config_error(
+                "Output owner",
+                &format!("Unknown user '{}'. Use a user name or numeric ID", user),
+            )
+        })
+}
+
+/// User and group IDs `processing.output_owner` gives `path`: those of the
+/// directory it is in for `target`, otherwise `USER[:GROUP]`
+fn resolve_owner(path: &Path, owner: &str) -> Result<(u32, Option<u32>)> {
+    if owner == OWNER_OF_TARGET {
+        let directory = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let metadata = std::fs::metadata(directory)
+            .with_context(|| format!("Failed to read owner of {}", directory.display()))?;
+        return Ok((metadata.uid(), Some(metadata.gid())));
+    }
+    match owner.split_once(':') {
+        Some((user, group)) => Ok((resolve_user(user)?, Some(resolve_group(group)?))),
+        None => Ok((resolve_user(owner)?, None)),
+    }
+}
+
+/// Apply `processing.output_mode`, `processing.output_owner` and
+/// `processing.output_group` to a written output file. Does nothing when
+/// none is configured.
+pub fn apply_output_permissions(path: &Path, config: &Config) -> Result<()> {
+    set_ownership(
+        path,
+        config.processing.output_mode.map(|mode| mode.bits),
+        config,
+    )
+}
+
+/// Create `path` and any missing parents, giving the directories this call
+/// created the configured group and the output mode plus search permission
+pub fn create_output_directory(path: &Path, config: &Config) -> Result<()> {
+    let missing: Vec<PathBuf> = path
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .map(Path::to_path_buf)
+        .collect();
+
+    std::fs::create_dir_all(path)
+        .with_context(|| format!("Failed to create target directory: {}", path.display()))?;
+
+    let mode = config
+        .processing
+        .output_mode
+        .map(|mode| mode.directory_bits());
+    for directory in missing.iter().rev() {
+        set_ownership(directory, mode, config)?;
+    }
+
+    Ok(())
+}
+
+/// Check that files can be created in `path` or, when it doesn't exist yet,
+/// in the nearest existing ancestor it would be created under. Probes with a
+/// short-lived empty file, since mode bits miss read-only mounts, ACLs and root.
+pub fn check_directory_writable(path: &Path) -> Result<()> {
+    let Some(existing) = path.ancestors().find(|ancestor| ancestor.exists()) else {
+        return Err(directory_error(
+            path,
+            "Write",
+            "No existing parent directory",
+        ));
+    };
+    if !existing.is_dir() {
+        return Err(directory_error(existing, "Write", "Not a directory"));
+    }
+
+    let probe = existing.join(format!(".mkv-slimmer-write-test-{}", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| directory_error(existing, "Write", &e.to_string()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+fn set_ownership(path: &Path, mode: Option<u32>, config: &Config) -> Result<()> {
+    let (uid, mut gid) = match &config.processing.output_owner {
+        Some(owner) => {
+            let (uid, gid) = resolve_owner(path, owner)?;
+            (Some(uid), gid)
+        }
+        None => (None, None),
+    };
+    if let Some(group) = &config.processing.output_group {
+        gid = Some(resolve_group(group)?);
+    }
+    if uid.is_some() || gid.is_some() {
+        std::os::unix::fs::chown(path, uid, gid)
+            .with_context(|| format!("Failed to change owner/group of {}", path.display()))?;
+    }
+    // chown may clear setuid/setgid bits, so the mode goes last
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to change permissions of {}", path.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::models::RadarrContext;
+use crate::report;
+
+/// Collect Radarr environment variables into a RadarrContext struct
+/// Performs case-insensitive matching for environment variable names
+pub fn collect_radarr_environment() -> RadarrContext {
+    let mut context = RadarrContext::default();
+
+    // Collect all environment variables and filter for Radarr ones
+    let env_vars: std::collections::HashMap<String, String> = std::env::vars()
+        .filter(|(key, _)| key.to_lowercase().starts_with("radarr_"))
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect();
+
+    // Helper function to get environment variable value
+    let get_env =
+        |key: &str| -> Option<String> { env_vars.get(&format!("radarr_{}", key)).cloned() };
+
+    // File Paths
+    context.source_path = get_env("sourcepath");
+    context.destination_path = get_env("destinationpath");
+
+    // Instance Information
+    context.instance_name = get_env("instancename");
+    context.application_url = get_env("applicationurl");
+    context.transfer_mode = get_env("transfermode");
+
+    // Movie Metadata
+    context.movie_id = get_env("movie_id");
+    context.movie_title = get_env("movie_title");
+    context.movie_year = get_env("movie_year");
+    context.movie_path = get_env("movie_path");
+    context.movie_imdb_id = get_env("movie_imdbid");
+    context.movie_tmdb_id = get_env("movie_tmdbid");
+    context.movie_overview = get_env("movie_overview");
+    context.movie_original_language = get_env("movie_originallanguage");
+    context.movie_genres = get_env("movie_genres");
+    context.movie_tags = get_env("movie_tags");
+    context.movie_physical_release_date = get_env("movie_physical_release_date");
+
+    // Quality and Media Information
+    context.movie_file_quality = get_env("moviefile_quality");
+    context.movie_file_quality_version = get_env("moviefile_qualityversion");
+    context.movie_file_release_group = get_env("moviefile_releasegroup");
+    context.movie_file_scene_name = get_env("moviefile_scenename");
+    context.movie_file_media_info_audio_channels = get_env("moviefile_mediainfo_audiochannels");
+    context.movie_file_media_info_audio_codec = get_env("moviefile_mediainfo_audiocodec");
+    context.movie_file_media_info_audio_languages = get_env("moviefile_mediainfo_audiolanguages");
+    context.movie_file_media_info_languages = get_env("moviefile_mediainfo_languages");
+    context.movie_file_media_info_height = get_env("moviefile_mediainfo_height");
+    context.movie_file_media_info_width = get_env("moviefile_mediainfo_width");
+    context.movie_file_media_info_subtitles = get_env("moviefile_mediainfo_subtitles");
+    context.movie_file_media_info_video_codec = get_env("moviefile_mediainfo_videocodec");
+    context.movie_file_media_info_video_dynamic_range_type =
+        get_env("moviefile_mediainfo_videodynamicrangetype");
+
+    // Custom Formats
+    context.movie_file_custom_format = get_env("moviefile_customformat");
+    context.movie_file_custom_format_score = get_env("moviefile_customformatscore");
+
+    // Download Information
+    context.download_client = get_env("download_client");
+    context.download_client_type = get_env("download_client_type");
+    context.download_id = get_env("download_id");
+
+    // Deleted Files (for upgrades)
+    context.deleted_relative_paths = get_env("deletedrelativepaths");
+    context.deleted_paths = get_env("deletedpaths");
+    context.deleted_date_added = get_env("deleteddateadded");
+    context.deleted_recycle_bin_paths = get_env("deletedrecyclebinpaths");
+
+    if context.is_present() {
+        report!("🎬 Detected Radarr environment context");
+        if let Some(ref movie_title) = context.movie_title {
+            match context.movie_year {
+                Some(ref year) => report!("🎞️  Processing for movie: {} ({})", movie_title, year),
+                None => report!("🎞️  Processing for movie: {}", movie_title),
+            }
+        }
+    }
+
+    context
+}
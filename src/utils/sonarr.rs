@@ -1,6 +1,7 @@
-use crate::models::SonarrContext;
+use crate::models::{SonarrContext, StreamDecisions, StreamInfo, StreamType};
+use crate::report;
 
-/// Move status reported back to Sonarr's import script via stdout.
+/// Move status reported back to Sonarr's (or Radarr's) import script via stdout.
 pub enum SonarrMoveStatus {
     /// File was not modified and can be moved/hardlinked as-is.
     MoveComplete,
@@ -8,7 +9,7 @@ pub enum SonarrMoveStatus {
     RenameRequested,
 }
 
-/// Emit the Sonarr `[MoveStatus]` line that the import script parses from stdout.
+/// Emit the `[MoveStatus]` line that Sonarr's or Radarr's import script parses from stdout.
 pub fn output_sonarr_move_status(status: SonarrMoveStatus) {
     match status {
         SonarrMoveStatus::MoveComplete => println!("[MoveStatus] MoveComplete"),
@@ -16,6 +17,37 @@ pub fn output_sonarr_move_status(status: SonarrMoveStatus) {
     }
 }
 
+/// Emit `[MkvSlimmer]` lines listing the audio and subtitle languages kept and
+/// removed, e.g. `[MkvSlimmer] RemovedLanguages audio=fre,ger subtitles=none`.
+/// Sonarr and Radarr keep the import script's output in their logs, so the
+/// result is visible there and not only in local logs.
+pub fn output_import_markers(streams: &[StreamInfo], decisions: &StreamDecisions) {
+    let languages = |stream_type: StreamType, kept: bool| -> String {
+        let mut languages: Vec<&str> = Vec::new();
+        for stream in streams.iter().filter(|s| s.stream_type == stream_type) {
+            let language = stream.effective_language();
+            if decisions.get(stream.index).is_some_and(|d| d.is_kept()) == kept
+                && !languages.contains(&language)
+            {
+                languages.push(language);
+            }
+        }
+        if languages.is_empty() {
+            "none".to_string()
+        } else {
+            languages.join(",")
+        }
+    };
+    for (label, kept) in [("KeptLanguages", true), ("RemovedLanguages", false)] {
+        println!(
+            "[MkvSlimmer] {} audio={} subtitles={}",
+            label,
+            languages(StreamType::Audio, kept),
+            languages(StreamType::Subtitle, kept)
+        );
+    }
+}
+
 /// Collect Sonarr environment variables into a SonarrContext struct
 /// Performs case-insensitive matching for environment variable names
 pub fn collect_sonarr_environment() -> SonarrContext {
@@ -28,9 +60,8 @@ pub fn collect_sonarr_environment() -> SonarrContext {
         .collect();
 
     // Helper function to get environment variable value
-    let get_env = |key: &str| -> Option<String> {
-        env_vars.get(&format!("sonarr_{}", key)).map(|s| s.clone())
-    };
+    let get_env =
+        |key: &str| -> Option<String> { env_vars.get(&format!("sonarr_{}", key)).cloned() };
 
     // File Paths
     context.source_path = get_env("sourcepath");
@@ -98,14 +129,14 @@ pub fn collect_sonarr_environment() -> SonarrContext {
     context.deleted_recycle_bin_paths = get_env("deletedrecyclebinpaths");
 
     if context.is_present() {
-        println!("🎬 Detected Sonarr environment context");
+        report!("🎬 Detected Sonarr environment context");
         if let Some(ref series_title) = context.series_title {
-            println!("📺 Processing for series: {}", series_title);
+            report!("📺 Processing for series: {}", series_title);
         }
-        if let Some(ref season) = context.episode_file_season_number {
-            if let Some(ref episode) = context.episode_file_episode_numbers {
-                println!("📋 Episode: S{}E{}", season, episode);
-            }
+        if let Some(ref season) = context.episode_file_season_number
+            && let Some(ref episode) = context.episode_file_episode_numbers
+        {
+            report!("📋 Episode: S{}E{}", season, episode);
         }
     }
 
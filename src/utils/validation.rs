@@ -1,6 +1,6 @@
 use crate::error::{file_validation_error, path_safety_error};
 use anyhow::{Context, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Checks if a file is a valid MKV file without throwing errors
 ///
@@ -32,7 +32,7 @@ pub fn is_valid_mkv_file<P: AsRef<Path>>(file_path: P) -> bool {
     // Check file extension
     if let Some(ext) = path.extension() {
         let ext_str = ext.to_string_lossy().to_lowercase();
-        if !["mkv", "mka", "mks"].contains(&ext_str.as_str()) {
+        if !["mkv", "mka", "mks", "webm"].contains(&ext_str.as_str()) {
             return false;
         }
     } else {
@@ -43,6 +43,30 @@ pub fn is_valid_mkv_file<P: AsRef<Path>>(file_path: P) -> bool {
     std::fs::File::open(path).is_ok()
 }
 
+/// Matroska files in `dir` (and its subdirectories when `recursive`), sorted
+pub fn collect_mkv_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_mkv_files_into(dir, recursive, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_mkv_files_into(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && is_valid_mkv_file(&path) {
+            files.push(path);
+        } else if recursive && path.is_dir() {
+            collect_mkv_files_into(&path, recursive, files)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Validate that the file is a valid MKV file
 pub fn validate_mkv_file<P: AsRef<Path>>(file_path: P) -> Result<()> {
     let path = file_path.as_ref();
@@ -64,11 +88,11 @@ pub fn validate_mkv_file<P: AsRef<Path>>(file_path: P) -> Result<()> {
     // Check file extension
     if let Some(ext) = path.extension() {
         let ext_str = ext.to_string_lossy().to_lowercase();
-        if !["mkv", "mka", "mks"].contains(&ext_str.as_str()) {
+        if !["mkv", "mka", "mks", "webm"].contains(&ext_str.as_str()) {
             return Err(file_validation_error(
                 path,
                 &format!(
-                    "File has extension '{}' but expected .mkv, .mka, or .mks",
+                    "File has extension '{}' but expected .mkv, .mka, .mks, or .webm",
                     ext_str
                 ),
             ));
@@ -76,7 +100,7 @@ pub fn validate_mkv_file<P: AsRef<Path>>(file_path: P) -> Result<()> {
     } else {
         return Err(file_validation_error(
             path,
-            "File has no extension. Expected .mkv, .mka, or .mks file.",
+            "File has no extension. Expected .mkv, .mka, .mks, or .webm file.",
         ));
     }
 
@@ -99,12 +123,15 @@ pub fn validate_mkv_file<P: AsRef<Path>>(file_path: P) -> Result<()> {
 
 /// Validates that source and target paths are safe for batch processing
 ///
-/// This function prevents dangerous directory relationships that could cause
-/// infinite loops or data corruption during recursive batch processing:
-/// - Same directory (source == target)  
-/// - Target nested in source (/movies → /movies/output)
+/// This function prevents directory relationships that would overwrite the
+/// originals:
+/// - Same directory (source == target)
 /// - Source nested in target (/movies/season1 → /movies)
 ///
+/// A target nested in the source (/movies → /movies/slimmed) is fine: batches
+/// leave the target tree out of file collection, so outputs are never picked
+/// up as sources.
+///
 /// Uses canonical paths to resolve symlinks and relative paths properly.
 ///
 /// # Arguments
@@ -125,33 +152,21 @@ pub fn validate_mkv_file<P: AsRef<Path>>(file_path: P) -> Result<()> {
 ///     Path::new("/movies")
 /// );
 /// assert!(result.is_err());
+///
+/// // A target inside the source is excluded from collection instead
+/// let source = std::env::temp_dir();
+/// assert!(validate_source_target_paths(&source, &source.join("slimmed")).is_ok());
 /// ```
 ///
 /// This prevents scenarios like:
-/// - Source: /movies/season1, Target: /movies/season1/processed
 /// - Source: /movies/season1/episode1.mkv, Target: /movies
-/// - Source: /movies, Target: /movies/processed
+/// - Source: /movies/season1, Target: /movies
 pub fn validate_source_target_paths(source_path: &Path, target_path: &Path) -> Result<()> {
     // Canonicalize source path to resolve symlinks and relative paths
     let source_canonical = source_path
         .canonicalize()
         .with_context(|| format!("Could not resolve source path: {}", source_path.display()))?;
-
-    // For target path, handle the case where it might not exist yet
-    let target_canonical = if target_path.exists() {
-        target_path
-            .canonicalize()
-            .with_context(|| format!("Could not resolve target path: {}", target_path.display()))?
-    } else {
-        // If target doesn't exist, canonicalize its parent directory
-        let parent = target_path
-            .parent()
-            .context("Target path has no parent directory")?;
-        let parent_canonical = parent.canonicalize().with_context(|| {
-            format!("Could not resolve target parent path: {}", parent.display())
-        })?;
-        parent_canonical.join(target_path.file_name().unwrap_or_default())
-    };
+    let target_canonical = resolve_target_path(target_path)?;
 
     // Check if paths are exactly the same
     if source_canonical == target_canonical {
@@ -162,15 +177,6 @@ pub fn validate_source_target_paths(source_path: &Path, target_path: &Path) -> R
         ));
     }
 
-    // Check if target is nested within source
-    if target_canonical.starts_with(&source_canonical) {
-        return Err(path_safety_error(
-            source_path,
-            target_path,
-            "Target is inside source directory - this creates infinite loops in recursive mode",
-        ));
-    }
-
     // Check if source is nested within target
     if source_canonical.starts_with(&target_canonical) {
         return Err(path_safety_error(
@@ -182,3 +188,19 @@ pub fn validate_source_target_paths(source_path: &Path, target_path: &Path) -> R
 
     Ok(())
 }
+
+/// Canonical form of a target path that may not exist yet (its parent must)
+pub fn resolve_target_path(target_path: &Path) -> Result<PathBuf> {
+    if target_path.exists() {
+        return target_path
+            .canonicalize()
+            .with_context(|| format!("Could not resolve target path: {}", target_path.display()));
+    }
+    let parent = target_path
+        .parent()
+        .context("Target path has no parent directory")?;
+    let parent_canonical = parent
+        .canonicalize()
+        .with_context(|| format!("Could not resolve target parent path: {}", parent.display()))?;
+    Ok(parent_canonical.join(target_path.file_name().unwrap_or_default()))
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::PathBuf;
+
+use super::console::current_file;
+use crate::report_warn;
+
+/// Most warnings listed per kind in the summary; the rest are counted
+const LISTED_PER_KIND: usize = 10;
+
+/// Kind of a non-fatal problem, which the batch summary groups warnings by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    /// Warnings mkvmerge printed while remuxing (exit code 1)
+    Mkvmerge,
+    /// Problems the analyzers ran into, such as a failed ffprobe
+    Analysis,
+    /// Audio or subtitle tracks without a language tag
+    MissingLanguage,
+    /// Stream sizes estimated from the container size
+    EstimatedSizes,
+    /// Remuxes or copies below the throughput warning's minimum rate
+    SlowTransfer,
+    /// Source directories that couldn't be read and target directories
+    /// that can't be written
+    SkippedDirectory,
+    /// Remuxed outputs whose duration differs from the source's
+    DurationMismatch,
+    /// Disagreements between ffprobe and `mkvmerge -J` about a file
+    SourceConflict,
+}
+
+impl WarningKind {
+    /// Identifier used in `--result-json`
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::Mkvmerge => "mkvmerge",
+            WarningKind::Analysis => "analysis",
+            WarningKind::MissingLanguage => "missing_language",
+            WarningKind::EstimatedSizes => "estimated_sizes",
+            WarningKind::SlowTransfer => "slow_transfer",
+            WarningKind::SkippedDirectory => "skipped_directory",
+            WarningKind::DurationMismatch => "duration_mismatch",
+            WarningKind::SourceConflict => "source_conflict",
+        }
+    }
+
+    /// Heading of the kind in the warning summary
+    pub fn heading(&self) -> &'static str {
+        match self {
+            WarningKind::Mkvmerge => "mkvmerge warnings",
+            WarningKind::Analysis => "Analysis problems",
+            WarningKind::MissingLanguage => "Missing language tags",
+            WarningKind::EstimatedSizes => "Estimated stream sizes",
+            WarningKind::SlowTransfer => "Slow remuxes/copies",
+            WarningKind::SkippedDirectory => "Skipped directories",
+            WarningKind::DurationMismatch => "Output duration mismatches",
+            WarningKind::SourceConflict => "ffprobe/mkvmerge disagreements",
+        }
+    }
+}
+
+/// A warning recorded while collecting, with the file it came up for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectedWarning {
+    pub kind: WarningKind,
+    pub file: Option<PathBuf>,
+    pub message: String,
+}
+
+tokio::task_local! {
+    static COLLECTED: RefCell<Vec<CollectedWarning>>;
+}
+
+/// Run `future`, returning its output with the warnings recorded by
+/// `record_warning()` meanwhile, in order and without repeats
+///
+/// # Examples
+/// ```rust
+/// use mkv_slimmer::utils::{WarningKind, collect_warnings, record_warning};
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let ((), warnings) = collect_warnings(async {
+///     record_warning(WarningKind::Analysis, "ffprobe failed");
+///     record_warning(WarningKind::Analysis, "ffprobe failed");
+/// })
+/// .await;
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].message, "ffprobe failed");
+/// # });
+/// ```
+pub async fn collect_warnings<F: Future>(future: F) -> (F::Output, Vec<CollectedWarning>) {
+    COLLECTED
+        .scope(RefCell::new(Vec::new()), async {
+            let output = future.await;
+            (output, COLLECTED.with(|warnings| warnings.take()))
+        })
+        .await
+}
+
+/// Record a warning for the summary of the enclosing `collect_warnings()`,
+/// attributed to the file the task's messages are; does nothing outside one.
+/// The warning is not printed: sites that print it do so themselves.
+pub fn record_warning(kind: WarningKind, message: impl Into<String>) {
+    let warning = CollectedWarning {
+        kind,
+        file: current_file(),
+        message: message.into(),
+    };
+    let _ = COLLECTED.try_with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    });
+}
+
+/// Number of warnings per `WarningKind::name()`
+pub fn warning_counts(warnings: &[CollectedWarning]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for warning in warnings {
+        *counts.entry(warning.kind.name().to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Print the warnings grouped by kind, listing the first few of each
+pub fn print_warning_summary(warnings: &[CollectedWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+
+    let mut by_kind: BTreeMap<WarningKind, Vec<&CollectedWarning>> = BTreeMap::new();
+    for warning in warnings {
+        by_kind.entry(warning.kind).or_default().push(warning);
+    }
+
+    report_warn!("\n⚠️  Warnings ({}):", warnings.len());
+    for (kind, warnings) in by_kind {
+        report_warn!("   {}: {}", kind.heading(), warnings.len());
+        for warning in warnings.iter().take(LISTED_PER_KIND) {
+            match &warning.file {
+                Some(file) => report_warn!("      {} - {}", file.display(), warning.message),
+                None => report_warn!("      {}", warning.message),
+            }
+        }
+        if warnings.len() > LISTED_PER_KIND {
+            report_warn!("      … and {} more", warnings.len() - LISTED_PER_KIND);
+        }
+    }
+}